@@ -0,0 +1,202 @@
+//! JWT bearer tokens, granted access by the claims a file requires (git mode).
+//!
+//! A file's `auth` can list `claims` entries; a JWT signed with the configured
+//! key (`--jwt-secret` or `--jwt-public-key`) that is not expired, comes from
+//! the configured issuer if any, and carries all the claims of one entry may
+//! read the file:
+//!
+//! ```yaml
+//! <!>:
+//!   auth:
+//!     - claims:
+//!         aud: konf
+//!         scope: [db:read]
+//! ```
+//!
+//! A required string matches a claim equal to it or an array claim containing
+//! it; `scope` and `scp` claims may also be space-separated lists (OAuth). A
+//! required list matches when each of its items does. Other values must be
+//! equal.
+
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode};
+use serde_json::Map;
+
+use crate::{Mapping, Value};
+
+/// Entry of an `auth` list mapping to the claims a JWT must carry.
+pub const CLAIMS_KEY: &str = "claims";
+
+/// Claims holding space-separated lists (OAuth scopes) rather than one value.
+const SPACE_SEPARATED_CLAIMS: [&str; 2] = ["scope", "scp"];
+
+/// Checks the signature, expiry and issuer of JWTs.
+pub struct JwtValidator {
+    key: DecodingKey,
+    validation: Validation,
+}
+
+impl std::fmt::Debug for JwtValidator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JwtValidator")
+            .field("algorithms", &self.validation.algorithms)
+            .field("issuer", &self.validation.iss)
+            .finish_non_exhaustive()
+    }
+}
+
+impl JwtValidator {
+    /// Validates HS256 tokens signed with a shared secret.
+    pub fn hmac(secret: &[u8]) -> Self {
+        Self::new(DecodingKey::from_secret(secret), Algorithm::HS256)
+    }
+
+    /// Validates RS256 tokens against a PEM-encoded RSA public key.
+    pub fn rsa_pem(pem: &[u8]) -> jsonwebtoken::errors::Result<Self> {
+        Ok(Self::new(DecodingKey::from_rsa_pem(pem)?, Algorithm::RS256))
+    }
+
+    fn new(key: DecodingKey, algorithm: Algorithm) -> Self {
+        let mut validation = Validation::new(algorithm);
+        // Audiences are matched as claims, per file
+        validation.validate_aud = false;
+        Self { key, validation }
+    }
+
+    /// Only accepts tokens whose `iss` claim is `issuer`.
+    pub fn with_issuer(mut self, issuer: &str) -> Self {
+        self.validation.set_issuer(&[issuer]);
+        self
+    }
+
+    /// Accepts tokens up to `seconds` past their `exp` (60 by default), for clock skew.
+    pub fn with_leeway(mut self, seconds: u64) -> Self {
+        self.validation.leeway = seconds;
+        self
+    }
+
+    /// Returns the claims of `token` if it is valid, or `None`.
+    pub fn decode(&self, token: &str) -> Option<Map<String, serde_json::Value>> {
+        match decode::<Map<String, serde_json::Value>>(token, &self.key, &self.validation) {
+            Ok(data) => Some(data.claims),
+            Err(e) => {
+                tracing::debug!("rejected JWT: {e}");
+                None
+            }
+        }
+    }
+}
+
+/// Returns true if `claims` carries every claim of `required`.
+pub fn satisfies(claims: &Map<String, serde_json::Value>, required: &Mapping) -> bool {
+    required
+        .iter()
+        .all(|(name, expected)| {
+            let split = SPACE_SEPARATED_CLAIMS.contains(&name.as_str());
+            claims.get(name).is_some_and(|claim| claim_matches(claim, expected, split))
+        })
+}
+
+/// Matches one claim; `split` lets a string claim list space-separated values.
+fn claim_matches(claim: &serde_json::Value, expected: &Value, split: bool) -> bool {
+    match (claim, expected) {
+        (_, Value::Sequence(items)) => items.iter().all(|item| claim_matches(claim, item, split)),
+        (serde_json::Value::Array(values), expected) => {
+            values.iter().any(|value| claim_matches(value, expected, split))
+        }
+        (serde_json::Value::String(s), Value::String(expected)) => {
+            s == expected || split && s.split_whitespace().any(|word| word == expected)
+        }
+        (serde_json::Value::Bool(b), Value::Boolean(expected)) => b == expected,
+        (serde_json::Value::Number(n), Value::Int(expected)) => n.as_i64() == Some(*expected),
+        (serde_json::Value::Number(n), Value::Float(expected)) => n.as_f64() == Some(*expected),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::{EncodingKey, Header, encode};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{loader::Loader, loaders::yaml::YamlLoader};
+
+    const SECRET: &[u8] = b"jwt-test-secret";
+
+    fn now() -> i64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64
+    }
+
+    fn token(claims: serde_json::Value) -> String {
+        encode(&Header::default(), &claims, &EncodingKey::from_secret(SECRET)).unwrap()
+    }
+
+    fn required(yaml: &str) -> Mapping {
+        YamlLoader {}.load(yaml).unwrap().as_mapping().unwrap().clone()
+    }
+
+    #[test]
+    fn test_valid_token_carries_required_claims() {
+        let validator = JwtValidator::hmac(SECRET).with_issuer("https://auth.example.com");
+        let claims = validator
+            .decode(&token(json!({
+                "exp": now() + 60,
+                "iss": "https://auth.example.com",
+                "aud": ["konf", "other"],
+                "scope": "db:read cache:read",
+                "admin": false,
+            })))
+            .expect("token should be valid");
+
+        assert!(satisfies(&claims, &required("aud: konf\nscope: [db:read, cache:read]")));
+        assert!(satisfies(&claims, &required("admin: false")));
+        assert!(!satisfies(&claims, &required("aud: billing")));
+    }
+
+    #[test]
+    fn test_expired_or_foreign_tokens_are_rejected() {
+        let validator = JwtValidator::hmac(SECRET).with_issuer("https://auth.example.com");
+
+        let expired = token(json!({"exp": now() - 3600, "iss": "https://auth.example.com"}));
+        assert!(validator.decode(&expired).is_none());
+
+        let other_issuer = token(json!({"exp": now() + 60, "iss": "https://evil.example.com"}));
+        assert!(validator.decode(&other_issuer).is_none());
+
+        let other_key = encode(
+            &Header::default(),
+            &json!({"exp": now() + 60, "iss": "https://auth.example.com"}),
+            &EncodingKey::from_secret(b"another-secret"),
+        )
+        .unwrap();
+        assert!(validator.decode(&other_key).is_none());
+        assert!(validator.decode("not-a-jwt").is_none());
+    }
+
+    #[test]
+    fn test_missing_scope_is_not_satisfied() {
+        let validator = JwtValidator::hmac(SECRET);
+        let claims = validator
+            .decode(&token(json!({"exp": now() + 60, "scopes": ["cache:read"]})))
+            .unwrap();
+
+        assert!(!satisfies(&claims, &required("scopes: [db:read]")));
+        assert!(!satisfies(&claims, &required("scopes: [cache:read, db:read]")));
+        assert!(satisfies(&claims, &required("scopes: cache:read")));
+    }
+
+    #[test]
+    fn test_only_scope_claims_are_space_separated() {
+        let validator = JwtValidator::hmac(SECRET);
+        let claims = validator
+            .decode(&token(json!({"exp": now() + 60, "sub": "guest admin", "scp": "db:read db:write"})))
+            .unwrap();
+
+        assert!(!satisfies(&claims, &required("sub: admin")));
+        assert!(satisfies(&claims, &required("sub: guest admin")));
+        assert!(satisfies(&claims, &required("scp: db:write")));
+    }
+}
@@ -1,6 +1,19 @@
+pub mod jwt;
+
+use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::{
+    Mapping, Value,
+    authorizer::jwt::{CLAIMS_KEY, JwtValidator, satisfies},
+    fs::FileProvider,
+    loader::MultiLoader,
+    metadata::{AUTH_KEY, METADATA_KEY},
+};
 
-use crate::{fs::FileProvider, loader::MultiLoader, utils::get_conf_strings};
+/// Key of the file defining roles, e.g. `_roles.yaml` at the root of the config folder.
+pub const ROLES_KEY: &str = "_roles";
 
 /// Token-based authorizer for controlling access to configuration files.
 ///
@@ -13,51 +26,403 @@ use crate::{fs::FileProvider, loader::MultiLoader, utils::get_conf_strings};
 ///     - token1
 ///     - token2
 /// ```
+///
+/// Entries can also name a role defined in the [`ROLES_KEY`] file, which maps
+/// each role to its tokens; every token of a granted role is authorized:
+///
+/// ```yaml
+/// # _roles.yaml
+/// db-readers:
+///   - token1
+///   - token3
+/// ```
+///
+/// An entry can also be a mapping of path patterns to the tokens or roles
+/// allowed to access the matching files, so one file can grant a whole folder.
+/// `*` matches any characters, `/` included:
+///
+/// ```yaml
+/// <!>:
+///   auth:
+///     - services/*: [ops-token, db-readers]
+/// ```
+///
+/// A file's own `auth` takes precedence over patterns, including patterns other
+/// files write without wildcard to name it; otherwise the pattern with the
+/// longest literal prefix decides.
+///
+/// With a [`JwtValidator`] (see [`Authorizer::with_jwt`]), `claims` entries
+/// also grant access to bearer JWTs carrying the listed claims (see [`jwt`]).
+///
+/// Admin tokens can optionally be configured with [`Authorizer::with_admin_tokens`]
+/// for break-glass access: they authorize any path, and each use is logged.
 #[derive(Debug)]
 pub struct Authorizer {
     /// Maps file paths to the set of tokens allowed to access them.
     paths: HashMap<String, HashSet<String>>,
+    /// Path patterns with the tokens they grant, most specific first.
+    patterns: Vec<(String, HashSet<String>)>,
+    /// Tokens allowed to access every path (empty unless configured).
+    admin_tokens: HashSet<String>,
+    /// Maps file paths to the claim sets a JWT may carry to access them, any one sufficing.
+    claims: HashMap<String, Vec<Mapping>>,
+    /// Validates JWTs; without one, `claims` entries grant nothing.
+    jwt: Option<Arc<JwtValidator>>,
 }
 
 impl Authorizer {
     /// Checks if the given token is authorized to access the file at `path`.
     ///
-    /// Returns `false` if the path has no authorization configured or the token is not in the allowed list.
+    /// Returns `false` if the path has no authorization configured or the token is not in the allowed list,
+    /// unless the token is a configured admin token.
     pub fn authorize(&self, path: &str, token: &str) -> bool {
-        self.paths
+        if self.admin_tokens.contains(token) {
+            tracing::warn!(path, "access granted with admin token");
+            return true;
+        }
+        let granted = self
+            .paths
             .get(path)
-            .map(|tokens| tokens.contains(token))
-            .unwrap_or(false)
+            .or_else(|| {
+                self.patterns
+                    .iter()
+                    .find(|(pattern, _)| matches_pattern(pattern, path))
+                    .map(|(_, tokens)| tokens)
+            })
+            .is_some_and(|tokens| tokens.contains(token));
+        granted || self.authorize_jwt(path, token)
+    }
+
+    /// Returns true if `path` declares who may access it: its own `auth`, a
+    /// matching pattern or required claims. Other paths are only open to admins.
+    pub fn is_restricted(&self, path: &str) -> bool {
+        self.paths.contains_key(path)
+            || self.claims.contains_key(path)
+            || self.patterns.iter().any(|(pattern, _)| matches_pattern(pattern, path))
+    }
+
+    /// Checks if `token` is a valid JWT carrying one of the claim sets `path` requires.
+    fn authorize_jwt(&self, path: &str, token: &str) -> bool {
+        let (Some(jwt), Some(required)) = (&self.jwt, self.claims.get(path)) else {
+            return false;
+        };
+        jwt.decode(token)
+            .is_some_and(|claims| required.iter().any(|required| satisfies(&claims, required)))
     }
 
     /// Creates a new authorizer by scanning all files for auth configurations.
+    ///
+    /// Role names granted in `auth` are expanded to the tokens of that role.
     pub async fn new<P: FileProvider>(fs: &P, loader: &MultiLoader) -> Self {
-        const IMPORT_KEY: &str = "auth";
-        let mut paths: HashMap<String, HashSet<String>> = HashMap::new();
+        let mut files = Vec::new();
+        let mut roles: HashMap<String, Vec<String>> = HashMap::new();
         for path in fs.list().await {
             if let Some(content) = fs.load(&path.full_path).await {
                 match loader.load(&path.ext, &content) {
-                    Ok(p) => {
-                        let values = get_conf_strings(&p, IMPORT_KEY);
-                        for i in values.iter() {
-                            match paths.entry(path.filename.clone()) {
-                                std::collections::hash_map::Entry::Occupied(mut occupied_entry) => {
-                                    occupied_entry.get_mut().insert(i.clone());
-                                }
-                                std::collections::hash_map::Entry::Vacant(vacant_entry) => {
-                                    let mut s = HashSet::new();
-                                    s.insert(i.clone());
-                                    vacant_entry.insert(s);
-                                }
-                            }
-                        }
-                    }
+                    Ok(p) if path.filename == ROLES_KEY => roles = parse_roles(&p),
+                    Ok(p) => files.push((path.filename, p)),
                     Err(_) => {
                         tracing::warn!("failed to read {:?}", &path);
                     }
                 }
             }
         }
-        Self { paths }
+
+        let expand = |tokens: &mut HashSet<String>, grant: &str| match roles.get(grant) {
+            Some(role_tokens) => tokens.extend(role_tokens.iter().cloned()),
+            None => {
+                tokens.insert(grant.to_string());
+            }
+        };
+        let mut paths: HashMap<String, HashSet<String>> = HashMap::new();
+        let mut patterns: HashMap<String, HashSet<String>> = HashMap::new();
+        let mut claims: HashMap<String, Vec<Mapping>> = HashMap::new();
+        for (filename, p) in files {
+            for entry in auth_entries(&p) {
+                match entry {
+                    Value::String(grant) => expand(paths.entry(filename.clone()).or_default(), grant),
+                    Value::Mapping(grants) => {
+                        for (pattern, grants) in grants {
+                            if let (CLAIMS_KEY, Value::Mapping(required)) = (pattern.as_str(), grants) {
+                                claims.entry(filename.clone()).or_default().push(required.clone());
+                                continue;
+                            }
+                            // A pattern without wildcard names a single file. Naming
+                            // another file, it is still a pattern, so that the file's own
+                            // `auth` keeps precedence over grants from other files.
+                            let tokens = if *pattern == filename {
+                                paths.entry(filename.clone()).or_default()
+                            } else {
+                                patterns.entry(pattern.clone()).or_default()
+                            };
+                            for grant in grants_of(grants) {
+                                expand(tokens, grant);
+                            }
+                        }
+                    }
+                    _ => tracing::warn!("ignoring invalid auth entry in {filename}: {entry:?}"),
+                }
+            }
+        }
+
+        let mut patterns: Vec<(String, HashSet<String>)> = patterns.into_iter().collect();
+        patterns.sort_by_key(|(pattern, _)| Reverse((literal_prefix_len(pattern), pattern.len())));
+        Self {
+            paths,
+            patterns,
+            admin_tokens: HashSet::new(),
+            claims,
+            jwt: None,
+        }
+    }
+
+    /// Accepts JWTs checked by `jwt` for files that declare required `claims`.
+    pub fn with_jwt(mut self, jwt: Option<Arc<JwtValidator>>) -> Self {
+        self.jwt = jwt;
+        self
+    }
+
+    /// Sets the admin tokens, which are authorized to access any path.
+    pub fn with_admin_tokens(mut self, tokens: impl IntoIterator<Item = String>) -> Self {
+        self.admin_tokens = tokens.into_iter().collect();
+        self
+    }
+}
+
+/// Returns the entries of a file's `auth` list.
+fn auth_entries(value: &Value) -> &[Value] {
+    value
+        .get(METADATA_KEY)
+        .and_then(|metadata| metadata.get(AUTH_KEY))
+        .and_then(|auth| auth.as_sequence())
+        .map_or(&[], |entries| entries.as_slice())
+}
+
+/// Returns the tokens or roles a pattern grants: a list, or a single one.
+fn grants_of(value: &Value) -> Vec<&str> {
+    match value {
+        Value::String(grant) => vec![grant.as_str()],
+        Value::Sequence(grants) => grants.iter().filter_map(|g| g.as_str().map(String::as_str)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Length of a pattern before its first wildcard, which orders patterns by specificity.
+fn literal_prefix_len(pattern: &str) -> usize {
+    pattern.find('*').unwrap_or(pattern.len())
+}
+
+/// Returns true if `path` matches `pattern`, where `*` matches any characters (`/` included).
+fn matches_pattern(pattern: &str, path: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcard: the pattern must match exactly
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Reads the roles file: a mapping of role name to a list of tokens.
+fn parse_roles(value: &Value) -> HashMap<String, Vec<String>> {
+    let Some(mapping) = value.as_mapping() else {
+        tracing::warn!("{ROLES_KEY} must be a mapping of role name to tokens");
+        return HashMap::new();
+    };
+    mapping
+        .iter()
+        .map(|(role, tokens)| {
+            let tokens = tokens
+                .as_sequence()
+                .map(|seq| seq.iter().filter_map(|t| t.as_str().cloned()).collect())
+                .unwrap_or_default();
+            (role.clone(), tokens)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fs::memory::MemoryFileProvider, loaders::yaml::YamlLoader};
+
+    async fn authorizer() -> Authorizer {
+        let fs = MemoryFileProvider::new()
+            .with_file("app.yaml", "<!>:\n  auth:\n    - app-token\nkey: value")
+            .with_file("secrets.yaml", "<!>:\n  auth:\n    - ops-token\nkey: value")
+            .with_file("public.yaml", "key: value");
+        let loader = MultiLoader::new(vec![Box::new(YamlLoader {})]);
+        Authorizer::new(&fs, &loader).await
+    }
+
+    #[tokio::test]
+    async fn test_tokens_are_path_scoped() {
+        let auth = authorizer().await;
+
+        assert!(auth.authorize("app", "app-token"));
+        assert!(!auth.authorize("secrets", "app-token"));
+        assert!(!auth.authorize("public", "app-token"));
+    }
+
+    #[tokio::test]
+    async fn test_admin_token_accesses_any_path() {
+        let auth = authorizer()
+            .await
+            .with_admin_tokens(["admin-token".to_string()]);
+
+        assert!(auth.authorize("app", "admin-token"));
+        assert!(auth.authorize("secrets", "admin-token"));
+        assert!(auth.authorize("public", "admin-token"));
+
+        // Normal tokens remain path-scoped
+        assert!(auth.authorize("app", "app-token"));
+        assert!(!auth.authorize("secrets", "app-token"));
+    }
+
+    #[tokio::test]
+    async fn test_role_grants_its_tokens() {
+        let fs = MemoryFileProvider::new()
+            .with_file("_roles.yaml", "db-readers:\n  - reader-a\n  - reader-b\nadmin:\n  - ops-token")
+            .with_file("database.yaml", "<!>:\n  auth:\n    - db-readers\n    - app-token\nkey: value")
+            .with_file("secrets.yaml", "<!>:\n  auth:\n    - admin\nkey: value");
+        let loader = MultiLoader::new(vec![Box::new(YamlLoader {})]);
+        let auth = Authorizer::new(&fs, &loader).await;
+
+        assert!(auth.authorize("database", "reader-a"));
+        assert!(auth.authorize("database", "reader-b"));
+        assert!(!auth.authorize("secrets", "reader-a"));
+        assert!(auth.authorize("secrets", "ops-token"));
+
+        // Literal tokens can be mixed with roles, and role names are not tokens
+        assert!(auth.authorize("database", "app-token"));
+        assert!(!auth.authorize("database", "db-readers"));
+    }
+
+    async fn pattern_authorizer() -> Authorizer {
+        let fs = MemoryFileProvider::new()
+            .with_file("_roles.yaml", "ops:\n  - ops-token")
+            .with_file(
+                "_access.yaml",
+                "<!>:\n  auth:\n    - common/*: [common-token, ops]\n    - common/db/*: db-token\n    - services/api: api-token",
+            )
+            .with_file("common/database.yaml", "key: value")
+            .with_file("common/db/replica.yaml", "key: value")
+            .with_file("common/secrets.yaml", "<!>:\n  auth:\n    - secrets-token\nkey: value")
+            .with_file("other/x.yaml", "key: value")
+            .with_file("services/api.yaml", "key: value");
+        let loader = MultiLoader::new(vec![Box::new(YamlLoader {})]);
+        Authorizer::new(&fs, &loader).await
+    }
+
+    #[tokio::test]
+    async fn test_pattern_grants_matching_paths() {
+        let auth = pattern_authorizer().await;
+
+        assert!(auth.authorize("common/database", "common-token"));
+        assert!(auth.authorize("common/database", "ops-token"));
+        assert!(!auth.authorize("other/x", "common-token"));
+        assert!(!auth.authorize("common", "common-token"));
+
+        // A pattern without wildcard grants a single path
+        assert!(auth.authorize("services/api", "api-token"));
+    }
+
+    #[tokio::test]
+    async fn test_exact_and_longest_prefix_win() {
+        let auth = pattern_authorizer().await;
+
+        // The file's own auth wins over the folder pattern
+        assert!(auth.authorize("common/secrets", "secrets-token"));
+        assert!(!auth.authorize("common/secrets", "common-token"));
+
+        // The most specific pattern decides
+        assert!(auth.authorize("common/db/replica", "db-token"));
+        assert!(!auth.authorize("common/db/replica", "common-token"));
+    }
+
+    #[tokio::test]
+    async fn test_foreign_grant_cannot_widen_own_auth() {
+        let fs = MemoryFileProvider::new()
+            .with_file("secrets.yaml", "<!>:\n  auth:\n    - ops-token\nkey: value")
+            .with_file("app.yaml", "<!>:\n  auth:\n    - secrets: app-token\n    - app: app-token\nkey: value");
+        let loader = MultiLoader::new(vec![Box::new(YamlLoader {})]);
+        let auth = Authorizer::new(&fs, &loader).await;
+
+        assert!(auth.authorize("secrets", "ops-token"));
+        assert!(!auth.authorize("secrets", "app-token"), "secrets declares its own auth");
+        // A file may still name itself
+        assert!(auth.authorize("app", "app-token"));
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("services/*", "services/api/config"));
+        assert!(matches_pattern("*/database", "common/database"));
+        assert!(matches_pattern("services/*/config", "services/api/config"));
+        assert!(!matches_pattern("services/*/config", "services/api/secrets"));
+        assert!(!matches_pattern("common/*", "other/x"));
+        assert!(!matches_pattern("a*a", "a"));
+        assert!(matches_pattern("exact", "exact"));
+        assert!(!matches_pattern("exact", "exact/more"));
+    }
+
+    #[tokio::test]
+    async fn test_restricted_paths_declare_access() {
+        let auth = pattern_authorizer().await;
+
+        assert!(auth.is_restricted("common/secrets"));
+        assert!(auth.is_restricted("common/database"), "covered by the common/* pattern");
+        assert!(!auth.is_restricted("other/x"));
+    }
+
+    #[tokio::test]
+    async fn test_jwt_claims_grant_access() {
+        use jsonwebtoken::{EncodingKey, Header, encode};
+
+        let fs = MemoryFileProvider::new().with_file(
+            "database.yaml",
+            "<!>:\n  auth:\n    - app-token\n    - claims:\n        aud: konf\n        scope: [db:read]\nkey: value",
+        );
+        let loader = MultiLoader::new(vec![Box::new(YamlLoader {})]);
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let jwt = |claims: serde_json::Value| {
+            encode(&Header::default(), &claims, &EncodingKey::from_secret(b"secret")).unwrap()
+        };
+        let valid = jwt(serde_json::json!({"exp": now + 60, "aud": "konf", "scope": "db:read"}));
+        let expired = jwt(serde_json::json!({"exp": now - 3600, "aud": "konf", "scope": "db:read"}));
+        let missing_scope = jwt(serde_json::json!({"exp": now + 60, "aud": "konf", "scope": "cache:read"}));
+
+        let static_only = Authorizer::new(&fs, &loader).await;
+        assert!(!static_only.authorize("database", &valid), "JWTs are opt-in");
+
+        let auth = Authorizer::new(&fs, &loader)
+            .await
+            .with_jwt(Some(Arc::new(JwtValidator::hmac(b"secret"))));
+        assert!(auth.authorize("database", &valid));
+        assert!(!auth.authorize("database", &expired));
+        assert!(!auth.authorize("database", &missing_scope));
+
+        // Static tokens keep working alongside
+        assert!(auth.authorize("database", "app-token"));
+    }
+
+    #[tokio::test]
+    async fn test_admin_tokens_are_opt_in() {
+        let auth = authorizer().await;
+
+        assert!(!auth.authorize("app", "admin-token"));
     }
 }
@@ -4,21 +4,36 @@
 //!
 //! Usage:
 //!   konf render -f /path/to/configs -n myconfig -o yaml
+//!   konf render -f /path/to/configs --all -d out -o json
+//!   konf schema -f /path/to/configs -n myconfig
+//!   konf fmt -f /path/to/configs [--check]
+//!   konf export -f /path/to/configs -d out -o json
+//!   konf orphans -f /path/to/configs [--entrypoint services/]
+//!   konf validate -f /path/to/configs [-n myconfig]
+//!   konf graph -f /path/to/configs [--output deps.dot]
 //!   konf lsp
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use clap::{Parser, Subcommand};
 
 use konf_provider::{
+    export::export_all,
+    fmt::format_yaml,
     fs::local::BasicFsFileProvider,
+    imports::ImportContext,
     loader::MultiLoader,
-    loaders::yaml::YamlLoader,
+    loaders::{env::EnvLoader, json::JsonLoader, yaml::YamlLoader},
     render::Dag,
+    render_helper::{EnvAccess, UnresolvedPolicy},
+    schema::infer_schema,
+    validate::validate_all,
     writer::{
-        MultiWriter, docker_env::DockerEnvVarWriter, env::EnvVarWriter, json::JsonWriter,
-        properties::PropertiesWriter, toml::TomlWriter, yaml::YamlWriter,
+        DEFAULT_FORMAT, MultiWriter, SequenceEncoding, docker_env::DockerEnvVarWriter, env::EnvVarWriter,
+        helm::HelmValuesWriter, ini::IniWriter, json::JsonWriter,
+        k8s::{ConfigMapWriter, SecretWriter, resource_name}, properties::PropertiesWriter, shell::ShellWriter,
+        toml::TomlWriter, xml::XmlWriter, yaml::YamlWriter,
     },
 };
 
@@ -42,12 +57,162 @@ enum Commands {
         folder: PathBuf,
 
         /// File to render (without extension, e.g., "app" for "app.yaml")
+        #[arg(long, short = 'n', required_unless_present = "all")]
+        file: Option<String>,
+
+        /// Render every file into `--output-dir` instead of printing one
+        #[arg(long, conflicts_with = "file", requires = "output_dir")]
+        all: bool,
+
+        /// Directory to write `<key>.<format>` files into, with `--all`
+        #[arg(long, short = 'd', conflicts_with = "file")]
+        output_dir: Option<PathBuf>,
+
+        /// With `--all`, stop at the first config that fails to render instead of skipping it
+        #[arg(long, conflicts_with = "file")]
+        fail_fast: bool,
+
+        /// Output format (yaml, json, env, properties, toml, docker-env, helm-values)
+        #[arg(long, short = 'o', default_value = DEFAULT_FORMAT, env = "KONF_DEFAULT_FORMAT")]
+        format: String,
+
+        /// Indent JSON output
+        #[arg(long)]
+        pretty: bool,
+
+        /// Write sequences of scalars in `env` and `properties` output as one entry joined by this separator
+        #[arg(long, env = "KONF_SEQUENCE_SEPARATOR")]
+        sequence_separator: Option<String>,
+
+        /// Fail on references left unresolved instead of keeping them as `${...}`
+        #[arg(long)]
+        strict: bool,
+
+        /// Let `${env.NAME}` read the variables starting with this prefix (repeatable; `KONF_*` and `OTEL_*` never)
+        #[arg(long = "env-prefix", env = "KONF_ENV_PREFIXES", value_delimiter = ',')]
+        env_prefixes: Vec<String>,
+
+        /// Active profile, used to evaluate conditional imports
+        #[arg(long, env = "KONF_PROFILE")]
+        profile: Option<String>,
+    },
+
+    /// Infer a JSON Schema from a rendered configuration file
+    Schema {
+        /// Folder containing configuration files
+        #[arg(long, short)]
+        folder: PathBuf,
+
+        /// File to describe (without extension, e.g., "app" for "app.yaml")
         #[arg(long, short = 'n')]
         file: String,
 
-        /// Output format (yaml, json, env, properties, toml, docker_env)
-        #[arg(long, short = 'o', default_value = "yaml")]
+        /// Active profile, used to evaluate conditional imports
+        #[arg(long, env = "KONF_PROFILE")]
+        profile: Option<String>,
+    },
+
+    /// Render every configuration file into a directory, one file per config
+    Export {
+        /// Folder containing configuration files
+        #[arg(long, short)]
+        folder: PathBuf,
+
+        /// Directory to write `<key>.<format>` files into
+        #[arg(long, short = 'd')]
+        output_dir: PathBuf,
+
+        /// Output format (yaml, json, env, properties, toml, docker-env, helm-values)
+        #[arg(long, short = 'o', default_value = DEFAULT_FORMAT, env = "KONF_DEFAULT_FORMAT")]
         format: String,
+
+        /// Indent JSON output
+        #[arg(long)]
+        pretty: bool,
+
+        /// Write sequences of scalars in `env` and `properties` output as one entry joined by this separator
+        #[arg(long, env = "KONF_SEQUENCE_SEPARATOR")]
+        sequence_separator: Option<String>,
+
+        /// Fail on references left unresolved instead of keeping them as `${...}`
+        #[arg(long)]
+        strict: bool,
+
+        /// Stop at the first config that fails to render instead of skipping it
+        #[arg(long)]
+        fail_fast: bool,
+
+        /// Let `${env.NAME}` read the variables starting with this prefix (repeatable; `KONF_*` and `OTEL_*` never)
+        #[arg(long = "env-prefix", env = "KONF_ENV_PREFIXES", value_delimiter = ',')]
+        env_prefixes: Vec<String>,
+
+        /// Active profile, used to evaluate conditional imports
+        #[arg(long, env = "KONF_PROFILE")]
+        profile: Option<String>,
+    },
+
+    /// Check that configuration files render, resolve every reference and match their `*.schema.json`
+    Validate {
+        /// Folder containing configuration files
+        #[arg(long, short)]
+        folder: PathBuf,
+
+        /// Only validate this file (default: every file in the folder)
+        #[arg(long, short = 'n')]
+        file: Option<String>,
+
+        /// Let `${env.NAME}` read the variables starting with this prefix (repeatable; `KONF_*` and `OTEL_*` never)
+        #[arg(long = "env-prefix", env = "KONF_ENV_PREFIXES", value_delimiter = ',')]
+        env_prefixes: Vec<String>,
+
+        /// Active profile, used to evaluate conditional imports
+        #[arg(long, env = "KONF_PROFILE")]
+        profile: Option<String>,
+    },
+
+    /// List configuration files that no other file imports
+    Orphans {
+        /// Folder containing configuration files
+        #[arg(long, short)]
+        folder: PathBuf,
+
+        /// Key prefix of configs served directly, not reported as orphans (repeatable)
+        #[arg(long = "entrypoint", short = 'e')]
+        entrypoints: Vec<String>,
+
+        /// Exit with an error if any orphan is found
+        #[arg(long)]
+        check: bool,
+
+        /// Active profile, used to evaluate conditional imports
+        #[arg(long, env = "KONF_PROFILE")]
+        profile: Option<String>,
+    },
+
+    /// Export the import graph in Graphviz DOT, import cycles in red
+    Graph {
+        /// Folder containing configuration files
+        #[arg(long, short)]
+        folder: PathBuf,
+
+        /// File to write the graph to (default: stdout)
+        #[arg(long)]
+        output: Option<PathBuf>,
+
+        /// Active profile, used to evaluate conditional imports
+        #[arg(long, env = "KONF_PROFILE")]
+        profile: Option<String>,
+    },
+
+    /// Format YAML configuration files in place, preserving comments and key order
+    Fmt {
+        /// Folder containing configuration files
+        #[arg(long, short)]
+        folder: PathBuf,
+
+        /// Only report files that need formatting; exit with an error if any do
+        #[arg(long)]
+        check: bool,
     },
 
     /// Start the Language Server Protocol (LSP) server
@@ -58,8 +223,45 @@ fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Render { folder, file, format } => {
-            run_render(folder, file, format)
+        Commands::Render {
+            folder,
+            file,
+            all,
+            output_dir,
+            fail_fast,
+            format,
+            pretty,
+            sequence_separator,
+            strict,
+            env_prefixes,
+            profile,
+        } => {
+            let writer = build_writer(pretty, SequenceEncoding::from_separator(sequence_separator));
+            let options = RenderOptions::new(strict, env_prefixes, profile);
+            match (file, output_dir) {
+                (None, Some(output_dir)) if all => run_export(folder, output_dir, format, writer, fail_fast, options),
+                (Some(file), None) => run_render(folder, file, format, writer, options),
+                _ => anyhow::bail!("pass either --file, or --all with --output-dir"),
+            }
+        }
+        Commands::Schema { folder, file, profile } => {
+            run_schema(folder, file, RenderOptions::new(false, vec![], profile))
+        }
+        Commands::Export { folder, output_dir, format, pretty, sequence_separator, fail_fast, strict, env_prefixes, profile } => {
+            let writer = build_writer(pretty, SequenceEncoding::from_separator(sequence_separator));
+            run_export(folder, output_dir, format, writer, fail_fast, RenderOptions::new(strict, env_prefixes, profile))
+        }
+        Commands::Validate { folder, file, env_prefixes, profile } => {
+            run_validate(folder, file, RenderOptions::new(false, env_prefixes, profile))
+        }
+        Commands::Orphans { folder, entrypoints, check, profile } => {
+            run_orphans(folder, entrypoints, check, profile)
+        }
+        Commands::Graph { folder, output, profile } => {
+            run_graph(folder, output, profile)
+        }
+        Commands::Fmt { folder, check } => {
+            run_fmt(folder, check)
         }
         Commands::Lsp => {
             run_lsp()
@@ -67,36 +269,98 @@ fn main() -> anyhow::Result<()> {
     }
 }
 
-fn run_render(folder: PathBuf, file: String, format: String) -> anyhow::Result<()> {
-    let multiloader = Arc::from(MultiLoader::new(vec![Box::new(YamlLoader {})]));
-    let multiwriter = MultiWriter::new(vec![
-        YamlWriter::new_boxed(),
-        JsonWriter::new_boxed(),
-        EnvVarWriter::new_boxed(),
-        PropertiesWriter::new_boxed(),
-        TomlWriter::new_boxed(),
-        DockerEnvVarWriter::new_boxed(),
-    ]);
+/// Loads the configs in `folder`, evaluating conditional imports for `profile`.
+fn load_dag(
+    rt: &tokio::runtime::Runtime,
+    folder: &Path,
+    profile: Option<String>,
+) -> anyhow::Result<Dag<BasicFsFileProvider>> {
+    let multiloader = Arc::from(MultiLoader::new(vec![
+        Box::new(YamlLoader {}),
+        Box::new(JsonLoader {}),
+        Box::new(EnvLoader::default()),
+    ]));
+
+    rt.block_on(Dag::with_context(
+        BasicFsFileProvider::new(folder.to_path_buf()),
+        multiloader,
+        ImportContext::new(profile),
+    ))
+    .map_err(|e| anyhow::anyhow!("Failed to load configs from {:?}: {}", folder, e))
+}
+
+/// How configs are rendered, from the `--strict`, `--env-prefix` and `--profile` options.
+struct RenderOptions {
+    strict: bool,
+    env: EnvAccess,
+    profile: Option<String>,
+}
 
+impl RenderOptions {
+    fn new(strict: bool, env_prefixes: Vec<String>, profile: Option<String>) -> Self {
+        Self {
+            strict,
+            env: EnvAccess::with_prefixes(env_prefixes),
+            profile,
+        }
+    }
+
+    /// Loads the configs in `folder`, rendered with these options.
+    fn load_dag(self, rt: &tokio::runtime::Runtime, folder: &Path) -> anyhow::Result<Dag<BasicFsFileProvider>> {
+        Ok(load_dag(rt, folder, self.profile)?
+            .with_unresolved_policy(unresolved_policy(self.strict))
+            .with_env_access(self.env))
+    }
+}
+
+/// Loads the configs in `folder` and renders `file`.
+fn render_file(folder: &Path, file: &str, options: RenderOptions) -> anyhow::Result<konf_provider::Value> {
     let rt = tokio::runtime::Runtime::new()?;
+    let dag = options.load_dag(&rt, folder)?;
+
+    rt.block_on(dag.get_rendered(file))
+        .map_err(|e| anyhow::anyhow!("Failed to render '{}': {}", file, e))
+}
 
-    let dag = rt
-        .block_on(Dag::new(
-            BasicFsFileProvider::new(folder.clone()),
-            multiloader,
-        ))
-        .map_err(|e| anyhow::anyhow!("Failed to load configs from {:?}: {}", folder, e))?;
+/// Strict mode fails renders leaving references unresolved; they are kept as `${...}` otherwise.
+fn unresolved_policy(strict: bool) -> UnresolvedPolicy {
+    if strict { UnresolvedPolicy::Error } else { UnresolvedPolicy::Leave }
+}
+
+/// Builds the writers of every format, indenting JSON if `pretty` and writing env and properties sequences as `sequences`.
+fn build_writer(pretty: bool, sequences: SequenceEncoding) -> MultiWriter {
+    MultiWriter::new(vec![
+        YamlWriter::new_boxed(),
+        Box::new(JsonWriter { pretty }),
+        Box::new(EnvVarWriter::with_sequence_encoding(sequences.clone())),
+        Box::new(PropertiesWriter::with_sequence_encoding(sequences)),
+        TomlWriter::new_boxed(),
+        DockerEnvVarWriter::new_boxed(),
+        HelmValuesWriter::new_boxed(),
+        IniWriter::new_boxed(),
+        XmlWriter::new_boxed(),
+        ConfigMapWriter::new_boxed(),
+        SecretWriter::new_boxed(),
+        ShellWriter::new_boxed(),
+    ])
+}
 
-    let rendered = rt
-        .block_on(dag.get_rendered(&file))
-        .map_err(|e| anyhow::anyhow!("Failed to render '{}': {}", file, e))?;
+fn run_render(
+    folder: PathBuf,
+    file: String,
+    format: String,
+    multiwriter: MultiWriter,
+    options: RenderOptions,
+) -> anyhow::Result<()> {
+    let rendered = render_file(&folder, &file, options)?;
 
     let output = multiwriter
-        .write(&format, &rendered)
+        .write_named(&format, &rendered, &resource_name(&file))
         .ok_or_else(|| {
             anyhow::anyhow!(
-                "Unknown format '{}'. Supported formats: yaml, json, env, properties, toml, docker_env",
-                format
+                "Unknown format '{}'. Supported formats: {}",
+                format,
+                multiwriter.formats().join(", ")
             )
         })?
         .map_err(|e| anyhow::anyhow!("Failed to serialize to {}: {}", format, e))?;
@@ -105,6 +369,141 @@ fn run_render(folder: PathBuf, file: String, format: String) -> anyhow::Result<(
     Ok(())
 }
 
+fn run_schema(folder: PathBuf, file: String, options: RenderOptions) -> anyhow::Result<()> {
+    let rendered = render_file(&folder, &file, options)?;
+    let schema = infer_schema(&rendered);
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
+
+fn run_export(
+    folder: PathBuf,
+    output_dir: PathBuf,
+    format: String,
+    multiwriter: MultiWriter,
+    fail_fast: bool,
+    options: RenderOptions,
+) -> anyhow::Result<()> {
+    let rt = tokio::runtime::Runtime::new()?;
+    let dag = options.load_dag(&rt, &folder)?;
+
+    let summary = rt.block_on(export_all(&dag, &multiwriter, &format, &output_dir, fail_fast))?;
+
+    for (key, reason) in &summary.failed {
+        eprintln!("skipped {}: {}", key, reason);
+    }
+    println!(
+        "exported {} config(s) to {}, skipped {}",
+        summary.written.len(),
+        output_dir.display(),
+        summary.failed.len()
+    );
+    Ok(())
+}
+
+fn run_validate(folder: PathBuf, file: Option<String>, options: RenderOptions) -> anyhow::Result<()> {
+    let rt = tokio::runtime::Runtime::new()?;
+    let dag = options.load_dag(&rt, &folder)?;
+
+    let keys = match file {
+        Some(file) => vec![file],
+        None => dag.keys(),
+    };
+
+    let report = rt.block_on(validate_all(&dag, &keys));
+    for key in &report.valid {
+        println!("ok {}", key);
+    }
+    for (key, problems) in &report.invalid {
+        println!("invalid {}", key);
+        for problem in problems {
+            println!("  {}", problem);
+        }
+    }
+
+    if !report.is_ok() {
+        anyhow::bail!("{} of {} config(s) failed validation", report.invalid.len(), keys.len());
+    }
+    Ok(())
+}
+
+fn run_orphans(
+    folder: PathBuf,
+    entrypoints: Vec<String>,
+    check: bool,
+    profile: Option<String>,
+) -> anyhow::Result<()> {
+    let rt = tokio::runtime::Runtime::new()?;
+    let dag = load_dag(&rt, &folder, profile)?;
+
+    let report = dag.find_orphans(&entrypoints);
+    for key in &report.entrypoints {
+        println!("entrypoint {}", key);
+    }
+    for key in &report.orphans {
+        println!("orphan {}", key);
+    }
+    println!(
+        "{} entrypoint(s), {} orphan(s)",
+        report.entrypoints.len(),
+        report.orphans.len()
+    );
+
+    if check && !report.orphans.is_empty() {
+        anyhow::bail!("{} orphan file(s) found", report.orphans.len());
+    }
+    Ok(())
+}
+
+fn run_graph(folder: PathBuf, output: Option<PathBuf>, profile: Option<String>) -> anyhow::Result<()> {
+    let rt = tokio::runtime::Runtime::new()?;
+    let dag = load_dag(&rt, &folder, profile)?;
+
+    let dot = dag.to_dot();
+    match output {
+        Some(path) => std::fs::write(&path, dot)
+            .map_err(|e| anyhow::anyhow!("Failed to write {:?}: {}", path, e))?,
+        None => print!("{}", dot),
+    }
+    Ok(())
+}
+
+fn run_fmt(folder: PathBuf, check: bool) -> anyhow::Result<()> {
+    let mut unformatted = Vec::new();
+
+    for entry in walkdir::WalkDir::new(&folder).sort_by_file_name() {
+        let entry = entry?;
+        let path = entry.path();
+        let is_yaml = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e == "yaml" || e == "yml");
+        if !entry.file_type().is_file() || !is_yaml {
+            continue;
+        }
+
+        let source = std::fs::read_to_string(path)?;
+        let formatted = format_yaml(&source)
+            .map_err(|e| anyhow::anyhow!("Failed to format {:?}: {}", path, e))?;
+        if formatted == source {
+            continue;
+        }
+
+        if check {
+            println!("{}", path.display());
+        } else {
+            std::fs::write(path, formatted)?;
+            println!("formatted {}", path.display());
+        }
+        unformatted.push(path.to_path_buf());
+    }
+
+    if check && !unformatted.is_empty() {
+        anyhow::bail!("{} file(s) need formatting", unformatted.len());
+    }
+    Ok(())
+}
+
 fn run_lsp() -> anyhow::Result<()> {
     let rt = tokio::runtime::Runtime::new()?;
     rt.block_on(konf_provider::lsp::run_lsp());
@@ -1,17 +1,34 @@
-use std::{collections::HashSet, path::PathBuf, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
+use anyhow::Context;
 use arc_swap::ArcSwap;
 use dashmap::DashMap;
 use metrics_exporter_prometheus::PrometheusHandle;
+use serde::Deserialize;
 
 use crate::{
     DagEntry,
+    authorizer::jwt::JwtValidator,
+    dag_cache::DagCacheLimits,
+    in_flight::InFlight,
     fs::{FileProvider, git::Creds},
+    imports::ImportContext,
     loader::MultiLoader,
+    peers::PeerNotifier,
+    response_cache::ResponseCache,
     render::Dag,
+    render_helper::{EnvAccess, UnresolvedPolicy},
+    utils::GetError,
     writer::MultiWriter,
 };
 
+/// A freshly built DAG entry, moved into the cache by the first request awaiting it.
+pub type PendingDagEntry<P> = Arc<Mutex<Option<DagEntry<P>>>>;
+
 #[derive(Debug)]
 pub struct RepoConfig {
     pub url: String,
@@ -21,11 +38,41 @@ pub struct RepoConfig {
 
 pub struct GitAppState<P: FileProvider> {
     pub dag: DashMap<String, DagEntry<P>>,
+    /// Capacity and TTL bounding `dag`.
+    pub dag_cache: DagCacheLimits,
+    /// DAGs being built, shared by concurrent requests for the same commit.
+    pub dag_builds: InFlight<Result<PendingDagEntry<P>, GetError>>,
     pub writer: Arc<MultiWriter>,
     pub commits: ArcSwap<HashSet<String>>,
+    /// Branch and tag names, mapped to the commit they pointed to at the last reload.
+    pub refs: ArcSwap<HashMap<String, String>>,
     pub multiloader: Arc<MultiLoader>,
     pub repo_config: RepoConfig,
     pub metrics: Arc<PrometheusHandle>,
+    /// Break-glass tokens authorized to access every config (empty by default).
+    pub admin_tokens: Vec<String>,
+    /// Validates bearer JWTs for files requiring `claims` (disabled if unset).
+    pub jwt: Option<Arc<JwtValidator>>,
+    /// Also require access to every restricted file a config imports, transitively.
+    pub inherit_auth: bool,
+    /// Peers notified after a successful reload.
+    pub peers: PeerNotifier,
+    /// Shared secret push webhooks are signed with (`POST /webhook` disabled if unset).
+    pub webhook_secret: Option<String>,
+    /// Accept config paths that differ from a loaded key only by case.
+    pub case_insensitive_paths: bool,
+    /// Add a `_meta` key (source, commit, render time) to rendered configs.
+    pub render_meta: bool,
+    /// Context (active profile) in which conditional imports are evaluated.
+    pub import_context: ImportContext,
+    /// Serialized responses, keyed by commit, path, format and token.
+    pub response_cache: ResponseCache,
+    /// Validate every render against its schema, not only `?validate=true` requests.
+    pub strict_schemas: bool,
+    /// What to do with references still unresolved in rendered configs.
+    pub unresolved: UnresolvedPolicy,
+    /// Environment variables `${env.NAME}` references may read.
+    pub env: EnvAccess,
 }
 
 #[derive(Debug, Clone)]
@@ -33,6 +80,87 @@ pub struct LocalAppState<P: FileProvider> {
     pub dag: Dag<P>,
     pub writer: Arc<MultiWriter>,
     pub multiloader: Arc<MultiLoader>,
+    /// Folder or archive the configs are read from.
     pub folder: PathBuf,
     pub metrics: Arc<PrometheusHandle>,
+    /// Accept config paths that differ from a loaded key only by case.
+    pub case_insensitive_paths: bool,
+    /// Peers notified after a successful reload.
+    pub peers: PeerNotifier,
+    /// Add a `_meta` key (source, render time) to rendered configs.
+    pub render_meta: bool,
+    /// Serialized responses, keyed by path and format.
+    pub response_cache: ResponseCache,
+    /// Validate every render against its schema, not only `?validate=true` requests.
+    pub strict_schemas: bool,
+    /// What to do with references still unresolved in rendered configs.
+    pub unresolved: UnresolvedPolicy,
+}
+
+/// Server options read from a `--config` file, in TOML or YAML.
+///
+/// Keys are the long flag names in snake_case (`repo_url`, `dag_cache_size`),
+/// plus `otlp_endpoint` for trace export. The server merges each key set in the
+/// file into its options, unless the flag or its environment variable is set.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerConfig {
+    // Git mode
+    pub repo_url: Option<String>,
+    pub branch: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub ssh_key: Option<PathBuf>,
+    pub ssh_passphrase: Option<String>,
+    pub ssh_agent: Option<bool>,
+    pub admin_tokens: Option<Vec<String>>,
+    pub jwt_secret: Option<String>,
+    pub jwt_public_key: Option<PathBuf>,
+    pub jwt_issuer: Option<String>,
+    pub inherit_auth: Option<bool>,
+    pub webhook_secret: Option<String>,
+    pub dag_cache_size: Option<usize>,
+    pub dag_cache_ttl: Option<u64>,
+    // Local and HTTP modes
+    pub folder: Option<PathBuf>,
+    pub archive: Option<String>,
+    pub watch: Option<bool>,
+    pub strict_startup: Option<bool>,
+    pub base_url: Option<String>,
+    pub manifest: Option<String>,
+    pub retry_attempts: Option<u32>,
+    pub retry_backoff_ms: Option<u64>,
+    pub retry_max_backoff_ms: Option<u64>,
+    // All modes
+    pub port: Option<u16>,
+    pub default_format: Option<String>,
+    pub max_concurrency: Option<usize>,
+    pub response_cache_size: Option<usize>,
+    pub sequence_separator: Option<String>,
+    pub case_insensitive_paths: Option<bool>,
+    pub peers: Option<Vec<String>>,
+    pub render_meta: Option<bool>,
+    pub profile: Option<String>,
+    pub strict_schemas: Option<bool>,
+    pub unresolved: Option<String>,
+    pub env_prefixes: Option<Vec<String>>,
+    pub otlp_endpoint: Option<String>,
+}
+
+impl ServerConfig {
+    /// Reads a config file, as YAML if its extension is `.yaml` or `.yml` and as TOML otherwise.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let yaml = matches!(path.extension().and_then(|ext| ext.to_str()), Some("yaml" | "yml"));
+        Self::parse(&content, yaml).with_context(|| format!("invalid config file {}", path.display()))
+    }
+
+    /// Parses the content of a config file, as YAML or TOML.
+    pub fn parse(content: &str, yaml: bool) -> anyhow::Result<Self> {
+        Ok(if yaml {
+            serde_yaml::from_str(content)?
+        } else {
+            toml::from_str(content)?
+        })
+    }
 }
@@ -0,0 +1,66 @@
+//! Eviction policy of the per-commit DAG cache (git mode).
+//!
+//! Every requested commit gets its DAG built and cached. Without limits the
+//! cache only grows, so a long-running server serving many commits keeps all
+//! of them in memory. With a capacity, the least recently served entries are
+//! evicted to make room for new ones; with a TTL, entries idle for longer are
+//! dropped. Eviction runs before a new entry is built.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use dashmap::DashMap;
+
+use crate::{DagEntry, fs::FileProvider, metrics};
+
+/// Bounds of the per-commit DAG cache. Both are unlimited by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DagCacheLimits {
+    /// Maximum number of cached commits.
+    pub capacity: Option<usize>,
+    /// How long an entry may go unserved before it is evicted.
+    pub ttl: Option<Duration>,
+}
+
+impl DagCacheLimits {
+    /// Evicts entries idle for longer than the TTL, then the least recently
+    /// served ones until `reserve` new entries fit. Returns the number evicted.
+    ///
+    /// Must not be called while holding a guard on `cache`.
+    pub fn evict<P: FileProvider>(&self, cache: &DashMap<String, DagEntry<P>>, reserve: usize) -> usize {
+        if self.capacity.is_none() && self.ttl.is_none() {
+            return 0;
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        let mut entries: Vec<(String, u64)> = cache
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.last_access_millis()))
+            .collect();
+        let mut evicted = 0;
+
+        if let Some(ttl) = self.ttl {
+            let ttl = ttl.as_millis() as u64;
+            entries.retain(|(commit, last_access)| {
+                let expired = now.saturating_sub(*last_access) > ttl;
+                if expired && cache.remove(commit).is_some() {
+                    metrics::record_git_cache_eviction("ttl");
+                    evicted += 1;
+                }
+                !expired
+            });
+        }
+
+        if let Some(capacity) = self.capacity {
+            let excess = (entries.len() + reserve).saturating_sub(capacity);
+            entries.sort_by_key(|(_, last_access)| *last_access);
+            for (commit, _) in entries.into_iter().take(excess) {
+                if cache.remove(&commit).is_some() {
+                    metrics::record_git_cache_eviction("capacity");
+                    evicted += 1;
+                }
+            }
+        }
+        evicted
+    }
+}
@@ -0,0 +1,105 @@
+//! Key-level comparison of two rendered configs.
+//!
+//! Mappings are walked recursively and every other value (scalars and
+//! sequences) is compared as a whole leaf, identified by its dotted path
+//! (`database.host`). Sequences are not diffed element by element, since an
+//! insertion would shift every following index.
+
+use std::collections::BTreeMap;
+
+use crate::{Mapping, Value};
+
+/// Leaves added, removed and changed between two values, keyed by dotted path.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ValueDiff {
+    /// Leaves only present in the new value.
+    pub added: BTreeMap<String, Value>,
+    /// Leaves only present in the old value.
+    pub removed: BTreeMap<String, Value>,
+    /// Leaves present in both with different values, as `(old, new)`.
+    pub changed: BTreeMap<String, (Value, Value)>,
+}
+
+impl ValueDiff {
+    /// Returns true if both values were equal.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Returns the diff as a `{added, removed, changed}` mapping, each changed
+    /// leaf holding its `from` and `to` values, ready to be serialized by any writer.
+    pub fn to_value(&self) -> Value {
+        let leaves = |entries: &BTreeMap<String, Value>| {
+            Value::Mapping(entries.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+        };
+        let changed = self
+            .changed
+            .iter()
+            .map(|(key, (from, to))| {
+                let mut change = Mapping::new();
+                change.insert("from".to_string(), from.clone());
+                change.insert("to".to_string(), to.clone());
+                (key.clone(), Value::Mapping(change))
+            })
+            .collect();
+
+        let mut map = Mapping::new();
+        map.insert("added".to_string(), leaves(&self.added));
+        map.insert("removed".to_string(), leaves(&self.removed));
+        map.insert("changed".to_string(), Value::Mapping(changed));
+        Value::Mapping(map)
+    }
+}
+
+/// Compares `from` with `to`, leaf by leaf.
+pub fn diff_values(from: &Value, to: &Value) -> ValueDiff {
+    let mut diff = ValueDiff::default();
+    walk("", from, to, &mut diff);
+    diff
+}
+
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+fn walk(prefix: &str, from: &Value, to: &Value, diff: &mut ValueDiff) {
+    match (from, to) {
+        (Value::Mapping(old), Value::Mapping(new)) => {
+            for (key, old_value) in old {
+                let path = join(prefix, key);
+                match new.get(key) {
+                    Some(new_value) => walk(&path, old_value, new_value, diff),
+                    None => leaves(&path, old_value, &mut diff.removed),
+                }
+            }
+            for (key, new_value) in new {
+                if !old.contains_key(key) {
+                    leaves(&join(prefix, key), new_value, &mut diff.added);
+                }
+            }
+        }
+        (old, new) if old != new => {
+            diff.changed.insert(prefix.to_string(), (old.clone(), new.clone()));
+        }
+        _ => {}
+    }
+}
+
+/// Records every leaf of `value` under `prefix`, so a removed or added
+/// mapping is reported key by key.
+fn leaves(prefix: &str, value: &Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Mapping(map) if !map.is_empty() => {
+            for (key, value) in map {
+                leaves(&join(prefix, key), value, out);
+            }
+        }
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! Bulk export of rendered configs to a directory of files.
+
+use std::path::{Path, PathBuf};
+
+use crate::{fs::FileProvider, render::Dag, writer::{MultiWriter, k8s::resource_name}};
+
+/// Outcome of an [`export_all`] run.
+#[derive(Debug, Default)]
+pub struct ExportSummary {
+    /// Files written, in key order.
+    pub written: Vec<PathBuf>,
+    /// Keys that were skipped, with the reason.
+    pub failed: Vec<(String, String)>,
+}
+
+/// Renders every key of `dag` with the `format` writer and writes it to
+/// `out_dir/<key>.<format>`, creating subdirectories for nested keys.
+///
+/// Keys that fail to render or serialize are recorded in the summary and
+/// skipped, unless `fail_fast` is set, in which case the first failure is returned.
+pub async fn export_all<P: FileProvider>(
+    dag: &Dag<P>,
+    writer: &MultiWriter,
+    format: &str,
+    out_dir: &Path,
+    fail_fast: bool,
+) -> anyhow::Result<ExportSummary> {
+    if !writer.supports(format) {
+        anyhow::bail!(
+            "Unknown format '{}'. Supported formats: {}",
+            format,
+            writer.formats().join(", ")
+        );
+    }
+
+    let mut summary = ExportSummary::default();
+    for key in dag.keys() {
+        let output = match dag.get_rendered(&key).await {
+            Ok(rendered) => writer
+                .write_named(format, &rendered, &resource_name(&key))
+                .expect("format support checked above")
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+
+        match output {
+            Ok(content) => {
+                let path = out_dir.join(format!("{key}.{format}"));
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                tokio::fs::write(&path, content).await?;
+                summary.written.push(path);
+            }
+            Err(reason) if fail_fast => anyhow::bail!("Failed to render '{}': {}", key, reason),
+            Err(reason) => summary.failed.push((key, reason)),
+        }
+    }
+    Ok(summary)
+}
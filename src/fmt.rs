@@ -0,0 +1,307 @@
+//! Comment-preserving YAML formatting.
+//!
+//! The render path parses YAML into [`crate::Value`], which drops comments and
+//! key order. Formatting must not destroy authored structure, so it uses a
+//! separate, line-based parse: each line keeps its original text and is only
+//! re-indented to its nesting level.
+//!
+//! Formatting normalizes:
+//! - indentation to two spaces per nesting level
+//! - `-   item` to `- item`
+//! - trailing whitespace, runs of blank lines, and the final newline
+//!
+//! Comments, key order, quoting and block scalar contents are kept as written.
+//! The formatted output is re-parsed and compared with the input, so a format
+//! that would change the document's meaning is rejected instead of written.
+
+use std::fmt;
+
+use thiserror::Error;
+
+/// Number of spaces per nesting level in formatted output.
+const INDENT: usize = 2;
+
+/// Errors that can occur while formatting a document.
+#[derive(Debug, Error)]
+pub enum FormatError {
+    /// The input is not valid YAML.
+    #[error("invalid YAML: {0}")]
+    InvalidYaml(String),
+
+    /// The formatted output would not parse to the same document.
+    #[error("formatting would change the document's meaning (unsupported layout near line {line})")]
+    SemanticsChanged { line: usize },
+}
+
+/// A single source line classified for formatting.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Line {
+    /// An empty (or whitespace-only) line.
+    Blank,
+    /// A line holding only a comment, without its indentation.
+    Comment { level: usize, text: String },
+    /// A line holding content (and possibly a trailing comment), without its indentation.
+    Content { level: usize, text: String },
+    /// A line inside a block scalar (`|` or `>`), kept verbatim relative to its header.
+    Verbatim { level: usize, extra: usize, text: String },
+    /// A document separator or directive (`---`, `...`, `%YAML`).
+    Marker(String),
+}
+
+/// A YAML document parsed line by line, preserving comments and order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub lines: Vec<Line>,
+}
+
+impl Document {
+    /// Parses `source` into lines, computing the nesting level of each.
+    pub fn parse(source: &str) -> Self {
+        let mut lines = Vec::new();
+        // Original columns of the open nesting levels, innermost last.
+        let mut columns: Vec<usize> = vec![0];
+        // Original indent and level of the current block scalar header, if any.
+        let mut block: Option<(usize, usize)> = None;
+        // Comment lines (index, original indent) awaiting the next content line.
+        let mut pending_comments: Vec<(usize, usize)> = Vec::new();
+
+        for raw in source.lines() {
+            let indent = raw.len() - raw.trim_start().len();
+            let text = raw.trim();
+
+            if let Some((header_indent, header_level)) = block {
+                if text.is_empty() {
+                    lines.push(Line::Blank);
+                    continue;
+                }
+                if indent > header_indent {
+                    // Trailing whitespace is significant inside block scalars
+                    lines.push(Line::Verbatim {
+                        level: header_level,
+                        extra: indent - header_indent,
+                        text: raw.trim_start().to_string(),
+                    });
+                    continue;
+                }
+                block = None;
+            }
+
+            if text.is_empty() {
+                lines.push(Line::Blank);
+                continue;
+            }
+
+            if indent == 0 && (text.starts_with("---") || text.starts_with("...") || text.starts_with('%')) {
+                columns = vec![0];
+                pending_comments.clear();
+                lines.push(Line::Marker(text.to_string()));
+                continue;
+            }
+
+            if text.starts_with('#') {
+                // Provisional level; a comment aligned with the next content line takes its level.
+                let level = columns.iter().filter(|&&c| c <= indent).count().max(1) - 1;
+                pending_comments.push((lines.len(), indent));
+                lines.push(Line::Comment {
+                    level,
+                    text: text.to_string(),
+                });
+                continue;
+            }
+
+            while columns.len() > 1 && columns.last().is_some_and(|&c| c > indent) {
+                columns.pop();
+            }
+            if columns.last().is_some_and(|&c| c < indent) {
+                columns.push(indent);
+            }
+            let level = columns.len() - 1;
+
+            for (index, comment_indent) in pending_comments.drain(..) {
+                if comment_indent == indent
+                    && let Line::Comment { level: comment_level, .. } = &mut lines[index]
+                {
+                    *comment_level = level;
+                }
+            }
+
+            // Collapse `-   item` to `- item`, and open a level for the item content.
+            let mut normalized = String::new();
+            let mut rest = text;
+            let mut column = indent;
+            while let Some(after) = rest.strip_prefix('-')
+                && after.starts_with(' ')
+            {
+                let content = after.trim_start();
+                column += rest.len() - content.len();
+                normalized.push_str("- ");
+                rest = content;
+                columns.push(column);
+            }
+            normalized.push_str(rest);
+
+            if is_block_scalar_header(&normalized) {
+                block = Some((indent, level));
+            }
+            lines.push(Line::Content {
+                level,
+                text: normalized,
+            });
+        }
+
+        Self { lines }
+    }
+}
+
+impl fmt::Display for Document {
+    /// Writes the document with normalized indentation and spacing.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut previous_blank = true;
+        let mut pending_blank = false;
+        for line in &self.lines {
+            if matches!(line, Line::Blank) {
+                pending_blank = !previous_blank;
+                continue;
+            }
+            if pending_blank {
+                writeln!(f)?;
+                pending_blank = false;
+            }
+            previous_blank = false;
+            match line {
+                Line::Blank => {}
+                Line::Comment { level, text } | Line::Content { level, text } => {
+                    writeln!(f, "{:width$}{text}", "", width = level * INDENT)?;
+                }
+                Line::Verbatim { level, extra, text } => {
+                    writeln!(f, "{:width$}{text}", "", width = level * INDENT + extra)?;
+                }
+                Line::Marker(text) => writeln!(f, "{text}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Formats a YAML document, preserving comments and key order.
+///
+/// Returns an error if the input is invalid or if the formatted output would
+/// not parse to the same document.
+pub fn format_yaml(source: &str) -> Result<String, FormatError> {
+    let original = parse_all(source).map_err(FormatError::InvalidYaml)?;
+    let formatted = Document::parse(source).to_string();
+
+    if parse_all(&formatted).ok().as_ref() != Some(&original) {
+        let line = source
+            .lines()
+            .zip(formatted.lines())
+            .position(|(a, b)| a.trim() != b.trim())
+            .map_or(1, |i| i + 1);
+        return Err(FormatError::SemanticsChanged { line });
+    }
+    Ok(formatted)
+}
+
+/// Parses every document in `source`, for comparing meaning before and after formatting.
+fn parse_all(source: &str) -> Result<Vec<serde_yaml::Value>, String> {
+    use serde::Deserialize;
+
+    serde_yaml::Deserializer::from_str(source)
+        .map(|document| serde_yaml::Value::deserialize(document).map_err(|e| e.to_string()))
+        .collect()
+}
+
+/// Returns true if `text` ends a line with a block scalar indicator (`|`, `>-`, `|+2`...).
+fn is_block_scalar_header(text: &str) -> bool {
+    let content = strip_comment(text).trim_end();
+    let indicator = content.trim_end_matches(|c: char| c.is_ascii_digit() || c == '+' || c == '-');
+    let Some(before) = indicator.strip_suffix(['|', '>']) else {
+        return false;
+    };
+    before.is_empty() || before.ends_with(' ')
+}
+
+/// Removes a trailing ` # comment`, ignoring `#` inside quoted strings.
+fn strip_comment(text: &str) -> &str {
+    let mut quote: Option<char> = None;
+    let mut previous = ' ';
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' && previous.is_whitespace() => return &text[..i],
+            None => {}
+        }
+        previous = c;
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comments_survive_round_trip() {
+        let source = "# Service settings\nservice:\n    # the public name\n    name: api  # inline\n    port: 8080\n";
+        let formatted = format_yaml(source).unwrap();
+
+        assert_eq!(
+            formatted,
+            "# Service settings\nservice:\n  # the public name\n  name: api  # inline\n  port: 8080\n"
+        );
+    }
+
+    #[test]
+    fn test_key_order_is_preserved() {
+        let source = "zeta: 1\nalpha: 2\nmiddle: 3\n";
+        assert_eq!(format_yaml(source).unwrap(), source);
+    }
+
+    #[test]
+    fn test_format_is_idempotent() {
+        let source = "a:\n    b:\n        - x\n        -   y: 1\n            z: 2\n\n\n\nc: 3   \n";
+        let once = format_yaml(source).unwrap();
+        let twice = format_yaml(&once).unwrap();
+
+        assert_eq!(once, "a:\n  b:\n    - x\n    - y: 1\n      z: 2\n\nc: 3\n");
+        assert_eq!(once, twice);
+    }
+
+    #[test]
+    fn test_block_scalars_are_kept_verbatim() {
+        let source = "script: |\n    echo one\n      # not a comment\n    echo two\nafter: 1\n";
+        let formatted = format_yaml(source).unwrap();
+
+        assert_eq!(
+            formatted,
+            "script: |\n    echo one\n      # not a comment\n    echo two\nafter: 1\n"
+        );
+    }
+
+    #[test]
+    fn test_metadata_section_is_preserved() {
+        let source = "<!>:\n    import:\n        common/database: db  # alias\nurl: ${db.host}\n";
+        let formatted = format_yaml(source).unwrap();
+
+        assert_eq!(
+            formatted,
+            "<!>:\n  import:\n    common/database: db  # alias\nurl: ${db.host}\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid_yaml_is_rejected() {
+        assert!(matches!(
+            format_yaml("key: [unclosed"),
+            Err(FormatError::InvalidYaml(_))
+        ));
+    }
+
+    #[test]
+    fn test_strip_comment_ignores_quoted_hash() {
+        assert_eq!(strip_comment("key: \"a # b\" # c"), "key: \"a # b\" ");
+        assert_eq!(strip_comment("url: http://x/#anchor"), "url: http://x/#anchor");
+    }
+}
@@ -0,0 +1,171 @@
+//! Configs read from a `.tar`, `.tar.gz` or `.zip` archive.
+//!
+//! The archive is read once, when the provider is created, and its files are
+//! kept in memory: an archive is an immutable deployment artifact, so a reload
+//! serves the same contents.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+
+use crate::fs::{
+    DirEntry, FileProvider,
+    retry::{RetryPolicy, fetch_bytes},
+};
+use crate::utils::normalize_path;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// File provider serving the files of an archive, keyed by their path inside it.
+#[derive(Clone, Debug, Default)]
+pub struct ArchiveFileProvider {
+    files: HashMap<String, String>,
+}
+
+impl ArchiveFileProvider {
+    /// Reads an archive from a local path or an `http(s)://` URL.
+    ///
+    /// Downloads retry transient failures according to `retry`.
+    pub async fn open(source: &str, retry: &RetryPolicy) -> Result<Self> {
+        let bytes = if source.starts_with("http://") || source.starts_with("https://") {
+            fetch_bytes(&reqwest::Client::new(), source, retry)
+                .await
+                .with_context(|| format!("failed to download archive '{source}'"))?
+        } else {
+            tokio::fs::read(source)
+                .await
+                .with_context(|| format!("failed to read archive '{source}'"))?
+        };
+        Self::from_bytes(&bytes)
+    }
+
+    /// Reads an archive held in memory.
+    ///
+    /// The format is detected from the content: zip, gzip-compressed tar, or
+    /// plain tar. Entries that are not valid UTF-8 are skipped.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut files = HashMap::new();
+        if bytes.starts_with(ZIP_MAGIC) {
+            read_zip(bytes, &mut files)?;
+        } else if bytes.starts_with(GZIP_MAGIC) {
+            read_tar(GzDecoder::new(bytes), &mut files)?;
+        } else {
+            read_tar(bytes, &mut files)?;
+        }
+        Ok(Self { files })
+    }
+}
+
+fn read_tar(reader: impl Read, files: &mut HashMap<String, String>) -> Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries().context("invalid tar archive")? {
+        let mut entry = entry.context("invalid tar entry")?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path().context("invalid tar entry path")?.to_string_lossy().into_owned();
+        insert_entry(files, &path, &mut entry);
+    }
+    Ok(())
+}
+
+fn read_zip(bytes: &[u8], files: &mut HashMap<String, String>) -> Result<()> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).context("invalid zip archive")?;
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).context("invalid zip entry")?;
+        if !entry.is_file() {
+            continue;
+        }
+        let path = entry.name().to_string();
+        insert_entry(files, &path, &mut entry);
+    }
+    Ok(())
+}
+
+/// Stores an entry under its normalized path (`./common/db.yaml` becomes `common/db.yaml`).
+fn insert_entry(files: &mut HashMap<String, String>, path: &str, reader: &mut impl Read) {
+    let path = normalize_path(path.trim_start_matches("./"));
+    let mut content = String::new();
+    match reader.read_to_string(&mut content) {
+        Ok(_) => {
+            files.insert(path, content);
+        }
+        Err(e) => tracing::warn!("skipping archive entry '{path}': {e}"),
+    }
+}
+
+impl FileProvider for ArchiveFileProvider {
+    async fn load(&self, path: &str) -> Option<String> {
+        self.files.get(path).cloned()
+    }
+
+    async fn list(&self) -> Vec<DirEntry> {
+        self.files
+            .keys()
+            .filter_map(|path| DirEntry::from_relative_path(std::path::Path::new(path), path))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    const FILES: &[(&str, &str)] = &[
+        ("./common/database.yaml", "host: localhost"),
+        ("./app.yaml", "port: 8080"),
+    ];
+
+    fn tar_bytes() -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, content) in FILES {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, content.as_bytes()).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    async fn keys(provider: &ArchiveFileProvider) -> Vec<String> {
+        let mut keys: Vec<String> = provider.list().await.into_iter().map(|e| e.filename).collect();
+        keys.sort();
+        keys
+    }
+
+    #[tokio::test]
+    async fn test_reads_plain_and_gzipped_tar() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&tar_bytes()).unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        for bytes in [tar_bytes(), gzipped] {
+            let provider = ArchiveFileProvider::from_bytes(&bytes).unwrap();
+            assert_eq!(keys(&provider).await, vec!["app", "common/database"]);
+            assert_eq!(provider.load("common/database.yaml").await.as_deref(), Some("host: localhost"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reads_zip() {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer.add_directory("common/", zip::write::SimpleFileOptions::default()).unwrap();
+        for (path, content) in FILES {
+            writer
+                .start_file(path.trim_start_matches("./"), zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let provider = ArchiveFileProvider::from_bytes(&bytes).unwrap();
+        assert_eq!(keys(&provider).await, vec!["app", "common/database"]);
+        assert_eq!(provider.load("app.yaml").await.as_deref(), Some("port: 8080"));
+    }
+}
@@ -1,12 +1,13 @@
 use anyhow::{Result, anyhow};
 use git2::build::RepoBuilder;
-use git2::{Cred, Error, FetchOptions, RemoteCallbacks};
+use git2::{Cred, CredentialType, Error, FetchOptions, RemoteCallbacks};
 use git2::{Oid, Repository};
 use sha2::{Digest, Sha256};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 use regex::Regex;
+use serde::Serialize;
 
 use crate::fs::{DirEntry, FileProvider};
 
@@ -158,6 +159,57 @@ impl FileProvider for GitFileProvider {
     }
 }
 
+/// Config keys that differ between two commits.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ChangedKeys {
+    /// Keys present only in the newer commit, sorted.
+    pub added: Vec<String>,
+    /// Keys present only in the older commit, sorted.
+    pub removed: Vec<String>,
+    /// Keys whose file content differs, sorted.
+    pub modified: Vec<String>,
+}
+
+/// Lists the config keys that changed between commits `from` and `to`.
+///
+/// Trees are compared by blob id, so nothing is loaded or rendered. A renamed
+/// file is reported as removed under its old key and added under its new one.
+pub fn changed_keys(repo_path: &Path, from: &str, to: &str) -> Result<ChangedKeys> {
+    let repo = Repository::open(repo_path)?;
+    let tree = |hash: &str| -> Result<git2::Tree<'_>> {
+        let commit = repo
+            .find_commit(Oid::from_str(hash)?)
+            .map_err(|e| anyhow!("Commit '{}' not found: {}", hash, e))?;
+        Ok(commit.tree()?)
+    };
+    let (old, new) = (tree(from)?, tree(to)?);
+    let diff = repo.diff_tree_to_tree(Some(&old), Some(&new), None)?;
+
+    let key = |file: git2::DiffFile<'_>| {
+        let path = file.path()?;
+        DirEntry::from_relative_path(path, &path.to_string_lossy()).map(|entry| entry.filename)
+    };
+    let mut changes = ChangedKeys::default();
+    for delta in diff.deltas() {
+        match delta.status() {
+            git2::Delta::Added | git2::Delta::Copied => changes.added.extend(key(delta.new_file())),
+            git2::Delta::Deleted => changes.removed.extend(key(delta.old_file())),
+            git2::Delta::Renamed => {
+                changes.removed.extend(key(delta.old_file()));
+                changes.added.extend(key(delta.new_file()));
+            }
+            git2::Delta::Modified | git2::Delta::Typechange => {
+                changes.modified.extend(key(delta.new_file()))
+            }
+            _ => {}
+        }
+    }
+    changes.added.sort();
+    changes.removed.sort();
+    changes.modified.sort();
+    Ok(changes)
+}
+
 /// Walks the Git history and collects all reachable commit hashes.
 pub fn list_all_commit_hashes(repo_url: &str) -> Result<HashSet<String>, Error> {
     let path = get_git_directory(repo_url);
@@ -170,15 +222,70 @@ pub fn list_all_commit_hashes(repo_url: &str) -> Result<HashSet<String>, Error>
         .collect::<Result<HashSet<String>, Error>>()
 }
 
+/// Maps the repository's branch and tag names to the commit they point to.
+///
+/// Remote-tracking branches (`origin/main`, updated by fetches) are listed
+/// under their short name and take precedence over local branches; tags take
+/// precedence over branches, as in `git rev-parse`. Refs not pointing to a
+/// commit are skipped.
+pub fn list_refs(repo_url: &str) -> Result<HashMap<String, String>, Error> {
+    let path = get_git_directory(repo_url);
+    let repo = Repository::open(&path)?;
+    let mut refs = HashMap::new();
+    for prefix in ["refs/heads/", "refs/remotes/origin/", "refs/tags/"] {
+        for reference in repo.references_glob(&format!("{prefix}*"))? {
+            let reference = reference?;
+            let Some(name) = reference.name().and_then(|name| name.strip_prefix(prefix)) else {
+                continue;
+            };
+            if name == "HEAD" {
+                continue;
+            }
+            if let Ok(commit) = reference.peel_to_commit() {
+                refs.insert(name.to_string(), commit.id().to_string());
+            }
+        }
+    }
+    Ok(refs)
+}
+
+/// Credentials used to clone and fetch the repository.
 #[derive(Debug, Clone)]
-pub struct Creds {
-    username: String,
-    password: String,
+pub enum Creds {
+    /// Username and password (or token), for http(s) URLs.
+    UserPass { username: String, password: String },
+    /// Private key file, for ssh URLs (`git@host:path`, `ssh://`).
+    SshKey {
+        private_key: PathBuf,
+        passphrase: Option<String>,
+    },
+    /// Keys of the running ssh-agent, for ssh URLs.
+    SshAgent,
 }
 
 impl Creds {
+    /// Username and password credentials.
     pub fn new(username: String, password: String) -> Self {
-        Self { username, password }
+        Self::UserPass { username, password }
+    }
+}
+
+/// Number of times libgit2 may ask for credentials before the fetch fails.
+///
+/// libgit2 asks again whenever the server rejects them, which would loop
+/// forever with credentials that never change.
+const MAX_AUTH_ATTEMPTS: usize = 3;
+
+/// Builds the credential libgit2 asks for. `username_from_url` is the user
+/// of an ssh URL (`git` in `git@github.com:org/repo`).
+fn credential(creds: &Creds, username_from_url: Option<&str>, allowed: CredentialType) -> Result<Cred, Error> {
+    let user = username_from_url.unwrap_or("git");
+    match creds {
+        Creds::UserPass { username, password } => Cred::userpass_plaintext(username, password),
+        // Over ssh, libgit2 first asks for the username alone when the URL has none
+        Creds::SshKey { .. } | Creds::SshAgent if allowed.contains(CredentialType::USERNAME) => Cred::username(user),
+        Creds::SshKey { private_key, passphrase } => Cred::ssh_key(user, None, private_key, passphrase.as_deref()),
+        Creds::SshAgent => Cred::ssh_key_from_agent(user),
     }
 }
 
@@ -186,9 +293,14 @@ fn create_auth_options(creds: Creds) -> FetchOptions<'static> {
     let mut callbacks = RemoteCallbacks::new();
 
     // The 'move' closure takes ownership of the credentials (`creds`).
-    // This ensures the username and password live as long as the callback does.
-    callbacks.credentials(move |_url, _username_from_git, _allowed_types| {
-        Cred::userpass_plaintext(&creds.username, &creds.password)
+    // This ensures they live as long as the callback does.
+    let mut attempts = 0;
+    callbacks.credentials(move |_url, username_from_url, allowed_types| {
+        attempts += 1;
+        if attempts > MAX_AUTH_ATTEMPTS {
+            return Err(Error::from_str("authentication failed: credentials were rejected"));
+        }
+        credential(&creds, username_from_url, allowed_types)
     });
 
     let mut fetch_options = FetchOptions::new();
@@ -251,3 +363,30 @@ pub async fn clone_or_update(
 
     Ok(rep)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credtype(creds: &Creds, allowed: CredentialType) -> u32 {
+        credential(creds, Some("git"), allowed).unwrap().credtype()
+    }
+
+    #[test]
+    fn test_credential_matches_creds_kind() {
+        let userpass = Creds::new("user".to_string(), "token".to_string());
+        let key = Creds::SshKey {
+            private_key: PathBuf::from("/keys/deploy_key"),
+            passphrase: Some("secret".to_string()),
+        };
+
+        assert_eq!(
+            credtype(&userpass, CredentialType::USER_PASS_PLAINTEXT),
+            CredentialType::USER_PASS_PLAINTEXT.bits()
+        );
+        assert_eq!(credtype(&key, CredentialType::SSH_KEY), CredentialType::SSH_KEY.bits());
+        assert_eq!(credtype(&Creds::SshAgent, CredentialType::SSH_KEY), CredentialType::SSH_KEY.bits());
+        // An ssh URL without a user first asks for the username
+        assert_eq!(credtype(&key, CredentialType::USERNAME), CredentialType::USERNAME.bits());
+    }
+}
@@ -0,0 +1,164 @@
+//! Configs served over HTTP(S), e.g. by another service or a static bucket website.
+//!
+//! The base URL hosts a manifest, a JSON array of file paths relative to it
+//! (`["app.yaml", "common/database.yaml"]`), and each file at its path. The
+//! manifest is fetched on every listing, so a reload picks up added files.
+
+use crate::fs::{
+    DirEntry, FileProvider,
+    retry::{RetryPolicy, fetch_text},
+};
+use crate::utils::normalize_path;
+
+/// Manifest file name used when none is given.
+pub const DEFAULT_MANIFEST: &str = "index.json";
+
+/// File provider fetching files listed in a remote manifest.
+#[derive(Clone, Debug)]
+pub struct HttpFileProvider {
+    client: reqwest::Client,
+    base_url: String,
+    manifest: String,
+    retry: RetryPolicy,
+}
+
+impl HttpFileProvider {
+    /// Serves the files listed in `<base_url>/index.json`.
+    pub fn new(base_url: &str) -> Self {
+        Self::with_client(reqwest::Client::new(), base_url)
+    }
+
+    /// Same as [`HttpFileProvider::new`], sending requests with `client`.
+    pub fn with_client(client: reqwest::Client, base_url: &str) -> Self {
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            manifest: DEFAULT_MANIFEST.to_string(),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Reads the file list from `manifest` (relative to the base URL) instead of `index.json`.
+    pub fn with_manifest(mut self, manifest: &str) -> Self {
+        self.manifest = manifest.trim_start_matches('/').to_string();
+        self
+    }
+
+    /// Retries transient failures according to `retry`.
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{path}", self.base_url)
+    }
+}
+
+impl FileProvider for HttpFileProvider {
+    async fn load(&self, path: &str) -> Option<String> {
+        fetch_text(&self.client, &self.url(path), &self.retry).await
+    }
+
+    async fn list(&self) -> Vec<DirEntry> {
+        let url = self.url(&self.manifest);
+        let Some(body) = fetch_text(&self.client, &url, &self.retry).await else {
+            tracing::error!("failed to fetch manifest '{url}'");
+            return Vec::new();
+        };
+        let paths: Vec<String> = match serde_json::from_str(&body) {
+            Ok(paths) => paths,
+            Err(e) => {
+                tracing::error!("invalid manifest '{url}', expected a JSON array of paths: {e}");
+                return Vec::new();
+            }
+        };
+        paths
+            .iter()
+            .map(|path| normalize_path(path.trim_start_matches("./")))
+            .filter_map(|path| DirEntry::from_relative_path(std::path::Path::new(&path), &path))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Serves `files` by path, and 404 for any other path.
+    async fn mock_server(files: &'static [(&'static str, &'static str)]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/configs", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut buf = [0u8; 1024];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+                let response = match files.iter().find(|(file, _)| *file == path) {
+                    Some((_, body)) => format!(
+                        "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                        body.len()
+                    ),
+                    None => "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n".to_string(),
+                };
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        url
+    }
+
+    fn provider(url: &str) -> HttpFileProvider {
+        HttpFileProvider::new(url).with_retry_policy(RetryPolicy {
+            max_attempts: 1,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_lists_manifest_and_loads_files() {
+        let url = mock_server(&[
+            ("/configs/index.json", r#"["app.yaml", "./common/database.yaml"]"#),
+            ("/configs/common/database.yaml", "host: localhost"),
+        ])
+        .await;
+        let provider = provider(&url);
+
+        let mut entries = provider.list().await;
+        entries.sort_by(|a, b| a.filename.cmp(&b.filename));
+        let keys: Vec<&str> = entries.iter().map(|e| e.filename.as_str()).collect();
+        assert_eq!(keys, vec!["app", "common/database"]);
+        assert_eq!(entries[1].ext, "yaml");
+
+        let content = provider.load(&entries[1].full_path).await;
+        assert_eq!(content.as_deref(), Some("host: localhost"));
+        assert_eq!(provider.load("app.yaml").await, None, "app.yaml is listed but not served");
+    }
+
+    #[tokio::test]
+    async fn test_custom_manifest() {
+        let url = mock_server(&[("/configs/files.json", r#"["a.json"]"#)]).await;
+
+        let entries = provider(&format!("{url}/")).with_manifest("files.json").list().await;
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].filename, "a");
+    }
+
+    #[tokio::test]
+    async fn test_missing_or_invalid_manifest_lists_nothing() {
+        let url = mock_server(&[("/configs/index.json", r#"{"files": []}"#)]).await;
+
+        assert!(provider(&url).list().await.is_empty());
+        assert!(provider(&url).with_manifest("missing.json").list().await.is_empty());
+    }
+}
@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+
+use crate::fs::{DirEntry, FileProvider};
+
+/// In-memory file provider, mainly useful for tests and embedding.
+///
+/// Files are keyed by their relative path including the extension,
+/// e.g. `common/base.yaml`.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryFileProvider {
+    files: HashMap<String, String>,
+}
+
+impl MemoryFileProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds (or replaces) a file with the given relative path and content.
+    pub fn with_file(mut self, path: &str, content: &str) -> Self {
+        self.files.insert(path.to_string(), content.to_string());
+        self
+    }
+}
+
+impl FileProvider for MemoryFileProvider {
+    async fn load(&self, path: &str) -> Option<String> {
+        self.files.get(path).cloned()
+    }
+
+    async fn list(&self) -> Vec<DirEntry> {
+        self.files
+            .keys()
+            .filter_map(|path| DirEntry::from_relative_path(std::path::Path::new(path), path))
+            .collect()
+    }
+}
@@ -1,5 +1,11 @@
 pub mod local;
 pub mod git;
+pub mod memory;
+pub mod archive;
+pub mod http;
+pub mod overlay;
+pub mod source;
+pub mod retry;
 
 /// Represents a file entry with metadata for configuration loading.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -0,0 +1,105 @@
+//! Layered file providers, e.g. shared defaults overridden by an environment folder.
+//!
+//! Layers are ordered from lowest to highest priority: a file of a later layer
+//! shadows the file with the same key (path without extension) of an earlier
+//! one, whatever their extensions. Shadowing is per file; to override single
+//! keys, have the environment file extend the base one (`<!>.extends`).
+
+use std::collections::HashMap;
+
+use futures::future::join_all;
+
+use crate::fs::{DirEntry, FileProvider};
+
+/// File provider serving the union of its layers, later layers shadowing earlier ones.
+#[derive(Clone, Debug, Default)]
+pub struct OverlayFileProvider<P> {
+    layers: Vec<P>,
+}
+
+impl<P: FileProvider> OverlayFileProvider<P> {
+    /// Stacks `layers`, the last one taking precedence.
+    pub fn new(layers: Vec<P>) -> Self {
+        Self { layers }
+    }
+
+    /// Adds a layer on top of the existing ones.
+    pub fn with_layer(mut self, layer: P) -> Self {
+        self.layers.push(layer);
+        self
+    }
+}
+
+impl<P: FileProvider + Sync> FileProvider for OverlayFileProvider<P> {
+    async fn load(&self, path: &str) -> Option<String> {
+        for layer in self.layers.iter().rev() {
+            if let Some(content) = layer.load(path).await {
+                return Some(content);
+            }
+        }
+        None
+    }
+
+    async fn list(&self) -> Vec<DirEntry> {
+        let listings = join_all(self.layers.iter().map(|layer| layer.list())).await;
+        let mut entries: HashMap<String, DirEntry> = HashMap::new();
+        for entry in listings.into_iter().flatten() {
+            entries.insert(entry.filename.clone(), entry);
+        }
+        entries.into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::memory::MemoryFileProvider;
+
+    fn overlay() -> OverlayFileProvider<MemoryFileProvider> {
+        let defaults = MemoryFileProvider::new()
+            .with_file("app.yaml", "level: info")
+            .with_file("common/database.yaml", "host: localhost")
+            .with_file("feature.yaml", "enabled: false");
+        let prod = MemoryFileProvider::new()
+            .with_file("app.yaml", "level: warn")
+            .with_file("feature.json", r#"{"enabled": true}"#)
+            .with_file("prod_only.yaml", "region: eu");
+        OverlayFileProvider::new(vec![defaults, prod])
+    }
+
+    #[tokio::test]
+    async fn test_later_layers_shadow_earlier_ones() {
+        let provider = overlay();
+
+        assert_eq!(provider.load("app.yaml").await.as_deref(), Some("level: warn"));
+        assert_eq!(provider.load("common/database.yaml").await.as_deref(), Some("host: localhost"));
+        assert_eq!(provider.load("missing.yaml").await, None);
+
+        // Swapping the layers swaps the winner
+        let mut layers = provider.layers;
+        layers.reverse();
+        let provider = OverlayFileProvider::new(layers);
+        assert_eq!(provider.load("app.yaml").await.as_deref(), Some("level: info"));
+    }
+
+    #[tokio::test]
+    async fn test_list_is_union_shadowed_by_key() {
+        let mut entries = overlay().list().await;
+        entries.sort_by(|a, b| a.filename.cmp(&b.filename));
+
+        let listed: Vec<(&str, &str)> = entries
+            .iter()
+            .map(|entry| (entry.filename.as_str(), entry.ext.as_str()))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                ("app", "yaml"),
+                ("common/database", "yaml"),
+                ("feature", "json"),
+                ("prod_only", "yaml"),
+            ],
+            "feature.json of the prod layer shadows feature.yaml"
+        );
+    }
+}
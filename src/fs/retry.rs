@@ -0,0 +1,210 @@
+//! Retry with exponential backoff for network-backed file providers.
+//!
+//! A network blip must not make `load` return `None`, which would silently drop
+//! a config from the DAG. Failures are classified as transient (5xx, 429,
+//! timeouts, connection errors), which are retried, or permanent (404 and
+//! other client errors), which give up immediately.
+
+use std::future::Future;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+
+/// How many times, and how long to wait between, attempts of a fallible operation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled after each failed attempt.
+    pub initial_backoff: Duration,
+    /// Upper bound for the delay between attempts.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Result of a single attempt.
+#[derive(Debug)]
+pub enum Outcome<T> {
+    /// The operation succeeded.
+    Success(T),
+    /// The operation failed but may succeed if retried.
+    Transient(String),
+    /// The operation failed and retrying will not help.
+    Permanent(String),
+}
+
+impl RetryPolicy {
+    /// Returns the delay to wait after the given (zero-based) failed attempt.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+
+    /// Runs `op` until it succeeds, fails permanently, or attempts run out.
+    ///
+    /// Returns `None` if no attempt succeeded; failures are logged.
+    pub async fn run<T, F, Fut>(&self, what: &str, mut op: F) -> Option<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Outcome<T>>,
+    {
+        let attempts = self.max_attempts.max(1);
+        for attempt in 0..attempts {
+            match op().await {
+                Outcome::Success(value) => return Some(value),
+                Outcome::Permanent(reason) => {
+                    tracing::debug!(what, reason, "permanent failure, not retrying");
+                    return None;
+                }
+                Outcome::Transient(reason) if attempt + 1 < attempts => {
+                    let delay = self.backoff(attempt);
+                    tracing::warn!(what, reason, attempt = attempt + 1, ?delay, "transient failure, retrying");
+                    tokio::time::sleep(delay).await;
+                }
+                Outcome::Transient(reason) => {
+                    tracing::error!(what, reason, attempts, "giving up after transient failures");
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Fetches `url` as text, retrying transient failures according to `policy`.
+///
+/// Returns `None` on a permanent failure (e.g. 404) or once attempts run out.
+pub async fn fetch_text(client: &reqwest::Client, url: &str, policy: &RetryPolicy) -> Option<String> {
+    fetch(client, url, policy, |response| response.text()).await
+}
+
+/// Same as [`fetch_text`], for a binary body.
+pub async fn fetch_bytes(client: &reqwest::Client, url: &str, policy: &RetryPolicy) -> Option<Vec<u8>> {
+    fetch(client, url, policy, |response| async move { response.bytes().await.map(|b| b.to_vec()) }).await
+}
+
+/// Fetches `url`, reading a successful response's body with `read`.
+async fn fetch<T, F, Fut>(client: &reqwest::Client, url: &str, policy: &RetryPolicy, read: F) -> Option<T>
+where
+    F: Fn(reqwest::Response) -> Fut,
+    Fut: Future<Output = reqwest::Result<T>>,
+{
+    policy
+        .run(url, || async {
+            let response = match client.get(url).send().await {
+                Ok(response) => response,
+                Err(e) if e.is_timeout() || e.is_connect() || e.is_request() => {
+                    return Outcome::Transient(e.to_string());
+                }
+                Err(e) => return Outcome::Permanent(e.to_string()),
+            };
+            let status = response.status();
+            if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+                return Outcome::Transient(format!("status {status}"));
+            }
+            if !status.is_success() {
+                return Outcome::Permanent(format!("status {status}"));
+            }
+            match read(response).await {
+                Ok(body) => Outcome::Success(body),
+                Err(e) => Outcome::Transient(e.to_string()),
+            }
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    fn fast_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+        }
+    }
+
+    /// Serves `statuses` in order (repeating the last one), counting requests.
+    async fn mock_server(statuses: Vec<u16>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/config.yaml", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let hit = counter.fetch_add(1, Ordering::SeqCst);
+                let status = statuses[hit.min(statuses.len() - 1)];
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf).await;
+                let body = "key: value";
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        (url, hits)
+    }
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(300),
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(2), Duration::from_millis(300));
+    }
+
+    #[tokio::test]
+    async fn test_transient_failure_is_retried() {
+        let (url, hits) = mock_server(vec![503, 200]).await;
+
+        let body = fetch_text(&reqwest::Client::new(), &url, &fast_policy(3)).await;
+
+        assert_eq!(body.as_deref(), Some("key: value"));
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_not_found_is_not_retried() {
+        let (url, hits) = mock_server(vec![404]).await;
+
+        let body = fetch_text(&reqwest::Client::new(), &url, &fast_policy(3)).await;
+
+        assert_eq!(body, None);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let (url, hits) = mock_server(vec![500]).await;
+
+        let body = fetch_text(&reqwest::Client::new(), &url, &fast_policy(3)).await;
+
+        assert_eq!(body, None);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,32 @@
+use crate::fs::{
+    DirEntry, FileProvider, archive::ArchiveFileProvider, http::HttpFileProvider, local::BasicFsFileProvider,
+};
+
+/// Where the local server reads its configs from: a folder, an archive or a remote index.
+#[derive(Clone, Debug)]
+pub enum SourceFileProvider {
+    /// Files of a folder, read on each reload (`--folder`).
+    Folder(BasicFsFileProvider),
+    /// Files of an archive, read once at startup (`--archive`).
+    Archive(ArchiveFileProvider),
+    /// Files listed in a remote manifest, fetched on each reload (`http` subcommand).
+    Http(HttpFileProvider),
+}
+
+impl FileProvider for SourceFileProvider {
+    async fn load(&self, path: &str) -> Option<String> {
+        match self {
+            SourceFileProvider::Folder(provider) => provider.load(path).await,
+            SourceFileProvider::Archive(provider) => provider.load(path).await,
+            SourceFileProvider::Http(provider) => provider.load(path).await,
+        }
+    }
+
+    async fn list(&self) -> Vec<DirEntry> {
+        match self {
+            SourceFileProvider::Folder(provider) => provider.list().await,
+            SourceFileProvider::Archive(provider) => provider.list().await,
+            SourceFileProvider::Http(provider) => provider.list().await,
+        }
+    }
+}
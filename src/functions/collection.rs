@@ -0,0 +1,191 @@
+//! Sequence functions for shaping list values.
+
+use std::cmp::Ordering;
+
+use crate::Value;
+
+use super::{value_type_name, FunctionArg, FunctionError, FunctionSignature, TemplateFunction};
+
+/// Signature shared by the sequence functions: no arguments, sequence in and out.
+const SEQUENCE_SIGNATURE: FunctionSignature = FunctionSignature {
+    args: 0..=0,
+    accepts: &["sequence"],
+    returns: Some("sequence"),
+};
+
+/// Returns the items of a sequence, or an error naming `function` for any other type.
+fn sequence_items(function: &str, value: Value) -> Result<Vec<Value>, FunctionError> {
+    match value {
+        Value::Sequence(items) => Ok(items),
+        other => Err(FunctionError::UnsupportedType {
+            function: function.to_string(),
+            got: value_type_name(&other),
+        }),
+    }
+}
+
+/// Reverses the order of a sequence.
+pub struct Reverse;
+
+impl TemplateFunction for Reverse {
+    fn name(&self) -> &'static str {
+        "reverse"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        SEQUENCE_SIGNATURE
+    }
+
+    fn execute(&self, value: Value, _args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        let mut items = sequence_items(self.name(), value)?;
+        items.reverse();
+        Ok(Value::Sequence(items))
+    }
+}
+
+/// Sorts a sequence of scalars.
+///
+/// Items are compared numerically when they are all numbers, and as strings
+/// otherwise (`10` sorts before `9` in a mixed list). Sequences holding
+/// mappings or sequences are rejected, since they have no defined order.
+pub struct Sort;
+
+impl TemplateFunction for Sort {
+    fn name(&self) -> &'static str {
+        "sort"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        SEQUENCE_SIGNATURE
+    }
+
+    fn execute(&self, value: Value, _args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        let mut items = sequence_items(self.name(), value)?;
+        if let Some(complex) = items.iter().find(|item| matches!(item, Value::Sequence(_) | Value::Mapping(_))) {
+            return Err(FunctionError::ExecutionError {
+                function: self.name().to_string(),
+                message: format!("cannot order a sequence containing a {}", value_type_name(complex)),
+            });
+        }
+
+        if items.iter().all(|item| matches!(item, Value::Int(_) | Value::Float(_))) {
+            items.sort_by(compare_numbers);
+        } else {
+            items.sort_by_cached_key(scalar_string);
+        }
+        Ok(Value::Sequence(items))
+    }
+}
+
+fn compare_numbers(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Int(a), Value::Int(b)) => a.cmp(b),
+        _ => as_f64(a).total_cmp(&as_f64(b)),
+    }
+}
+
+fn as_f64(value: &Value) -> f64 {
+    match value {
+        Value::Int(n) => *n as f64,
+        Value::Float(f) => *f,
+        _ => 0.0,
+    }
+}
+
+fn scalar_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Int(n) => n.to_string(),
+        Value::Float(f) => f.to_string(),
+        Value::Boolean(b) => b.to_string(),
+        Value::Null => "null".to_string(),
+        Value::Sequence(_) | Value::Mapping(_) => String::new(),
+    }
+}
+
+/// Removes duplicate items from a sequence, keeping the first occurrence.
+pub struct Unique;
+
+impl TemplateFunction for Unique {
+    fn name(&self) -> &'static str {
+        "unique"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        SEQUENCE_SIGNATURE
+    }
+
+    fn execute(&self, value: Value, _args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        let items = sequence_items(self.name(), value)?;
+        let mut unique: Vec<Value> = Vec::with_capacity(items.len());
+        for item in items {
+            if !unique.contains(&item) {
+                unique.push(item);
+            }
+        }
+        Ok(Value::Sequence(unique))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn strings(items: &[&str]) -> Value {
+        Value::Sequence(items.iter().map(|s| Value::String(s.to_string())).collect())
+    }
+
+    #[test]
+    fn test_reverse() {
+        let result = Reverse.execute(strings(&["a", "b", "c"]), &[]);
+        assert_eq!(result.unwrap(), strings(&["c", "b", "a"]));
+
+        assert!(Reverse.execute(Value::String("abc".to_string()), &[]).is_err());
+    }
+
+    #[test]
+    fn test_sort_numeric() {
+        let value = Value::Sequence(vec![Value::Int(10), Value::Float(2.5), Value::Int(9), Value::Int(-1)]);
+        let result = Sort.execute(value, &[]);
+        assert_eq!(
+            result.unwrap(),
+            Value::Sequence(vec![Value::Int(-1), Value::Float(2.5), Value::Int(9), Value::Int(10)])
+        );
+    }
+
+    #[test]
+    fn test_sort_strings() {
+        let result = Sort.execute(strings(&["pear", "apple", "Zebra", "fig"]), &[]);
+        assert_eq!(result.unwrap(), strings(&["Zebra", "apple", "fig", "pear"]));
+
+        // Mixed scalars are compared as strings
+        let value = Value::Sequence(vec![Value::Int(10), Value::String("9".to_string())]);
+        let result = Sort.execute(value, &[]);
+        assert_eq!(
+            result.unwrap(),
+            Value::Sequence(vec![Value::Int(10), Value::String("9".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_sort_rejects_complex_items() {
+        let value = Value::Sequence(vec![Value::Int(1), Value::Mapping(HashMap::new())]);
+        let error = Sort.execute(value, &[]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "function 'sort' execution error: cannot order a sequence containing a mapping"
+        );
+
+        assert!(Sort.execute(Value::Int(1), &[]).is_err());
+    }
+
+    #[test]
+    fn test_unique_keeps_first_occurrence() {
+        let result = Unique.execute(strings(&["b", "a", "b", "c", "a"]), &[]);
+        assert_eq!(result.unwrap(), strings(&["b", "a", "c"]));
+
+        assert!(Unique.execute(Value::Null, &[]).is_err());
+    }
+}
@@ -0,0 +1,260 @@
+//! Type coercion functions, e.g. to turn an imported `"8080"` into a number.
+
+use crate::{
+    writer::{json::to_json, yaml::to_yaml},
+    Value,
+};
+
+use super::{value_type_name, FunctionArg, FunctionError, FunctionSignature, TemplateFunction};
+
+fn parse_error(function: &str, input: &str, target: &str) -> FunctionError {
+    FunctionError::ExecutionError {
+        function: function.to_string(),
+        message: format!("cannot parse '{input}' as {target}"),
+    }
+}
+
+/// Converts a string to an integer.
+pub struct ToInt;
+
+impl TemplateFunction for ToInt {
+    fn name(&self) -> &'static str {
+        "to_int"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature {
+            args: 0..=0,
+            accepts: &["int", "string"],
+            returns: Some("int"),
+        }
+    }
+
+    fn execute(&self, value: Value, _args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        match value {
+            Value::Int(n) => Ok(Value::Int(n)),
+            Value::String(s) => s
+                .trim()
+                .parse()
+                .map(Value::Int)
+                .map_err(|_| parse_error(self.name(), &s, "an integer")),
+            other => Err(FunctionError::UnsupportedType {
+                function: self.name().to_string(),
+                got: value_type_name(&other),
+            }),
+        }
+    }
+}
+
+/// Converts a string or integer to a float.
+pub struct ToFloat;
+
+impl TemplateFunction for ToFloat {
+    fn name(&self) -> &'static str {
+        "to_float"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature {
+            args: 0..=0,
+            accepts: &["float", "int", "string"],
+            returns: Some("float"),
+        }
+    }
+
+    fn execute(&self, value: Value, _args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        match value {
+            Value::Float(f) => Ok(Value::Float(f)),
+            Value::Int(n) => Ok(Value::Float(n as f64)),
+            Value::String(s) => s
+                .trim()
+                .parse()
+                .map(Value::Float)
+                .map_err(|_| parse_error(self.name(), &s, "a float")),
+            other => Err(FunctionError::UnsupportedType {
+                function: self.name().to_string(),
+                got: value_type_name(&other),
+            }),
+        }
+    }
+}
+
+/// Converts `"true"` / `"false"` (case-insensitive) to a boolean.
+pub struct ToBool;
+
+impl TemplateFunction for ToBool {
+    fn name(&self) -> &'static str {
+        "to_bool"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature {
+            args: 0..=0,
+            accepts: &["boolean", "string"],
+            returns: Some("boolean"),
+        }
+    }
+
+    fn execute(&self, value: Value, _args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        match value {
+            Value::Boolean(b) => Ok(Value::Boolean(b)),
+            Value::String(s) => match s.trim().to_ascii_lowercase().as_str() {
+                "true" => Ok(Value::Boolean(true)),
+                "false" => Ok(Value::Boolean(false)),
+                _ => Err(parse_error(self.name(), &s, "a boolean")),
+            },
+            other => Err(FunctionError::UnsupportedType {
+                function: self.name().to_string(),
+                got: value_type_name(&other),
+            }),
+        }
+    }
+}
+
+/// Serializes any value to a compact JSON string, so a mapping or sequence
+/// can be embedded in a string: `blob: ${a.nested | to_json}`.
+pub struct ToJson;
+
+impl TemplateFunction for ToJson {
+    fn name(&self) -> &'static str {
+        "to_json"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature {
+            args: 0..=0,
+            accepts: &[],
+            returns: Some("string"),
+        }
+    }
+
+    fn execute(&self, value: Value, _args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        Ok(Value::String(to_json(&value).to_string()))
+    }
+}
+
+/// Serializes any value to a YAML document string, without the trailing newline.
+pub struct ToYaml;
+
+impl TemplateFunction for ToYaml {
+    fn name(&self) -> &'static str {
+        "to_yaml"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature {
+            args: 0..=0,
+            accepts: &[],
+            returns: Some("string"),
+        }
+    }
+
+    fn execute(&self, value: Value, _args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        let yaml = serde_yaml::to_string(&to_yaml(&value)).map_err(|e| FunctionError::ExecutionError {
+            function: self.name().to_string(),
+            message: e.to_string(),
+        })?;
+        Ok(Value::String(yaml.trim_end().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_int() {
+        let func = ToInt;
+        assert_eq!(func.name(), "to_int");
+
+        let result = func.execute(Value::String("8080".to_string()), &[]);
+        assert_eq!(result.unwrap(), Value::Int(8080));
+
+        // Already an int
+        let result = func.execute(Value::Int(42), &[]);
+        assert_eq!(result.unwrap(), Value::Int(42));
+
+        // Unparseable
+        let result = func.execute(Value::String("abc".to_string()), &[]);
+        assert!(result.is_err());
+
+        // Unsupported type
+        let result = func.execute(Value::Boolean(true), &[]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_to_float() {
+        let func = ToFloat;
+        assert_eq!(func.name(), "to_float");
+
+        let result = func.execute(Value::String("2.5".to_string()), &[]);
+        assert_eq!(result.unwrap(), Value::Float(2.5));
+
+        let result = func.execute(Value::Int(3), &[]);
+        assert_eq!(result.unwrap(), Value::Float(3.0));
+
+        let result = func.execute(Value::String("abc".to_string()), &[]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_to_bool() {
+        let func = ToBool;
+        assert_eq!(func.name(), "to_bool");
+
+        let result = func.execute(Value::String("true".to_string()), &[]);
+        assert_eq!(result.unwrap(), Value::Boolean(true));
+
+        let result = func.execute(Value::String("False".to_string()), &[]);
+        assert_eq!(result.unwrap(), Value::Boolean(false));
+
+        let result = func.execute(Value::String("abc".to_string()), &[]);
+        assert!(result.is_err());
+    }
+
+    fn nested() -> Value {
+        let mut db = crate::Mapping::new();
+        db.insert("host".to_string(), Value::String("localhost".to_string()));
+        db.insert("port".to_string(), Value::Int(5432));
+        let mut root = crate::Mapping::new();
+        root.insert("db".to_string(), Value::Mapping(db));
+        Value::Mapping(root)
+    }
+
+    #[test]
+    fn test_to_json() {
+        let func = ToJson;
+        assert_eq!(func.name(), "to_json");
+
+        let result = func.execute(nested(), &[]);
+        assert_eq!(
+            result.unwrap(),
+            Value::String(r#"{"db":{"host":"localhost","port":5432}}"#.to_string())
+        );
+
+        let sequence = Value::Sequence(vec![Value::Int(1), Value::String("two".to_string()), Value::Null]);
+        let result = func.execute(sequence, &[]);
+        assert_eq!(result.unwrap(), Value::String(r#"[1,"two",null]"#.to_string()));
+
+        // Scalars serialize too
+        let result = func.execute(Value::String("quoted".to_string()), &[]);
+        assert_eq!(result.unwrap(), Value::String(r#""quoted""#.to_string()));
+    }
+
+    #[test]
+    fn test_to_yaml() {
+        let func = ToYaml;
+        assert_eq!(func.name(), "to_yaml");
+
+        let result = func.execute(nested(), &[]);
+        let Value::String(yaml) = result.unwrap() else {
+            panic!("to_yaml should return a string");
+        };
+        assert_eq!(serde_yaml::from_str::<serde_yaml::Value>(&yaml).unwrap(), to_yaml(&nested()));
+
+        let sequence = Value::Sequence(vec![Value::String("a".to_string()), Value::Boolean(true)]);
+        let result = func.execute(sequence, &[]);
+        assert_eq!(result.unwrap(), Value::String("- a\n- true".to_string()));
+    }
+}
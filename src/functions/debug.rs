@@ -0,0 +1,56 @@
+//! Debugging functions for template authors.
+
+use crate::Value;
+
+use super::{value_type_name, FunctionArg, FunctionError, FunctionSignature, TemplateFunction};
+
+/// Returns the type name of the piped value (`"string"`, `"mapping"`, ...).
+///
+/// Useful to understand why an interpolation or another function failed.
+pub struct Describe;
+
+impl TemplateFunction for Describe {
+    fn name(&self) -> &'static str {
+        "describe"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature {
+            args: 0..=0,
+            accepts: &[],
+            returns: Some("string"),
+        }
+    }
+
+    fn execute(&self, value: Value, _args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        Ok(Value::String(value_type_name(&value).to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let func = Describe;
+        assert_eq!(func.name(), "describe");
+
+        let cases = [
+            (Value::String("hello".to_string()), "string"),
+            (Value::Int(42), "int"),
+            (Value::Float(2.5), "float"),
+            (Value::Boolean(true), "boolean"),
+            (Value::Null, "null"),
+            (Value::Sequence(vec![Value::Int(1)]), "sequence"),
+            (Value::Mapping(HashMap::new()), "mapping"),
+        ];
+
+        for (value, expected) in cases {
+            let result = func.execute(value, &[]);
+            assert_eq!(result.unwrap(), Value::String(expected.to_string()));
+        }
+    }
+}
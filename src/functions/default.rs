@@ -1,8 +1,8 @@
-//! Default value function.
+//! Fallback functions for null values.
 
 use crate::Value;
 
-use super::{FunctionArg, FunctionError, TemplateFunction};
+use super::{FunctionArg, FunctionError, FunctionSignature, TemplateFunction};
 
 /// Returns a default value if the input is null.
 pub struct Default;
@@ -12,6 +12,14 @@ impl TemplateFunction for Default {
         "default"
     }
 
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature {
+            args: 1..=1,
+            accepts: &[],
+            returns: None,
+        }
+    }
+
     fn execute(&self, value: Value, args: &[FunctionArg]) -> Result<Value, FunctionError> {
         // If value is not null, return it as-is
         if !matches!(value, Value::Null) {
@@ -24,6 +32,7 @@ impl TemplateFunction for Default {
             Some(FunctionArg::Int(n)) => Ok(Value::Int(*n)),
             Some(FunctionArg::Float(f)) => Ok(Value::Float(*f)),
             Some(FunctionArg::Boolean(b)) => Ok(Value::Boolean(*b)),
+            Some(FunctionArg::Value(v)) => Ok(v.clone()),
             None => Err(FunctionError::InvalidArgument {
                 function: self.name().to_string(),
                 expected: "a default value argument",
@@ -33,11 +42,51 @@ impl TemplateFunction for Default {
     }
 }
 
+/// Returns the first of the piped value and its arguments that is neither
+/// null nor an empty string: `${primary.host | coalesce(backup.host, "localhost")}`.
+///
+/// Quoted arguments are literals; bare paths are references resolved against
+/// the imports, a missing one counting as null. Returns null if all are.
+pub struct Coalesce;
+
+impl TemplateFunction for Coalesce {
+    fn name(&self) -> &'static str {
+        "coalesce"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature {
+            args: 1..=usize::MAX,
+            accepts: &[],
+            returns: None,
+        }
+    }
+
+    fn execute(&self, value: Value, args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        let candidates = std::iter::once(value).chain(args.iter().map(|arg| match arg {
+            FunctionArg::String(s) => Value::String(s.clone()),
+            FunctionArg::Int(n) => Value::Int(*n),
+            FunctionArg::Float(f) => Value::Float(*f),
+            FunctionArg::Boolean(b) => Value::Boolean(*b),
+            FunctionArg::Value(v) => v.clone(),
+        }));
+        for candidate in candidates {
+            match &candidate {
+                Value::Null => {}
+                Value::String(s) if s.is_empty() => {}
+                _ => return Ok(candidate),
+            }
+        }
+        Ok(Value::Null)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_default_with_null() {
         let func = Default;
         assert_eq!(func.name(), "default");
@@ -86,4 +135,39 @@ mod tests {
         let result = func.execute(Value::Null, &[]);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_coalesce_null_then_literal() {
+        let func = Coalesce;
+        assert_eq!(func.name(), "coalesce");
+
+        // Null and empty values are skipped, in order
+        let args = [
+            FunctionArg::Value(Value::Null),
+            FunctionArg::String(String::new()),
+            FunctionArg::String("localhost".to_string()),
+            FunctionArg::String("unused".to_string()),
+        ];
+        let result = func.execute(Value::Null, &args);
+        assert_eq!(result.unwrap(), Value::String("localhost".to_string()));
+
+        // A resolved reference wins over later literals
+        let args = [
+            FunctionArg::Value(Value::String("backup.internal".to_string())),
+            FunctionArg::String("localhost".to_string()),
+        ];
+        let result = func.execute(Value::String(String::new()), &args);
+        assert_eq!(result.unwrap(), Value::String("backup.internal".to_string()));
+
+        // The piped value wins when set, whatever its type
+        let result = func.execute(Value::Int(0), &[FunctionArg::Int(1)]);
+        assert_eq!(result.unwrap(), Value::Int(0));
+    }
+
+    #[test]
+    fn test_coalesce_all_null() {
+        let args = [FunctionArg::Value(Value::Null), FunctionArg::String(String::new())];
+        let result = Coalesce.execute(Value::Null, &args);
+        assert_eq!(result.unwrap(), Value::Null);
+    }
 }
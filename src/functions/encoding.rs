@@ -4,7 +4,7 @@ use base64::{engine::general_purpose::STANDARD, Engine};
 
 use crate::Value;
 
-use super::{value_type_name, FunctionArg, FunctionError, TemplateFunction};
+use super::{value_type_name, FunctionArg, FunctionError, FunctionSignature, TemplateFunction};
 
 /// Encodes a string to base64.
 pub struct Base64Encode;
@@ -14,6 +14,10 @@ impl TemplateFunction for Base64Encode {
         "base64"
     }
 
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature::string(0..=0)
+    }
+
     fn execute(&self, value: Value, _args: &[FunctionArg]) -> Result<Value, FunctionError> {
         match value {
             Value::String(s) => Ok(Value::String(STANDARD.encode(s.as_bytes()))),
@@ -33,6 +37,10 @@ impl TemplateFunction for Base64Decode {
         "base64_decode"
     }
 
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature::string(0..=0)
+    }
+
     fn execute(&self, value: Value, _args: &[FunctionArg]) -> Result<Value, FunctionError> {
         match value {
             Value::String(s) => {
@@ -66,6 +74,10 @@ impl TemplateFunction for UrlEscape {
         "url_escape"
     }
 
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature::string(0..=0)
+    }
+
     fn execute(&self, value: Value, _args: &[FunctionArg]) -> Result<Value, FunctionError> {
         match value {
             Value::String(s) => Ok(Value::String(urlencoding::encode(&s).into_owned())),
@@ -0,0 +1,134 @@
+//! Hashing functions returning the digest of a string's UTF-8 bytes.
+//!
+//! The digest is hex-encoded by default; pass `"base64"` to get it in base64:
+//! `${token | sha256("base64")}`.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sha2::Digest;
+
+use crate::Value;
+
+use super::{value_type_name, FunctionArg, FunctionError, FunctionSignature, TemplateFunction};
+
+/// Hashes `value` with `D`, encoding the digest as the optional argument asks.
+fn hash<D: Digest>(name: &str, value: Value, args: &[FunctionArg]) -> Result<Value, FunctionError> {
+    let Value::String(s) = value else {
+        return Err(FunctionError::UnsupportedType {
+            function: name.to_string(),
+            got: value_type_name(&value),
+        });
+    };
+    let digest = D::digest(s.as_bytes());
+    match args.first() {
+        None => Ok(Value::String(hex::encode(digest))),
+        Some(FunctionArg::String(encoding)) if encoding == "hex" => Ok(Value::String(hex::encode(digest))),
+        Some(FunctionArg::String(encoding)) if encoding == "base64" => Ok(Value::String(STANDARD.encode(digest))),
+        Some(other) => Err(FunctionError::InvalidArgument {
+            function: name.to_string(),
+            expected: "\"hex\" or \"base64\"",
+            got: format!("{other:?}"),
+        }),
+    }
+}
+
+/// SHA-256 digest of a string.
+pub struct Sha256;
+
+impl TemplateFunction for Sha256 {
+    fn name(&self) -> &'static str {
+        "sha256"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature::string(0..=1)
+    }
+
+    fn execute(&self, value: Value, args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        hash::<sha2::Sha256>(self.name(), value, args)
+    }
+}
+
+/// SHA-1 digest of a string, for consumers that still expect it.
+pub struct Sha1;
+
+impl TemplateFunction for Sha1 {
+    fn name(&self) -> &'static str {
+        "sha1"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature::string(0..=1)
+    }
+
+    fn execute(&self, value: Value, args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        hash::<sha1::Sha1>(self.name(), value, args)
+    }
+}
+
+/// MD5 digest of a string, for checksums only: MD5 is not collision resistant.
+pub struct Md5;
+
+impl TemplateFunction for Md5 {
+    fn name(&self) -> &'static str {
+        "md5"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature::string(0..=1)
+    }
+
+    fn execute(&self, value: Value, args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        hash::<md5::Md5>(self.name(), value, args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hello() -> Value {
+        Value::String("hello".to_string())
+    }
+
+    #[test]
+    fn test_sha256() {
+        let result = Sha256.execute(hello(), &[]);
+        assert_eq!(
+            result.unwrap(),
+            Value::String("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824".to_string())
+        );
+
+        let result = Sha256.execute(hello(), &[FunctionArg::String("base64".to_string())]);
+        assert_eq!(
+            result.unwrap(),
+            Value::String("LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=".to_string())
+        );
+    }
+
+    #[test]
+    fn test_sha1() {
+        let result = Sha1.execute(hello(), &[FunctionArg::String("hex".to_string())]);
+        assert_eq!(
+            result.unwrap(),
+            Value::String("aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d".to_string())
+        );
+    }
+
+    #[test]
+    fn test_md5() {
+        let result = Md5.execute(hello(), &[]);
+        assert_eq!(result.unwrap(), Value::String("5d41402abc4b2a76b9719d911017c592".to_string()));
+
+        let result = Md5.execute(hello(), &[FunctionArg::String("base64".to_string())]);
+        assert_eq!(result.unwrap(), Value::String("XUFAKrxLKna5cZ2REBfFkg==".to_string()));
+    }
+
+    #[test]
+    fn test_hash_rejects_non_strings_and_unknown_encodings() {
+        let result = Sha256.execute(Value::Int(42), &[]);
+        assert!(matches!(result, Err(FunctionError::UnsupportedType { got: "int", .. })));
+
+        let result = Md5.execute(hello(), &[FunctionArg::String("binary".to_string())]);
+        assert!(matches!(result, Err(FunctionError::InvalidArgument { .. })));
+    }
+}
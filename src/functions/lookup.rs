@@ -0,0 +1,106 @@
+//! Functions looking up values inside mappings and sequences.
+
+use crate::Value;
+
+use super::{value_type_name, FunctionArg, FunctionError, FunctionSignature, TemplateFunction};
+
+/// Indexes a mapping by key, or a sequence by position, with a key computed
+/// at render time: `${config.tenants | get(request.tenant)}`.
+///
+/// Returns `Null` when the key or index is absent, so it composes with `default`.
+pub struct Get;
+
+impl TemplateFunction for Get {
+    fn name(&self) -> &'static str {
+        "get"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature {
+            args: 1..=1,
+            accepts: &["mapping", "sequence"],
+            returns: None,
+        }
+    }
+
+    fn execute(&self, value: Value, args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        let key = match args.first() {
+            Some(FunctionArg::String(s)) | Some(FunctionArg::Value(Value::String(s))) => Key::Name(s),
+            Some(FunctionArg::Int(n)) | Some(FunctionArg::Value(Value::Int(n))) => Key::Index(*n),
+            other => {
+                return Err(FunctionError::InvalidArgument {
+                    function: self.name().to_string(),
+                    expected: "a string key or integer index",
+                    got: format!("{other:?}"),
+                });
+            }
+        };
+
+        let found = match (&value, key) {
+            (Value::Mapping(map), Key::Name(name)) => map.get(name),
+            (Value::Mapping(map), Key::Index(n)) => map.get(&n.to_string()),
+            (Value::Sequence(seq), Key::Index(n)) => usize::try_from(n).ok().and_then(|i| seq.get(i)),
+            (Value::Sequence(seq), Key::Name(name)) => name.parse::<usize>().ok().and_then(|i| seq.get(i)),
+            (other, _) => {
+                return Err(FunctionError::UnsupportedType {
+                    function: self.name().to_string(),
+                    got: value_type_name(other),
+                });
+            }
+        };
+        Ok(found.cloned().unwrap_or(Value::Null))
+    }
+}
+
+enum Key<'a> {
+    Name(&'a str),
+    Index(i64),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn tenants() -> Value {
+        let mut map = HashMap::new();
+        map.insert("acme".to_string(), Value::String("db-acme".to_string()));
+        map.insert("globex".to_string(), Value::String("db-globex".to_string()));
+        Value::Mapping(map)
+    }
+
+    #[test]
+    fn test_get_present_key() {
+        let func = Get;
+        assert_eq!(func.name(), "get");
+
+        let result = func.execute(tenants(), &[FunctionArg::String("acme".to_string())]);
+        assert_eq!(result.unwrap(), Value::String("db-acme".to_string()));
+
+        let result = func.execute(tenants(), &[FunctionArg::Value(Value::String("globex".to_string()))]);
+        assert_eq!(result.unwrap(), Value::String("db-globex".to_string()));
+    }
+
+    #[test]
+    fn test_get_absent_key_is_null() {
+        let result = Get.execute(tenants(), &[FunctionArg::String("initech".to_string())]);
+        assert_eq!(result.unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn test_get_sequence_index() {
+        let seq = Value::Sequence(vec![Value::Int(10), Value::Int(20)]);
+
+        assert_eq!(Get.execute(seq.clone(), &[FunctionArg::Int(1)]).unwrap(), Value::Int(20));
+        assert_eq!(Get.execute(seq.clone(), &[FunctionArg::Int(5)]).unwrap(), Value::Null);
+        assert_eq!(Get.execute(seq, &[FunctionArg::Int(-1)]).unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn test_get_invalid_input() {
+        assert!(Get.execute(Value::String("x".to_string()), &[FunctionArg::Int(0)]).is_err());
+        assert!(Get.execute(tenants(), &[]).is_err());
+        assert!(Get.execute(tenants(), &[FunctionArg::Boolean(true)]).is_err());
+    }
+}
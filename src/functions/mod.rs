@@ -3,22 +3,32 @@
 //! This module provides a registry of functions that can be applied to values
 //! using pipe syntax: `${path.to.value | trim | upper}`
 
+pub mod collection;
+pub mod convert;
+pub mod debug;
 pub mod default;
 pub mod encoding;
+pub mod hash;
+pub mod lookup;
+pub mod required;
 pub mod string;
 
 use std::collections::HashMap;
+use std::fmt;
+use std::ops::RangeInclusive;
 use std::sync::OnceLock;
 
 use crate::Value;
 
 /// Arguments that can be passed to template functions.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum FunctionArg {
     String(String),
     Int(i64),
     Float(f64),
     Boolean(bool),
+    /// The value of a reference argument (`get:request.tenant`), resolved before execution.
+    Value(Value),
 }
 
 /// Errors that can occur when executing template functions.
@@ -37,8 +47,182 @@ pub enum FunctionError {
     #[error("function '{function}' does not support type {got}")]
     UnsupportedType { function: String, got: &'static str },
 
+    #[error("function '{function}' takes {expected} argument(s), got {got}")]
+    WrongArity {
+        function: String,
+        expected: String,
+        got: usize,
+    },
+
     #[error("function '{function}' execution error: {message}")]
     ExecutionError { function: String, message: String },
+
+    #[error("malformed call to '{function}': {message}")]
+    MalformedCall { function: String, message: String },
+
+    #[error("environment variable '{0}' is not set or not allowed (see --env-prefix)")]
+    MissingEnvVar(String),
+
+    #[error("index {index} out of bounds in '{path}' (sequence of {len})")]
+    IndexOutOfBounds { path: String, index: usize, len: usize },
+}
+
+/// An argument as written in a template, before references are resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CallArg {
+    /// A literal: quoted string, number or boolean.
+    Literal(FunctionArg),
+    /// A bare dotted path (`request.tenant`), resolved against the imports.
+    Reference(String),
+}
+
+/// Parses a function's argument list, the text between the parentheses of `name(...)`.
+///
+/// Arguments are separated by commas. Strings are quoted with `"` or `'`, may
+/// contain commas, and support the `\n`, `\t` and `\<char>` escapes. `true`
+/// and `false` are booleans, numeric literals are ints or floats, and any other
+/// bare word is a reference.
+///
+/// ```
+/// use konf_provider::functions::{CallArg, FunctionArg, parse_args};
+///
+/// let args = parse_args(r#""a, b", 20, request.tenant"#).unwrap();
+/// assert_eq!(args, vec![
+///     CallArg::Literal(FunctionArg::String("a, b".to_string())),
+///     CallArg::Literal(FunctionArg::Int(20)),
+///     CallArg::Reference("request.tenant".to_string()),
+/// ]);
+/// assert!(parse_args("1,").is_err());
+/// ```
+pub fn parse_args(text: &str) -> Result<Vec<CallArg>, String> {
+    let mut args = Vec::new();
+    let mut chars = text.trim().chars().peekable();
+    if chars.peek().is_none() {
+        return Ok(args);
+    }
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let arg = match chars.peek() {
+            Some(&quote @ ('"' | '\'')) => {
+                chars.next();
+                CallArg::Literal(FunctionArg::String(parse_quoted(&mut chars, quote)?))
+            }
+            _ => {
+                let mut token = String::new();
+                while let Some(c) = chars.next_if(|c| *c != ',') {
+                    token.push(c);
+                }
+                parse_bare_arg(token.trim())?
+            }
+        };
+        args.push(arg);
+
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        match chars.next() {
+            None => return Ok(args),
+            Some(',') => continue,
+            Some(c) => return Err(format!("unexpected '{c}' after argument {}", args.len())),
+        }
+    }
+}
+
+/// Reads a quoted string whose opening quote was consumed, up to the closing one.
+fn parse_quoted(chars: &mut std::iter::Peekable<std::str::Chars>, quote: char) -> Result<String, String> {
+    let mut value = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some(escaped) => value.push(escaped),
+                None => break,
+            },
+            c if c == quote => return Ok(value),
+            c => value.push(c),
+        }
+    }
+    Err("unterminated string".to_string())
+}
+
+/// Parses an unquoted argument: boolean, number or reference.
+fn parse_bare_arg(token: &str) -> Result<CallArg, String> {
+    if token.is_empty() {
+        return Err("empty argument".to_string());
+    }
+    let literal = match token {
+        "true" => Some(FunctionArg::Boolean(true)),
+        "false" => Some(FunctionArg::Boolean(false)),
+        _ if token.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.') => {
+            let number = token
+                .parse()
+                .map(FunctionArg::Int)
+                .or_else(|_| token.parse().map(FunctionArg::Float))
+                .map_err(|_| format!("invalid number '{token}'"))?;
+            Some(number)
+        }
+        _ => None,
+    };
+    if let Some(literal) = literal {
+        return Ok(CallArg::Literal(literal));
+    }
+
+    let is_reference = token.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && token.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '/'));
+    if is_reference {
+        Ok(CallArg::Reference(token.to_string()))
+    } else {
+        Err(format!("invalid argument '{token}'"))
+    }
+}
+
+/// Static description of a function's arguments and types, used to validate
+/// calls without executing them (e.g. in the LSP).
+///
+/// Types use the names returned by [`value_type_name`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionSignature {
+    /// Accepted number of arguments.
+    pub args: RangeInclusive<usize>,
+    /// Accepted input types; empty means any type.
+    pub accepts: &'static [&'static str],
+    /// Type of the result, if it does not depend on the input.
+    pub returns: Option<&'static str>,
+}
+
+impl FunctionSignature {
+    /// A function taking `args` arguments, accepting and returning strings.
+    pub const fn string(args: RangeInclusive<usize>) -> Self {
+        Self {
+            args,
+            accepts: &["string"],
+            returns: Some("string"),
+        }
+    }
+
+    /// Human-readable description of the accepted argument count.
+    fn expected_args(&self) -> String {
+        let (min, max) = (*self.args.start(), *self.args.end());
+        if min == max {
+            min.to_string()
+        } else if max == usize::MAX {
+            format!("at least {min}")
+        } else {
+            format!("{min} to {max}")
+        }
+    }
+}
+
+/// Formats as `string -> string (args: 0)`, `any` standing for any type.
+impl fmt::Display for FunctionSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let accepts = if self.accepts.is_empty() {
+            "any".to_string()
+        } else {
+            self.accepts.join(" | ")
+        };
+        let returns = self.returns.unwrap_or("any");
+        write!(f, "{accepts} -> {returns} (args: {})", self.expected_args())
+    }
 }
 
 /// Trait for implementing template functions.
@@ -46,6 +230,9 @@ pub trait TemplateFunction: Send + Sync {
     /// Returns the name of the function as used in templates.
     fn name(&self) -> &'static str;
 
+    /// Returns the function's argument count and types.
+    fn signature(&self) -> FunctionSignature;
+
     /// Executes the function on the given value with optional arguments.
     fn execute(&self, value: Value, args: &[FunctionArg]) -> Result<Value, FunctionError>;
 }
@@ -66,14 +253,48 @@ impl FunctionRegistry {
         registry.register(Box::new(string::Trim));
         registry.register(Box::new(string::Upper));
         registry.register(Box::new(string::Lower));
+        registry.register(Box::new(string::Split));
+        registry.register(Box::new(string::SplitN));
+        registry.register(Box::new(string::Redact));
+        registry.register(Box::new(string::Replace));
+        registry.register(Box::new(string::RegexReplace));
+        registry.register(Box::new(string::SnakeCase));
+        registry.register(Box::new(string::CamelCase));
+        registry.register(Box::new(string::KebabCase));
+        registry.register(Box::new(string::Title));
 
         // Register encoding functions
         registry.register(Box::new(encoding::Base64Encode));
         registry.register(Box::new(encoding::Base64Decode));
         registry.register(Box::new(encoding::UrlEscape));
 
-        // Register default function
+        // Register hashing functions
+        registry.register(Box::new(hash::Sha256));
+        registry.register(Box::new(hash::Sha1));
+        registry.register(Box::new(hash::Md5));
+
+        // Register type coercion functions
+        registry.register(Box::new(convert::ToInt));
+        registry.register(Box::new(convert::ToFloat));
+        registry.register(Box::new(convert::ToBool));
+        registry.register(Box::new(convert::ToJson));
+        registry.register(Box::new(convert::ToYaml));
+
+        // Register sequence functions
+        registry.register(Box::new(collection::Reverse));
+        registry.register(Box::new(collection::Sort));
+        registry.register(Box::new(collection::Unique));
+
+        // Register lookup functions
+        registry.register(Box::new(lookup::Get));
+
+        // Register null-handling functions
         registry.register(Box::new(default::Default));
+        registry.register(Box::new(default::Coalesce));
+        registry.register(Box::new(required::Required));
+
+        // Register debugging functions
+        registry.register(Box::new(debug::Describe));
 
         registry
     }
@@ -88,6 +309,47 @@ impl FunctionRegistry {
         self.functions.get(name).map(|b| b.as_ref())
     }
 
+    /// Returns the names of all registered functions, sorted.
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names: Vec<&'static str> = self.functions.keys().copied().collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Checks a call against the function's signature without executing it.
+    ///
+    /// `input` is the type of the piped value when known. On success, returns
+    /// the type of the result when it can be determined.
+    pub fn check(
+        &self,
+        name: &str,
+        arg_count: usize,
+        input: Option<&'static str>,
+    ) -> Result<Option<&'static str>, FunctionError> {
+        let func = self
+            .get(name)
+            .ok_or_else(|| FunctionError::UnknownFunction(name.to_string()))?;
+        let signature = func.signature();
+
+        if !signature.args.contains(&arg_count) {
+            return Err(FunctionError::WrongArity {
+                function: name.to_string(),
+                expected: signature.expected_args(),
+                got: arg_count,
+            });
+        }
+        if let Some(input) = input
+            && !signature.accepts.is_empty()
+            && !signature.accepts.contains(&input)
+        {
+            return Err(FunctionError::UnsupportedType {
+                function: name.to_string(),
+                got: input,
+            });
+        }
+        Ok(signature.returns)
+    }
+
     /// Executes a function by name on the given value.
     pub fn execute(
         &self,
@@ -128,3 +390,51 @@ pub fn value_type_name(value: &Value) -> &'static str {
         Value::Mapping(_) => "mapping",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal(arg: FunctionArg) -> CallArg {
+        CallArg::Literal(arg)
+    }
+
+    #[test]
+    fn test_parse_args_multiple() {
+        let args = parse_args(r#" "=", 2, -1.5, false, db.host "#).unwrap();
+        assert_eq!(
+            args,
+            vec![
+                literal(FunctionArg::String("=".to_string())),
+                literal(FunctionArg::Int(2)),
+                literal(FunctionArg::Float(-1.5)),
+                literal(FunctionArg::Boolean(false)),
+                CallArg::Reference("db.host".to_string()),
+            ]
+        );
+        assert_eq!(parse_args("  ").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_parse_args_quoted_strings() {
+        let args = parse_args(r#""a, b", 'it\'s', "say \"hi\"\n""#).unwrap();
+        assert_eq!(
+            args,
+            vec![
+                literal(FunctionArg::String("a, b".to_string())),
+                literal(FunctionArg::String("it's".to_string())),
+                literal(FunctionArg::String("say \"hi\"\n".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_args_malformed() {
+        assert_eq!(parse_args(r#""open"#).unwrap_err(), "unterminated string");
+        assert_eq!(parse_args("1,").unwrap_err(), "empty argument");
+        assert_eq!(parse_args(",1").unwrap_err(), "empty argument");
+        assert_eq!(parse_args("1 2").unwrap_err(), "invalid number '1 2'");
+        assert_eq!(parse_args(r#""a" "b""#).unwrap_err(), "unexpected '\"' after argument 1");
+        assert_eq!(parse_args("a-b").unwrap_err(), "invalid argument 'a-b'");
+    }
+}
@@ -0,0 +1,82 @@
+//! Required value function.
+
+use crate::Value;
+
+use super::{FunctionArg, FunctionError, FunctionSignature, TemplateFunction};
+
+/// Fails the render if the input is null, optionally with a custom message.
+pub struct Required;
+
+impl TemplateFunction for Required {
+    fn name(&self) -> &'static str {
+        "required"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature {
+            args: 0..=1,
+            accepts: &[],
+            returns: None,
+        }
+    }
+
+    fn execute(&self, value: Value, args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        if !matches!(value, Value::Null) {
+            return Ok(value);
+        }
+
+        let message = match args.first() {
+            Some(FunctionArg::String(message)) => message.clone(),
+            Some(other) => {
+                return Err(FunctionError::InvalidArgument {
+                    function: self.name().to_string(),
+                    expected: "a string message",
+                    got: format!("{other:?}"),
+                });
+            }
+            None => "a required value is missing".to_string(),
+        };
+        Err(FunctionError::ExecutionError {
+            function: self.name().to_string(),
+            message,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_required_with_non_null() {
+        let func = Required;
+        assert_eq!(func.name(), "required");
+
+        let result = func.execute(Value::String("s3cret".to_string()), &[]);
+        assert_eq!(result.unwrap(), Value::String("s3cret".to_string()));
+
+        // Falsy values are still values
+        let result = func.execute(Value::Boolean(false), &[FunctionArg::String("unused".to_string())]);
+        assert_eq!(result.unwrap(), Value::Boolean(false));
+        let result = func.execute(Value::String(String::new()), &[]);
+        assert_eq!(result.unwrap(), Value::String(String::new()));
+    }
+
+    #[test]
+    fn test_required_with_null() {
+        let err = Required.execute(Value::Null, &[]).unwrap_err();
+        assert!(matches!(err, FunctionError::ExecutionError { ref function, .. } if function == "required"));
+        assert_eq!(err.to_string(), "function 'required' execution error: a required value is missing");
+    }
+
+    #[test]
+    fn test_required_custom_message() {
+        let err = Required
+            .execute(Value::Null, &[FunctionArg::String("DB password must be set".to_string())])
+            .unwrap_err();
+        assert_eq!(err.to_string(), "function 'required' execution error: DB password must be set");
+
+        let err = Required.execute(Value::Null, &[FunctionArg::Int(1)]).unwrap_err();
+        assert!(matches!(err, FunctionError::InvalidArgument { .. }));
+    }
+}
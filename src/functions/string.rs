@@ -2,7 +2,7 @@
 
 use crate::Value;
 
-use super::{value_type_name, FunctionArg, FunctionError, TemplateFunction};
+use super::{value_type_name, FunctionArg, FunctionError, FunctionSignature, TemplateFunction};
 
 /// Trims whitespace from both ends of a string.
 pub struct Trim;
@@ -12,6 +12,10 @@ impl TemplateFunction for Trim {
         "trim"
     }
 
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature::string(0..=0)
+    }
+
     fn execute(&self, value: Value, _args: &[FunctionArg]) -> Result<Value, FunctionError> {
         match value {
             Value::String(s) => Ok(Value::String(s.trim().to_string())),
@@ -31,6 +35,10 @@ impl TemplateFunction for Upper {
         "upper"
     }
 
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature::string(0..=0)
+    }
+
     fn execute(&self, value: Value, _args: &[FunctionArg]) -> Result<Value, FunctionError> {
         match value {
             Value::String(s) => Ok(Value::String(s.to_uppercase())),
@@ -50,6 +58,10 @@ impl TemplateFunction for Lower {
         "lower"
     }
 
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature::string(0..=0)
+    }
+
     fn execute(&self, value: Value, _args: &[FunctionArg]) -> Result<Value, FunctionError> {
         match value {
             Value::String(s) => Ok(Value::String(s.to_lowercase())),
@@ -61,6 +73,325 @@ impl TemplateFunction for Lower {
     }
 }
 
+/// Splits a string on a delimiter into a sequence of strings.
+pub struct Split;
+
+impl TemplateFunction for Split {
+    fn name(&self) -> &'static str {
+        "split"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature {
+            returns: Some("sequence"),
+            ..FunctionSignature::string(1..=1)
+        }
+    }
+
+    fn execute(&self, value: Value, args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        let delimiter = delimiter_arg(self.name(), args)?;
+        match value {
+            Value::String(s) => Ok(strings_to_sequence(s.split(delimiter))),
+            other => Err(FunctionError::UnsupportedType {
+                function: self.name().to_string(),
+                got: value_type_name(&other),
+            }),
+        }
+    }
+}
+
+/// Splits a string on a delimiter into at most `limit` parts.
+///
+/// The last part holds the unsplit remainder, e.g. `splitn("=", 2)` turns
+/// `key=value=withequals` into `["key", "value=withequals"]`.
+pub struct SplitN;
+
+impl TemplateFunction for SplitN {
+    fn name(&self) -> &'static str {
+        "splitn"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature {
+            returns: Some("sequence"),
+            ..FunctionSignature::string(2..=2)
+        }
+    }
+
+    fn execute(&self, value: Value, args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        let delimiter = delimiter_arg(self.name(), args)?;
+        let limit = match args.get(1) {
+            Some(FunctionArg::Int(n)) if *n >= 1 => *n as usize,
+            other => {
+                return Err(FunctionError::InvalidArgument {
+                    function: self.name().to_string(),
+                    expected: "a positive integer limit as second argument",
+                    got: format!("{other:?}"),
+                });
+            }
+        };
+        match value {
+            Value::String(s) => Ok(strings_to_sequence(s.splitn(limit, delimiter))),
+            other => Err(FunctionError::UnsupportedType {
+                function: self.name().to_string(),
+                got: value_type_name(&other),
+            }),
+        }
+    }
+}
+
+/// Masks a secret string for safe display.
+///
+/// `redact` or `redact("partial")` keeps the first and last characters around
+/// a fixed `****` (`password` becomes `p****d`), so the length isn't leaked.
+/// `redact("full")`, and strings too short to mask partially, give `***`.
+pub struct Redact;
+
+/// Strings up to this many characters are fully masked even in partial mode.
+const REDACT_MIN_PARTIAL_LEN: usize = 4;
+
+impl TemplateFunction for Redact {
+    fn name(&self) -> &'static str {
+        "redact"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature::string(0..=1)
+    }
+
+    fn execute(&self, value: Value, args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        let full = match args.first() {
+            None => false,
+            Some(FunctionArg::String(mode)) if mode == "partial" => false,
+            Some(FunctionArg::String(mode)) if mode == "full" => true,
+            other => {
+                return Err(FunctionError::InvalidArgument {
+                    function: self.name().to_string(),
+                    expected: "\"partial\" or \"full\"",
+                    got: format!("{other:?}"),
+                });
+            }
+        };
+        let Value::String(s) = value else {
+            return Err(FunctionError::UnsupportedType {
+                function: self.name().to_string(),
+                got: value_type_name(&value),
+            });
+        };
+
+        let chars: Vec<char> = s.chars().collect();
+        if full || chars.len() <= REDACT_MIN_PARTIAL_LEN {
+            return Ok(Value::String("***".to_string()));
+        }
+        Ok(Value::String(format!("{}****{}", chars[0], chars[chars.len() - 1])))
+    }
+}
+
+/// Replaces every occurrence of a substring: `replace("old", "new")`.
+pub struct Replace;
+
+impl TemplateFunction for Replace {
+    fn name(&self) -> &'static str {
+        "replace"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature::string(2..=2)
+    }
+
+    fn execute(&self, value: Value, args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        let (from, to) = replacement_args(self.name(), args)?;
+        if from.is_empty() {
+            return Err(FunctionError::InvalidArgument {
+                function: self.name().to_string(),
+                expected: "a non-empty string to replace",
+                got: "\"\"".to_string(),
+            });
+        }
+        match value {
+            Value::String(s) => Ok(Value::String(s.replace(from, to))),
+            other => Err(FunctionError::UnsupportedType {
+                function: self.name().to_string(),
+                got: value_type_name(&other),
+            }),
+        }
+    }
+}
+
+/// Replaces every match of a regular expression: `regex_replace("\\d+", "#")`.
+///
+/// The replacement may refer to capture groups as `$1` or `$name`.
+pub struct RegexReplace;
+
+impl TemplateFunction for RegexReplace {
+    fn name(&self) -> &'static str {
+        "regex_replace"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature::string(2..=2)
+    }
+
+    fn execute(&self, value: Value, args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        let (pattern, replacement) = replacement_args(self.name(), args)?;
+        let Value::String(s) = value else {
+            return Err(FunctionError::UnsupportedType {
+                function: self.name().to_string(),
+                got: value_type_name(&value),
+            });
+        };
+        let regex = regex::Regex::new(pattern).map_err(|e| FunctionError::ExecutionError {
+            function: self.name().to_string(),
+            message: e.to_string(),
+        })?;
+        Ok(Value::String(regex.replace_all(&s, replacement).into_owned()))
+    }
+}
+
+/// Extracts the two string arguments of a replacement: what to replace and what with.
+fn replacement_args<'a>(function: &str, args: &'a [FunctionArg]) -> Result<(&'a str, &'a str), FunctionError> {
+    match args {
+        [FunctionArg::String(from), FunctionArg::String(to)] => Ok((from, to)),
+        other => Err(FunctionError::InvalidArgument {
+            function: function.to_string(),
+            expected: "two string arguments",
+            got: format!("{other:?}"),
+        }),
+    }
+}
+
+/// Converts a string to `snake_case`.
+pub struct SnakeCase;
+
+impl TemplateFunction for SnakeCase {
+    fn name(&self) -> &'static str {
+        "snake_case"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature::string(0..=0)
+    }
+
+    fn execute(&self, value: Value, _args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        convert_case(self.name(), value, |words| words.join("_").to_lowercase())
+    }
+}
+
+/// Converts a string to `kebab-case`.
+pub struct KebabCase;
+
+impl TemplateFunction for KebabCase {
+    fn name(&self) -> &'static str {
+        "kebab_case"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature::string(0..=0)
+    }
+
+    fn execute(&self, value: Value, _args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        convert_case(self.name(), value, |words| words.join("-").to_lowercase())
+    }
+}
+
+/// Converts a string to `camelCase`.
+pub struct CamelCase;
+
+impl TemplateFunction for CamelCase {
+    fn name(&self) -> &'static str {
+        "camel_case"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature::string(0..=0)
+    }
+
+    fn execute(&self, value: Value, _args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        convert_case(self.name(), value, |words| {
+            let mut words = words.iter();
+            let first = words.next().map(|w| w.to_lowercase()).unwrap_or_default();
+            words.fold(first, |acc, word| acc + &capitalize(word))
+        })
+    }
+}
+
+/// Converts a string to `Title Case`.
+pub struct Title;
+
+impl TemplateFunction for Title {
+    fn name(&self) -> &'static str {
+        "title"
+    }
+
+    fn signature(&self) -> FunctionSignature {
+        FunctionSignature::string(0..=0)
+    }
+
+    fn execute(&self, value: Value, _args: &[FunctionArg]) -> Result<Value, FunctionError> {
+        convert_case(self.name(), value, |words| {
+            words.iter().map(|w| capitalize(w)).collect::<Vec<_>>().join(" ")
+        })
+    }
+}
+
+/// Applies a case conversion to the words of a string value.
+fn convert_case(function: &str, value: Value, join: impl Fn(&[&str]) -> String) -> Result<Value, FunctionError> {
+    match value {
+        Value::String(s) => Ok(Value::String(join(&split_words(&s)))),
+        other => Err(FunctionError::UnsupportedType {
+            function: function.to_string(),
+            got: value_type_name(&other),
+        }),
+    }
+}
+
+/// Splits a string into words on spaces, underscores, hyphens and camelCase
+/// humps. An acronym stays one word: `HTTPServer` gives `HTTP` and `Server`.
+fn split_words(s: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    for part in s.split([' ', '_', '-']).filter(|part| !part.is_empty()) {
+        let chars: Vec<(usize, char)> = part.char_indices().collect();
+        let mut start = 0;
+        for i in 1..chars.len() {
+            let (idx, c) = chars[i];
+            let prev = chars[i - 1].1;
+            let next_is_lower = chars.get(i + 1).is_some_and(|(_, n)| n.is_lowercase());
+            let hump = c.is_uppercase() && (!prev.is_uppercase() || next_is_lower);
+            if hump {
+                words.push(&part[start..idx]);
+                start = idx;
+            }
+        }
+        words.push(&part[start..]);
+    }
+    words
+}
+
+/// Uppercases the first character of a word and lowercases the rest.
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+        None => String::new(),
+    }
+}
+
+/// Extracts the non-empty string delimiter passed as first argument.
+fn delimiter_arg<'a>(function: &str, args: &'a [FunctionArg]) -> Result<&'a str, FunctionError> {
+    match args.first() {
+        Some(FunctionArg::String(d)) if !d.is_empty() => Ok(d),
+        other => Err(FunctionError::InvalidArgument {
+            function: function.to_string(),
+            expected: "a non-empty string delimiter",
+            got: format!("{other:?}"),
+        }),
+    }
+}
+
+fn strings_to_sequence<'a>(parts: impl Iterator<Item = &'a str>) -> Value {
+    Value::Sequence(parts.map(|p| Value::String(p.to_string())).collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -118,4 +449,196 @@ mod tests {
         let result = func.execute(Value::Null, &[]);
         assert!(result.is_err());
     }
+
+    fn strings(parts: &[&str]) -> Value {
+        Value::Sequence(parts.iter().map(|p| Value::String(p.to_string())).collect())
+    }
+
+    #[test]
+    fn test_split() {
+        let func = Split;
+        assert_eq!(func.name(), "split");
+
+        let result = func.execute(
+            Value::String("a,b,c".to_string()),
+            &[FunctionArg::String(",".to_string())],
+        );
+        assert_eq!(result.unwrap(), strings(&["a", "b", "c"]));
+
+        // Missing delimiter
+        let result = func.execute(Value::String("a,b".to_string()), &[]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_splitn_limit_smaller_than_delimiters() {
+        let func = SplitN;
+        assert_eq!(func.name(), "splitn");
+
+        let result = func.execute(
+            Value::String("key=value=withequals".to_string()),
+            &[FunctionArg::String("=".to_string()), FunctionArg::Int(2)],
+        );
+        assert_eq!(result.unwrap(), strings(&["key", "value=withequals"]));
+    }
+
+    #[test]
+    fn test_splitn_limit_larger_than_delimiters() {
+        let func = SplitN;
+
+        let result = func.execute(
+            Value::String("a=b".to_string()),
+            &[FunctionArg::String("=".to_string()), FunctionArg::Int(5)],
+        );
+        assert_eq!(result.unwrap(), strings(&["a", "b"]));
+    }
+
+    #[test]
+    fn test_splitn_invalid_arguments() {
+        let func = SplitN;
+        let value = Value::String("a=b".to_string());
+
+        // Missing limit
+        let result = func.execute(value.clone(), &[FunctionArg::String("=".to_string())]);
+        assert!(result.is_err());
+
+        // Zero limit
+        let result = func.execute(
+            value.clone(),
+            &[FunctionArg::String("=".to_string()), FunctionArg::Int(0)],
+        );
+        assert!(result.is_err());
+
+        // Unsupported type
+        let result = func.execute(
+            Value::Int(1),
+            &[FunctionArg::String("=".to_string()), FunctionArg::Int(2)],
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_redact() {
+        let func = Redact;
+        assert_eq!(func.name(), "redact");
+        let password = || Value::String("password".to_string());
+
+        let result = func.execute(password(), &[]);
+        assert_eq!(result.unwrap(), Value::String("p****d".to_string()));
+        let result = func.execute(password(), &[FunctionArg::String("partial".to_string())]);
+        assert_eq!(result.unwrap(), Value::String("p****d".to_string()));
+        let result = func.execute(password(), &[FunctionArg::String("full".to_string())]);
+        assert_eq!(result.unwrap(), Value::String("***".to_string()));
+
+        // Short strings would be revealed by a partial mask
+        let result = func.execute(Value::String("pin1".to_string()), &[]);
+        assert_eq!(result.unwrap(), Value::String("***".to_string()));
+
+        assert!(func.execute(password(), &[FunctionArg::String("half".to_string())]).is_err());
+        assert!(func.execute(Value::Int(1234), &[]).is_err());
+    }
+
+    #[test]
+    fn test_snake_case() {
+        let func = SnakeCase;
+        assert_eq!(func.name(), "snake_case");
+
+        let result = func.execute(Value::String("hello world".to_string()), &[]);
+        assert_eq!(result.unwrap(), Value::String("hello_world".to_string()));
+
+        let result = func.execute(Value::String("myConfig-key".to_string()), &[]);
+        assert_eq!(result.unwrap(), Value::String("my_config_key".to_string()));
+
+        let result = func.execute(Value::String("HTTPServer2Port".to_string()), &[]);
+        assert_eq!(result.unwrap(), Value::String("http_server2_port".to_string()));
+
+        // Unsupported type
+        let result = func.execute(Value::Int(42), &[]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_camel_case() {
+        let func = CamelCase;
+        assert_eq!(func.name(), "camel_case");
+
+        let result = func.execute(Value::String("my-config_key".to_string()), &[]);
+        assert_eq!(result.unwrap(), Value::String("myConfigKey".to_string()));
+
+        let result = func.execute(Value::String("Database URL".to_string()), &[]);
+        assert_eq!(result.unwrap(), Value::String("databaseUrl".to_string()));
+
+        let result = func.execute(Value::String(String::new()), &[]);
+        assert_eq!(result.unwrap(), Value::String(String::new()));
+
+        // Unsupported type
+        let result = func.execute(Value::Null, &[]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_kebab_case() {
+        let func = KebabCase;
+        assert_eq!(func.name(), "kebab_case");
+
+        let result = func.execute(Value::String("myConfigKey".to_string()), &[]);
+        assert_eq!(result.unwrap(), Value::String("my-config-key".to_string()));
+
+        let result = func.execute(Value::String("  spaced   out__words ".to_string()), &[]);
+        assert_eq!(result.unwrap(), Value::String("spaced-out-words".to_string()));
+    }
+
+    #[test]
+    fn test_title() {
+        let func = Title;
+        assert_eq!(func.name(), "title");
+
+        let result = func.execute(Value::String("hello world".to_string()), &[]);
+        assert_eq!(result.unwrap(), Value::String("Hello World".to_string()));
+
+        let result = func.execute(Value::String("api_gatewayService".to_string()), &[]);
+        assert_eq!(result.unwrap(), Value::String("Api Gateway Service".to_string()));
+
+        // Unsupported type
+        let result = func.execute(Value::Boolean(true), &[]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_replace() {
+        let func = Replace;
+        assert_eq!(func.name(), "replace");
+        let args = |from: &str, to: &str| [FunctionArg::String(from.to_string()), FunctionArg::String(to.to_string())];
+
+        let result = func.execute(Value::String("db.staging.internal".to_string()), &args("staging", "prod"));
+        assert_eq!(result.unwrap(), Value::String("db.prod.internal".to_string()));
+
+        // Every occurrence, literally (no pattern syntax)
+        let result = func.execute(Value::String("a.b.c".to_string()), &args(".", "/"));
+        assert_eq!(result.unwrap(), Value::String("a/b/c".to_string()));
+
+        assert!(func.execute(Value::String("abc".to_string()), &args("", "x")).is_err());
+        assert!(func.execute(Value::Int(42), &args("4", "5")).is_err());
+    }
+
+    #[test]
+    fn test_regex_replace() {
+        let func = RegexReplace;
+        assert_eq!(func.name(), "regex_replace");
+        let args = |from: &str, to: &str| [FunctionArg::String(from.to_string()), FunctionArg::String(to.to_string())];
+
+        let result = func.execute(Value::String("order 66, room 101".to_string()), &args(r"\d+", "#"));
+        assert_eq!(result.unwrap(), Value::String("order #, room #".to_string()));
+
+        // Capture group references
+        let result = func.execute(Value::String("admin@example".to_string()), &args(r"(?<user>\w+)@(\w+)", "$2/$user"));
+        assert_eq!(result.unwrap(), Value::String("example/admin".to_string()));
+
+        // Invalid patterns are execution errors
+        let result = func.execute(Value::String("abc".to_string()), &args("(", "x"));
+        assert!(matches!(result, Err(FunctionError::ExecutionError { .. })));
+
+        assert!(func.execute(Value::Null, &args("a", "b")).is_err());
+        assert!(func.execute(Value::String("abc".to_string()), &[FunctionArg::String("a".to_string())]).is_err());
+    }
 }
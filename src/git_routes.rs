@@ -1,22 +1,40 @@
 use async_once_cell::OnceCell;
-use dashmap::Entry;
+use dashmap::mapref::one::RefMut;
 
 use crate::{
     DagEntry,
-    authorizer::Authorizer,
+    authorizer::{Authorizer, jwt::JwtValidator},
     config::GitAppState,
-    fs::git::{GitFileProvider, clone_or_update, is_valid_commit_hash, list_all_commit_hashes},
+    diff::diff_values,
+    fs::git::{
+        ChangedKeys, GitFileProvider, changed_keys, clone_or_update, get_git_directory, is_valid_commit_hash,
+        list_all_commit_hashes, list_refs,
+    },
+    imports::ImportContext,
     loader::MultiLoader,
     metrics,
+    peers::PeerNotifier,
     render::Dag,
-    utils::GetError,
+    render_helper::EnvAccess,
+    validate::validate_all,
+    response_cache::{ResponseKey, token_scope},
+    webhook,
+    utils::{
+        GetError, RENDER_META_KEY, Readiness, RenderQuery, RenderedConfig, etag, if_none_match, negotiate_format, parse_overlay,
+        request_id, with_diagnostics, with_render_meta,
+    },
+    writer::{AUTO_FORMAT, ValueWriter, json::JsonWriter, k8s::resource_name},
+    Value,
 };
 
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::time::Instant;
 
+use xitca_web::handler::json::Json;
+use xitca_web::handler::query::Query;
 use xitca_web::handler::state::StateRef;
-use xitca_web::{handler::params::Params, http::HeaderMap};
+use xitca_web::{handler::params::Params, http::{HeaderMap, header::AUTHORIZATION}};
 
 use anyhow::Result;
 use tokio::sync::Mutex;
@@ -25,6 +43,10 @@ async fn new_dag_git(
     repo_url: &str,
     commit: &str,
     multiloader: Arc<MultiLoader>,
+    admin_tokens: &[String],
+    jwt: Option<Arc<JwtValidator>>,
+    import_context: ImportContext,
+    env: EnvAccess,
 ) -> Result<DagEntry<GitFileProvider>, GetError> {
     let fs = GitFileProvider::new(repo_url, commit)
         .await
@@ -32,83 +54,527 @@ async fn new_dag_git(
             commit: commit.to_string(),
             reason: format!("failed to create git file provider: {e}"),
         })?;
-    let authorizer = Authorizer::new(&fs, &multiloader).await;
-    let d = Dag::new(fs, multiloader)
+    let authorizer = Authorizer::new(&fs, &multiloader)
+        .await
+        .with_admin_tokens(admin_tokens.iter().cloned())
+        .with_jwt(jwt);
+    let d = Dag::with_context(fs, multiloader, import_context)
         .await
         .map_err(|e| GetError::DagInitError {
             commit: commit.to_string(),
             reason: format!("failed to load config files: {e}"),
-        })?;
-    Ok(DagEntry { dag: d, authorizer })
+        })?
+        .with_env_access(env);
+    Ok(DagEntry::new(d, authorizer))
 }
 
-pub async fn get_data(
-    headers: HeaderMap,
-    Params((commit, format, path)): Params<(String, String, String)>,
-    StateRef(state): StateRef<'_, GitAppState<GitFileProvider>>,
-) -> Result<String, GetError> {
-    let start = Instant::now();
-
-    let token = headers
+/// Extracts the caller's token from the request headers.
+///
+/// An `Authorization: Bearer <token>` header is preferred; the custom `token`
+/// header is still accepted for compatibility.
+fn extract_token(headers: &HeaderMap) -> Result<&str, GetError> {
+    let invalid = |name: &str| GetError::BadRequest {
+        reason: format!("invalid '{name}' header: must be valid UTF-8"),
+    };
+    if let Some(authorization) = headers.get(AUTHORIZATION) {
+        let authorization = authorization.to_str().map_err(|_| invalid("authorization"))?;
+        if let Some((scheme, token)) = authorization.split_once(' ')
+            && scheme.eq_ignore_ascii_case("bearer")
+        {
+            return Ok(token.trim());
+        }
+    }
+    headers
         .get("token")
         .ok_or(GetError::Unauthorized {
-            reason: "missing 'token' header".to_string(),
+            reason: "missing 'Authorization: Bearer <token>' or 'token' header".to_string(),
         })?
         .to_str()
-        .map_err(|_| GetError::BadRequest {
-            reason: "invalid 'token' header: must be valid UTF-8".to_string(),
-        })?;
+        .map_err(|_| invalid("token"))
+}
 
+/// Resolves the commit segment of a URL to a commit hash.
+///
+/// A 40-char hex segment is taken as a commit hash and returned as is;
+/// anything else is looked up among the branch and tag names listed at the
+/// last reload.
+fn resolve_commit(state: &GitAppState<GitFileProvider>, commit: String) -> Result<String, GetError> {
+    if is_valid_commit_hash(&commit) {
+        return Ok(commit);
+    }
+    state
+        .refs
+        .load()
+        .get(&commit)
+        .cloned()
+        .ok_or(GetError::CommitNotFound { commit })
+}
+
+/// Returns the DAG and authorizer for `commit`, after checking it exists.
+///
+/// The DAG for the commit is built on first access and cached in `state.dag`,
+/// evicting older entries first if the cache is bounded. Concurrent requests
+/// for a commit being built share the same build.
+async fn commit_entry<'a>(
+    state: &'a GitAppState<GitFileProvider>,
+    commit: &str,
+) -> Result<RefMut<'a, String, DagEntry<GitFileProvider>>, GetError> {
     // Validate commit hash format before checking if it exists
-    if !is_valid_commit_hash(&commit) {
+    if !is_valid_commit_hash(commit) {
         return Err(GetError::BadRequest {
             reason: format!("invalid commit hash format: '{commit}' (expected 40-char hex string)"),
         });
     }
 
-    if !state.commits.load().contains(&commit) {
+    if !state.commits.load().contains(commit) {
         return Err(GetError::CommitNotFound {
-            commit: commit.clone(),
+            commit: commit.to_string(),
         });
     }
 
-    let dag = match state.dag.entry(commit.clone()) {
-        Entry::Occupied(entry) => {
-            metrics::record_git_cache(true);
-            entry.into_ref()
-        }
-        Entry::Vacant(entry) => {
-            metrics::record_git_cache(false);
-            let d = new_dag_git(&state.repo_config.url, &commit, state.multiloader.clone()).await?;
-            entry.insert(d)
-        }
-    };
+    if let Some(entry) = state.dag.get_mut(commit) {
+        metrics::record_git_cache(true);
+        entry.touch();
+        return Ok(entry);
+    }
+    metrics::record_git_cache(false);
+
+    loop {
+        // Make room before building: evicting iterates the map
+        state.dag_cache.evict(&state.dag, 1);
 
-    if !dag.authorizer.authorize(&path, token) {
-        return Err(GetError::Forbidden { path: path.clone() });
+        let (repo_url, owned_commit) = (state.repo_config.url.clone(), commit.to_string());
+        let (multiloader, admin_tokens) = (state.multiloader.clone(), state.admin_tokens.clone());
+        let (jwt, import_context, env) = (state.jwt.clone(), state.import_context.clone(), state.env.clone());
+        let built = state
+            .dag_builds
+            .run(commit, move || async move {
+                let entry = new_dag_git(&repo_url, &owned_commit, multiloader, &admin_tokens, jwt, import_context, env).await?;
+                Ok(Arc::new(std::sync::Mutex::new(Some(entry))))
+            })
+            .await?;
+
+        // The first waiter moves the entry into the cache, the others read it there
+        let entry = built.lock().expect("built entry poisoned").take();
+        match entry {
+            Some(entry) => return Ok(state.dag.entry(commit.to_string()).or_insert(entry)),
+            None => {
+                if let Some(entry) = state.dag.get_mut(commit) {
+                    return Ok(entry);
+                }
+                // Evicted right after being built: build it again
+            }
+        }
     }
+}
 
-    let rendered = dag
+/// Resolves `path` to a loaded key of `entry` and checks that `token` may access it.
+///
+/// With `--inherit-auth`, `token` must also be authorized for every restricted
+/// file the config imports, since their values end up in the rendered output.
+fn authorized_key(
+    state: &GitAppState<GitFileProvider>,
+    entry: &DagEntry<GitFileProvider>,
+    path: &str,
+    token: &str,
+) -> Result<String, GetError> {
+    let key = entry
         .dag
-        .get_rendered(&path)
-        .await
-        .map_err(|e| GetError::RenderError {
-            path: path.clone(),
-            reason: e.to_string(),
+        .resolve_key(path, state.case_insensitive_paths)
+        .map_err(|suggestions| GetError::ConfigNotFound {
+            path: path.to_string(),
+            suggestions,
         })?;
 
-    let result = state
+    if !entry.authorizer.authorize(&key, token) {
+        return Err(GetError::Forbidden { path: key });
+    }
+    if state.inherit_auth {
+        let denied = entry
+            .dag
+            .transitive_imports(&key)
+            .into_iter()
+            .find(|import| entry.authorizer.is_restricted(import) && !entry.authorizer.authorize(import, token));
+        if let Some(import) = denied {
+            tracing::debug!(path = key, import, "access denied by an imported file");
+            return Err(GetError::Forbidden { path: key });
+        }
+    }
+    Ok(key)
+}
+
+/// Renders `path` at `commit` after checking that `token` may access it.
+///
+/// With `--render-meta`, a `_meta` key recording the source and commit is added.
+/// `query` selects isolated rendering and schema validation (always on with
+/// `--strict-schemas`).
+async fn render_authorized(
+    state: &GitAppState<GitFileProvider>,
+    commit: &str,
+    path: &str,
+    token: &str,
+    query: &RenderQuery,
+) -> Result<Value, GetError> {
+    let dag = commit_entry(state, commit).await?;
+    let key = authorized_key(state, &dag, path, token)?;
+
+    let render_error = |e: anyhow::Error| GetError::RenderError {
+        path: key.clone(),
+        reason: e.to_string(),
+    };
+    let rendered = if query.isolated() {
+        let (rendered, failures) = dag.dag.get_rendered_isolated(&key).await.map_err(render_error)?;
+        if query.diagnostics {
+            with_diagnostics(rendered, &failures)
+        } else {
+            rendered
+        }
+    } else {
+        dag.dag
+            .get_rendered_with_policy(&key, state.unresolved)
+            .await
+            .map_err(render_error)?
+    };
+    if query.validate || state.strict_schemas {
+        let errors = dag.dag.schema_violations(&key, &rendered);
+        if !errors.is_empty() {
+            return Err(GetError::ValidationFailed { path: key, errors });
+        }
+    }
+    if state.render_meta {
+        return Ok(with_render_meta(rendered, &key, Some(commit)));
+    }
+    Ok(rendered)
+}
+
+/// Serializes `value` with the writer registered for `format`, naming the
+/// document `name` for formats that embed one.
+fn write_value(
+    state: &GitAppState<GitFileProvider>,
+    format: &str,
+    value: &Value,
+    name: &str,
+) -> Result<String, GetError> {
+    state
         .writer
-        .write(&format, &rendered)
+        .write_named(format, value, name)
         .ok_or_else(|| GetError::BadRequest {
             reason: format!("unknown output format: '{format}'"),
         })?
         .map_err(|e| GetError::InternalError {
             reason: format!("failed to serialize to '{format}': {e}"),
-        });
+        })
+}
+
+/// Resolves the auto format `_` to the format `path` declares at `commit`
+/// (or the default). Any other format is returned unchanged.
+async fn resolve_format(
+    state: &GitAppState<GitFileProvider>,
+    commit: &str,
+    path: &str,
+    format: &str,
+) -> Result<String, GetError> {
+    if format != AUTO_FORMAT {
+        return Ok(format.to_string());
+    }
+    let entry = commit_entry(state, commit).await?;
+    let declared = entry
+        .dag
+        .resolve_key(path, state.case_insensitive_paths)
+        .ok()
+        .and_then(|key| entry.dag.output_format(&key));
+    Ok(state.writer.auto_format(declared.as_deref()).to_string())
+}
+
+/// Returns the cache TTL `path` declares at `commit`, if any.
+async fn output_ttl(state: &GitAppState<GitFileProvider>, commit: &str, path: &str) -> Result<Option<u64>, GetError> {
+    let entry = commit_entry(state, commit).await?;
+    Ok(entry
+        .dag
+        .resolve_key(path, state.case_insensitive_paths)
+        .ok()
+        .and_then(|key| entry.dag.output_ttl(&key)))
+}
+
+/// Renders and serializes `path` at `commit`, serving repeated requests from
+/// the response cache.
+///
+/// The token is authorized on every request, cache hits included, so a JWT
+/// that expired since its response was cached is turned away; entries are
+/// keyed by a hash of the token too. Isolated, explicitly validated and
+/// explicitly named renders are never cached, nor is pretty-printed JSON or
+/// anything with `--render-meta`.
+async fn render_cached(
+    state: &GitAppState<GitFileProvider>,
+    commit: &str,
+    path: &str,
+    format: &str,
+    token: &str,
+    query: &RenderQuery,
+) -> Result<String, GetError> {
+    let name = query.document_name(path);
+    if state.render_meta || query.isolated() || query.validate || query.name.is_some() || query.pretty_json(format) {
+        let rendered = render_authorized(state, commit, path, token, query).await?;
+        if query.pretty_json(format) {
+            return JsonWriter::pretty().to_str(&rendered).map_err(|e| GetError::InternalError {
+                reason: format!("failed to serialize to '{format}': {e}"),
+            });
+        }
+        return write_value(state, format, &rendered, &name);
+    }
+
+    let key = ResponseKey {
+        commit: commit.to_string(),
+        path: path.to_string(),
+        format: format.to_string(),
+        scope: token_scope(token),
+    };
+    {
+        let entry = commit_entry(state, commit).await?;
+        authorized_key(state, &entry, path, token)?;
+    }
+    if let Some(body) = state.response_cache.get(&key) {
+        return Ok(body);
+    }
+    let rendered = render_authorized(state, commit, path, token, query).await?;
+    let body = write_value(state, format, &rendered, &name)?;
+    state.response_cache.insert(key, &body);
+    Ok(body)
+}
+
+/// Returns the ETag of `path` rendered at `commit` in `format`.
+///
+/// Configs are immutable per commit, so the tag only depends on the request.
+/// Returns `None` with `--render-meta`, whose render time makes every body differ.
+fn render_etag(
+    state: &GitAppState<GitFileProvider>,
+    commit: &str,
+    format: &str,
+    path: &str,
+    query: &RenderQuery,
+) -> Option<String> {
+    if state.render_meta {
+        return None;
+    }
+    let name = query.document_name(path);
+    let mode = match (query.isolate, query.diagnostics) {
+        (_, true) => "diagnostics",
+        (true, false) => "isolate",
+        (false, false) => "",
+    };
+    let pretty = if query.pretty_json(format) { "pretty" } else { "" };
+    Some(etag(&[commit, format, path, &name, mode, pretty]))
+}
+
+/// Renders a config at a commit, given by hash or by branch or tag name.
+///
+/// Responses carry an ETag; a request whose `If-None-Match` matches it gets
+/// `304 Not Modified` once the token is checked, without rendering.
+#[tracing::instrument(
+    name = "get_data",
+    skip_all,
+    fields(
+        path = %path,
+        commit = tracing::field::Empty,
+        format = tracing::field::Empty,
+        request_id = request_id(&headers),
+    )
+)]
+pub async fn get_data(
+    headers: HeaderMap,
+    Params((commit, format, path)): Params<(String, String, String)>,
+    Query(query): Query<RenderQuery>,
+    StateRef(state): StateRef<'_, GitAppState<GitFileProvider>>,
+) -> Result<RenderedConfig, GetError> {
+    let start = Instant::now();
+
+    let token = extract_token(&headers)?;
+    let commit = resolve_commit(state, commit)?;
+    let format = resolve_format(state, &commit, &path, &format).await?;
+    tracing::Span::current()
+        .record("commit", commit.as_str())
+        .record("format", format.as_str());
+    let etag = render_etag(state, &commit, &format, &path, &query);
+    if let Some(etag) = etag.clone().filter(|etag| if_none_match(&headers, etag)) {
+        {
+            let entry = commit_entry(state, &commit).await?;
+            authorized_key(state, &entry, &path, token)?;
+        }
+        return Ok(RenderedConfig::not_modified(etag, output_ttl(state, &commit, &path).await?));
+    }
+    let result = render_cached(state, &commit, &path, &format, token, &query).await;
 
     metrics::record_render(&format, result.is_ok(), start.elapsed());
-    result
+    Ok(RenderedConfig {
+        body: result?,
+        content_type: state.writer.content_type(&format),
+        ttl: output_ttl(state, &commit, &path).await?,
+        etag,
+        not_modified: false,
+    })
+}
+
+/// Renders a config in the format the `Accept` header asks for, or the
+/// server's default output format.
+#[tracing::instrument(
+    name = "get_data",
+    skip_all,
+    fields(
+        path = %path,
+        commit = tracing::field::Empty,
+        format = tracing::field::Empty,
+        request_id = request_id(&headers),
+    )
+)]
+pub async fn get_data_default_format(
+    headers: HeaderMap,
+    Params((commit, path)): Params<(String, String)>,
+    Query(query): Query<RenderQuery>,
+    StateRef(state): StateRef<'_, GitAppState<GitFileProvider>>,
+) -> Result<RenderedConfig, GetError> {
+    let start = Instant::now();
+    let format = negotiate_format(&state.writer, &headers)?;
+
+    let token = extract_token(&headers)?;
+    let commit = resolve_commit(state, commit)?;
+    tracing::Span::current()
+        .record("commit", commit.as_str())
+        .record("format", format);
+    let result = render_cached(state, &commit, &path, format, token, &query).await;
+
+    metrics::record_render(format, result.is_ok(), start.elapsed());
+    Ok(RenderedConfig {
+        body: result?,
+        content_type: state.writer.content_type(format),
+        ttl: output_ttl(state, &commit, &path).await?,
+        ..Default::default()
+    })
+}
+
+/// Renders a config with an ad-hoc overlay deep-merged over it.
+///
+/// The request body (JSON or YAML) is merged over the rendered config before
+/// serialization, which allows previewing a change without committing it.
+/// Authorization is checked against the base path.
+pub async fn post_overlay(
+    headers: HeaderMap,
+    Params((commit, format, path)): Params<(String, String, String)>,
+    StateRef(state): StateRef<'_, GitAppState<GitFileProvider>>,
+    body: String,
+) -> Result<RenderedConfig, GetError> {
+    let start = Instant::now();
+
+    let token = extract_token(&headers)?;
+    let commit = resolve_commit(state, commit)?;
+    let format = resolve_format(state, &commit, &path, &format).await?;
+    let overlay = parse_overlay(&state.multiloader, &body)?;
+    let mut rendered = render_authorized(state, &commit, &path, token, &RenderQuery::default()).await?;
+    rendered.merge(&overlay);
+    let result = write_value(state, &format, &rendered, &resource_name(&path));
+
+    metrics::record_render(&format, result.is_ok(), start.elapsed());
+    Ok(RenderedConfig {
+        body: result?,
+        content_type: state.writer.content_type(&format),
+        ttl: None,
+        ..Default::default()
+    })
+}
+
+/// Query string of [`get_changes`].
+#[derive(Debug, Deserialize)]
+pub struct ChangesQuery {
+    /// Commit, branch or tag compared from.
+    pub from: String,
+    /// Commit, branch or tag compared to.
+    pub to: String,
+}
+
+/// Lists the config keys added, removed or modified between two commits.
+///
+/// Only file contents are compared, nothing is rendered. Keys the token may
+/// not read are left out: added and modified keys are checked against `to`,
+/// removed keys against `from`.
+pub async fn get_changes(
+    headers: HeaderMap,
+    Query(query): Query<ChangesQuery>,
+    StateRef(state): StateRef<'_, GitAppState<GitFileProvider>>,
+) -> Result<Json<ChangedKeys>, GetError> {
+    let token = extract_token(&headers)?;
+    let (from, to) = (resolve_commit(state, query.from)?, resolve_commit(state, query.to)?);
+    // Validates both commits and builds their entries before diffing. Entry
+    // guards are dropped right away: holding both could deadlock if from == to.
+    commit_entry(state, &from).await?;
+    commit_entry(state, &to).await?;
+
+    let repo_path = get_git_directory(&state.repo_config.url);
+    let (from_hash, to_hash) = (from.clone(), to.clone());
+    let mut changes = tokio::task::spawn_blocking(move || changed_keys(&repo_path, &from_hash, &to_hash))
+        .await
+        .map_err(|e| GetError::InternalError { reason: e.to_string() })?
+        .map_err(|e| GetError::InternalError {
+            reason: format!("failed to compare '{from}' and '{to}': {e}"),
+        })?;
+
+    {
+        let to_entry = commit_entry(state, &to).await?;
+        changes.added.retain(|key| to_entry.authorizer.authorize(key, token));
+        changes.modified.retain(|key| to_entry.authorizer.authorize(key, token));
+    }
+    let from_entry = commit_entry(state, &from).await?;
+    changes.removed.retain(|key| from_entry.authorizer.authorize(key, token));
+    Ok(Json(changes))
+}
+
+/// Renders `path` at `commit` for a diff: a config missing at this commit
+/// counts as empty, and render metadata is dropped since it always differs.
+async fn render_for_diff(
+    state: &GitAppState<GitFileProvider>,
+    commit: &str,
+    path: &str,
+    token: &str,
+) -> Result<Option<Value>, GetError> {
+    match render_authorized(state, commit, path, token, &RenderQuery::default()).await {
+        Ok(mut rendered) => {
+            if let Value::Mapping(map) = &mut rendered {
+                map.remove(RENDER_META_KEY);
+            }
+            Ok(Some(rendered))
+        }
+        Err(GetError::ConfigNotFound { .. }) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Compares the config at `path` rendered at two commits.
+///
+/// Returns the leaves added, removed and changed between `from` and `to`
+/// (see [`diff_values`]), serialized in `format`. Unlike `/diff/files`, imports
+/// are resolved, so a config changed through an import shows up too. The
+/// config must exist at one of the commits at least.
+pub async fn get_diff(
+    headers: HeaderMap,
+    Params((from, to, format, path)): Params<(String, String, String, String)>,
+    StateRef(state): StateRef<'_, GitAppState<GitFileProvider>>,
+) -> Result<RenderedConfig, GetError> {
+    let token = extract_token(&headers)?;
+    let (from, to) = (resolve_commit(state, from)?, resolve_commit(state, to)?);
+    let old = render_for_diff(state, &from, &path, token).await?;
+    let new = render_for_diff(state, &to, &path, token).await?;
+    if old.is_none() && new.is_none() {
+        return Err(GetError::ConfigNotFound {
+            path,
+            suggestions: Vec::new(),
+        });
+    }
+
+    let empty = Value::Mapping(Default::default());
+    let diff = diff_values(old.as_ref().unwrap_or(&empty), new.as_ref().unwrap_or(&empty));
+    Ok(RenderedConfig {
+        body: write_value(state, &format, &diff.to_value(), &resource_name(&path))?,
+        content_type: state.writer.content_type(&format),
+        ..Default::default()
+    })
 }
 
 /// We wrap the reload lock in a OnceCell, so it's globally available.
@@ -121,10 +587,36 @@ async fn reload_lock() -> &'static Arc<Mutex<()>> {
         .await) as _
 }
 
-/// reload the commit set
+/// reload the commit set, then notify peers unless the reload came from a peer
 pub async fn reload(
+    headers: HeaderMap,
+    StateRef(state): StateRef<'_, GitAppState<GitFileProvider>>,
+) -> Result<String, GetError> {
+    update_repository(state, &headers).await
+}
+
+/// Reloads like `/reload` when a signed push webhook is received.
+///
+/// The request must be signed with `--webhook-secret` (see [`webhook::verify`]);
+/// unsigned or mismatched requests, and every request when no secret is
+/// configured, get `401 Unauthorized`.
+pub async fn webhook(
+    headers: HeaderMap,
     StateRef(state): StateRef<'_, GitAppState<GitFileProvider>>,
+    body: String,
 ) -> Result<String, GetError> {
+    let Some(secret) = &state.webhook_secret else {
+        return Err(GetError::Unauthorized {
+            reason: "webhooks are disabled (no --webhook-secret)".to_string(),
+        });
+    };
+    webhook::verify(secret, &headers, body.as_bytes())?;
+    update_repository(state, &headers).await
+}
+
+/// Fetches the repository and refreshes the commit set, then notifies peers
+/// unless the reload came from a peer. Does nothing if a reload is running.
+async fn update_repository(state: &GitAppState<GitFileProvider>, headers: &HeaderMap) -> Result<String, GetError> {
     let lock = reload_lock().await.clone();
     if let Ok(guard) = lock.try_lock() {
         let result = clone_or_update(
@@ -147,13 +639,133 @@ pub async fn reload(
                 reason: format!("failed to list commit hashes: {e}"),
             }
         })?;
+        let refs = list_refs(&state.repo_config.url).map_err(|e| GetError::InternalError {
+            reason: format!("failed to list refs: {e}"),
+        })?;
         state.commits.store(Arc::from(commits));
+        state.refs.store(Arc::from(refs));
+        state.response_cache.clear();
         drop(guard);
+
+        if !PeerNotifier::is_cascaded(headers) {
+            state.peers.notify().await;
+        }
     }
 
     Ok("OK".to_string())
 }
 
+/// A cached commit DAG, as reported by [`get_cache_stats`].
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct CachedCommit {
+    pub commit: String,
+    /// When the DAG was built, in Unix seconds.
+    pub built_at: u64,
+    /// When the DAG was built or last served, in Unix seconds.
+    pub last_access: u64,
+    /// Rough size of the loaded and rendered files, in bytes.
+    pub estimated_bytes: usize,
+}
+
+/// Contents of the per-commit DAG cache, returned by `GET /cache/stats`.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct CacheStats {
+    /// Number of cached commit DAGs.
+    pub entries: usize,
+    /// Sum of the entries' estimated sizes, in bytes.
+    pub estimated_bytes: usize,
+    /// Number of cached serialized responses.
+    pub response_cache_entries: usize,
+    /// Cached commits, most recently accessed first.
+    pub commits: Vec<CachedCommit>,
+}
+
+impl CachedCommit {
+    fn new(commit: &str, entry: &DagEntry<GitFileProvider>) -> Self {
+        Self {
+            commit: commit.to_string(),
+            built_at: entry.built_at(),
+            last_access: entry.last_access(),
+            estimated_bytes: entry.dag.estimated_size(),
+        }
+    }
+}
+
+/// Checks that the request carries one of the admin tokens (`--admin-token`).
+fn require_admin(headers: &HeaderMap, state: &GitAppState<GitFileProvider>, path: &str) -> Result<(), GetError> {
+    let token = extract_token(headers)?;
+    if !state.admin_tokens.iter().any(|admin| admin == token) {
+        return Err(GetError::Forbidden { path: path.to_string() });
+    }
+    Ok(())
+}
+
+/// Reports the cached commit DAGs, for debugging cache behavior.
+///
+/// Only admin tokens (`--admin-token`) may read it.
+pub async fn get_cache_stats(
+    headers: HeaderMap,
+    StateRef(state): StateRef<'_, GitAppState<GitFileProvider>>,
+) -> Result<Json<CacheStats>, GetError> {
+    require_admin(&headers, state, "/cache/stats")?;
+
+    let mut commits: Vec<CachedCommit> = state
+        .dag
+        .iter()
+        .map(|entry| CachedCommit::new(entry.key(), entry.value()))
+        .collect();
+    commits.sort_by(|a, b| b.last_access.cmp(&a.last_access).then_with(|| a.commit.cmp(&b.commit)));
+
+    Ok(Json(CacheStats {
+        entries: commits.len(),
+        estimated_bytes: commits.iter().map(|c| c.estimated_bytes).sum(),
+        response_cache_entries: state.response_cache.len(),
+        commits,
+    }))
+}
+
+/// Query string of [`rebuild_commit`].
+#[derive(Debug, Deserialize)]
+pub struct RebuildQuery {
+    /// Commit whose cache entry is rebuilt.
+    pub commit: String,
+}
+
+/// Evicts and immediately rebuilds the cached DAG of one commit.
+///
+/// Runs under the reload lock so it can't interleave with a reload. The
+/// commit's cached responses are dropped too; other commits are untouched.
+/// Only admin tokens (`--admin-token`) may call it.
+pub async fn rebuild_commit(
+    headers: HeaderMap,
+    Query(query): Query<RebuildQuery>,
+    StateRef(state): StateRef<'_, GitAppState<GitFileProvider>>,
+) -> Result<Json<CachedCommit>, GetError> {
+    require_admin(&headers, state, "/cache/rebuild")?;
+
+    let lock = reload_lock().await.clone();
+    let _guard = lock.lock().await;
+
+    state.dag.remove(&query.commit);
+    state.response_cache.evict_commit(&query.commit);
+    let entry = commit_entry(state, &query.commit).await?;
+    Ok(Json(CachedCommit::new(&query.commit, &entry)))
+}
+
+/// Validates every config of the tracked branch's latest commit, like
+/// `konf validate`, to report readiness.
+pub async fn ready(StateRef(state): StateRef<'_, GitAppState<GitFileProvider>>) -> Readiness {
+    let dag = match resolve_commit(state, state.repo_config.branch.clone()) {
+        // Cloned so the cache entry isn't locked while every config renders
+        Ok(commit) => commit_entry(state, &commit).await.map(|entry| entry.dag.clone()),
+        Err(e) => Err(e),
+    };
+    match dag {
+        Ok(dag) => validate_all(&dag, &dag.keys()).await.into(),
+        Err(e) => Readiness::unavailable(e.to_string()),
+    }
+}
+
 pub async fn metrics_handler(
     StateRef(state): StateRef<'_, GitAppState<GitFileProvider>>,
 ) -> String {
@@ -0,0 +1,389 @@
+//! Static validation of the import graph.
+//!
+//! Rendering only discovers a broken import or a cycle when the affected
+//! config is first requested. [`analyze`] walks every loaded file up front so
+//! misconfiguration can be reported (or refused) at startup.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use crate::{
+    Value,
+    authorizer::ROLES_KEY,
+    imports::{ImportContext, parse_imports_for},
+    metadata::{ENTRYPOINT_KEY, METADATA_KEY},
+    render_helper::{collect_placeholders, env_var_name, lookup_in_deps, placeholder_path},
+};
+
+/// A problem found in the import graph.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GraphProblem {
+    /// `file` imports `import`, which is not loaded.
+    MissingImport { file: String, import: String },
+    /// The files import each other in a loop; the first file is repeated at the end.
+    Cycle { files: Vec<String> },
+    /// `file` references `reference`, which does not resolve against its imports.
+    UnresolvedReference { file: String, reference: String },
+}
+
+impl fmt::Display for GraphProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphProblem::MissingImport { file, import } => {
+                write!(f, "'{file}' imports missing file '{import}'")
+            }
+            GraphProblem::Cycle { files } => write!(f, "import cycle: {}", files.join(" -> ")),
+            GraphProblem::UnresolvedReference { file, reference } => {
+                write!(f, "'{file}' has unresolved reference '${{{reference}}}'")
+            }
+        }
+    }
+}
+
+/// Summary of an import graph analysis.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphReport {
+    /// Number of loaded files.
+    pub files: usize,
+    /// Number of import declarations across all files.
+    pub imports: usize,
+    /// Problems found, sorted.
+    pub problems: Vec<GraphProblem>,
+}
+
+impl GraphReport {
+    /// Returns true if no problem was found.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Logs the summary line and one warning per problem.
+    pub fn log(&self) {
+        tracing::info!(
+            files = self.files,
+            imports = self.imports,
+            problems = self.problems.len(),
+            "import graph: {} files, {} imports, {} problems",
+            self.files,
+            self.imports,
+            self.problems.len()
+        );
+        for problem in &self.problems {
+            tracing::warn!("import graph: {problem}");
+        }
+    }
+}
+
+/// Analyzes the raw (unrendered) files for missing imports and cycles.
+///
+/// When `check_references` is set, every `${...}` reference must also resolve
+/// to a key of an imported file. Conditional imports are evaluated in `context`.
+pub fn analyze<'a>(
+    files: impl IntoIterator<Item = (&'a String, &'a Value)>,
+    check_references: bool,
+    context: &ImportContext,
+) -> GraphReport {
+    let files: BTreeMap<&str, &Value> = files.into_iter().map(|(k, v)| (k.as_str(), v)).collect();
+    let mut report = GraphReport {
+        files: files.len(),
+        ..Default::default()
+    };
+    let mut edges: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+
+    for (&key, &value) in &files {
+        let imports = parse_imports_for(value, key, context);
+        report.imports += imports.len();
+
+        let mut deps: HashMap<String, Value> = HashMap::new();
+        for info in imports.values() {
+            let target = info.resolved_path.clone().unwrap_or_else(|| info.path.clone());
+            match files.get(target.as_str()) {
+                Some(&dep) => {
+                    deps.insert(info.alias.clone(), dep.clone());
+                    edges.entry(key).or_default().push(target);
+                }
+                None => report.problems.push(GraphProblem::MissingImport {
+                    file: key.to_string(),
+                    import: target,
+                }),
+            }
+        }
+
+        if check_references {
+            let mut body = value.clone();
+            if let Value::Mapping(map) = &mut body {
+                map.remove(METADATA_KEY);
+            }
+            for reference in collect_placeholders(&body) {
+                // Environment variables are only read at render time
+                let resolved = placeholder_path(&reference).is_some_and(|path| {
+                    env_var_name(path).is_some() || lookup_in_deps(path, &deps).is_some()
+                });
+                if !resolved {
+                    report.problems.push(GraphProblem::UnresolvedReference {
+                        file: key.to_string(),
+                        reference,
+                    });
+                }
+            }
+        }
+    }
+
+    report.problems.extend(find_cycles(&edges));
+    report.problems.sort();
+    report.problems.dedup();
+    report
+}
+
+/// Files that no other file imports.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OrphanReport {
+    /// Unimported files meant to be served directly, sorted.
+    pub entrypoints: Vec<String>,
+    /// Unimported files that are not entrypoints, likely dead, sorted.
+    pub orphans: Vec<String>,
+}
+
+/// Returns true if the file declares `entrypoint: true` in its metadata.
+pub fn is_entrypoint(value: &Value) -> bool {
+    matches!(
+        value.get(METADATA_KEY).and_then(|meta| meta.get(ENTRYPOINT_KEY)),
+        Some(Value::Boolean(true))
+    )
+}
+
+/// Finds the files that are never imported by another file.
+///
+/// A file counts as an entrypoint if it declares `entrypoint: true` or its key
+/// starts with one of `entrypoint_prefixes`; every other unimported file is
+/// reported as an orphan. The roles file is never reported. Conditional
+/// imports are evaluated in `context`.
+pub fn find_orphans<'a>(
+    files: impl IntoIterator<Item = (&'a String, &'a Value)>,
+    entrypoint_prefixes: &[String],
+    context: &ImportContext,
+) -> OrphanReport {
+    let files: BTreeMap<&str, &Value> = files.into_iter().map(|(k, v)| (k.as_str(), v)).collect();
+    let imported: HashSet<String> = files
+        .iter()
+        .flat_map(|(&key, &value)| {
+            parse_imports_for(value, key, context)
+                .into_values()
+                .map(|info| info.resolved_path.unwrap_or(info.path))
+                .filter(move |target| target != key)
+        })
+        .collect();
+
+    let mut report = OrphanReport::default();
+    for (&key, &value) in &files {
+        if key == ROLES_KEY || imported.contains(key) {
+            continue;
+        }
+        if is_entrypoint(value) || entrypoint_prefixes.iter().any(|p| key.starts_with(p.as_str())) {
+            report.entrypoints.push(key.to_string());
+        } else {
+            report.orphans.push(key.to_string());
+        }
+    }
+    report
+}
+
+/// Renders the import graph in Graphviz DOT, one node per file and one edge
+/// per import. Files and imports taking part in a cycle are drawn in red.
+/// Conditional imports are evaluated in `context`.
+pub fn to_dot<'a>(files: impl IntoIterator<Item = (&'a String, &'a Value)>, context: &ImportContext) -> String {
+    fn quote(key: &str) -> String {
+        format!("\"{}\"", key.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+
+    let files: BTreeMap<&str, &Value> = files.into_iter().map(|(k, v)| (k.as_str(), v)).collect();
+    let edges: BTreeMap<&str, Vec<String>> = files
+        .iter()
+        .map(|(&key, &value)| {
+            let mut targets: Vec<String> = parse_imports_for(value, key, context)
+                .into_values()
+                .map(|info| info.resolved_path.unwrap_or(info.path))
+                .collect();
+            targets.sort();
+            targets.dedup();
+            (key, targets)
+        })
+        .collect();
+
+    let mut cyclic_nodes = HashSet::new();
+    let mut cyclic_edges = HashSet::new();
+    for cycle in find_cycles(&edges) {
+        if let GraphProblem::Cycle { files } = cycle {
+            for pair in files.windows(2) {
+                cyclic_nodes.insert(pair[0].clone());
+                cyclic_edges.insert((pair[0].clone(), pair[1].clone()));
+            }
+        }
+    }
+
+    let mut dot = String::from("digraph konf {\n");
+    for &key in files.keys() {
+        let style = if cyclic_nodes.contains(key) { " [color=red]" } else { "" };
+        dot.push_str(&format!("    {}{style};\n", quote(key)));
+    }
+    for (&key, targets) in &edges {
+        for target in targets {
+            let style = if cyclic_edges.contains(&(key.to_string(), target.clone())) { " [color=red]" } else { "" };
+            dot.push_str(&format!("    {} -> {}{style};\n", quote(key), quote(target)));
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Finds import cycles with a depth-first search, reporting each cycle once.
+fn find_cycles(edges: &BTreeMap<&str, Vec<String>>) -> Vec<GraphProblem> {
+    fn visit<'a>(
+        node: &'a str,
+        edges: &'a BTreeMap<&str, Vec<String>>,
+        stack: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+        cycles: &mut Vec<GraphProblem>,
+    ) {
+        if let Some(start) = stack.iter().position(|&n| n == node) {
+            let mut files: Vec<String> = stack[start..].iter().map(|n| n.to_string()).collect();
+            // Rotate so the same cycle found from another entry point is reported identically
+            let min = files.iter().enumerate().min_by_key(|(_, f)| *f).map_or(0, |(i, _)| i);
+            files.rotate_left(min);
+            files.push(files[0].clone());
+            cycles.push(GraphProblem::Cycle { files });
+            return;
+        }
+        if done.contains(node) {
+            return;
+        }
+        stack.push(node);
+        for next in edges.get(node).into_iter().flatten() {
+            visit(next, edges, stack, done, cycles);
+        }
+        stack.pop();
+        done.insert(node);
+    }
+
+    let mut cycles = Vec::new();
+    let mut done = HashSet::new();
+    for &node in edges.keys() {
+        visit(node, edges, &mut Vec::new(), &mut done, &mut cycles);
+    }
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{loader::Loader, loaders::yaml::YamlLoader};
+
+    fn files(entries: &[(&str, &str)]) -> Vec<(String, Value)> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), YamlLoader {}.load(v).unwrap()))
+            .collect()
+    }
+
+    fn run(entries: &[(&str, &str)], check_references: bool) -> GraphReport {
+        let files = files(entries);
+        analyze(files.iter().map(|(k, v)| (k, v)), check_references, &ImportContext::default())
+    }
+
+    #[test]
+    fn test_valid_graph() {
+        let report = run(
+            &[
+                ("base", "host: localhost"),
+                ("app", "<!>:\n  import:\n    base: b\nurl: ${b.host}"),
+            ],
+            true,
+        );
+
+        assert!(report.is_ok(), "{:?}", report.problems);
+        assert_eq!(report.files, 2);
+        assert_eq!(report.imports, 1);
+    }
+
+    #[test]
+    fn test_missing_import() {
+        let report = run(&[("app", "<!>:\n  import:\n    missing:\nkey: value")], false);
+
+        assert_eq!(
+            report.problems,
+            vec![GraphProblem::MissingImport {
+                file: "app".to_string(),
+                import: "missing".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_cycle_reported_once() {
+        let report = run(
+            &[
+                ("a", "<!>:\n  import:\n    b:\nkey: 1"),
+                ("b", "<!>:\n  import:\n    a:\nkey: 2"),
+            ],
+            false,
+        );
+
+        assert_eq!(
+            report.problems,
+            vec![GraphProblem::Cycle {
+                files: vec!["a".to_string(), "b".to_string(), "a".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_dot_highlights_cycles() {
+        let files = files(&[
+            ("a", "<!>:\n  import:\n    b:\nkey: 1"),
+            ("b", "<!>:\n  import:\n    a:\n    c:\nkey: 2"),
+            ("c", "key: 3"),
+        ]);
+        let dot = to_dot(files.iter().map(|(k, v)| (k, v)), &ImportContext::default());
+
+        assert_eq!(
+            dot,
+            "digraph konf {\n    \"a\" [color=red];\n    \"b\" [color=red];\n    \"c\";\n    \"a\" -> \"b\" [color=red];\n    \"b\" -> \"a\" [color=red];\n    \"b\" -> \"c\";\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_orphans_split_from_entrypoints() {
+        let files = files(&[
+            ("common/db", "host: localhost"),
+            ("common/unused", "key: value"),
+            ("services/api", "<!>:\n  import:\n    common/db: db\nhost: ${db.host}"),
+            ("dashboard", "<!>:\n  entrypoint: true\nkey: value"),
+            ("_roles", "readers:\n  - token"),
+        ]);
+        let report = find_orphans(
+            files.iter().map(|(k, v)| (k, v)),
+            &["services/".to_string()],
+            &ImportContext::default(),
+        );
+
+        assert_eq!(report.entrypoints, vec!["dashboard", "services/api"]);
+        assert_eq!(report.orphans, vec!["common/unused"]);
+    }
+
+    #[test]
+    fn test_unresolved_references_only_in_strict_mode() {
+        let entries = [
+            ("base", "host: localhost"),
+            ("app", "<!>:\n  import:\n    base: b\nurl: ${b.port | trim}\nsecret: ${env.KONF_SECRET}"),
+        ];
+
+        assert!(run(&entries, false).is_ok());
+        assert_eq!(
+            run(&entries, true).problems,
+            vec![GraphProblem::UnresolvedReference {
+                file: "app".to_string(),
+                reference: "b.port | trim".to_string(),
+            }]
+        );
+    }
+}
@@ -16,14 +16,68 @@
 //!     common/redis: cache       # Explicit alias "cache"
 //!     ../shared/config: cfg     # Relative path with alias
 //! ```
+//!
+//! An import can also be gated by a condition on the active profile, using a
+//! mapping with `alias` and `when`. It is only included when the condition holds:
+//!
+//! ```yaml
+//! <!>:
+//!   import:
+//!     common/prod-db: { alias: db, when: "profile == prod" }
+//!     common/dev-db: { alias: db, when: "profile != prod" }
+//! ```
 
 use std::collections::HashMap;
 
 use crate::Value;
 use serde_yaml::Value as YamlValue;
 
-/// The metadata key used in konf config files
-pub const METADATA_KEY: &str = "<!>";
+pub use crate::metadata::METADATA_KEY;
+use crate::metadata::{EXTENDS_KEY, IMPORT_KEY, OUTPUT_FORMAT_KEY, OUTPUT_KEY, OUTPUT_TTL_KEY};
+
+/// Alias field of a conditional import.
+pub const ALIAS_KEY: &str = "alias";
+
+/// Condition field of a conditional import.
+pub const WHEN_KEY: &str = "when";
+
+/// Context in which import conditions are evaluated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportContext {
+    /// The active profile, if any.
+    pub profile: Option<String>,
+}
+
+impl ImportContext {
+    /// Creates a context with the given active profile.
+    pub fn new(profile: Option<String>) -> Self {
+        Self { profile }
+    }
+
+    /// Evaluates an import condition: `profile == <name>` or `profile != <name>`.
+    ///
+    /// The name may be quoted. Conditions that can't be parsed are false, so a
+    /// malformed condition never silently pulls an import in.
+    pub fn evaluate(&self, condition: &str) -> bool {
+        let (variable, expected, equal) = if let Some((l, r)) = condition.split_once("==") {
+            (l, r, true)
+        } else if let Some((l, r)) = condition.split_once("!=") {
+            (l, r, false)
+        } else {
+            tracing::warn!("invalid import condition '{condition}'");
+            return false;
+        };
+        let expected = expected.trim().trim_matches(|c| c == '"' || c == '\'');
+
+        match variable.trim() {
+            "profile" => (self.profile.as_deref() == Some(expected)) == equal,
+            other => {
+                tracing::warn!("unknown variable '{other}' in import condition '{condition}'");
+                false
+            }
+        }
+    }
+}
 
 /// Information about an import declaration.
 #[derive(Debug, Clone, PartialEq)]
@@ -36,6 +90,8 @@ pub struct ImportInfo {
     /// This is `Some` when the path has been resolved against a document key,
     /// or `None` when path resolution is not needed (e.g., in LSP context).
     pub resolved_path: Option<String>,
+    /// The condition gating this import, if any (e.g. `profile == prod`).
+    pub when: Option<String>,
 }
 
 /// Parse imports from a configuration value.
@@ -47,6 +103,9 @@ pub struct ImportInfo {
 ///   common/redis:            # path with null value -> uses path as alias
 /// ```
 ///
+/// Conditional imports are evaluated without an active profile; use
+/// [`parse_imports_for`] to evaluate them in a given context.
+///
 /// # Arguments
 /// * `value` - The configuration value containing the `<!>` metadata section
 /// * `doc_key` - The key of the current document, used for resolving relative paths
@@ -54,6 +113,16 @@ pub struct ImportInfo {
 /// # Returns
 /// A HashMap mapping alias to ImportInfo
 pub fn parse_imports(value: &Value, doc_key: &str) -> HashMap<String, ImportInfo> {
+    parse_imports_for(value, doc_key, &ImportContext::default())
+}
+
+/// Parse imports from a configuration value, keeping only the conditional
+/// imports whose `when` condition holds in `context`.
+pub fn parse_imports_for(
+    value: &Value,
+    doc_key: &str,
+    context: &ImportContext,
+) -> HashMap<String, ImportInfo> {
     let Some(main_value) = value.get(METADATA_KEY) else {
         return HashMap::new();
     };
@@ -62,7 +131,7 @@ pub fn parse_imports(value: &Value, doc_key: &str) -> HashMap<String, ImportInfo
         return HashMap::new();
     };
 
-    let Some(import_value) = main_map.get("import") else {
+    let Some(import_value) = main_map.get(IMPORT_KEY) else {
         return HashMap::new();
     };
 
@@ -72,6 +141,17 @@ pub fn parse_imports(value: &Value, doc_key: &str) -> HashMap<String, ImportInfo
     // If alias is null or empty, the path is used as the alias
     if let Value::Mapping(map) = import_value {
         for (path_key, alias_value) in map {
+            // A mapping value carries an alias and an optional condition
+            let (alias_value, when) = match alias_value {
+                Value::Mapping(entry) => (
+                    entry.get(ALIAS_KEY).unwrap_or(&Value::Null),
+                    entry.get(WHEN_KEY).and_then(Value::as_str).cloned(),
+                ),
+                other => (other, None),
+            };
+            if when.as_deref().is_some_and(|condition| !context.evaluate(condition)) {
+                continue;
+            }
             // If alias is a non-empty string, use it; otherwise use the path as alias
             let alias = match alias_value {
                 Value::String(s) if !s.is_empty() => s.clone(),
@@ -84,6 +164,7 @@ pub fn parse_imports(value: &Value, doc_key: &str) -> HashMap<String, ImportInfo
                     path: path_key.clone(),
                     alias,
                     resolved_path: Some(resolved),
+                    when,
                 },
             );
         }
@@ -95,7 +176,8 @@ pub fn parse_imports(value: &Value, doc_key: &str) -> HashMap<String, ImportInfo
 /// Parse imports from a serde_yaml::Value.
 ///
 /// This function is useful when working directly with serde_yaml (e.g., in the LSP)
-/// rather than the internal `Value` type.
+/// rather than the internal `Value` type. Conditions are not evaluated: every
+/// conditional import is returned with its `when` condition.
 ///
 /// # Arguments
 /// * `yaml` - The YAML value containing the `<!>` metadata section
@@ -120,7 +202,7 @@ pub fn parse_imports_from_yaml(
         return HashMap::new();
     };
 
-    let Some(import_value) = meta_map.get(YamlValue::String("import".to_string())) else {
+    let Some(import_value) = meta_map.get(YamlValue::String(IMPORT_KEY.to_string())) else {
         return HashMap::new();
     };
 
@@ -135,6 +217,15 @@ pub fn parse_imports_from_yaml(
             continue;
         };
 
+        // A mapping value carries an alias and an optional condition
+        let (alias_val, when) = match alias_val.as_mapping() {
+            Some(entry) => (
+                entry.get(ALIAS_KEY).unwrap_or(&YamlValue::Null),
+                entry.get(WHEN_KEY).and_then(YamlValue::as_str).map(String::from),
+            ),
+            None => (alias_val, None),
+        };
+
         // If alias is a non-empty string, use it; otherwise use the path as alias
         let alias = match alias_val.as_str() {
             Some(s) if !s.is_empty() => s.to_string(),
@@ -149,6 +240,7 @@ pub fn parse_imports_from_yaml(
                 path: path.to_string(),
                 alias,
                 resolved_path,
+                when,
             },
         );
     }
@@ -156,6 +248,54 @@ pub fn parse_imports_from_yaml(
     imports
 }
 
+/// Returns the output format a file declares under `<!>: output: format:`, if any.
+///
+/// ```
+/// use konf_provider::{imports::parse_output_format, loader::Loader, loaders::yaml::YamlLoader};
+///
+/// let value = YamlLoader {}.load("<!>:\n  output:\n    format: env\nport: 80\n").unwrap();
+/// assert_eq!(parse_output_format(&value).as_deref(), Some("env"));
+/// ```
+pub fn parse_output_format(value: &Value) -> Option<String> {
+    value
+        .get(METADATA_KEY)?
+        .get(OUTPUT_KEY)?
+        .get(OUTPUT_FORMAT_KEY)?
+        .as_str()
+        .map(String::from)
+}
+
+/// Returns the cache TTL, in seconds, a file declares under `<!>: output: ttl:`, if any.
+///
+/// Negative or non-integer values are ignored.
+///
+/// ```
+/// use konf_provider::{imports::parse_output_ttl, loader::Loader, loaders::yaml::YamlLoader};
+///
+/// let value = YamlLoader {}.load("<!>:\n  output:\n    ttl: 300\nport: 80\n").unwrap();
+/// assert_eq!(parse_output_ttl(&value), Some(300));
+/// ```
+pub fn parse_output_ttl(value: &Value) -> Option<u64> {
+    match value.get(METADATA_KEY)?.get(OUTPUT_KEY)?.get(OUTPUT_TTL_KEY)? {
+        Value::Int(ttl) => u64::try_from(*ttl).ok(),
+        _ => None,
+    }
+}
+
+/// Returns the import aliases a file extends under `<!>: extends:`, in merge order.
+///
+/// ```
+/// use konf_provider::{imports::parse_extends, loader::Loader, loaders::yaml::YamlLoader};
+///
+/// let value = YamlLoader {}
+///     .load("<!>:\n  import:\n    common/base: base\n  extends:\n    - base\nport: 80\n")
+///     .unwrap();
+/// assert_eq!(parse_extends(&value), vec!["base"]);
+/// ```
+pub fn parse_extends(value: &Value) -> Vec<String> {
+    crate::utils::get_conf_strings(value, EXTENDS_KEY)
+}
+
 /// Get import paths as a list (for backwards compatibility).
 ///
 /// Returns the resolved paths for all imports.
@@ -398,6 +538,45 @@ mod tests {
         assert!(paths.contains(&"common/redis".to_string()));
     }
 
+    fn conditional_imports() -> Value {
+        use crate::{loader::Loader, loaders::yaml::YamlLoader};
+
+        YamlLoader {}
+            .load(
+                "<!>:\n  import:\n    common/prod-db: { alias: db, when: \"profile == prod\" }\n    common/dev-db: { alias: db, when: \"profile != prod\" }\n    common/redis: cache\n",
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_conditional_import_follows_profile() {
+        let value = conditional_imports();
+        let prod = ImportContext::new(Some("prod".to_string()));
+
+        let imports = parse_imports_for(&value, "services/api", &prod);
+        assert_eq!(imports.len(), 2);
+        assert_eq!(imports["db"].path, "common/prod-db");
+        assert_eq!(imports["db"].when.as_deref(), Some("profile == prod"));
+        assert_eq!(imports["cache"].when, None);
+
+        let imports = parse_imports(&value, "services/api");
+        assert_eq!(imports["db"].path, "common/dev-db");
+    }
+
+    #[test]
+    fn test_evaluate_condition() {
+        let prod = ImportContext::new(Some("prod".to_string()));
+
+        assert!(prod.evaluate("profile == prod"));
+        assert!(prod.evaluate("profile == 'prod'"));
+        assert!(!prod.evaluate("profile != prod"));
+        assert!(!ImportContext::default().evaluate("profile == prod"));
+        assert!(ImportContext::default().evaluate("profile != prod"));
+        // Malformed or unknown conditions never hold
+        assert!(!prod.evaluate("profile"));
+        assert!(!prod.evaluate("region == eu"));
+    }
+
     #[test]
     fn test_parse_imports_from_yaml_with_doc_key() {
         let yaml: YamlValue = serde_yaml::from_str(
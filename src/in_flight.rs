@@ -0,0 +1,70 @@
+//! Deduplication of concurrent identical work.
+//!
+//! When several requests need the same expensive result at once (building a
+//! commit's DAG clones and loads the repository), the first caller starts the
+//! work and the others await the same shared future instead of repeating it.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+
+use futures::future::{BoxFuture, FutureExt, Shared};
+
+/// A boxed future whose output is cloned to every caller awaiting it.
+pub type InFlightFuture<T> = Shared<BoxFuture<'static, T>>;
+
+/// Futures currently running, by key.
+///
+/// A key is only in flight while its future runs: once it completes, the
+/// next call for the key starts the work again.
+pub struct InFlight<T> {
+    futures: Mutex<HashMap<String, InFlightFuture<T>>>,
+}
+
+impl<T> Default for InFlight<T> {
+    fn default() -> Self {
+        Self {
+            futures: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> InFlight<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Awaits the work running for `key`, starting it with `start` if none is.
+    pub async fn run<F, Fut>(&self, key: &str, start: F) -> T
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T> + Send + 'static,
+    {
+        let future = self
+            .futures
+            .lock()
+            .expect("in-flight map poisoned")
+            .entry(key.to_string())
+            .or_insert_with(|| start().boxed().shared())
+            .clone();
+        let output = future.clone().await;
+
+        // The first waiter to finish removes the entry, unless a new run
+        // already replaced it
+        let mut futures = self.futures.lock().expect("in-flight map poisoned");
+        if futures.get(key).is_some_and(|f| f.ptr_eq(&future)) {
+            futures.remove(key);
+        }
+        output
+    }
+
+    /// Returns the number of keys currently in flight.
+    pub fn len(&self) -> usize {
+        self.futures.lock().expect("in-flight map poisoned").len()
+    }
+
+    /// Returns true if no work is in flight.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -1,24 +1,40 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use async_once_cell::OnceCell;
-use serde::Serialize;
+use serde::{Deserialize, Deserializer, Serialize, de};
 
 use crate::{authorizer::Authorizer, fs::FileProvider, render::Dag};
 pub mod utils;
 pub mod writer;
 pub mod loaders;
 pub mod loader;
+pub mod export;
 pub mod fs;
+pub mod graph;
+pub mod limit;
+pub mod fmt;
 pub mod render_helper;
 pub mod render;
+pub mod response_cache;
+pub mod schema;
+pub mod validate;
 pub mod authorizer;
 pub mod git_routes;
 pub mod local_routes;
 pub mod config;
+pub mod dag_cache;
+pub mod diff;
 pub mod metrics;
+pub mod peers;
 pub mod telemetry;
+pub mod watch;
+pub mod webhook;
 pub mod functions;
 pub mod imports;
+pub mod in_flight;
+pub mod metadata;
 pub mod lsp;
 /// A configuration entry that holds both raw and rendered versions.
 ///
@@ -51,6 +67,54 @@ pub struct DagEntry<P: FileProvider> {
     pub dag: Dag<P>,
     /// The authorizer controlling access to configurations.
     pub authorizer: Authorizer,
+    /// When the entry was built, in Unix seconds.
+    built_at: u64,
+    /// When the entry was created or last served, in Unix milliseconds.
+    last_access: AtomicU64,
+}
+
+impl<P: FileProvider> DagEntry<P> {
+    /// Creates an entry, marking it as accessed now.
+    pub fn new(dag: Dag<P>, authorizer: Authorizer) -> Self {
+        Self {
+            dag,
+            authorizer,
+            built_at: unix_now(),
+            last_access: AtomicU64::new(unix_now_millis()),
+        }
+    }
+
+    /// Returns when the entry was built, in Unix seconds.
+    pub fn built_at(&self) -> u64 {
+        self.built_at
+    }
+
+    /// Marks the entry as accessed now.
+    pub fn touch(&self) {
+        self.last_access.store(unix_now_millis(), Ordering::Relaxed);
+    }
+
+    /// Returns when the entry was created or last served, in Unix seconds.
+    pub fn last_access(&self) -> u64 {
+        self.last_access_millis() / 1000
+    }
+
+    /// Returns when the entry was created or last served, in Unix milliseconds.
+    pub fn last_access_millis(&self) -> u64 {
+        self.last_access.load(Ordering::Relaxed)
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn unix_now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
 }
 
 /// Internal representation of configuration values.
@@ -87,6 +151,16 @@ impl Value {
         }
     }
 
+    /// Looks up a dotted path (e.g. `"database.replicas.0.host"`) below this value.
+    ///
+    /// Segments are mapping keys, or indexes into sequences when numeric.
+    pub fn get_path(&self, dotted: &str) -> Option<&Value> {
+        dotted.split('.').try_fold(self, |current, key| match current {
+            Value::Sequence(seq) => key.parse::<usize>().ok().and_then(|index| seq.get(index)),
+            _ => current.get(key),
+        })
+    }
+
     pub fn as_sequence(&self) -> Option<&Sequence> {
         match self {
             Value::Sequence(values) => Some(values),
@@ -108,6 +182,128 @@ impl Value {
             _ => None,
         }
     }
+
+    /// Rough number of bytes the value occupies, including its heap allocations.
+    ///
+    /// Hash map overhead is ignored, so this is a lower bound meant for cache
+    /// statistics rather than exact accounting.
+    pub fn estimated_size(&self) -> usize {
+        let heap = match self {
+            Value::String(s) => s.capacity(),
+            Value::Sequence(seq) => seq.iter().map(Value::estimated_size).sum(),
+            Value::Mapping(map) => map
+                .iter()
+                .map(|(key, value)| std::mem::size_of::<String>() + key.capacity() + value.estimated_size())
+                .sum(),
+            Value::Int(_) | Value::Float(_) | Value::Boolean(_) | Value::Null => 0,
+        };
+        std::mem::size_of::<Value>() + heap
+    }
+
+    /// Deep-merges `other` into `self`, with `other` taking precedence.
+    ///
+    /// Mappings are merged key by key, recursively. Any other combination
+    /// (scalars, sequences, or a type mismatch) replaces the value in `self`.
+    pub fn merge(&mut self, other: &Value) {
+        match (self, other) {
+            (Value::Mapping(base), Value::Mapping(overlay)) => {
+                for (key, value) in overlay {
+                    match base.get_mut(key) {
+                        Some(existing) => existing.merge(value),
+                        None => {
+                            base.insert(key.clone(), value.clone());
+                        }
+                    }
+                }
+            }
+            (base, overlay) => *base = overlay.clone(),
+        }
+    }
+}
+
+/// Deserializes from any self-describing format (JSON, YAML, ...) the way the
+/// loaders convert their parsed documents: unsigned integers beyond `i64` wrap,
+/// number and boolean mapping keys become strings, other keys are skipped and
+/// YAML tags are dropped.
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> de::Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a configuration value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Boolean(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Int(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::Int(v as i64))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::Float(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(Value::Sequence(values))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut mapping = Mapping::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some((key, value)) = map.next_entry::<Value, Value>()? {
+            let key = match key {
+                Value::String(s) => s,
+                Value::Int(n) => n.to_string(),
+                Value::Float(n) => n.to_string(),
+                Value::Boolean(b) => b.to_string(),
+                _ => continue,
+            };
+            mapping.insert(key, value);
+        }
+        Ok(Value::Mapping(mapping))
+    }
+
+    fn visit_enum<A: de::EnumAccess<'de>>(self, data: A) -> Result<Value, A::Error> {
+        // A tagged YAML value (`!tag value`): keep the value
+        let (_tag, variant) = data.variant::<de::IgnoredAny>()?;
+        de::VariantAccess::newtype_variant(variant)
+    }
 }
 
 pub type DagFiles = HashMap<String, Konf>;
\ No newline at end of file
@@ -0,0 +1,106 @@
+//! Concurrency limiting middleware.
+//!
+//! Expensive requests (e.g. the first render of a git commit, which builds its
+//! DAG) can pile up under load. With a limit set, requests beyond it are
+//! rejected immediately with `503 Service Unavailable` instead of queueing
+//! until they time out. Health and metrics endpoints are never limited.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use tokio::sync::Semaphore;
+use xitca_web::{
+    WebContext,
+    error::Error,
+    http::WebResponse,
+    service::{Service, ready::ReadyService},
+};
+
+use crate::{metrics, utils::GetError};
+
+/// Paths that bypass the limit, so probes and scrapes keep working when saturated.
+const EXEMPT_PATHS: &[&str] = &["/live", "/ready", "/metrics"];
+
+/// Builder for the concurrency limiting middleware.
+///
+/// The state is shared across worker threads, so the limit applies to the
+/// whole server.
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimit {
+    /// `None` means unlimited; in-flight requests are still counted.
+    semaphore: Option<Arc<Semaphore>>,
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl ConcurrencyLimit {
+    /// Limits the server to `max` concurrent requests, or only counts them if `None`.
+    pub fn new(max: Option<usize>) -> Self {
+        Self {
+            semaphore: max.map(|max| Arc::new(Semaphore::new(max))),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+impl<S, E> Service<Result<S, E>> for ConcurrencyLimit {
+    type Response = ConcurrencyLimitService<S>;
+    type Error = E;
+
+    async fn call(&self, res: Result<S, E>) -> Result<Self::Response, Self::Error> {
+        res.map(|service| ConcurrencyLimitService {
+            service,
+            limit: self.clone(),
+        })
+    }
+}
+
+pub struct ConcurrencyLimitService<S> {
+    service: S,
+    limit: ConcurrencyLimit,
+}
+
+impl<'r, C, B, S, ResB> Service<WebContext<'r, C, B>> for ConcurrencyLimitService<S>
+where
+    S: for<'r2> Service<WebContext<'r2, C, B>, Response = WebResponse<ResB>, Error = Error>,
+{
+    type Response = WebResponse<ResB>;
+    type Error = Error;
+
+    async fn call(&self, ctx: WebContext<'r, C, B>) -> Result<Self::Response, Self::Error> {
+        if EXEMPT_PATHS.contains(&ctx.req().uri().path()) {
+            return self.service.call(ctx).await;
+        }
+
+        let _permit = match &self.limit.semaphore {
+            Some(semaphore) => match semaphore.try_acquire() {
+                Ok(permit) => Some(permit),
+                Err(_) => {
+                    metrics::record_rejected();
+                    return Err(GetError::Unavailable {
+                        reason: "too many concurrent requests".to_string(),
+                    }
+                    .into());
+                }
+            },
+            None => None,
+        };
+
+        let in_flight = &self.limit.in_flight;
+        metrics::record_in_flight(in_flight.fetch_add(1, Ordering::SeqCst) + 1);
+        let result = self.service.call(ctx).await;
+        metrics::record_in_flight(in_flight.fetch_sub(1, Ordering::SeqCst) - 1);
+        result
+    }
+}
+
+impl<S> ReadyService for ConcurrencyLimitService<S>
+where
+    S: ReadyService,
+{
+    type Ready = S::Ready;
+
+    #[inline]
+    async fn ready(&self) -> Self::Ready {
+        self.service.ready().await
+    }
+}
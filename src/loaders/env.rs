@@ -0,0 +1,147 @@
+//! Loader for dotenv files (`KEY=value` lines).
+
+use std::collections::HashMap;
+
+use crate::{loader::{Loader, LoaderError}, Value};
+
+/// Separator splitting a key into nested mappings with [`EnvLoader::nested`].
+const NESTING_SEPARATOR: &str = "__";
+
+/// Loads `.env` files into a mapping of string values.
+///
+/// Supports blank lines, `#` comments, an optional `export ` prefix, and
+/// single- or double-quoted values (double quotes understand `\n`, `\t`,
+/// `\"` and `\\`). Unquoted values end at a ` #` comment.
+#[derive(Debug, Default)]
+pub struct EnvLoader {
+    /// Expand `DB__HOST=x` into `DB: { HOST: x }`.
+    pub nested: bool,
+}
+
+impl EnvLoader {
+    /// Creates a loader expanding `__` in keys into nested mappings.
+    pub fn nested() -> Self {
+        Self { nested: true }
+    }
+}
+
+impl Loader for EnvLoader {
+    fn ext(&self) -> &'static str {
+        "env"
+    }
+
+    fn load(&self, content: &str) -> Result<Value, LoaderError> {
+        let mut root = Value::Mapping(HashMap::new());
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = line.strip_prefix("export ").map_or(line, str::trim_start);
+            let (key, raw) = line.split_once('=').ok_or(LoaderError::ParseFailed)?;
+            let key = key.trim();
+            if key.is_empty() {
+                return Err(LoaderError::ParseFailed);
+            }
+            let value = Value::String(parse_value(raw.trim())?);
+
+            let path: Vec<&str> = if self.nested {
+                key.split(NESTING_SEPARATOR).collect()
+            } else {
+                vec![key]
+            };
+            let mut nested = value;
+            for segment in path.iter().rev() {
+                nested = Value::Mapping(HashMap::from([(segment.to_string(), nested)]));
+            }
+            root.merge(&nested);
+        }
+        Ok(root)
+    }
+}
+
+/// Parses the right-hand side of a `KEY=value` line.
+fn parse_value(raw: &str) -> Result<String, LoaderError> {
+    if let Some(rest) = raw.strip_prefix('\'') {
+        let end = rest.find('\'').ok_or(LoaderError::ParseFailed)?;
+        return Ok(rest[..end].to_string());
+    }
+    if let Some(rest) = raw.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = rest.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return Ok(value),
+                '\\' => match chars.next().ok_or(LoaderError::ParseFailed)? {
+                    'n' => value.push('\n'),
+                    't' => value.push('\t'),
+                    other => value.push(other),
+                },
+                c => value.push(c),
+            }
+        }
+        return Err(LoaderError::ParseFailed);
+    }
+    let value = raw.split_once(" #").map_or(raw, |(value, _)| value);
+    Ok(value.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(content: &str) -> Value {
+        EnvLoader::default().load(content).unwrap()
+    }
+
+    /// Returns the string at a `.`-separated path of nested mappings.
+    fn get<'a>(value: &'a Value, path: &str) -> Option<&'a str> {
+        path.split('.')
+            .try_fold(value, |current, key| current.get(key))?
+            .as_str()
+            .map(String::as_str)
+    }
+
+    #[test]
+    fn test_quoted_values_may_contain_equals() {
+        let value = load("DSN=\"host=db port=5432\"\nTOKEN='a=b#c'\nMSG=\"say \\\"hi\\\"\\n\"\n");
+        assert_eq!(get(&value, "DSN"), Some("host=db port=5432"));
+        assert_eq!(get(&value, "TOKEN"), Some("a=b#c"));
+        assert_eq!(get(&value, "MSG"), Some("say \"hi\"\n"));
+    }
+
+    #[test]
+    fn test_comments_and_blank_lines_are_skipped() {
+        let value = load("# database\n\nHOST=localhost # inline\n  # indented comment\nURL=http://x/#anchor\n");
+        assert_eq!(value, Value::Mapping(HashMap::from([
+            ("HOST".to_string(), Value::String("localhost".to_string())),
+            ("URL".to_string(), Value::String("http://x/#anchor".to_string())),
+        ])));
+    }
+
+    #[test]
+    fn test_export_prefix() {
+        let value = load("export FOO=bar\nexport  EMPTY=\n");
+        assert_eq!(get(&value, "FOO"), Some("bar"));
+        assert_eq!(get(&value, "EMPTY"), Some(""));
+    }
+
+    #[test]
+    fn test_nested_keys() {
+        let value = EnvLoader::nested().load("DB__HOST=db\nDB__PORT=5432\nNAME=api\n").unwrap();
+        assert_eq!(get(&value, "DB.HOST"), Some("db"));
+        assert_eq!(get(&value, "DB.PORT"), Some("5432"));
+        assert_eq!(get(&value, "NAME"), Some("api"));
+
+        // Without nesting, keys are kept as written
+        assert_eq!(get(&load("DB__HOST=db"), "DB__HOST"), Some("db"));
+    }
+
+    #[test]
+    fn test_malformed_lines_fail() {
+        let loader = EnvLoader::default();
+        assert!(loader.load("NOT_AN_ASSIGNMENT").is_err());
+        assert!(loader.load("=value").is_err());
+        assert!(loader.load("KEY=\"unterminated").is_err());
+    }
+}
@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+use crate::{loader::{Loader, LoaderError}, Value};
+
+#[derive(Debug)]
+pub struct JsonLoader {}
+
+impl Loader for JsonLoader {
+    fn ext(&self) -> &'static str {
+        "json"
+    }
+
+    fn load(&self, content: &str) -> Result<Value, LoaderError> {
+        serde_json::from_str(content).map_err(|_| LoaderError::ParseFailed)
+    }
+}
+
+pub fn from_json(json_value: serde_json::Value) -> Value {
+    match json_value {
+        serde_json::Value::String(s) => Value::String(s),
+        serde_json::Value::Array(items) => Value::Sequence(items.into_iter().map(from_json).collect()),
+        serde_json::Value::Object(map) => Value::Mapping(
+            map.into_iter()
+                .map(|(key, value)| (key, from_json(value)))
+                .collect::<HashMap<_, _>>(),
+        ),
+
+        // Handle numbers - distinguish between integers and floats, like `from_yaml`
+        serde_json::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Value::Int(i)
+            } else if let Some(u) = n.as_u64() {
+                // Handle large unsigned integers that don't fit in i64
+                Value::Int(u as i64)
+            } else {
+                Value::Float(n.as_f64().unwrap_or(0.0))
+            }
+        }
+        serde_json::Value::Bool(b) => Value::Boolean(b),
+        serde_json::Value::Null => Value::Null,
+    }
+}
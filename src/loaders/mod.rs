@@ -1 +1,3 @@
+pub mod env;
+pub mod json;
 pub mod yaml;
\ No newline at end of file
@@ -1,50 +1,364 @@
-use crate::fs::local::BasicFsFileProvider;
-use crate::{config::LocalAppState, metrics, utils::GetError};
+use crate::fs::source::SourceFileProvider;
+use crate::response_cache::ResponseKey;
+use crate::utils::{
+    Readiness, RenderQuery, RenderedConfig, negotiate_format, normalize_path, parse_overlay, request_id, split_key_path,
+    with_diagnostics, with_render_meta,
+};
+use crate::writer::{AUTO_FORMAT, ValueWriter, json::JsonWriter};
+use crate::writer::k8s::resource_name;
+use crate::writer::bundle::{BundleError, env_bundle, parse_bundle_spec};
+use crate::validate::validate_all;
+use crate::{Value, config::LocalAppState, metrics, peers::PeerNotifier, utils::GetError};
 
 use std::time::Instant;
+use serde::Deserialize;
 use xitca_web::handler::params::Params;
+use xitca_web::handler::query::Query;
 use xitca_web::handler::state::StateRef;
+use xitca_web::http::HeaderMap;
 
-pub async fn get_data(
-    Params((format, path)): Params<(String, String)>,
-    StateRef(state): StateRef<'_, LocalAppState<BasicFsFileProvider>>,
-) -> Result<String, GetError> {
-    let start = Instant::now();
-
-    let rendered = state
+/// Renders the config at `path`, after normalizing it to a loaded key.
+///
+/// When `meta` is set and the server was started with `--render-meta`, a
+/// `_meta` key recording the source is added. `query` selects isolated
+/// rendering and schema validation (always on with `--strict-schemas`).
+async fn render(
+    state: &LocalAppState<SourceFileProvider>,
+    path: &str,
+    meta: bool,
+    query: &RenderQuery,
+) -> Result<Value, GetError> {
+    let key = state
         .dag
-        .get_rendered(&path)
-        .await
-        .map_err(|e| GetError::RenderError {
-            path: path.clone(),
-            reason: e.to_string(),
+        .resolve_key(path, state.case_insensitive_paths)
+        .map_err(|suggestions| GetError::ConfigNotFound {
+            path: path.to_string(),
+            suggestions,
         })?;
+    let render_error = |e: anyhow::Error| GetError::RenderError {
+        path: key.clone(),
+        reason: e.to_string(),
+    };
+    let rendered = if query.isolated() {
+        let (rendered, failures) = state.dag.get_rendered_isolated(&key).await.map_err(render_error)?;
+        if query.diagnostics {
+            with_diagnostics(rendered, &failures)
+        } else {
+            rendered
+        }
+    } else {
+        state
+            .dag
+            .get_rendered_with_policy(&key, state.unresolved)
+            .await
+            .map_err(render_error)?
+    };
+    if query.validate || state.strict_schemas {
+        let errors = state.dag.schema_violations(&key, &rendered);
+        if !errors.is_empty() {
+            return Err(GetError::ValidationFailed { path: key, errors });
+        }
+    }
+    if meta && state.render_meta {
+        return Ok(with_render_meta(rendered, &key, None));
+    }
+    Ok(rendered)
+}
 
-    let result = state
+/// Serializes `value` with the writer registered for `format`, naming the
+/// document `name` for formats that embed one.
+fn write_value(
+    state: &LocalAppState<SourceFileProvider>,
+    format: &str,
+    value: &Value,
+    name: &str,
+) -> Result<String, GetError> {
+    state
         .writer
-        .write(&format, &rendered)
+        .write_named(format, value, name)
         .ok_or_else(|| GetError::BadRequest {
             reason: format!("unknown output format: '{format}'"),
         })?
         .map_err(|e| GetError::InternalError {
             reason: format!("failed to serialize to '{format}': {e}"),
+        })
+}
+
+/// Resolves the auto format `_` to the format `path` declares (or the default).
+///
+/// Any other format is returned unchanged.
+fn resolve_format(state: &LocalAppState<SourceFileProvider>, path: &str, format: &str) -> String {
+    if format != AUTO_FORMAT {
+        return format.to_string();
+    }
+    let declared = state
+        .dag
+        .resolve_key(path, state.case_insensitive_paths)
+        .ok()
+        .and_then(|key| state.dag.output_format(&key));
+    state.writer.auto_format(declared.as_deref()).to_string()
+}
+
+/// Returns the cache TTL `path` declares, if any.
+fn output_ttl(state: &LocalAppState<SourceFileProvider>, path: &str) -> Option<u64> {
+    let key = state.dag.resolve_key(path, state.case_insensitive_paths).ok()?;
+    state.dag.output_ttl(&key)
+}
+
+/// Renders and serializes `path`, serving repeated requests from the response cache.
+///
+/// Isolated, explicitly validated and explicitly named renders are never
+/// cached, nor is pretty-printed JSON or anything with `--render-meta`.
+async fn render_cached(
+    state: &LocalAppState<SourceFileProvider>,
+    path: &str,
+    format: &str,
+    query: &RenderQuery,
+) -> Result<String, GetError> {
+    let name = query.document_name(path);
+    if state.render_meta || query.isolated() || query.validate || query.name.is_some() || query.pretty_json(format) {
+        let rendered = render(state, path, true, query).await?;
+        if query.pretty_json(format) {
+            return JsonWriter::pretty().to_str(&rendered).map_err(|e| GetError::InternalError {
+                reason: format!("failed to serialize to '{format}': {e}"),
+            });
+        }
+        return write_value(state, format, &rendered, &name);
+    }
+
+    let key = ResponseKey {
+        commit: String::new(),
+        path: path.to_string(),
+        format: format.to_string(),
+        scope: String::new(),
+    };
+    if let Some(body) = state.response_cache.get(&key) {
+        return Ok(body);
+    }
+    let rendered = render(state, path, true, query).await?;
+    let body = write_value(state, format, &rendered, &name)?;
+    state.response_cache.insert(key, &body);
+    Ok(body)
+}
+
+#[tracing::instrument(
+    name = "get_data",
+    skip_all,
+    fields(path = %path, format = tracing::field::Empty, request_id = request_id(&headers))
+)]
+pub async fn get_data(
+    headers: HeaderMap,
+    Params((format, path)): Params<(String, String)>,
+    Query(query): Query<RenderQuery>,
+    StateRef(state): StateRef<'_, LocalAppState<SourceFileProvider>>,
+) -> Result<RenderedConfig, GetError> {
+    let start = Instant::now();
+    let format = resolve_format(state, &path, &format);
+    tracing::Span::current().record("format", format.as_str());
+
+    let result = render_cached(state, &path, &format, &query).await;
+
+    metrics::record_render(&format, result.is_ok(), start.elapsed());
+    Ok(RenderedConfig {
+        body: result?,
+        content_type: state.writer.content_type(&format),
+        ttl: output_ttl(state, &path),
+        ..Default::default()
+    })
+}
+
+/// Renders a config in the format the `Accept` header asks for, or the
+/// server's default output format.
+#[tracing::instrument(
+    name = "get_data",
+    skip_all,
+    fields(path = %path, format = tracing::field::Empty, request_id = request_id(&headers))
+)]
+pub async fn get_data_default_format(
+    headers: HeaderMap,
+    Params(path): Params<String>,
+    Query(query): Query<RenderQuery>,
+    StateRef(state): StateRef<'_, LocalAppState<SourceFileProvider>>,
+) -> Result<RenderedConfig, GetError> {
+    let start = Instant::now();
+    let format = negotiate_format(&state.writer, &headers)?;
+    tracing::Span::current().record("format", format);
+
+    let result = render_cached(state, &path, format, &query).await;
+
+    metrics::record_render(format, result.is_ok(), start.elapsed());
+    Ok(RenderedConfig {
+        body: result?,
+        content_type: state.writer.content_type(format),
+        ttl: output_ttl(state, &path),
+        ..Default::default()
+    })
+}
+
+/// Renders a config with an ad-hoc overlay (JSON or YAML request body)
+/// deep-merged over it, for previewing a change without editing files.
+pub async fn post_overlay(
+    Params((format, path)): Params<(String, String)>,
+    StateRef(state): StateRef<'_, LocalAppState<SourceFileProvider>>,
+    body: String,
+) -> Result<RenderedConfig, GetError> {
+    let start = Instant::now();
+    let format = resolve_format(state, &path, &format);
+
+    let overlay = parse_overlay(&state.multiloader, &body)?;
+    let mut rendered = render(state, &path, true, &RenderQuery::default()).await?;
+    rendered.merge(&overlay);
+    let result = write_value(state, &format, &rendered, &resource_name(&path));
+
+    metrics::record_render(&format, result.is_ok(), start.elapsed());
+    Ok(RenderedConfig {
+        body: result?,
+        content_type: state.writer.content_type(&format),
+        ttl: None,
+        ..Default::default()
+    })
+}
+
+/// Renders every config under a folder, deep-merged into a single document.
+///
+/// Configs are merged in alphabetical order of their keys, later ones taking
+/// precedence, so a service split across `services/api/*.yaml` is fetched in
+/// one request.
+pub async fn get_merged(
+    Params((format, dir)): Params<(String, String)>,
+    StateRef(state): StateRef<'_, LocalAppState<SourceFileProvider>>,
+) -> Result<RenderedConfig, GetError> {
+    let start = Instant::now();
+    let format = resolve_format(state, &dir, &format);
+
+    let keys = state.dag.keys_under(&dir);
+    if keys.is_empty() {
+        return Err(GetError::ConfigNotFound {
+            path: dir,
+            suggestions: Vec::new(),
         });
+    }
+    let mut merged = Value::Mapping(Default::default());
+    for key in &keys {
+        // Metadata would be overwritten by each file, so it's added once below
+        let rendered = render(state, key, false, &RenderQuery::default()).await?;
+        merged.merge(&rendered);
+    }
+    if state.render_meta {
+        merged = with_render_meta(merged, &normalize_path(&dir), None);
+    }
+    let result = write_value(state, &format, &merged, &resource_name(&dir));
+
+    metrics::record_render(&format, result.is_ok(), start.elapsed());
+    Ok(RenderedConfig {
+        body: result?,
+        content_type: state.writer.content_type(&format),
+        ttl: None,
+        ..Default::default()
+    })
+}
+
+/// Query string of [`get_value`].
+#[derive(Debug, Default, Deserialize)]
+pub struct ValueQuery {
+    /// Dotted key within the config; when set, the whole path is the config path.
+    pub key: Option<String>,
+}
+
+/// Renders a config and serves a single value within it, e.g.
+/// `/value/json/common/database.host` or `/value/json/common/database?key=host`.
+pub async fn get_value(
+    Params((format, path)): Params<(String, String)>,
+    Query(query): Query<ValueQuery>,
+    StateRef(state): StateRef<'_, LocalAppState<SourceFileProvider>>,
+) -> Result<RenderedConfig, GetError> {
+    let start = Instant::now();
+
+    let (path, key) = match &query.key {
+        Some(key) => (path.as_str(), key.as_str()),
+        None => split_key_path(&path).ok_or_else(|| GetError::BadRequest {
+            reason: format!("'{path}' has no key: use '<config>.<key>' or '?key='"),
+        })?,
+    };
+    let format = resolve_format(state, path, &format);
+    let rendered = render(state, path, false, &RenderQuery::default()).await?;
+    let value = rendered.get_path(key).ok_or_else(|| GetError::ConfigNotFound {
+        path: format!("{}.{key}", normalize_path(path)),
+        suggestions: Vec::new(),
+    })?;
+    let result = write_value(state, &format, value, &resource_name(path));
 
     metrics::record_render(&format, result.is_ok(), start.elapsed());
+    Ok(RenderedConfig {
+        body: result?,
+        content_type: state.writer.content_type(&format),
+        ttl: output_ttl(state, path),
+        ..Default::default()
+    })
+}
+
+/// Query string of [`get_env_bundle`].
+#[derive(Debug, Deserialize)]
+pub struct EnvBundleQuery {
+    /// Comma-separated config paths, each optionally prefixed: `db:common/database,a`.
+    pub paths: String,
+    /// `env` (default) or `docker-env`.
+    pub format: Option<String>,
+}
+
+/// Renders several configs into a single env file body.
+pub async fn get_env_bundle(
+    Query(query): Query<EnvBundleQuery>,
+    StateRef(state): StateRef<'_, LocalAppState<SourceFileProvider>>,
+) -> Result<String, GetError> {
+    let start = Instant::now();
+    let format = query.format.as_deref().unwrap_or("env");
+
+    let mut parts = Vec::new();
+    for part in parse_bundle_spec(&query.paths) {
+        // Metadata would collide across parts, so bundles never carry it
+        let rendered = render(state, &part.path, false, &RenderQuery::default()).await?;
+        parts.push((part, rendered));
+    }
+    if parts.is_empty() {
+        return Err(GetError::BadRequest {
+            reason: "'paths' must list at least one config".to_string(),
+        });
+    }
+
+    let result = env_bundle(format, &parts).map_err(|e| match e {
+        BundleError::Collision { .. } => GetError::Conflict { reason: e.to_string() },
+        BundleError::UnsupportedFormat(_) => GetError::BadRequest { reason: e.to_string() },
+    });
+
+    metrics::record_render(format, result.is_ok(), start.elapsed());
     result
 }
 
+/// Reloads all configs, then notifies peers unless the reload came from a peer.
 pub async fn reload(
-    StateRef(state): StateRef<'_, LocalAppState<BasicFsFileProvider>>,
+    headers: HeaderMap,
+    StateRef(state): StateRef<'_, LocalAppState<SourceFileProvider>>,
 ) -> Result<String, GetError> {
     let result = state.dag.reload().await;
     metrics::record_reload(result.is_ok());
     result.expect("failed to reload");
+    state.response_cache.clear();
+    if !PeerNotifier::is_cascaded(&headers) {
+        state.peers.notify().await;
+    }
     Ok("OK".to_string())
 }
 
+/// Validates every loaded config, like `konf validate`, to report readiness.
+///
+/// Renders are cached, so only the first check after a reload renders anything.
+pub async fn ready(StateRef(state): StateRef<'_, LocalAppState<SourceFileProvider>>) -> Readiness {
+    validate_all(&state.dag, &state.dag.keys()).await.into()
+}
+
 pub async fn metrics_handler(
-    StateRef(state): StateRef<'_, LocalAppState<BasicFsFileProvider>>,
+    StateRef(state): StateRef<'_, LocalAppState<SourceFileProvider>>,
 ) -> String {
     state.metrics.render()
 }
@@ -0,0 +1,171 @@
+//! Code action provider for konf-lsp
+//!
+//! Offers quick fixes for diagnostics: a reference to a config that is not
+//! imported gets an "Add '<file>' to imports" action, inserting the import
+//! (and the `<!>` and `import` sections if needed).
+
+use std::collections::HashSet;
+
+use tower_lsp::lsp_types::*;
+
+use super::parser::{KonfDocument, METADATA_KEY, is_in_import_section, placeholder_path};
+use super::workspace::Workspace;
+
+/// Get the quick fixes for the diagnostics of a code action request
+pub fn get_code_actions(ws: &Workspace, uri: &Url, diagnostics: &[Diagnostic]) -> Vec<CodeActionOrCommand> {
+    let Some(doc) = ws.get_document(uri) else {
+        return vec![];
+    };
+
+    let mut offered = HashSet::new();
+    let mut actions = vec![];
+    for diagnostic in diagnostics {
+        if diagnostic.code != Some(NumberOrString::String("unimported-reference".to_string())) {
+            continue;
+        }
+        // Without an alias, an import is referenced by its path, so only config keys can be fixed
+        let Some(file) = referenced_alias(doc, diagnostic.range).filter(|file| ws.has_key(file)) else {
+            continue;
+        };
+        if !offered.insert(file.clone()) {
+            continue;
+        }
+
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: format!("Add '{file}' to imports"),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![diagnostic.clone()]),
+            edit: Some(WorkspaceEdit {
+                changes: Some([(uri.clone(), vec![import_edit(&doc.content, &file)])].into()),
+                ..Default::default()
+            }),
+            is_preferred: Some(true),
+            ..Default::default()
+        }));
+    }
+    actions
+}
+
+/// Returns the alias of the `${...}` reference spanning `range`.
+fn referenced_alias(doc: &KonfDocument, range: Range) -> Option<String> {
+    let line = doc.content.lines().nth(range.start.line as usize)?;
+    let reference = line.get(range.start.character as usize..range.end.character as usize)?;
+    let content = reference.strip_prefix("${")?.strip_suffix('}')?;
+    let path = placeholder_path(content)?;
+    Some(path.split('.').next()?.to_string())
+}
+
+/// Builds the edit importing `file` in a document with `content`.
+fn import_edit(content: &str, file: &str) -> TextEdit {
+    let lines: Vec<&str> = content.lines().collect();
+    let indent_of = |line: &str| line.len() - line.trim_start().len();
+    let metadata_header = format!("{METADATA_KEY}:");
+
+    let Some(metadata) = lines.iter().position(|line| line.trim_end() == metadata_header) else {
+        return TextEdit::new(
+            Range::new(Position::new(0, 0), Position::new(0, 0)),
+            format!("{metadata_header}\n  import:\n    {file}:\n"),
+        );
+    };
+
+    let import = (metadata + 1..lines.len())
+        .find(|&i| lines[i].trim() == "import:" && is_in_import_section(content, i));
+    match import {
+        Some(import) => {
+            // Line up with the existing entries
+            let indent = lines
+                .get(import + 1)
+                .filter(|line| !line.trim().is_empty() && is_in_import_section(content, import + 1))
+                .map_or(indent_of(lines[import]) + 2, |line| indent_of(line));
+            insert_after(&lines, import, &format!("{}{file}:", " ".repeat(indent)))
+        }
+        None => {
+            let indent = lines
+                .get(metadata + 1)
+                .map(|line| indent_of(line))
+                .filter(|&indent| indent > 0)
+                .unwrap_or(2);
+            insert_after(
+                &lines,
+                metadata,
+                &format!("{}import:\n{}{file}:", " ".repeat(indent), " ".repeat(indent * 2)),
+            )
+        }
+    }
+}
+
+/// Inserts `text` as new lines after `line`.
+fn insert_after(lines: &[&str], line: usize, text: &str) -> TextEdit {
+    if line + 1 < lines.len() {
+        let start = Position::new(line as u32 + 1, 0);
+        TextEdit::new(Range::new(start, start), format!("{text}\n"))
+    } else {
+        let end = Position::new(line as u32, lines[line].len() as u32);
+        TextEdit::new(Range::new(end, end), format!("\n{text}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsp::diagnostics::get_diagnostics;
+
+    /// Builds a workspace with a `database` config and an `app` document with `app_content`
+    fn workspace_with(app_content: &str) -> (Workspace, Url) {
+        let mut ws = Workspace::new();
+        let db = Url::parse("file:///configs/database.yaml").unwrap();
+        ws.update_document(&db, "host: localhost\n");
+        let app = Url::parse("file:///configs/app.yaml").unwrap();
+        ws.update_document(&app, app_content);
+        (ws, app)
+    }
+
+    /// Returns the single action's title and edit
+    fn single_action(ws: &Workspace, app: &Url) -> (String, TextEdit) {
+        let actions = get_code_actions(ws, app, &get_diagnostics(ws, app));
+        assert_eq!(actions.len(), 1, "{actions:?}");
+        let CodeActionOrCommand::CodeAction(action) = &actions[0] else {
+            panic!("expected a code action");
+        };
+        let edits = &action.edit.as_ref().unwrap().changes.as_ref().unwrap()[app];
+        (action.title.clone(), edits[0].clone())
+    }
+
+    #[test]
+    fn test_adds_entry_to_existing_import_section() {
+        let (ws, app) = workspace_with("<!>:\n  import:\n    other: o\nhost: ${database.host}\n");
+
+        let (title, edit) = single_action(&ws, &app);
+
+        assert_eq!(title, "Add 'database' to imports");
+        assert_eq!(edit.range.start, Position::new(2, 0));
+        assert_eq!(edit.new_text, "    database:\n");
+    }
+
+    #[test]
+    fn test_creates_import_section() {
+        let (ws, app) = workspace_with("<!>:\n  auth:\n    - token\nhost: ${database.host}\n");
+
+        let (_, edit) = single_action(&ws, &app);
+
+        assert_eq!(edit.range.start, Position::new(1, 0));
+        assert_eq!(edit.new_text, "  import:\n    database:\n");
+    }
+
+    #[test]
+    fn test_creates_metadata_section() {
+        let (ws, app) = workspace_with("host: ${database.host}\nport: ${database.port}\n");
+
+        let (_, edit) = single_action(&ws, &app);
+
+        assert_eq!(edit.range.start, Position::new(0, 0));
+        assert_eq!(edit.new_text, "<!>:\n  import:\n    database:\n");
+    }
+
+    #[test]
+    fn test_unknown_config_gets_no_action() {
+        let (ws, app) = workspace_with("host: ${db.host}\n");
+
+        assert!(get_code_actions(&ws, &app, &get_diagnostics(&ws, &app)).is_empty());
+    }
+}
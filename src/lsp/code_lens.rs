@@ -0,0 +1,96 @@
+//! Code lens provider for konf-lsp
+//!
+//! Shows above each config how many files import it, so authors know the
+//! impact of editing a widely used base config. Clicking the lens lists them.
+
+use tower_lsp::lsp_types::*;
+
+use super::workspace::Workspace;
+
+/// Client command listing locations, understood by VS Code and most LSP clients
+const SHOW_REFERENCES_COMMAND: &str = "editor.action.showReferences";
+
+/// Get the code lenses for a document: a single "referenced by N files" lens on its first line
+pub fn get_code_lenses(ws: &Workspace, uri: &Url) -> Vec<CodeLens> {
+    let Some(doc) = ws.get_document(uri) else {
+        return vec![];
+    };
+
+    let start = Position::new(0, 0);
+    let locations: Vec<Location> = ws
+        .get_referencing_uris(&doc.key)
+        .into_iter()
+        .filter_map(|referencing| Url::parse(referencing).ok())
+        .map(|referencing| Location::new(referencing, Range::new(start, start)))
+        .collect();
+
+    let title = match locations.len() {
+        1 => "referenced by 1 file".to_string(),
+        n => format!("referenced by {n} files"),
+    };
+    // Nothing to list for an unreferenced config
+    let arguments = (!locations.is_empty()).then(|| {
+        vec![
+            serde_json::json!(uri),
+            serde_json::json!(start),
+            serde_json::json!(locations),
+        ]
+    });
+
+    vec![CodeLens {
+        range: Range::new(start, start),
+        command: Some(Command {
+            title,
+            command: if arguments.is_some() { SHOW_REFERENCES_COMMAND.to_string() } else { String::new() },
+            arguments,
+        }),
+        data: None,
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a workspace where `base` is imported by two of three other documents
+    fn fixture_workspace() -> (Workspace, Url, Url) {
+        let mut ws = Workspace::new();
+        let base = Url::parse("file:///configs/base.yaml").unwrap();
+        ws.update_document(&base, "name: base\n");
+        let key = ws.get_document(&base).unwrap().key.clone();
+
+        for name in ["api", "worker"] {
+            let uri = Url::parse(&format!("file:///configs/{name}.yaml")).unwrap();
+            ws.update_document(&uri, &format!("<!>:\n  import:\n    {key}: b\nname: ${{b.name}}\n"));
+        }
+        let standalone = Url::parse("file:///configs/standalone.yaml").unwrap();
+        ws.update_document(&standalone, "name: standalone\n");
+        (ws, base, standalone)
+    }
+
+    #[test]
+    fn test_lens_counts_referencing_files() {
+        let (ws, base, _) = fixture_workspace();
+
+        let lenses = get_code_lenses(&ws, &base);
+        assert_eq!(lenses.len(), 1);
+        let command = lenses[0].command.as_ref().unwrap();
+        assert_eq!(command.title, "referenced by 2 files");
+        assert_eq!(command.command, SHOW_REFERENCES_COMMAND);
+
+        let locations: Vec<Location> =
+            serde_json::from_value(command.arguments.as_ref().unwrap()[2].clone()).unwrap();
+        let paths: Vec<&str> = locations.iter().map(|l| l.uri.path()).collect();
+        assert_eq!(paths, vec!["/configs/api.yaml", "/configs/worker.yaml"]);
+    }
+
+    #[test]
+    fn test_lens_for_unreferenced_file() {
+        let (ws, _, standalone) = fixture_workspace();
+
+        let lenses = get_code_lenses(&ws, &standalone);
+        let command = lenses[0].command.as_ref().unwrap();
+        assert_eq!(command.title, "referenced by 0 files");
+        assert!(command.arguments.is_none());
+    }
+}
@@ -2,6 +2,7 @@
 //!
 //! Provides autocompletion for:
 //! - Template references: ${file.key.path}
+//! - Template functions after a `|`: ${file.key | trim}
 //! - Import paths in <!>: section
 
 use tower_lsp::lsp_types::*;
@@ -10,6 +11,7 @@ use super::parser::{
     get_template_at_position, is_in_import_section, parse_template_path, CompletionContext,
 };
 use super::workspace::Workspace;
+use crate::functions::registry;
 
 /// Get completion items for the current position
 pub fn get_completions(ws: &Workspace, uri: &Url, position: Position) -> Vec<CompletionItem> {
@@ -174,6 +176,34 @@ fn get_template_completions(
             tracing::info!("Returning {} completion items: {:?}", items.len(), items.iter().map(|i| &i.label).collect::<Vec<_>>());
             items
         }
+        CompletionContext::Function { partial } => {
+            let start_col = position.character - partial.len() as u32;
+            let range = Range {
+                start: Position::new(position.line, start_col),
+                end: position,
+            };
+
+            registry()
+                .names()
+                .into_iter()
+                .filter(|name| name.starts_with(&partial))
+                .filter_map(|name| {
+                    let signature = registry().get(name)?.signature();
+                    Some(CompletionItem {
+                        label: name.to_string(),
+                        kind: Some(CompletionItemKind::FUNCTION),
+                        detail: Some(signature.to_string()),
+                        filter_text: Some(name.to_string()),
+                        text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                            range,
+                            new_text: name.to_string(),
+                        })),
+                        ..Default::default()
+                    })
+                })
+                .collect()
+        }
+        CompletionContext::FunctionArgs => vec![],
     }
 }
 
@@ -320,3 +350,47 @@ pub fn hover(ws: &Workspace, uri: &Url, position: Position) -> Option<Hover> {
 fn format_yaml_preview(value: &serde_yaml::Value) -> String {
     serde_yaml::to_string(value).unwrap_or_else(|_| "...".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn completions_at(line: &str) -> Vec<CompletionItem> {
+        let mut ws = Workspace::new();
+        let db = Url::parse("file:///configs/database.yaml").unwrap();
+        ws.update_document(&db, "host: localhost\n");
+        let app = Url::parse("file:///configs/app.yaml").unwrap();
+        ws.update_document(&app, &format!("<!>:\n  import:\n    database: a\n{line}\n"));
+        get_completions(&ws, &app, Position::new(3, line.len() as u32))
+    }
+
+    fn labels(items: &[CompletionItem]) -> Vec<&str> {
+        items.iter().map(|item| item.label.as_str()).collect()
+    }
+
+    #[test]
+    fn test_function_completion_filters_by_prefix() {
+        let items = completions_at("host: ${a.host | tr");
+
+        assert_eq!(labels(&items), vec!["trim"]);
+        assert_eq!(items[0].kind, Some(CompletionItemKind::FUNCTION));
+        assert_eq!(items[0].detail.as_deref(), Some("string -> string (args: 0)"));
+        let Some(CompletionTextEdit::Edit(edit)) = &items[0].text_edit else {
+            panic!("expected a text edit");
+        };
+        assert_eq!(edit.range.start, Position::new(3, 17));
+    }
+
+    #[test]
+    fn test_function_completion_right_after_pipe() {
+        let items = completions_at("host: ${a.host |");
+
+        assert_eq!(labels(&items), registry().names());
+    }
+
+    #[test]
+    fn test_no_completion_in_function_arguments() {
+        assert!(completions_at("host: ${a.host | default:\"tr").is_empty());
+        assert_eq!(labels(&completions_at("host: ${a.host | default(\"x|y\") | up")), vec!["upper"]);
+    }
+}
@@ -2,15 +2,21 @@
 //!
 //! Provides error and warning diagnostics for:
 //! - Invalid import references
+//! - Unused imports
 //! - Invalid template references
 //! - Circular imports
 //! - Type warnings (complex types in string interpolation)
+//! - Invalid function calls (unknown names, wrong argument counts, type mismatches)
 
 use std::collections::HashSet;
 
 use tower_lsp::lsp_types::*;
 
-use super::parser::parse_template_path;
+use crate::functions::{registry, FunctionError};
+
+use super::parser::{
+    env_var_name, parse_template_path, placeholder_functions, placeholder_path, placeholder_reference_args,
+};
 use super::workspace::Workspace;
 
 /// Get diagnostics for a document
@@ -40,9 +46,15 @@ pub fn get_diagnostics(ws: &Workspace, uri: &Url) -> Vec<Diagnostic> {
     // Check imports
     diagnostics.extend(check_imports(ws, doc));
 
+    // Check for imports no reference uses
+    diagnostics.extend(check_unused_imports(doc));
+
     // Check template references
     diagnostics.extend(check_template_refs(ws, doc));
 
+    // Check pipe function calls
+    diagnostics.extend(check_template_functions(ws, doc));
+
     // Check for circular imports
     diagnostics.extend(check_circular_imports(ws, doc));
 
@@ -102,12 +114,58 @@ fn check_imports(ws: &Workspace, doc: &super::parser::KonfDocument) -> Vec<Diagn
     diagnostics
 }
 
+/// Check that every import is used by a reference, a function argument or `extends`
+fn check_unused_imports(doc: &super::parser::KonfDocument) -> Vec<Diagnostic> {
+    let mut used: HashSet<String> = doc.metadata.extends.iter().cloned().collect();
+    for tref in &doc.template_refs {
+        let args = placeholder_reference_args(&tref.path);
+        let paths = placeholder_path(&tref.path).into_iter().chain(args.iter().map(String::as_str));
+        // An import without alias is referenced by its path, which is then its alias
+        used.extend(paths.filter_map(parse_template_path).map(|(alias, _)| alias));
+    }
+
+    let mut unused: Vec<_> = doc
+        .metadata
+        .imports
+        .values()
+        .filter(|import_info| !used.contains(&import_info.alias))
+        .collect();
+    unused.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut diagnostics = vec![];
+    for import_info in unused {
+        for (line_idx, line) in doc.content.lines().enumerate() {
+            if line.contains(&import_info.path) && super::parser::is_in_import_section(&doc.content, line_idx) {
+                diagnostics.push(Diagnostic {
+                    range: Range {
+                        start: Position::new(line_idx as u32, 0),
+                        end: Position::new(line_idx as u32, line.len() as u32),
+                    },
+                    severity: Some(DiagnosticSeverity::WARNING),
+                    code: Some(NumberOrString::String("unused-import".to_string())),
+                    source: Some("konf-lsp".to_string()),
+                    message: format!("Import '{}' is never used", import_info.alias),
+                    ..Default::default()
+                });
+                break;
+            }
+        }
+    }
+
+    diagnostics
+}
+
 /// Check that all template references are valid
 fn check_template_refs(ws: &Workspace, doc: &super::parser::KonfDocument) -> Vec<Diagnostic> {
     let mut diagnostics = vec![];
 
     for tref in &doc.template_refs {
-        let Some((alias, key_path)) = parse_template_path(&tref.path) else {
+        let path = placeholder_path(&tref.path).unwrap_or(&tref.path);
+        // Environment variables are only known at render time
+        if env_var_name(path).is_some() {
+            continue;
+        }
+        let Some((alias, key_path)) = parse_template_path(path) else {
             continue;
         };
 
@@ -169,32 +227,36 @@ fn check_template_refs(ws: &Workspace, doc: &super::parser::KonfDocument) -> Vec
             .map(|v| v.trim() == format!("${{{}}}", tref.path))
             .unwrap_or(false);
 
-        if !is_exact_match {
+        // A chain ending in a function that returns a scalar (`| to_json`) can be interpolated
+        let returns_scalar = placeholder_functions(&tref.path)
+            .last()
+            .and_then(|(name, _)| registry().get(name))
+            .and_then(|func| func.signature().returns)
+            .is_some_and(|ty| !matches!(ty, "mapping" | "sequence"));
+
+        if !is_exact_match && !returns_scalar {
             // This is string interpolation, check if the type is complex
-            if let Some(value) = ref_doc.get_value_at_path(&path_refs) {
-                if matches!(
-                    value,
-                    serde_yaml::Value::Mapping(_) | serde_yaml::Value::Sequence(_)
-                ) {
-                    diagnostics.push(Diagnostic {
-                        range: Range {
-                            start: Position::new(tref.line as u32, tref.col_start as u32),
-                            end: Position::new(tref.line as u32, tref.col_end as u32),
-                        },
-                        severity: Some(DiagnosticSeverity::WARNING),
-                        code: Some(NumberOrString::String("complex-interpolation".to_string())),
-                        source: Some("konf-lsp".to_string()),
-                        message: format!(
-                            "Cannot interpolate complex type ({}) in string. Use exact match instead.",
-                            if matches!(value, serde_yaml::Value::Mapping(_)) {
-                                "Mapping"
-                            } else {
-                                "Sequence"
-                            }
-                        ),
-                        ..Default::default()
-                    });
-                }
+            if let Some(value) = ref_doc.get_value_at_path(&path_refs)
+                && matches!(value, serde_yaml::Value::Mapping(_) | serde_yaml::Value::Sequence(_))
+            {
+                diagnostics.push(Diagnostic {
+                    range: Range {
+                        start: Position::new(tref.line as u32, tref.col_start as u32),
+                        end: Position::new(tref.line as u32, tref.col_end as u32),
+                    },
+                    severity: Some(DiagnosticSeverity::WARNING),
+                    code: Some(NumberOrString::String("complex-interpolation".to_string())),
+                    source: Some("konf-lsp".to_string()),
+                    message: format!(
+                        "Cannot interpolate complex type ({}) in string. Use exact match instead.",
+                        if matches!(value, serde_yaml::Value::Mapping(_)) {
+                            "Mapping"
+                        } else {
+                            "Sequence"
+                        }
+                    ),
+                    ..Default::default()
+                });
             }
         }
     }
@@ -202,6 +264,83 @@ fn check_template_refs(ws: &Workspace, doc: &super::parser::KonfDocument) -> Vec
     diagnostics
 }
 
+/// Check that pipe functions exist and are called with valid arguments and types
+fn check_template_functions(ws: &Workspace, doc: &super::parser::KonfDocument) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let reg = registry();
+
+    for tref in &doc.template_refs {
+        // The referenced value's type, when it can be resolved
+        let mut input = placeholder_path(&tref.path)
+            .and_then(|path| referenced_value(ws, doc, path))
+            .map(yaml_type_name);
+
+        for (name, arg_count) in placeholder_functions(&tref.path) {
+            let error = match reg.check(&name, arg_count, input) {
+                Ok(returns) => {
+                    input = returns;
+                    continue;
+                }
+                Err(e) => e,
+            };
+            let (code, message) = match &error {
+                FunctionError::UnknownFunction(_) => (
+                    "unknown-function",
+                    format!(
+                        "Unknown function '{name}'. Available functions: {}",
+                        reg.names().join(", ")
+                    ),
+                ),
+                FunctionError::WrongArity { .. } => ("function-arity", error.to_string()),
+                _ => ("function-type", error.to_string()),
+            };
+            diagnostics.push(Diagnostic {
+                range: Range {
+                    start: Position::new(tref.line as u32, tref.col_start as u32),
+                    end: Position::new(tref.line as u32, tref.col_end as u32),
+                },
+                severity: Some(DiagnosticSeverity::ERROR),
+                code: Some(NumberOrString::String(code.to_string())),
+                source: Some("konf-lsp".to_string()),
+                message,
+                ..Default::default()
+            });
+            // Later calls in the chain can't be checked reliably after an error
+            break;
+        }
+    }
+
+    diagnostics
+}
+
+/// Resolve `alias.key.path` to the referenced YAML value, if it is imported and exists
+fn referenced_value<'a>(
+    ws: &'a Workspace,
+    doc: &super::parser::KonfDocument,
+    path: &str,
+) -> Option<&'a serde_yaml::Value> {
+    let (alias, key_path) = parse_template_path(path)?;
+    let import_info = doc.metadata.imports.get(&alias)?;
+    let resolved_path = import_info.resolved_path.as_ref().unwrap_or(&import_info.path);
+    let ref_doc = ws.get_document_by_key(resolved_path)?;
+    let path_refs: Vec<&str> = key_path.iter().map(|s| s.as_str()).collect();
+    ref_doc.get_value_at_path(&path_refs)
+}
+
+/// Type name of a YAML value, as used by function signatures
+fn yaml_type_name(value: &serde_yaml::Value) -> &'static str {
+    match value {
+        serde_yaml::Value::String(_) => "string",
+        serde_yaml::Value::Number(n) if n.is_i64() || n.is_u64() => "int",
+        serde_yaml::Value::Number(_) => "float",
+        serde_yaml::Value::Bool(_) => "boolean",
+        serde_yaml::Value::Null => "null",
+        serde_yaml::Value::Sequence(_) => "sequence",
+        serde_yaml::Value::Mapping(_) => "mapping",
+        serde_yaml::Value::Tagged(tagged) => yaml_type_name(&tagged.value),
+    }
+}
+
 /// Check for circular imports
 fn check_circular_imports(ws: &Workspace, doc: &super::parser::KonfDocument) -> Vec<Diagnostic> {
     let mut diagnostics = vec![];
@@ -270,3 +409,121 @@ fn detect_cycle(
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a workspace with `common/database` and an `app` document with `app_content`
+    fn workspace_with(app_content: &str) -> (Workspace, Url) {
+        let mut ws = Workspace::new();
+        let db = Url::parse("file:///configs/common/database.yaml").unwrap();
+        ws.update_document(&db, "host: localhost\nport: 5432\nsettings:\n  pool: 5\n");
+        // Keys fall back to the file stem without a workspace folder, so alias the import
+        let db_doc = ws.get_document(&db).unwrap().key.clone();
+
+        let app = Url::parse("file:///configs/app.yaml").unwrap();
+        let content = format!("<!>:\n  import:\n    {db_doc}: db\n{app_content}");
+        ws.update_document(&app, &content);
+        (ws, app)
+    }
+
+    fn codes(diagnostics: &[Diagnostic]) -> Vec<String> {
+        diagnostics
+            .iter()
+            .filter_map(|d| match &d.code {
+                Some(NumberOrString::String(code)) => Some(code.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_function_chain_has_no_diagnostics() {
+        let (ws, app) = workspace_with("host: ${db.host | trim | upper}\n");
+        assert!(get_diagnostics(&ws, &app).is_empty());
+    }
+
+    #[test]
+    fn test_env_reference_is_not_flagged() {
+        let (ws, app) = workspace_with("host: ${db.host}\npassword: ${env.DB_PASSWORD | default(\"dev\")}\n");
+        assert!(get_diagnostics(&ws, &app).is_empty());
+    }
+
+    #[test]
+    fn test_complex_interpolation_allowed_through_to_json() {
+        let (ws, app) = workspace_with("blob: \"cfg=${db.settings}\"\nok: \"cfg=${db.settings | to_json}\"\n");
+        let diagnostics = get_diagnostics(&ws, &app);
+
+        assert_eq!(codes(&diagnostics), vec!["complex-interpolation"]);
+        assert_eq!(diagnostics[0].range.start.line, 3);
+    }
+
+    #[test]
+    fn test_unknown_function_is_flagged() {
+        let (ws, app) = workspace_with("host: ${db.host | uppr}\n");
+        let diagnostics = get_diagnostics(&ws, &app);
+
+        assert_eq!(codes(&diagnostics), vec!["unknown-function"]);
+        assert!(diagnostics[0].message.contains("'uppr'"));
+        assert!(diagnostics[0].message.contains("upper"));
+    }
+
+    #[test]
+    fn test_wrong_arity_is_flagged() {
+        let (ws, app) = workspace_with("host: ${db.host | split}\nname: ${db.host | upper:\"x\"}\n");
+        let diagnostics = get_diagnostics(&ws, &app);
+
+        assert_eq!(codes(&diagnostics), vec!["function-arity", "function-arity"]);
+    }
+
+    #[test]
+    fn test_type_mismatch_is_flagged() {
+        let (ws, app) = workspace_with("settings: ${db.settings | upper}\nport: ${db.port | to_int}\n");
+        let diagnostics = get_diagnostics(&ws, &app);
+
+        assert_eq!(codes(&diagnostics), vec!["function-type"]);
+        assert!(diagnostics[0].message.contains("mapping"));
+    }
+
+    #[test]
+    fn test_type_flows_through_chain() {
+        let (ws, app) = workspace_with("hosts: ${db.host | split:\",\" | upper}\n");
+        let diagnostics = get_diagnostics(&ws, &app);
+
+        assert_eq!(codes(&diagnostics), vec!["function-type"]);
+        assert!(diagnostics[0].message.contains("sequence"));
+    }
+
+    #[test]
+    fn test_unused_import_is_flagged() {
+        let (mut ws, app) = workspace_with("host: ${db.host}\n");
+        let cache = Url::parse("file:///configs/cache.yaml").unwrap();
+        ws.update_document(&cache, "host: redis\n");
+        let content = ws.get_document(&app).unwrap().content.replace("    ", "    cache: c\n    ");
+        ws.update_document(&app, &content);
+
+        let diagnostics = get_diagnostics(&ws, &app);
+
+        assert_eq!(codes(&diagnostics), vec!["unused-import"]);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(diagnostics[0].range.start.line, 2);
+        assert!(diagnostics[0].message.contains("'c'"));
+    }
+
+    #[test]
+    fn test_every_import_used() {
+        let mut ws = Workspace::new();
+        for (file, content) in [("db", "host: localhost\n"), ("cache", "host: redis\n"), ("base", "port: 80\n")] {
+            ws.update_document(&Url::parse(&format!("file:///configs/{file}.yaml")).unwrap(), content);
+        }
+        let app = Url::parse("file:///configs/app.yaml").unwrap();
+        // `db` is imported without alias, `cache` is only used as a function argument
+        ws.update_document(
+            &app,
+            "<!>:\n  import:\n    db:\n    cache: c\n    base: b\n  extends:\n    - b\nhost: ${db.host | default(c.host)}\n",
+        );
+
+        assert!(get_diagnostics(&ws, &app).is_empty(), "{:?}", get_diagnostics(&ws, &app));
+    }
+}
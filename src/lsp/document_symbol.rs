@@ -0,0 +1,131 @@
+//! Document symbol provider for konf-lsp
+//!
+//! Builds the outline of a config: one symbol per key, nested like the YAML
+//! mappings they belong to. The `<!>` metadata section is left out.
+
+use serde_yaml::Mapping;
+use serde_yaml::Value as YamlValue;
+use tower_lsp::lsp_types::*;
+
+use super::parser::{KonfDocument, METADATA_KEY, value_preview};
+use super::symbols::range;
+use super::workspace::Workspace;
+
+/// Get the symbol tree of a document
+pub fn get_document_symbols(ws: &Workspace, uri: &Url) -> Vec<DocumentSymbol> {
+    let Some(doc) = ws.get_document(uri) else {
+        return vec![];
+    };
+    let Some(YamlValue::Mapping(map)) = &doc.yaml else {
+        return vec![];
+    };
+
+    let lines: Vec<&str> = doc.content.lines().collect();
+    mapping_symbols(doc, &lines, map, &mut vec![])
+}
+
+/// Symbols of the keys of `map`, found under `path` in `doc`.
+fn mapping_symbols<'a>(
+    doc: &KonfDocument,
+    lines: &[&str],
+    map: &'a Mapping,
+    path: &mut Vec<&'a str>,
+) -> Vec<DocumentSymbol> {
+    map.iter()
+        .filter_map(|(key, value)| {
+            let key = key.as_str()?;
+            if path.is_empty() && key == METADATA_KEY {
+                return None;
+            }
+            path.push(key);
+            let position = doc.find_key_position(path);
+            let children = match value {
+                YamlValue::Mapping(children) => Some(mapping_symbols(doc, lines, children, path)),
+                _ => None,
+            };
+            path.pop();
+
+            let (line, col) = position?;
+            let (kind, detail) = match value {
+                YamlValue::Mapping(_) => (SymbolKind::NAMESPACE, None),
+                YamlValue::Sequence(_) => (SymbolKind::ARRAY, Some(value_preview(value))),
+                _ => (SymbolKind::FIELD, Some(value_preview(value))),
+            };
+            let (end_line, end_col) = block_end(lines, line as usize);
+
+            #[allow(deprecated)]
+            Some(DocumentSymbol {
+                name: key.to_string(),
+                detail,
+                kind,
+                tags: None,
+                deprecated: None,
+                range: Range::new(Position::new(line, col), Position::new(end_line as u32, end_col as u32)),
+                selection_range: range(line as usize, col as usize, key),
+                children,
+            })
+        })
+        .collect()
+}
+
+/// Returns the end of the block of the key on `line`: its last more indented line.
+fn block_end(lines: &[&str], line: usize) -> (usize, usize) {
+    let indent_of = |text: &str| text.len() - text.trim_start().len();
+    let indent = indent_of(lines[line]);
+    let mut end = line;
+    for (i, text) in lines.iter().enumerate().skip(line + 1) {
+        let trimmed = text.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        // Sequence items may sit at the key's indentation
+        if indent_of(text) < indent || (indent_of(text) == indent && !trimmed.starts_with('-')) {
+            break;
+        }
+        end = i;
+    }
+    (end, lines[end].len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Flattens the tree as `(depth, name, kind, selection line)`
+    fn outline(symbols: &[DocumentSymbol], depth: usize, out: &mut Vec<(usize, String, SymbolKind, u32)>) {
+        for symbol in symbols {
+            out.push((depth, symbol.name.clone(), symbol.kind, symbol.selection_range.start.line));
+            outline(symbol.children.as_deref().unwrap_or_default(), depth + 1, out);
+        }
+    }
+
+    #[test]
+    fn test_symbol_tree_mirrors_yaml() {
+        let mut ws = Workspace::new();
+        let uri = Url::parse("file:///configs/app.yaml").unwrap();
+        ws.update_document(
+            &uri,
+            "<!>:\n  import:\n    db:\nname: app\nserver:\n  host: localhost\n  tls:\n    enabled: true\nports:\n- 80\n- 443\n",
+        );
+
+        let symbols = get_document_symbols(&ws, &uri);
+
+        let mut flat = vec![];
+        outline(&symbols, 0, &mut flat);
+        assert_eq!(
+            flat,
+            vec![
+                (0, "name".to_string(), SymbolKind::FIELD, 3),
+                (0, "server".to_string(), SymbolKind::NAMESPACE, 4),
+                (1, "host".to_string(), SymbolKind::FIELD, 5),
+                (1, "tls".to_string(), SymbolKind::NAMESPACE, 6),
+                (2, "enabled".to_string(), SymbolKind::FIELD, 7),
+                (0, "ports".to_string(), SymbolKind::ARRAY, 8),
+            ]
+        );
+        assert_eq!(symbols[1].range, Range::new(Position::new(4, 0), Position::new(7, 17)));
+        assert_eq!(symbols[1].selection_range, Range::new(Position::new(4, 0), Position::new(4, 6)));
+        assert_eq!(symbols[2].range.end, Position::new(10, 5));
+        assert_eq!(symbols[0].detail.as_deref(), Some("\"app\""));
+    }
+}
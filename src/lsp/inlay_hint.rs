@@ -0,0 +1,117 @@
+//! Inlay hint provider for konf-lsp
+//!
+//! Shows the value each `${alias.key}` reference points to right after it,
+//! e.g. `${common/database.host}: "localhost"`. Mappings and sequences are
+//! summarized (`{3 keys}`, `[2 items]`). References piping the value through
+//! functions, environment variables and values that are templates themselves
+//! are only known at render time, so they get no hint.
+
+use serde_yaml::Value as YamlValue;
+use tower_lsp::lsp_types::*;
+
+use super::parser::{env_var_name, parse_template_path, placeholder_functions, placeholder_path, value_preview};
+use super::symbols::resolve_alias;
+use super::workspace::Workspace;
+
+/// Get the inlay hints for the references of a document within `range`
+pub fn get_inlay_hints(ws: &Workspace, uri: &Url, range: Range) -> Vec<InlayHint> {
+    let Some(doc) = ws.get_document(uri) else {
+        return vec![];
+    };
+
+    doc.template_refs
+        .iter()
+        .filter(|tref| (range.start.line..=range.end.line).contains(&(tref.line as u32)))
+        .filter_map(|tref| {
+            let path = placeholder_path(&tref.path)?;
+            if env_var_name(path).is_some() || !placeholder_functions(&tref.path).is_empty() {
+                return None;
+            }
+            let (alias, key_path) = parse_template_path(path)?;
+            let target = ws.get_document_by_key(&resolve_alias(doc, &alias)?)?;
+            let path_refs: Vec<&str> = key_path.iter().map(String::as_str).collect();
+            let value = target.get_value_at_path(&path_refs)?;
+            if matches!(value, YamlValue::String(s) if s.contains("${")) {
+                return None;
+            }
+
+            Some(InlayHint {
+                position: Position::new(tref.line as u32, tref.col_end as u32),
+                label: InlayHintLabel::String(format!(": {}", value_preview(value))),
+                kind: None,
+                text_edits: None,
+                tooltip: None,
+                padding_left: Some(false),
+                padding_right: Some(false),
+                data: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a workspace with `database` and an `app` document with `app_content`, importing it as `db`
+    fn workspace_with(app_content: &str) -> (Workspace, Url) {
+        let mut ws = Workspace::new();
+        let db = Url::parse("file:///configs/database.yaml").unwrap();
+        ws.update_document(&db, "host: localhost\nport: 5432\nsettings:\n  pool: 5\nurl: ${x.url}\n");
+        let app = Url::parse("file:///configs/app.yaml").unwrap();
+        ws.update_document(&app, &format!("<!>:\n  import:\n    database: db\n{app_content}"));
+        (ws, app)
+    }
+
+    fn labels(hints: &[InlayHint]) -> Vec<(u32, u32, String)> {
+        hints
+            .iter()
+            .map(|hint| {
+                let InlayHintLabel::String(label) = &hint.label else {
+                    panic!("expected a string label");
+                };
+                (hint.position.line, hint.position.character, label.clone())
+            })
+            .collect()
+    }
+
+    fn whole_document() -> Range {
+        Range::new(Position::new(0, 0), Position::new(u32::MAX, 0))
+    }
+
+    #[test]
+    fn test_scalar_reference_shows_its_value() {
+        let (ws, app) = workspace_with("host: ${db.host}\nurl: \"${db.host}:${db.port}\"\n");
+
+        let hints = get_inlay_hints(&ws, &app, whole_document());
+
+        assert_eq!(
+            labels(&hints),
+            vec![
+                (3, 16, ": \"localhost\"".to_string()),
+                (4, 16, ": \"localhost\"".to_string()),
+                (4, 27, ": 5432".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_complex_and_unresolvable_references() {
+        let (ws, app) = workspace_with(
+            "settings: ${db.settings}\nloud: ${db.host | upper}\nurl: ${db.url}\nmissing: ${db.nope}\nhome: ${env.HOME}\n",
+        );
+
+        let hints = get_inlay_hints(&ws, &app, whole_document());
+
+        assert_eq!(labels(&hints), vec![(3, 24, ": {1 keys}".to_string())]);
+    }
+
+    #[test]
+    fn test_only_hints_within_range() {
+        let (ws, app) = workspace_with("host: ${db.host}\nport: ${db.port}\n");
+
+        let hints = get_inlay_hints(&ws, &app, Range::new(Position::new(4, 0), Position::new(4, 20)));
+
+        assert_eq!(labels(&hints), vec![(4, 16, ": 5432".to_string())]);
+    }
+}
@@ -1,10 +1,17 @@
 //! LSP (Language Server Protocol) module for konf-provider
 //!
-//! Provides IDE support (autocompletion, diagnostics, go-to-definition) for konf config files.
+//! Provides IDE support (autocompletion, diagnostics, go-to-definition, outline, references, rename, quick fixes, inlay hints) for konf config files.
 
+mod code_action;
+mod code_lens;
 mod completion;
 mod diagnostics;
+mod document_symbol;
+mod inlay_hint;
 mod parser;
+mod references;
+mod rename;
+mod symbols;
 mod workspace;
 
 use std::sync::Arc;
@@ -65,6 +72,7 @@ impl LanguageServer for KonfLsp {
                         "$".to_string(),
                         "{".to_string(),
                         ".".to_string(),
+                        "|".to_string(),
                         "-".to_string(),
                         " ".to_string(),
                     ]),
@@ -74,6 +82,23 @@ impl LanguageServer for KonfLsp {
                 definition_provider: Some(OneOf::Left(true)),
                 // Enable hover
                 hover_provider: Some(HoverProviderCapability::Simple(true)),
+                // Enable "referenced by N files" lenses
+                code_lens_provider: Some(CodeLensOptions {
+                    resolve_provider: Some(false),
+                }),
+                // Enable resolved values shown after references
+                inlay_hint_provider: Some(OneOf::Left(true)),
+                // Enable quick fixes for diagnostics
+                code_action_provider: Some(CodeActionProviderCapability::Options(CodeActionOptions {
+                    code_action_kinds: Some(vec![CodeActionKind::QUICKFIX]),
+                    ..Default::default()
+                })),
+                // Enable the outline of config keys
+                document_symbol_provider: Some(OneOf::Left(true)),
+                // Enable finding references to keys and import aliases
+                references_provider: Some(OneOf::Left(true)),
+                // Enable renaming keys and import aliases
+                rename_provider: Some(OneOf::Left(true)),
                 // Diagnostics are pushed via publish_diagnostics on didOpen/didChange/didSave
                 ..Default::default()
             },
@@ -171,6 +196,70 @@ impl LanguageServer for KonfLsp {
 
         Ok(completion::hover(&ws, uri, position))
     }
+
+    async fn code_lens(&self, params: CodeLensParams) -> Result<Option<Vec<CodeLens>>> {
+        let ws = self.workspace.read().await;
+
+        Ok(Some(code_lens::get_code_lenses(&ws, &params.text_document.uri)))
+    }
+
+    async fn document_symbol(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> Result<Option<DocumentSymbolResponse>> {
+        let ws = self.workspace.read().await;
+
+        Ok(Some(DocumentSymbolResponse::Nested(document_symbol::get_document_symbols(
+            &ws,
+            &params.text_document.uri,
+        ))))
+    }
+
+    async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+        let ws = self.workspace.read().await;
+
+        Ok(Some(inlay_hint::get_inlay_hints(&ws, &params.text_document.uri, params.range)))
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let ws = self.workspace.read().await;
+
+        Ok(Some(code_action::get_code_actions(
+            &ws,
+            &params.text_document.uri,
+            &params.context.diagnostics,
+        )))
+    }
+
+    async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        let uri = &params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+
+        let ws = self.workspace.read().await;
+
+        Ok(Some(references::find_references(
+            &ws,
+            uri,
+            position,
+            params.context.include_declaration,
+        )))
+    }
+
+    async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+        let uri = &params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+
+        if !rename::is_valid_name(&params.new_name) {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                "'{}' is not a valid key or alias name",
+                params.new_name
+            )));
+        }
+
+        let ws = self.workspace.read().await;
+
+        Ok(rename::rename(&ws, uri, position, &params.new_name))
+    }
 }
 
 impl KonfLsp {
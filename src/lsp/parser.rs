@@ -11,7 +11,10 @@ use serde_yaml::Value as YamlValue;
 
 // Re-use utilities from the base lib
 pub use crate::imports::{parse_imports_from_yaml, ImportInfo, METADATA_KEY};
-pub use crate::render_helper::{find_template_refs, template_re, TemplateRef};
+use crate::metadata::{AUTH_KEY, EXTENDS_KEY};
+pub use crate::render_helper::{
+    env_var_name, find_template_refs, is_valid_path_segment, placeholder_functions, placeholder_path, placeholder_reference_args, placeholders, split_chain, TemplateRef,
+};
 
 /// Regex for incomplete template references (for completion): ${path.to.value (no closing brace)
 static INCOMPLETE_TEMPLATE_RE: OnceLock<Regex> = OnceLock::new();
@@ -29,6 +32,8 @@ pub struct KonfMetadata {
     /// List of auth tokens (git mode only)
     #[allow(dead_code)]
     pub auth: Vec<String>,
+    /// Import aliases merged under this file's keys
+    pub extends: Vec<String>,
 }
 
 /// A parsed konf config file
@@ -142,6 +147,9 @@ impl KonfDocument {
                 YamlValue::Mapping(map) => {
                     current = map.get(YamlValue::String(part.to_string()))?;
                 }
+                YamlValue::Sequence(seq) => {
+                    current = seq.get(part.parse::<usize>().ok()?)?;
+                }
                 _ => return None,
             }
         }
@@ -206,22 +214,26 @@ pub struct KeyInfo {
 /// Extract metadata from a konf YAML document
 /// `doc_key` is the key of the current document, used for resolving relative paths
 fn extract_metadata(yaml: &YamlValue, doc_key: &str) -> KonfMetadata {
-    let imports = parse_imports_from_yaml(yaml, Some(doc_key));
+    KonfMetadata {
+        imports: parse_imports_from_yaml(yaml, Some(doc_key)),
+        auth: metadata_strings(yaml, AUTH_KEY),
+        extends: metadata_strings(yaml, EXTENDS_KEY),
+    }
+}
 
-    let auth = yaml
-        .as_mapping()
+/// Read a list of strings under `<!>: <key>:`
+fn metadata_strings(yaml: &YamlValue, key: &str) -> Vec<String> {
+    yaml.as_mapping()
         .and_then(|m| m.get(YamlValue::String(METADATA_KEY.to_string())))
         .and_then(|v| v.as_mapping())
-        .and_then(|m| m.get(YamlValue::String("auth".to_string())))
+        .and_then(|m| m.get(YamlValue::String(key.to_string())))
         .and_then(|v| v.as_sequence())
         .map(|seq| {
             seq.iter()
                 .filter_map(|v| v.as_str().map(String::from))
                 .collect()
         })
-        .unwrap_or_default();
-
-    KonfMetadata { imports, auth }
+        .unwrap_or_default()
 }
 
 /// Extract top-level keys from a YAML document (excluding metadata)
@@ -264,7 +276,7 @@ fn value_type_name(value: &YamlValue) -> String {
 }
 
 /// Get a preview of a YAML value
-fn value_preview(value: &YamlValue) -> String {
+pub fn value_preview(value: &YamlValue) -> String {
     match value {
         YamlValue::String(s) => {
             if s.len() > 50 {
@@ -302,15 +314,17 @@ pub fn get_template_at_position(content: &str, line: usize, col: usize) -> Optio
     let line_content = content.lines().nth(line)?;
 
     // First check complete templates: ${content}
-    for cap in template_re().captures_iter(line_content) {
-        let full_match = cap.get(0)?;
-        if col >= full_match.start() && col <= full_match.end() {
-            let path = cap.name("content")?.as_str();
-            let path_start = full_match.start() + 2; // after "${"
+    for placeholder in placeholders(line_content) {
+        if col >= placeholder.range.start && col <= placeholder.range.end {
+            // An escaped `$${...}` is a literal, not a reference
+            if placeholder.escaped {
+                return None;
+            }
+            let path_start = placeholder.range.start + 2; // after "${"
             let cursor_in_path = col.saturating_sub(path_start);
 
             return Some(TemplateContext {
-                full_path: path.to_string(),
+                full_path: placeholder.content.to_string(),
                 cursor_offset: cursor_in_path,
             });
         }
@@ -354,6 +368,19 @@ impl TemplateContext {
     pub fn completion_context(&self) -> CompletionContext {
         let before = self.path_before_cursor();
 
+        let calls = split_chain(before);
+        if calls.len() > 1 {
+            // After a `|`: typing a function name, or already its arguments
+            let call = calls[calls.len() - 1].trim_start();
+            return if call.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                CompletionContext::Function {
+                    partial: call.to_string(),
+                }
+            } else {
+                CompletionContext::FunctionArgs
+            };
+        }
+
         if !before.contains('.') {
             // Still typing the file name
             CompletionContext::FileName {
@@ -389,6 +416,10 @@ pub enum CompletionContext {
         key_path: Vec<String>,
         partial: String,
     },
+    /// Completing a function name (after a `|`)
+    Function { partial: String },
+    /// Typing the arguments of a function
+    FunctionArgs,
 }
 
 /// Check if position is in the import section
@@ -460,6 +491,19 @@ service:
         assert!(!is_in_import_section(content, 6));
     }
 
+    #[test]
+    fn test_get_value_at_path_indexes_sequences() {
+        let content = "servers:\n  - host: a\n  - host: b\n";
+        let doc = KonfDocument::parse("test".to_string(), content.to_string());
+
+        assert_eq!(
+            doc.get_value_at_path(&["servers", "1", "host"]),
+            Some(&YamlValue::String("b".to_string()))
+        );
+        assert!(doc.get_value_at_path(&["servers", "2", "host"]).is_none());
+        assert!(doc.get_value_at_path(&["servers", "first"]).is_none());
+    }
+
     #[test]
     fn test_find_key_position() {
         let content = r#"host: localhost
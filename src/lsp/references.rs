@@ -0,0 +1,104 @@
+//! "Find all references" provider for konf-lsp
+//!
+//! On a config key, either its definition or a `${alias.key}` segment,
+//! lists every reference to that key across the workspace. On an import
+//! alias, lists the references of the current file using it.
+
+use tower_lsp::lsp_types::*;
+
+use super::symbols::{Symbol, alias_usages, key_definition, key_usages, symbol_at};
+use super::workspace::Workspace;
+
+/// Get the references of the symbol at `position`, with its definition if `include_declaration`
+pub fn find_references(ws: &Workspace, uri: &Url, position: Position, include_declaration: bool) -> Vec<Location> {
+    let Some(doc) = ws.get_document(uri) else {
+        return vec![];
+    };
+
+    match symbol_at(doc, position) {
+        Some(Symbol::Key { file, path }) => {
+            let declaration = include_declaration
+                .then(|| key_definition(ws, &file, &path))
+                .flatten();
+            declaration.into_iter().chain(key_usages(ws, &file, &path)).collect()
+        }
+        Some(Symbol::Alias(alias)) => alias_usages(doc, &alias)
+            .into_iter()
+            .map(|range| Location::new(uri.clone(), range))
+            .collect(),
+        None => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a workspace where `common/database` is imported by `api` as `db` and by `worker` under its path
+    fn create_test_workspace() -> (Workspace, Url, Url, Url) {
+        let mut ws = Workspace::new();
+        let db = Url::parse("file:///configs/common/database.yaml").unwrap();
+        ws.update_document(&db, "host: localhost\nport: 5432\n");
+        // Keys fall back to the file stem without a workspace folder
+        let key = ws.get_document(&db).unwrap().key.clone();
+
+        let api = Url::parse("file:///configs/api.yaml").unwrap();
+        ws.update_document(
+            &api,
+            &format!("<!>:\n  import:\n    {key}: db\nurl: \"${{db.host}}:${{db.port}}\"\nhost: ${{db.host | upper}}\n"),
+        );
+        let worker = Url::parse("file:///configs/worker.yaml").unwrap();
+        ws.update_document(&worker, &format!("<!>:\n  import:\n    {key}:\nhost: ${{{key}.host}}\n"));
+        (ws, db, api, worker)
+    }
+
+    fn positions(locations: &[Location]) -> Vec<(&str, u32, u32, u32)> {
+        locations
+            .iter()
+            .map(|l| (l.uri.path(), l.range.start.line, l.range.start.character, l.range.end.character))
+            .collect()
+    }
+
+    #[test]
+    fn test_references_of_key_definition() {
+        let (ws, db, _, _) = create_test_workspace();
+
+        let locations = find_references(&ws, &db, Position::new(0, 2), true);
+
+        assert_eq!(
+            positions(&locations),
+            vec![
+                ("/configs/common/database.yaml", 0, 0, 4),
+                ("/configs/api.yaml", 3, 11, 15),
+                ("/configs/api.yaml", 4, 11, 15),
+                ("/configs/worker.yaml", 3, 17, 21),
+            ]
+        );
+
+        let without_declaration = find_references(&ws, &db, Position::new(0, 2), false);
+        assert_eq!(without_declaration.len(), 3);
+    }
+
+    #[test]
+    fn test_references_from_a_reference() {
+        let (ws, _, api, _) = create_test_workspace();
+
+        // Cursor on `port` in `${db.port}`
+        let locations = find_references(&ws, &api, Position::new(3, 23), false);
+
+        assert_eq!(positions(&locations), vec![("/configs/api.yaml", 3, 22, 26)]);
+    }
+
+    #[test]
+    fn test_references_of_alias_stay_in_file() {
+        let (ws, _, api, _) = create_test_workspace();
+
+        // Cursor on `db` in `${db.host}`
+        let locations = find_references(&ws, &api, Position::new(3, 8), false);
+
+        assert_eq!(
+            positions(&locations),
+            vec![("/configs/api.yaml", 3, 8, 10), ("/configs/api.yaml", 3, 19, 21), ("/configs/api.yaml", 4, 8, 10)]
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! Rename provider for konf-lsp
+//!
+//! Renaming a config key edits its definition and every `${alias.key}`
+//! reference to it across the workspace, whatever alias each file imports it
+//! under. Renaming an import alias only edits the file declaring it: its
+//! import entry and the references of that file using the alias.
+
+use std::collections::HashMap;
+
+use tower_lsp::lsp_types::*;
+
+use super::parser::{KonfDocument, is_in_import_section, is_valid_path_segment};
+use super::symbols::{Symbol, alias_usages, key_definition, key_usages, range, symbol_at};
+use super::workspace::Workspace;
+
+/// Returns true if `name` can be used as a key or an alias in `${...}` references.
+pub fn is_valid_name(name: &str) -> bool {
+    is_valid_path_segment(name)
+}
+
+/// Computes the edits renaming the key or alias under the cursor to `new_name`.
+pub fn rename(ws: &Workspace, uri: &Url, position: Position, new_name: &str) -> Option<WorkspaceEdit> {
+    let doc = ws.get_document(uri)?;
+    let changes = match symbol_at(doc, position)? {
+        Symbol::Key { file, path } => rename_key(ws, &file, &path, new_name),
+        Symbol::Alias(alias) => HashMap::from([(uri.clone(), rename_alias(doc, &alias, new_name)?)]),
+    };
+    Some(WorkspaceEdit {
+        changes: Some(changes),
+        ..Default::default()
+    })
+}
+
+/// Renames the last segment of `path` in `file` and in every reference to it.
+fn rename_key(ws: &Workspace, file: &str, path: &[String], new_name: &str) -> HashMap<Url, Vec<TextEdit>> {
+    let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+    for location in key_definition(ws, file, path).into_iter().chain(key_usages(ws, file, path)) {
+        changes
+            .entry(location.uri)
+            .or_default()
+            .push(TextEdit::new(location.range, new_name.to_string()));
+    }
+    changes
+}
+
+/// Renames an import alias of `doc`: its declaration and the references using it.
+fn rename_alias(doc: &KonfDocument, alias: &str, new_name: &str) -> Option<Vec<TextEdit>> {
+    let import = &doc.metadata.imports.get(alias)?.path;
+    let mut edits = vec![declaration_edit(doc, import, alias, new_name)?];
+    edits.extend(
+        alias_usages(doc, alias)
+            .into_iter()
+            .map(|range| TextEdit::new(range, new_name.to_string())),
+    );
+    Some(edits)
+}
+
+/// Edits the import entry of `import`, whose alias is written after the colon,
+/// as an `alias:` key, or not at all when it defaults to the path.
+fn declaration_edit(doc: &KonfDocument, import: &str, alias: &str, new_name: &str) -> Option<TextEdit> {
+    let lines: Vec<&str> = doc.content.lines().collect();
+    let entry = (0..lines.len()).find(|&i| {
+        is_in_import_section(&doc.content, i)
+            && lines[i].trim_start().split_once(':').is_some_and(|(path, _)| path.trim() == import)
+    })?;
+    let text = lines[entry];
+    let value = text.split_once(':')?.1;
+    let value_start = text.len() - value.len();
+
+    if value.trim() == alias {
+        let start = value_start + value.find(alias)?;
+        return Some(edit(entry, start, alias, new_name));
+    }
+    if let Some(pos) = value.find("alias:") {
+        let rest = &value[pos + "alias:".len()..];
+        let start = value_start + pos + "alias:".len() + rest.find(alias)?;
+        return Some(edit(entry, start, alias, new_name));
+    }
+    if !value.trim().is_empty() {
+        return None;
+    }
+    // A block mapping (`alias:` and `when:` on the following lines), or no alias at all
+    let indent = text.len() - text.trim_start().len();
+    for (i, line) in lines.iter().enumerate().skip(entry + 1) {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if line.len() - trimmed.len() <= indent {
+            break;
+        }
+        if let Some(rest) = trimmed.strip_prefix("alias:") {
+            let start = line.len() - rest.len() + rest.find(alias)?;
+            return Some(edit(i, start, alias, new_name));
+        }
+    }
+    let end = Position::new(entry as u32, text.len() as u32);
+    Some(TextEdit::new(Range::new(end, end), format!(" {new_name}")))
+}
+
+fn edit(line: usize, col: usize, old: &str, new_name: &str) -> TextEdit {
+    TextEdit::new(range(line, col, old), new_name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn example_workspace() -> (Workspace, PathBuf) {
+        let folder = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("example");
+        let mut ws = Workspace::new();
+        ws.add_folder(&Url::from_file_path(&folder).unwrap());
+        (ws, folder)
+    }
+
+    fn uri(folder: &std::path::Path, file: &str) -> Url {
+        Url::from_file_path(folder.join(file)).unwrap()
+    }
+
+    /// Returns the text of each edit's line, with the edit applied
+    fn applied(ws: &Workspace, edit: &WorkspaceEdit, uri: &Url) -> Vec<String> {
+        let doc = ws.get_document(uri).unwrap();
+        let mut edits = edit.changes.as_ref().unwrap().get(uri).cloned().unwrap_or_default();
+        edits.sort_by_key(|e| std::cmp::Reverse((e.range.start.line, e.range.start.character)));
+        let mut lines: Vec<String> = doc.content.lines().map(String::from).collect();
+        for e in &edits {
+            let line = &mut lines[e.range.start.line as usize];
+            line.replace_range(e.range.start.character as usize..e.range.end.character as usize, &e.new_text);
+        }
+        let mut changed: Vec<u32> = edits.iter().map(|e| e.range.start.line).collect();
+        changed.sort();
+        changed.dedup();
+        changed.iter().map(|&l| lines[l as usize].clone()).collect()
+    }
+
+    #[test]
+    fn test_rename_key_edits_definition_and_references() {
+        let (ws, folder) = example_workspace();
+        let database = uri(&folder, "common/database.yaml");
+
+        let edit = rename(&ws, &database, Position::new(0, 1), "hostname").expect("host should be renamable");
+
+        assert_eq!(applied(&ws, &edit, &database), vec!["hostname: localhost"]);
+        let api = applied(&ws, &edit, &uri(&folder, "services/api/config.yaml"));
+        assert_eq!(api.len(), 1);
+        assert!(api[0].contains("@${common/database.hostname}:"), "{}", api[0]);
+        let v2 = applied(&ws, &edit, &uri(&folder, "services/api/config_v2.yaml"));
+        assert!(v2.iter().any(|line| line.contains("${db.hostname}")), "aliased imports too: {v2:?}");
+        assert!(
+            applied(&ws, &edit, &uri(&folder, "common/redis.yaml")).is_empty(),
+            "other files' keys are untouched"
+        );
+    }
+
+    #[test]
+    fn test_rename_key_from_a_reference() {
+        let (ws, folder) = example_workspace();
+        let api = uri(&folder, "services/api/config.yaml");
+        let doc = ws.get_document(&api).unwrap();
+        let line = doc.content.lines().position(|l| l.contains("${common/database.host}")).unwrap();
+        let col = doc.content.lines().nth(line).unwrap().find("database.host}").unwrap() + "database.h".len();
+
+        let edit = rename(&ws, &api, Position::new(line as u32, col as u32), "hostname").unwrap();
+
+        let database = uri(&folder, "common/database.yaml");
+        assert_eq!(applied(&ws, &edit, &database), vec!["hostname: localhost"]);
+    }
+
+    #[test]
+    fn test_rename_alias_stays_in_its_file() {
+        let mut ws = Workspace::new();
+        let db = Url::parse("file:///configs/db.yaml").unwrap();
+        ws.update_document(&db, "host: localhost\n");
+        let key = ws.get_document(&db).unwrap().key.clone();
+        let app = Url::parse("file:///configs/app.yaml").unwrap();
+        ws.update_document(&app, &format!("<!>:\n  import:\n    {key}: db\nhost: ${{db.host}}\nurl: ${{db.host | upper}}\n"));
+        let other = Url::parse("file:///configs/other.yaml").unwrap();
+        ws.update_document(&other, &format!("<!>:\n  import:\n    {key}: db\nhost: ${{db.host}}\n"));
+
+        // Cursor on `db` in `${db.host}`
+        let edit = rename(&ws, &app, Position::new(3, 9), "database").unwrap();
+
+        assert_eq!(
+            applied(&ws, &edit, &app),
+            vec![
+                format!("    {key}: database"),
+                "host: ${database.host}".to_string(),
+                "url: ${database.host | upper}".to_string(),
+            ]
+        );
+        assert!(!edit.changes.as_ref().unwrap().contains_key(&other));
+    }
+
+    #[test]
+    fn test_rename_default_alias_declares_it() {
+        let (ws, folder) = example_workspace();
+        let api = uri(&folder, "services/api/config.yaml");
+
+        // Cursor on `common/redis` in `${common/redis.host}`
+        let doc = ws.get_document(&api).unwrap();
+        let line = doc.content.lines().position(|l| l.contains("${common/redis.host}")).unwrap();
+        let col = doc.content.lines().nth(line).unwrap().find("${common/redis").unwrap() + 3;
+        let edit = rename(&ws, &api, Position::new(line as u32, col as u32), "cache").unwrap();
+
+        let lines = applied(&ws, &edit, &api);
+        assert_eq!(lines[0], "    common/redis: cache");
+        assert_eq!(lines[1], "  url: redis://${cache.host}:${cache.port}/${cache.db}");
+    }
+
+    #[test]
+    fn test_names_with_dots_are_invalid() {
+        assert!(is_valid_name("host_name"));
+        assert!(!is_valid_name("db.host"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("a b"));
+    }
+
+    #[test]
+    fn test_names_with_dashes_are_invalid() {
+        // `${alias.db-host}` would never resolve
+        assert!(!is_valid_name("db-host"));
+        assert!(is_valid_name("common/database"));
+    }
+}
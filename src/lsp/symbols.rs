@@ -0,0 +1,192 @@
+//! Symbols of konf documents shared by the rename and references providers
+//!
+//! A symbol is either a config key, designated by a `${alias.key}` segment
+//! or by its definition, or an import alias, designated by a reference or
+//! its entry in the import section.
+
+use tower_lsp::lsp_types::*;
+
+use super::parser::{KonfDocument, METADATA_KEY, get_template_at_position, is_in_import_section, placeholder_path};
+use super::workspace::Workspace;
+
+/// What the cursor designates.
+#[derive(Debug, PartialEq)]
+pub enum Symbol {
+    /// A key path in the config `file`.
+    Key { file: String, path: Vec<String> },
+    /// An import alias of the current document.
+    Alias(String),
+}
+
+/// Finds the symbol at `position`: a segment of a `${...}` reference, a key
+/// definition, or an alias in the import section.
+pub fn symbol_at(doc: &KonfDocument, position: Position) -> Option<Symbol> {
+    let (line, col) = (position.line as usize, position.character as usize);
+
+    if let Some(ctx) = get_template_at_position(&doc.content, line, col) {
+        let segments: Vec<&str> = placeholder_path(&ctx.full_path)?.split('.').collect();
+        let mut end = 0;
+        for (i, segment) in segments.iter().enumerate() {
+            end += segment.len();
+            if ctx.cursor_offset <= end {
+                return if i == 0 {
+                    Some(Symbol::Alias(segment.to_string()))
+                } else {
+                    Some(Symbol::Key {
+                        file: resolve_alias(doc, segments[0])?,
+                        path: segments[1..=i].iter().map(|s| s.to_string()).collect(),
+                    })
+                };
+            }
+            end += 1;
+        }
+        return None;
+    }
+
+    let text = doc.content.lines().nth(line)?;
+    if is_in_import_section(&doc.content, line) {
+        let (import, alias) = text.trim().split_once(':')?;
+        let alias = alias.trim();
+        let alias_start = text.rfind(alias)?;
+        if alias.is_empty() || col < alias_start || col > alias_start + alias.len() {
+            return None;
+        }
+        return doc
+            .metadata
+            .imports
+            .get(alias)
+            .filter(|info| info.path == import.trim())
+            .map(|info| Symbol::Alias(info.alias.clone()));
+    }
+
+    let path = key_path_at_line(&doc.content, line)?;
+    let start = text.len() - text.trim_start().len();
+    let key = path.last()?;
+    (col >= start && col <= start + key.len()).then(|| Symbol::Key {
+        file: doc.key.clone(),
+        path,
+    })
+}
+
+/// Returns the config key an alias of `doc` imports.
+pub fn resolve_alias(doc: &KonfDocument, alias: &str) -> Option<String> {
+    let info = doc.metadata.imports.get(alias)?;
+    Some(info.resolved_path.clone().unwrap_or_else(|| info.path.clone()))
+}
+
+/// Returns the path of the key defined on `line`, following indentation up to a top-level key.
+fn key_path_at_line(content: &str, line: usize) -> Option<Vec<String>> {
+    fn key_on(text: &str) -> Option<(usize, &str)> {
+        let trimmed = text.trim_start();
+        if trimmed.starts_with('#') || trimmed.starts_with('-') {
+            return None;
+        }
+        let (key, _) = trimmed.split_once(':')?;
+        (!key.is_empty() && !key.contains(char::is_whitespace)).then(|| (text.len() - trimmed.len(), key))
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    let (mut indent, key) = key_on(lines.get(line)?)?;
+    let mut path = vec![key.to_string()];
+    for text in lines[..line].iter().rev() {
+        if indent == 0 {
+            break;
+        }
+        if let Some((parent_indent, parent)) = key_on(text)
+            && parent_indent < indent
+        {
+            path.push(parent.to_string());
+            indent = parent_indent;
+        }
+    }
+    path.reverse();
+    (indent == 0 && path[0] != METADATA_KEY).then_some(path)
+}
+
+/// Returns the location of the key `path` where it is defined, in the config `file`.
+pub fn key_definition(ws: &Workspace, file: &str, path: &[String]) -> Option<Location> {
+    let doc = ws.get_document_by_key(file)?;
+    let uri = Url::parse(ws.get_uri_for_key(file)?).ok()?;
+    let path_refs: Vec<&str> = path.iter().map(String::as_str).collect();
+    let (line, col) = doc.find_key_position(&path_refs)?;
+    Some(Location::new(uri, range(line as usize, col as usize, path.last()?)))
+}
+
+/// Returns the location of the last segment of every `${...}` reference to
+/// the key `path` of `file`, across the workspace, whatever the alias used.
+pub fn key_usages(ws: &Workspace, file: &str, path: &[String]) -> Vec<Location> {
+    let depth = path.len();
+    let mut keys = ws.get_all_keys();
+    keys.sort();
+
+    let mut locations = vec![];
+    for key in keys {
+        let (Some(doc), Some(uri)) = (
+            ws.get_document_by_key(key),
+            ws.get_uri_for_key(key).and_then(|uri| Url::parse(uri).ok()),
+        ) else {
+            continue;
+        };
+        for Reference { line, col, segments } in references(doc) {
+            let matches = segments.len() > depth
+                && resolve_alias(doc, segments[0].1).as_deref() == Some(file)
+                && segments[1..=depth].iter().map(|(_, s)| *s).eq(path.iter().map(String::as_str));
+            if matches {
+                let (offset, segment) = segments[depth];
+                locations.push(Location::new(uri.clone(), range(line, col + offset, segment)));
+            }
+        }
+    }
+    locations
+}
+
+/// Returns the ranges of the references of `doc` using the import alias `alias`.
+pub fn alias_usages(doc: &KonfDocument, alias: &str) -> Vec<Range> {
+    references(doc)
+        .into_iter()
+        .filter(|reference| reference.segments[0].1 == alias)
+        .map(|reference| range(reference.line, reference.col, alias))
+        .collect()
+}
+
+/// Returns the range of `text` starting at `line`, `col`.
+pub fn range(line: usize, col: usize, text: &str) -> Range {
+    Range::new(
+        Position::new(line as u32, col as u32),
+        Position::new(line as u32, (col + text.len()) as u32),
+    )
+}
+
+/// A `${...}` reference split into path segments.
+struct Reference<'a> {
+    line: usize,
+    /// Column where the path starts, right after `${`
+    col: usize,
+    /// Each segment with its offset in the path
+    segments: Vec<(usize, &'a str)>,
+}
+
+/// Returns the references of `doc`, split into path segments.
+fn references(doc: &KonfDocument) -> Vec<Reference<'_>> {
+    doc.template_refs
+        .iter()
+        .filter_map(|r| {
+            let path = placeholder_path(&r.path)?;
+            let mut offset = 0;
+            let segments = path
+                .split('.')
+                .map(|segment| {
+                    let start = offset;
+                    offset += segment.len() + 1;
+                    (start, segment)
+                })
+                .collect();
+            Some(Reference {
+                line: r.line,
+                col: r.col_start + 2,
+                segments,
+            })
+        })
+        .collect()
+}
+
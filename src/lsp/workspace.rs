@@ -58,16 +58,16 @@ impl Workspace {
             .filter_map(|e| e.ok())
         {
             let path = entry.path();
-            if path.file_name().is_some_and(|n| n == KONF_MARKER) {
-                if let Some(parent) = path.parent() {
-                    info!("Found konf root: {}", parent.display());
-                    self.konf_roots.push(parent.to_path_buf());
-                }
+            if path.file_name().is_some_and(|n| n == KONF_MARKER)
+                && let Some(parent) = path.parent()
+            {
+                info!("Found konf root: {}", parent.display());
+                self.konf_roots.push(parent.to_path_buf());
             }
         }
 
         // Sort by path length descending so we match the most specific root first
-        self.konf_roots.sort_by(|a, b| b.as_os_str().len().cmp(&a.as_os_str().len()));
+        self.konf_roots.sort_by_key(|root| std::cmp::Reverse(root.as_os_str().len()));
     }
 
     /// Index all YAML files in a folder
@@ -179,6 +179,23 @@ impl Workspace {
         self.documents.values()
     }
 
+    /// Get the URIs of the documents importing a config key, sorted
+    pub fn get_referencing_uris(&self, key: &str) -> Vec<&String> {
+        let mut uris: Vec<&String> = self
+            .documents
+            .iter()
+            .filter(|(_, doc)| {
+                doc.metadata
+                    .imports
+                    .values()
+                    .any(|info| info.resolved_path.as_deref() == Some(key))
+            })
+            .map(|(uri, _)| uri)
+            .collect();
+        uris.sort();
+        uris
+    }
+
     /// Check if a config key exists
     pub fn has_key(&self, key: &str) -> bool {
         self.key_to_uri.contains_key(key)
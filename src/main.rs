@@ -1,65 +1,290 @@
 use arc_swap::ArcSwap;
-use clap::Parser;
+use clap::parser::{ArgMatches, ValueSource};
+use clap::{CommandFactory, FromArgMatches, Parser};
 use dashmap::DashMap;
 
+use konf_provider::authorizer::jwt::JwtValidator;
+use konf_provider::dag_cache::DagCacheLimits;
 use konf_provider::fs::git::Creds;
+use konf_provider::in_flight::InFlight;
+use konf_provider::limit::ConcurrencyLimit;
 use konf_provider::local_routes;
-use konf_provider::metrics::init_metrics;
+use konf_provider::metadata::metadata_schema;
+use konf_provider::metrics::{self, init_metrics};
+use konf_provider::peers::PeerNotifier;
+use konf_provider::response_cache::ResponseCache;
 use konf_provider::telemetry::{init_tracing, TelemetryConfig};
+use konf_provider::watch::{watch_folder, DEFAULT_DEBOUNCE};
 use konf_provider::writer::docker_env::DockerEnvVarWriter;
 use konf_provider::writer::env::EnvVarWriter;
+use konf_provider::writer::helm::HelmValuesWriter;
+use konf_provider::writer::ini::IniWriter;
 use konf_provider::writer::properties::PropertiesWriter;
+use konf_provider::writer::shell::ShellWriter;
 use konf_provider::writer::toml::TomlWriter;
+use konf_provider::writer::k8s::{ConfigMapWriter, SecretWriter};
+use konf_provider::writer::xml::XmlWriter;
 use konf_provider::{
-    config::{GitAppState, LocalAppState, RepoConfig},
+    config::{GitAppState, LocalAppState, RepoConfig, ServerConfig},
     fs::{
+        archive::ArchiveFileProvider,
+        http::{DEFAULT_MANIFEST, HttpFileProvider},
         local::BasicFsFileProvider,
-        git::{clone_or_update, list_all_commit_hashes},
+        retry::RetryPolicy,
+        source::SourceFileProvider,
+        git::{clone_or_update, list_all_commit_hashes, list_refs},
     },
     git_routes,
+    imports::ImportContext,
     loader::MultiLoader,
-    loaders::yaml::YamlLoader,
+    loaders::{env::EnvLoader, json::JsonLoader, yaml::YamlLoader},
     render::Dag,
+    render_helper::{EnvAccess, UnresolvedPolicy},
     utils::{self},
-    writer::{MultiWriter, json::JsonWriter, yaml::YamlWriter},
+    writer::{DEFAULT_FORMAT, MultiWriter, SequenceEncoding, json::JsonWriter, yaml::YamlWriter},
 };
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::runtime::Runtime;
 use tower_http::trace::TraceLayer;
 use xitca_web::middleware::tower_http_compat::TowerHttpCompat;
-use xitca_web::{App, handler::handler_service, route::get};
+use xitca_web::{App, handler::{handler_service, json::Json}, route::{get, post}};
 
 #[derive(Debug, clap::Parser)]
 #[command(version, about, long_about = None)]
+// Parsed once at startup, so the size of the largest variant doesn't matter
+#[allow(clippy::large_enum_variant)]
 enum Args {
     Git {
-        #[arg(long)]
-        repo_url: String,
-        #[arg(long)]
-        branch: String,
+        #[arg(long, env = "KONF_REPO_URL", required = true)]
+        repo_url: Option<String>,
+        #[arg(long, env = "KONF_BRANCH", required = true)]
+        branch: Option<String>,
 
-        #[arg(long)]
+        #[arg(long, env = "KONF_USERNAME")]
         username: Option<String>,
-        #[arg(long)]
+        #[arg(long, env = "KONF_PASSWORD")]
         password: Option<String>,
 
+        /// Private key used to authenticate over ssh (`git@host:path` URLs)
+        #[arg(long, env = "KONF_SSH_KEY", conflicts_with_all = ["password", "ssh_agent"])]
+        ssh_key: Option<PathBuf>,
+
+        /// Passphrase of --ssh-key, if it is encrypted
+        #[arg(long, env = "KONF_SSH_PASSPHRASE", requires = "ssh_key")]
+        ssh_passphrase: Option<String>,
+
+        /// Authenticate over ssh with the keys of the running ssh-agent
+        #[arg(long, env = "KONF_SSH_AGENT", conflicts_with = "password")]
+        ssh_agent: bool,
+
+        /// Break-glass token authorized to access every config (repeatable, logged on use)
+        #[arg(long = "admin-token", env = "KONF_ADMIN_TOKENS", value_delimiter = ',')]
+        admin_tokens: Vec<String>,
+
+        /// Shared secret bearer JWTs are signed with (HS256), for files requiring `claims`
+        #[arg(long, env = "KONF_JWT_SECRET", conflicts_with = "jwt_public_key")]
+        jwt_secret: Option<String>,
+
+        /// PEM file of the RSA public key bearer JWTs are signed with (RS256)
+        #[arg(long, env = "KONF_JWT_PUBLIC_KEY")]
+        jwt_public_key: Option<PathBuf>,
+
+        /// Issuer (`iss` claim) bearer JWTs must come from
+        #[arg(long, env = "KONF_JWT_ISSUER")]
+        jwt_issuer: Option<String>,
+
+        /// Also require access to every restricted file a config imports, transitively
+        #[arg(long, env = "KONF_INHERIT_AUTH")]
+        inherit_auth: bool,
+
         /// Port to listen on
         #[arg(long, short, default_value = "4000", env = "KONF_PORT")]
         port: u16,
+
+        /// Output format used by `/render` requests that don't specify one
+        #[arg(long, default_value = DEFAULT_FORMAT, env = "KONF_DEFAULT_FORMAT")]
+        default_format: String,
+
+        /// Maximum number of concurrent requests; requests beyond it get 503 (unlimited by default)
+        #[arg(long, env = "KONF_MAX_CONCURRENCY")]
+        max_concurrency: Option<usize>,
+
+        /// Number of serialized responses to cache, cleared on reload (disabled by default)
+        #[arg(long, env = "KONF_RESPONSE_CACHE_SIZE")]
+        response_cache_size: Option<usize>,
+
+        /// Write sequences of scalars in `env` and `properties` output as one entry joined by this separator
+        #[arg(long, env = "KONF_SEQUENCE_SEPARATOR")]
+        sequence_separator: Option<String>,
+
+        /// Match config paths case-insensitively
+        #[arg(long, env = "KONF_CASE_INSENSITIVE_PATHS")]
+        case_insensitive_paths: bool,
+
+        /// Peer base URL notified after a successful reload (repeatable)
+        #[arg(long = "peer", env = "KONF_PEERS", value_delimiter = ',')]
+        peers: Vec<String>,
+
+        /// Shared secret push webhooks to `POST /webhook` are signed with (webhooks disabled if unset)
+        #[arg(long, env = "KONF_WEBHOOK_SECRET")]
+        webhook_secret: Option<String>,
+
+        /// Add a `_meta` key with the source, commit and render time to rendered configs
+        #[arg(long, env = "KONF_RENDER_META")]
+        render_meta: bool,
+
+        /// Active profile, used to evaluate conditional imports (`when: "profile == prod"`)
+        #[arg(long, env = "KONF_PROFILE")]
+        profile: Option<String>,
+
+        /// Validate every rendered config against its `*.schema.json`, failing on violations
+        #[arg(long, env = "KONF_STRICT_SCHEMAS")]
+        strict_schemas: bool,
+
+        /// What to do with unresolved references: leave, null, omit or error
+        #[arg(long, default_value = "leave", env = "KONF_UNRESOLVED")]
+        unresolved: UnresolvedPolicy,
+
+        /// Let `${env.NAME}` read the variables starting with this prefix (repeatable; none by default, `KONF_*` and `OTEL_*` never)
+        #[arg(long = "env-prefix", env = "KONF_ENV_PREFIXES", value_delimiter = ',')]
+        env_prefixes: Vec<String>,
+
+        /// Maximum number of commits whose DAG stays cached, least recently served evicted first (unlimited by default)
+        #[arg(long, env = "KONF_DAG_CACHE_SIZE")]
+        dag_cache_size: Option<usize>,
+
+        /// Seconds a cached commit DAG may go unserved before it is evicted (never by default)
+        #[arg(long, env = "KONF_DAG_CACHE_TTL")]
+        dag_cache_ttl: Option<u64>,
     },
     Local {
-        #[arg(long)]
-        folder: PathBuf,
+        /// Folder to read configs from
+        #[arg(long, env = "KONF_FOLDER", required_unless_present = "archive", conflicts_with = "archive")]
+        folder: Option<PathBuf>,
 
-        /// Port to listen on
-        #[arg(long, short, default_value = "4000", env = "KONF_PORT")]
-        port: u16,
+        /// Archive (.tar, .tar.gz or .zip) to read configs from, as a path or http(s) URL
+        #[arg(long, env = "KONF_ARCHIVE")]
+        archive: Option<String>,
+
+        /// Reload configs when files under --folder change
+        #[arg(long, env = "KONF_WATCH", conflicts_with = "archive")]
+        watch: bool,
+
+        #[command(flatten)]
+        serve: ServeArgs,
+    },
+    /// Serve configs fetched over HTTP(S) from the files a remote manifest lists
+    Http {
+        /// Base URL the manifest and file paths are relative to
+        #[arg(long, env = "KONF_HTTP_BASE_URL", required = true)]
+        base_url: Option<String>,
+
+        /// Manifest listing the files, as a JSON array of relative paths
+        #[arg(long, default_value = DEFAULT_MANIFEST, env = "KONF_HTTP_MANIFEST")]
+        manifest: String,
+
+        #[command(flatten)]
+        serve: ServeArgs,
     },
 }
 
-fn make_git_creds(username: Option<String>, password: Option<String>) -> Option<Creds> {
-    if let Some(u) = username
+/// Server options shared by the `local` and `http` subcommands.
+#[derive(Debug, clap::Args)]
+struct ServeArgs {
+    /// Port to listen on
+    #[arg(long, short, default_value = "4000", env = "KONF_PORT")]
+    port: u16,
+
+    /// Output format used by `/render` requests that don't specify one
+    #[arg(long, default_value = DEFAULT_FORMAT, env = "KONF_DEFAULT_FORMAT")]
+    default_format: String,
+
+    /// Maximum number of concurrent requests; requests beyond it get 503 (unlimited by default)
+    #[arg(long, env = "KONF_MAX_CONCURRENCY")]
+    max_concurrency: Option<usize>,
+
+    /// Number of serialized responses to cache, cleared on reload (disabled by default)
+    #[arg(long, env = "KONF_RESPONSE_CACHE_SIZE")]
+    response_cache_size: Option<usize>,
+
+    /// Write sequences of scalars in `env` and `properties` output as one entry joined by this separator
+    #[arg(long, env = "KONF_SEQUENCE_SEPARATOR")]
+    sequence_separator: Option<String>,
+
+    /// Match config paths case-insensitively
+    #[arg(long, env = "KONF_CASE_INSENSITIVE_PATHS")]
+    case_insensitive_paths: bool,
+
+    /// Peer base URL notified after a successful reload (repeatable)
+    #[arg(long = "peer", env = "KONF_PEERS", value_delimiter = ',')]
+    peers: Vec<String>,
+
+    /// Add a `_meta` key with the source and render time to rendered configs
+    #[arg(long, env = "KONF_RENDER_META")]
+    render_meta: bool,
+
+    /// Active profile, used to evaluate conditional imports (`when: "profile == prod"`)
+    #[arg(long, env = "KONF_PROFILE")]
+    profile: Option<String>,
+
+    /// Validate every rendered config against its `*.schema.json`, failing on violations
+    #[arg(long, env = "KONF_STRICT_SCHEMAS")]
+    strict_schemas: bool,
+
+    /// What to do with unresolved references: leave, null, omit or error
+    #[arg(long, default_value = "leave", env = "KONF_UNRESOLVED")]
+    unresolved: UnresolvedPolicy,
+
+    /// Let `${env.NAME}` read the variables starting with this prefix (repeatable; none by default, `KONF_*` and `OTEL_*` never)
+    #[arg(long = "env-prefix", env = "KONF_ENV_PREFIXES", value_delimiter = ',')]
+    env_prefixes: Vec<String>,
+
+    /// Refuse to start if the import graph has problems (also checks references)
+    #[arg(long, env = "KONF_STRICT_STARTUP")]
+    strict_startup: bool,
+
+    /// Attempts per remote fetch (http mode, archive URLs), the first one included (3 by default)
+    #[arg(long, env = "KONF_RETRY_ATTEMPTS")]
+    retry_attempts: Option<u32>,
+
+    /// Milliseconds before the first retry of a remote fetch, doubled after each failure (100 by default)
+    #[arg(long, env = "KONF_RETRY_BACKOFF_MS")]
+    retry_backoff_ms: Option<u64>,
+
+    /// Upper bound in milliseconds of the delay between remote fetch attempts (2000 by default)
+    #[arg(long, env = "KONF_RETRY_MAX_BACKOFF_MS")]
+    retry_max_backoff_ms: Option<u64>,
+}
+
+impl ServeArgs {
+    /// Retry policy of remote fetches, defaults filling the options left unset.
+    fn retry_policy(&self) -> RetryPolicy {
+        let default = RetryPolicy::default();
+        RetryPolicy {
+            max_attempts: self.retry_attempts.unwrap_or(default.max_attempts),
+            initial_backoff: self.retry_backoff_ms.map_or(default.initial_backoff, Duration::from_millis),
+            max_backoff: self.retry_max_backoff_ms.map_or(default.max_backoff, Duration::from_millis),
+        }
+    }
+}
+
+fn make_git_creds(
+    username: Option<String>,
+    password: Option<String>,
+    ssh_key: Option<PathBuf>,
+    ssh_passphrase: Option<String>,
+    ssh_agent: bool,
+) -> Option<Creds> {
+    if let Some(private_key) = ssh_key {
+        Some(Creds::SshKey {
+            private_key,
+            passphrase: ssh_passphrase,
+        })
+    } else if ssh_agent {
+        Some(Creds::SshAgent)
+    } else if let Some(u) = username
         && let Some(p) = password
     {
         Some(Creds::new(u, p))
@@ -68,55 +293,400 @@ fn make_git_creds(username: Option<String>, password: Option<String>) -> Option<
     }
 }
 
+/// Builds the JWT validator from `--jwt-secret` or `--jwt-public-key`, if either is set.
+fn make_jwt_validator(
+    secret: Option<String>,
+    public_key: Option<PathBuf>,
+    issuer: Option<String>,
+) -> std::io::Result<Option<Arc<JwtValidator>>> {
+    let validator = match (secret, public_key) {
+        (Some(secret), _) => JwtValidator::hmac(secret.as_bytes()),
+        (None, Some(path)) => {
+            let pem = std::fs::read(&path)?;
+            JwtValidator::rsa_pem(&pem).map_err(|e| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("invalid --jwt-public-key {}: {e}", path.display()),
+                )
+            })?
+        }
+        (None, None) => return Ok(None),
+    };
+    let validator = match issuer {
+        Some(issuer) => validator.with_issuer(&issuer),
+        None => validator,
+    };
+    Ok(Some(Arc::new(validator)))
+}
+
+
+/// Removes `--config <path>` from `args`, returning the path given there
+/// (or in `KONF_CONFIG`).
+fn take_config_arg(args: Vec<String>) -> std::io::Result<(Vec<String>, Option<PathBuf>)> {
+    let mut path = std::env::var_os("KONF_CONFIG").map(PathBuf::from);
+    let mut remaining = Vec::with_capacity(args.len());
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            let value = args.next().ok_or_else(|| invalid_input("--config requires a file path"))?;
+            path = Some(PathBuf::from(value));
+        } else if let Some(value) = arg.strip_prefix("--config=") {
+            path = Some(PathBuf::from(value));
+        } else {
+            remaining.push(arg);
+        }
+    }
+    Ok((remaining, path))
+}
+
+fn invalid_input(message: impl Into<String>) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message.into())
+}
+
+/// Parses the command line, filling the options it leaves unset with the
+/// config file, if any: flags and environment variables take precedence.
+fn parse_args(args: Vec<String>, config: Option<ServerConfig>) -> std::io::Result<Args> {
+    let Some(config) = config else {
+        return Ok(Args::parse_from(args));
+    };
+    // The file may provide the required options
+    let optional = |arg: clap::Arg| arg.required(false).required_unless_present(clap::builder::Resettable::Reset);
+    let command = Args::command()
+        .mut_subcommand("git", |git| git.mut_arg("repo_url", optional).mut_arg("branch", optional))
+        .mut_subcommand("local", |local| local.mut_arg("folder", optional))
+        .mut_subcommand("http", |http| http.mut_arg("base_url", optional));
+    let matches = command.get_matches_from(args);
+    let mut parsed = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let (_, matches) = matches.subcommand().expect("a subcommand is required");
+    merge_config(&mut parsed, matches, config)?;
+    Ok(parsed)
+}
+
+/// Returns true if the option `id` was given as a flag or an environment variable.
+fn is_explicit(matches: &ArgMatches, id: &str) -> bool {
+    matches!(matches.value_source(id), Some(ValueSource::CommandLine | ValueSource::EnvVariable))
+}
+
+/// Returns true if none of the options `ids` is explicit: options that pick
+/// the same setting (e.g. `--folder` and `--archive`) come from one place.
+fn none_explicit(matches: &ArgMatches, ids: &[&str]) -> bool {
+    !ids.iter().any(|id| is_explicit(matches, id))
+}
+
+/// Sets `field` to the config file's `value` unless the option `id` is explicit.
+fn fill<T>(matches: &ArgMatches, id: &str, field: &mut T, value: Option<T>) {
+    if let Some(value) = value
+        && !is_explicit(matches, id)
+    {
+        *field = value;
+    }
+}
+
+/// Fills each listed option from the config file key of the same name; options
+/// after `optional:` are `Option`s on the command line.
+macro_rules! fill_from {
+    ($matches:expr, $config:expr; $($field:ident),* $(; optional: $($optional:ident),*)?) => {
+        $(fill($matches, stringify!($field), $field, $config.$field.take());)*
+        $($(fill($matches, stringify!($optional), $optional, $config.$optional.take().map(Some));)*)?
+    };
+}
+
+/// Merges the options of the config file into `args`, field by field, where
+/// they were not given as flags or environment variables.
+fn merge_config(args: &mut Args, matches: &ArgMatches, mut config: ServerConfig) -> std::io::Result<()> {
+    let unresolved = config
+        .unresolved
+        .as_deref()
+        .map(str::parse::<UnresolvedPolicy>)
+        .transpose()
+        .map_err(invalid_input)?;
+    match args {
+        Args::Git {
+            repo_url,
+            branch,
+            username,
+            password,
+            ssh_key,
+            ssh_passphrase,
+            ssh_agent,
+            admin_tokens,
+            jwt_secret,
+            jwt_public_key,
+            jwt_issuer,
+            inherit_auth,
+            port,
+            default_format,
+            max_concurrency,
+            response_cache_size,
+            sequence_separator,
+            case_insensitive_paths,
+            peers,
+            webhook_secret,
+            render_meta,
+            profile,
+            strict_schemas,
+            unresolved: unresolved_policy,
+            env_prefixes,
+            dag_cache_size,
+            dag_cache_ttl,
+        } => {
+            fill(matches, "unresolved", unresolved_policy, unresolved);
+            fill_from!(
+                matches, config;
+                admin_tokens, inherit_auth, port, default_format, case_insensitive_paths, peers, render_meta,
+                strict_schemas, env_prefixes;
+                optional: repo_url, branch, jwt_issuer, max_concurrency, response_cache_size, sequence_separator, webhook_secret,
+                profile, dag_cache_size, dag_cache_ttl
+            );
+            if none_explicit(matches, &["username", "password", "ssh_key", "ssh_passphrase", "ssh_agent"]) {
+                fill_from!(matches, config; ssh_agent; optional: username, password, ssh_key, ssh_passphrase);
+            }
+            if none_explicit(matches, &["jwt_secret", "jwt_public_key"]) {
+                fill_from!(matches, config; ; optional: jwt_secret, jwt_public_key);
+            }
+        }
+        Args::Local { folder, archive, watch, serve } => {
+            fill_from!(matches, config; watch);
+            if none_explicit(matches, &["folder", "archive"]) {
+                fill_from!(matches, config; ; optional: folder, archive);
+            }
+            merge_serve_config(serve, matches, &mut config, unresolved);
+        }
+        Args::Http { base_url, manifest, serve } => {
+            fill_from!(matches, config; manifest; optional: base_url);
+            merge_serve_config(serve, matches, &mut config, unresolved);
+        }
+    }
+    Ok(())
+}
+
+/// Merges the options shared by the `local` and `http` subcommands, see [`merge_config`].
+fn merge_serve_config(
+    serve: &mut ServeArgs,
+    matches: &ArgMatches,
+    config: &mut ServerConfig,
+    unresolved: Option<UnresolvedPolicy>,
+) {
+    let ServeArgs {
+        port,
+        default_format,
+        max_concurrency,
+        response_cache_size,
+        sequence_separator,
+        case_insensitive_paths,
+        peers,
+        render_meta,
+        profile,
+        strict_schemas,
+        unresolved: unresolved_policy,
+        env_prefixes,
+        strict_startup,
+        retry_attempts,
+        retry_backoff_ms,
+        retry_max_backoff_ms,
+    } = serve;
+    fill(matches, "unresolved", unresolved_policy, unresolved);
+    fill_from!(
+        matches, config;
+        port, default_format, case_insensitive_paths, peers, render_meta, strict_schemas, env_prefixes,
+        strict_startup;
+        optional: max_concurrency, response_cache_size, sequence_separator, profile, retry_attempts, retry_backoff_ms,
+        retry_max_backoff_ms
+    );
+}
+
+/// Returns the value of a required option, which the config file may have left unset.
+fn required<T>(value: Option<T>, flag: &str) -> std::io::Result<T> {
+    value.ok_or_else(|| invalid_input(format!("missing {flag} (or its key in the config file)")))
+}
 
 fn main() -> std::io::Result<()> {
+    let (args, config_path) = take_config_arg(std::env::args().collect())?;
+    let config = config_path
+        .map(|path| ServerConfig::load(&path).map_err(|e| invalid_input(format!("{e:#}"))))
+        .transpose()?;
+    let mut telemetry = TelemetryConfig::default();
+    if telemetry.otlp_endpoint.is_none() {
+        telemetry.otlp_endpoint = config.as_ref().and_then(|config| config.otlp_endpoint.clone());
+    }
+    let args = parse_args(args, config)?;
+
     // Initialize tracing with optional OpenTelemetry export
-    let _tracer_provider = init_tracing(TelemetryConfig::default());
+    let _tracer_provider = init_tracing(telemetry);
 
     // Initialize Prometheus metrics
     let prometheus_handle = Arc::new(init_metrics());
 
-    let args = Args::parse();
+    let (default_format, max_concurrency, response_cache_size, sequence_separator) = match &args {
+        Args::Git { default_format, max_concurrency, response_cache_size, sequence_separator, .. }
+        | Args::Local {
+            serve: ServeArgs { default_format, max_concurrency, response_cache_size, sequence_separator, .. },
+            ..
+        }
+        | Args::Http {
+            serve: ServeArgs { default_format, max_concurrency, response_cache_size, sequence_separator, .. },
+            ..
+        } => (default_format.clone(), *max_concurrency, *response_cache_size, sequence_separator.clone()),
+    };
+    if max_concurrency == Some(0) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "--max-concurrency must be at least 1",
+        ));
+    }
+    let concurrency_limit = ConcurrencyLimit::new(max_concurrency);
+    let response_cache = ResponseCache::new(response_cache_size);
+    let sequences = SequenceEncoding::from_separator(sequence_separator);
     let multiwriter = MultiWriter::new(vec![
         YamlWriter::new_boxed(),
         JsonWriter::new_boxed(),
-        EnvVarWriter::new_boxed(),
-        PropertiesWriter::new_boxed(),
+        Box::new(EnvVarWriter::with_sequence_encoding(sequences.clone())),
+        Box::new(PropertiesWriter::with_sequence_encoding(sequences)),
         TomlWriter::new_boxed(),
         DockerEnvVarWriter::new_boxed(),
+        HelmValuesWriter::new_boxed(),
+        IniWriter::new_boxed(),
+        XmlWriter::new_boxed(),
+        ConfigMapWriter::new_boxed(),
+        SecretWriter::new_boxed(),
+        ShellWriter::new_boxed(),
     ]);
+    let formats = multiwriter.formats().join(", ");
+    let multiwriter = multiwriter.with_default_format(&default_format).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("unknown default format '{default_format}' (expected one of: {formats})"),
+        )
+    })?;
 
     match args {
-        Args::Local { folder, port } => {
-            let multiloader = Arc::from(MultiLoader::new(vec![Box::new(YamlLoader {})]));
+        Args::Local { .. } | Args::Http { .. } => {
+            let multiloader = Arc::from(MultiLoader::new(vec![
+                Box::new(YamlLoader {}),
+                Box::new(JsonLoader {}),
+                Box::new(EnvLoader::default()),
+            ]));
             let rt = Runtime::new().expect("failed to get tokio runtime");
 
+            let (provider, source, watch, serve) = match args {
+                Args::Local { folder, archive, watch, serve } => {
+                    let (provider, source) = match (folder, archive) {
+                        (_, Some(archive)) => {
+                            let provider = rt
+                                .block_on(ArchiveFileProvider::open(&archive, &serve.retry_policy()))
+                                .map_err(|e| std::io::Error::other(format!("{e:#}")))?;
+                            (SourceFileProvider::Archive(provider), PathBuf::from(archive))
+                        }
+                        (Some(folder), None) => (
+                            SourceFileProvider::Folder(BasicFsFileProvider::new(folder.clone())),
+                            folder,
+                        ),
+                        (None, None) => return Err(invalid_input("missing --folder or --archive (or their key in the config file)")),
+                    };
+                    (provider, source, watch, serve)
+                }
+                Args::Http { base_url, manifest, serve } => {
+                    let base_url = required(base_url, "--base-url")?;
+                    let provider = HttpFileProvider::new(&base_url)
+                        .with_manifest(&manifest)
+                        .with_retry_policy(serve.retry_policy());
+                    (SourceFileProvider::Http(provider), PathBuf::from(base_url), false, serve)
+                }
+                Args::Git { .. } => unreachable!("matched by the outer arm"),
+            };
+            let ServeArgs {
+                port,
+                case_insensitive_paths,
+                peers,
+                strict_startup,
+                render_meta,
+                profile,
+                strict_schemas,
+                unresolved,
+                env_prefixes,
+                ..
+            } = serve;
+
             // Run the async function in sync context
             let dag = rt
-                .block_on(Dag::new(
-                    BasicFsFileProvider::new(folder.clone()),
+                .block_on(Dag::with_context(
+                    provider,
                     multiloader.clone(),
+                    ImportContext::new(profile),
                 ))
-                .expect("failed to read directory");
+                .expect("failed to read directory")
+                .with_env_access(EnvAccess::with_prefixes(env_prefixes));
+
+            let report = dag.check_graph(strict_startup);
+            report.log();
+            if strict_startup && !report.is_ok() {
+                return Err(std::io::Error::other(format!(
+                    "refusing to start: {} import graph problem(s)",
+                    report.problems.len()
+                )));
+            }
 
             let state = LocalAppState {
-                folder,
+                folder: source,
                 dag,
                 writer: Arc::from(multiwriter),
                 multiloader,
                 metrics: prometheus_handle.clone(),
+                case_insensitive_paths,
+                peers: PeerNotifier::new(peers),
+                render_meta,
+                response_cache,
+                strict_schemas,
+                unresolved,
+            };
+
+            // Kept alive until the server stops; dropping it stops watching
+            let _watcher = if watch {
+                let watched = state.clone();
+                let _guard = rt.enter();
+                let watcher = watch_folder(&state.folder, DEFAULT_DEBOUNCE, move |path| {
+                    let state = watched.clone();
+                    async move {
+                        tracing::info!("{} changed, reloading configs", path.display());
+                        let result = state.dag.reload().await;
+                        metrics::record_reload(result.is_ok());
+                        match result {
+                            Ok(()) => {
+                                state.response_cache.clear();
+                                state.peers.notify().await;
+                            }
+                            Err(e) => tracing::error!("failed to reload configs: {e}"),
+                        }
+                    }
+                })
+                .map_err(|e| std::io::Error::other(format!("failed to watch {}: {e}", state.folder.display())))?;
+                Some(watcher)
+            } else {
+                None
             };
 
             App::new()
                 .with_state(state)
                 .at("/live", get(handler_service(async || "OK")))
+                .at("/ready", get(handler_service(local_routes::ready)))
                 .at("/metrics", get(handler_service(local_routes::metrics_handler)))
-                .at("/reload", get(handler_service(local_routes::reload)))
+                .at("/schema/metadata", get(handler_service(async || Json(metadata_schema()))))
+                .at(
+                    "/reload",
+                    get(handler_service(local_routes::reload))
+                        .post(handler_service(local_routes::reload)),
+                )
+                .at("/env-bundle", get(handler_service(local_routes::get_env_bundle)))
+                .at("/merged/:format/*rest", get(handler_service(local_routes::get_merged)))
+                .at("/value/:format/*rest", get(handler_service(local_routes::get_value)))
+                .at("/render/*rest", get(handler_service(local_routes::get_data_default_format)))
                 .at(
                     "/data/:format/*rest",
-                    get(handler_service(local_routes::get_data)),
+                    get(handler_service(local_routes::get_data))
+                        .post(handler_service(local_routes::post_overlay)),
                 )
                 .enclosed_fn(utils::error_handler)
+                .enclosed(concurrency_limit)
                 .enclosed(TowerHttpCompat::new(TraceLayer::new_for_http()))
                 .serve()
                 .bind(format!("0.0.0.0:{port}"))?
@@ -128,15 +698,43 @@ fn main() -> std::io::Result<()> {
             branch,
             username,
             password,
+            ssh_key,
+            ssh_passphrase,
+            ssh_agent,
+            admin_tokens,
+            jwt_secret,
+            jwt_public_key,
+            jwt_issuer,
+            inherit_auth,
             port,
+            case_insensitive_paths,
+            peers,
+            render_meta,
+            profile,
+            strict_schemas,
+            unresolved,
+            env_prefixes,
+            dag_cache_size,
+            dag_cache_ttl,
+            webhook_secret,
+            ..
         } => {
-            let creds = make_git_creds(username, password);
+            if dag_cache_size == Some(0) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "--dag-cache-size must be at least 1",
+                ));
+            }
+            let (repo_url, branch) = (required(repo_url, "--repo-url")?, required(branch, "--branch")?);
+            let jwt = make_jwt_validator(jwt_secret, jwt_public_key, jwt_issuer)?;
+            let creds = make_git_creds(username, password, ssh_key, ssh_passphrase, ssh_agent);
             let creds_clone = creds.clone();
             let rt = Runtime::new()?;
             rt.block_on(clone_or_update(&repo_url, &branch, &creds))
                 .expect("failed to initialize repository");
 
             let commits = list_all_commit_hashes(&repo_url).unwrap();
+            let refs = list_refs(&repo_url).unwrap();
 
             let state = Arc::from(GitAppState {
                 repo_config: RepoConfig {
@@ -145,22 +743,61 @@ fn main() -> std::io::Result<()> {
                     creds: creds_clone,
                 },
                 dag: DashMap::new(),
+                dag_cache: DagCacheLimits {
+                    capacity: dag_cache_size,
+                    ttl: dag_cache_ttl.map(Duration::from_secs),
+                },
+                dag_builds: InFlight::new(),
                 writer: Arc::from(multiwriter),
                 commits: ArcSwap::from(Arc::from(commits)),
-                multiloader: Arc::from(MultiLoader::new(vec![Box::new(YamlLoader {})])),
+                refs: ArcSwap::from(Arc::from(refs)),
+                multiloader: Arc::from(MultiLoader::new(vec![
+                    Box::new(YamlLoader {}),
+                    Box::new(JsonLoader {}),
+                    Box::new(EnvLoader::default()),
+                ])),
                 metrics: prometheus_handle,
+                case_insensitive_paths,
+                admin_tokens,
+                jwt,
+                inherit_auth,
+                peers: PeerNotifier::new(peers),
+                webhook_secret,
+                render_meta,
+                import_context: ImportContext::new(profile),
+                response_cache,
+                strict_schemas,
+                unresolved,
+                env: EnvAccess::with_prefixes(env_prefixes),
             });
 
             App::new()
                 .with_state(state)
                 .at("/live", get(handler_service(async || "OK")))
+                .at("/ready", get(handler_service(git_routes::ready)))
                 .at("/metrics", get(handler_service(git_routes::metrics_handler)))
-                .at("/reload", get(handler_service(git_routes::reload)))
+                .at("/schema/metadata", get(handler_service(async || Json(metadata_schema()))))
+                .at(
+                    "/reload",
+                    get(handler_service(git_routes::reload))
+                        .post(handler_service(git_routes::reload)),
+                )
+                .at("/webhook", post(handler_service(git_routes::webhook)))
+                .at("/diff/files", get(handler_service(git_routes::get_changes)))
+                .at("/diff/:from/:to/:format/*rest", get(handler_service(git_routes::get_diff)))
+                .at("/cache/stats", get(handler_service(git_routes::get_cache_stats)))
+                .at("/cache/rebuild", post(handler_service(git_routes::rebuild_commit)))
+                .at(
+                    "/render/:commit/*rest",
+                    get(handler_service(git_routes::get_data_default_format)),
+                )
                 .at(
                     "/data/:commit/:format/*rest",
-                    get(handler_service(git_routes::get_data)),
+                    get(handler_service(git_routes::get_data))
+                        .post(handler_service(git_routes::post_overlay)),
                 )
                 .enclosed_fn(utils::error_handler)
+                .enclosed(concurrency_limit)
                 .enclosed(TowerHttpCompat::new(TraceLayer::new_for_http()))
                 .serve()
                 .bind(format!("0.0.0.0:{port}"))?
@@ -0,0 +1,198 @@
+//! Definitions of the keys supported in the `<!>` metadata section.
+//!
+//! The parsers read these constants, and [`metadata_schema`] is generated from
+//! [`METADATA_KEYS`], so the published description stays in sync with what is
+//! actually accepted.
+
+use serde_json::{Map, Value as Json, json};
+
+/// The metadata key used in konf config files
+pub const METADATA_KEY: &str = "<!>";
+
+/// Mapping of imported file path to the alias used in templates.
+pub const IMPORT_KEY: &str = "import";
+
+/// List of tokens or role names allowed to read the file, or of path patterns
+/// mapped to the tokens or roles they grant (git mode).
+pub const AUTH_KEY: &str = "auth";
+
+/// Import aliases whose rendered mappings the file inherits, deep-merged under its own keys.
+pub const EXTENDS_KEY: &str = "extends";
+
+/// Marks a file as served directly rather than imported (see `konf orphans`).
+pub const ENTRYPOINT_KEY: &str = "entrypoint";
+
+/// Output hints for the file; `format` is used when the auto format (`_`) is
+/// requested, `ttl` sets the `Cache-Control` max-age of its responses.
+pub const OUTPUT_KEY: &str = "output";
+
+/// Field of [`OUTPUT_KEY`] naming the file's intended output format.
+pub const OUTPUT_FORMAT_KEY: &str = "format";
+
+/// Field of [`OUTPUT_KEY`] giving how long, in seconds, a response may be cached.
+pub const OUTPUT_TTL_KEY: &str = "ttl";
+
+/// Description of a key accepted in the `<!>` metadata section.
+#[derive(Debug, Clone, Copy)]
+pub struct MetadataKeyDef {
+    /// Key name as written under `<!>`.
+    pub name: &'static str,
+    /// JSON Schema describing the accepted value.
+    pub schema: fn() -> Json,
+    /// Human-readable description for editors and docs.
+    pub description: &'static str,
+}
+
+/// All keys supported in the `<!>` metadata section.
+pub const METADATA_KEYS: &[MetadataKeyDef] = &[
+    MetadataKeyDef {
+        name: IMPORT_KEY,
+        schema: || {
+            json!({
+                "type": "object",
+                "additionalProperties": {
+                    "oneOf": [
+                        { "type": ["string", "null"] },
+                        {
+                            "type": "object",
+                            "properties": {
+                                "alias": { "type": ["string", "null"] },
+                                "when": { "type": "string" },
+                            },
+                            "additionalProperties": false,
+                        },
+                    ],
+                },
+            })
+        },
+        description: "Files to import, as a mapping of path (relative paths allowed) to alias. \
+                      A null or empty alias uses the path itself as the alias. An \
+                      `{ alias, when }` mapping only imports the file when the condition \
+                      (`profile == <name>` or `profile != <name>`) holds.",
+    },
+    MetadataKeyDef {
+        name: EXTENDS_KEY,
+        schema: || json!({ "type": "array", "items": { "type": "string" } }),
+        description: "Imports (by alias) to inherit from: their rendered mappings are deep-merged \
+                      in order, later ones winning, and this file's own keys win over all of \
+                      them. Sequences and scalars are replaced, not merged.",
+    },
+    MetadataKeyDef {
+        name: AUTH_KEY,
+        schema: || {
+            json!({
+                "type": "array",
+                "items": {
+                    "oneOf": [
+                        { "type": "string" },
+                        {
+                            "type": "object",
+                            "properties": { "claims": { "type": "object" } },
+                            "additionalProperties": {
+                                "oneOf": [
+                                    { "type": "string" },
+                                    { "type": "array", "items": { "type": "string" } }
+                                ]
+                            }
+                        }
+                    ]
+                }
+            })
+        },
+        description: "Tokens or role names (defined in _roles.yaml) allowed to read this file, \
+                      or mappings of path patterns (`services/*`) to the tokens or roles allowed \
+                      to read the matching files, or `claims` a bearer JWT must carry to read \
+                      this file (git mode only).",
+    },
+    MetadataKeyDef {
+        name: ENTRYPOINT_KEY,
+        schema: || json!({ "type": "boolean" }),
+        description: "Marks this file as served directly, so `konf orphans` does not report it \
+                      as unused when nothing imports it.",
+    },
+    MetadataKeyDef {
+        name: OUTPUT_KEY,
+        schema: || {
+            json!({
+                "type": "object",
+                "properties": {
+                    "format": { "type": "string" },
+                    "ttl": { "type": "integer", "minimum": 0 },
+                },
+                "additionalProperties": false,
+            })
+        },
+        description: "Output hints. `format` is the format this file is served in when a \
+                      client requests the auto format `_` (e.g. `/data/_/app`). `ttl` is how \
+                      long, in seconds, its responses may be cached (`Cache-Control: max-age`).",
+    },
+];
+
+/// Returns a JSON Schema describing the `<!>` metadata section.
+pub fn metadata_schema() -> Json {
+    let properties: Map<String, Json> = METADATA_KEYS
+        .iter()
+        .map(|key| {
+            let mut schema = (key.schema)();
+            schema["description"] = Json::String(key.description.to_string());
+            (key.name.to_string(), schema)
+        })
+        .collect();
+
+    json!({
+        "$schema": crate::schema::SCHEMA_DIALECT,
+        "title": METADATA_KEY,
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        graph::is_entrypoint,
+        imports::{parse_extends, parse_imports, parse_output_format, parse_output_ttl},
+        loader::Loader,
+        loaders::yaml::YamlLoader,
+        utils::get_conf_strings,
+    };
+
+    fn load(yaml: &str) -> crate::Value {
+        YamlLoader {}.load(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_documented_keys_are_accepted() {
+        let value = load(
+            "<!>:\n  import:\n    common/database: db\n  extends:\n    - db\n  auth:\n    - token\n  entrypoint: true\n  output:\n    format: env\n    ttl: 300\n",
+        );
+
+        for key in METADATA_KEYS {
+            match key.name {
+                IMPORT_KEY => assert!(parse_imports(&value, "app").contains_key("db")),
+                EXTENDS_KEY => assert_eq!(parse_extends(&value), vec!["db"]),
+                AUTH_KEY => assert_eq!(get_conf_strings(&value, AUTH_KEY), vec!["token"]),
+                ENTRYPOINT_KEY => assert!(is_entrypoint(&value)),
+                OUTPUT_KEY => {
+                    assert_eq!(parse_output_format(&value).as_deref(), Some("env"));
+                    assert_eq!(parse_output_ttl(&value), Some(300));
+                }
+                other => panic!("metadata key '{other}' is documented but not tested"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_schema_lists_every_key() {
+        let schema = metadata_schema();
+        let properties = schema["properties"].as_object().unwrap();
+
+        assert_eq!(properties.len(), METADATA_KEYS.len());
+        for key in METADATA_KEYS {
+            let property = &properties[key.name];
+            assert_eq!(property["description"], key.description);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use metrics::{counter, describe_counter, describe_histogram, histogram};
+use metrics::{counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram};
 use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
 use std::time::Instant;
 
@@ -25,6 +25,22 @@ pub fn init_metrics() -> PrometheusHandle {
         "git_cache_lookups_total",
         "Total number of git DAG cache lookups"
     );
+    describe_counter!(
+        "git_cache_evictions_total",
+        "Total number of git DAG cache entries evicted, by reason"
+    );
+    describe_counter!(
+        "response_cache_lookups_total",
+        "Total number of serialized response cache lookups"
+    );
+    describe_gauge!(
+        "http_requests_in_flight",
+        "Number of requests currently being handled"
+    );
+    describe_counter!(
+        "http_requests_rejected_total",
+        "Total number of requests rejected by the concurrency limit"
+    );
 
     // Initialize counters with zero so they appear in output immediately
     // We use a placeholder label that won't conflict with real labels
@@ -32,6 +48,12 @@ pub fn init_metrics() -> PrometheusHandle {
     counter!("config_reloads_total", "success" => "false").absolute(0);
     counter!("git_cache_lookups_total", "hit" => "true").absolute(0);
     counter!("git_cache_lookups_total", "hit" => "false").absolute(0);
+    counter!("git_cache_evictions_total", "reason" => "capacity").absolute(0);
+    counter!("git_cache_evictions_total", "reason" => "ttl").absolute(0);
+    counter!("response_cache_lookups_total", "hit" => "true").absolute(0);
+    counter!("response_cache_lookups_total", "hit" => "false").absolute(0);
+    counter!("http_requests_rejected_total").absolute(0);
+    gauge!("http_requests_in_flight").set(0.0);
 
     handle
 }
@@ -71,6 +93,28 @@ pub fn record_git_cache(hit: bool) {
     counter!("git_cache_lookups_total", &labels).increment(1);
 }
 
+/// Record a git DAG cache entry evicted for `reason` (`capacity` or `ttl`).
+pub fn record_git_cache_eviction(reason: &str) {
+    let labels = [("reason", reason.to_string())];
+    counter!("git_cache_evictions_total", &labels).increment(1);
+}
+
+/// Record a response cache hit or miss.
+pub fn record_response_cache(hit: bool) {
+    let labels = [("hit", hit.to_string())];
+    counter!("response_cache_lookups_total", &labels).increment(1);
+}
+
+/// Record the number of requests currently being handled.
+pub fn record_in_flight(count: usize) {
+    gauge!("http_requests_in_flight").set(count as f64);
+}
+
+/// Record a request rejected by the concurrency limit.
+pub fn record_rejected() {
+    counter!("http_requests_rejected_total").increment(1);
+}
+
 /// A guard that records request duration when dropped.
 pub struct RequestTimer {
     start: Instant,
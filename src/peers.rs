@@ -0,0 +1,61 @@
+//! Reload cascading to peer konf instances.
+//!
+//! After a successful reload, an instance can notify its peers by POSTing to
+//! their `/reload` endpoint. Notifications carry [`HOP_HEADER`]; an instance
+//! reloaded by a peer does not notify its own peers, so cascades stop after
+//! one hop even when peers list each other.
+
+use std::time::Duration;
+
+use futures::future::join_all;
+use xitca_web::http::HeaderMap;
+
+/// Header marking a reload triggered by a peer.
+pub const HOP_HEADER: &str = "x-konf-reload-hop";
+
+/// Timeout for a single peer notification.
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Notifies peer instances after a reload.
+#[derive(Debug, Clone, Default)]
+pub struct PeerNotifier {
+    /// Base URLs of the peers, e.g. `http://konf-2:4000`.
+    peers: Vec<String>,
+    client: reqwest::Client,
+}
+
+impl PeerNotifier {
+    pub fn new(peers: Vec<String>) -> Self {
+        Self {
+            peers: peers
+                .into_iter()
+                .map(|peer| peer.trim_end_matches('/').to_string())
+                .collect(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Returns true if the request was itself a cascaded reload from a peer.
+    pub fn is_cascaded(headers: &HeaderMap) -> bool {
+        headers.contains_key(HOP_HEADER)
+    }
+
+    /// POSTs to every peer's `/reload` endpoint. Failures are logged, not returned.
+    pub async fn notify(&self) {
+        join_all(self.peers.iter().map(|peer| async move {
+            let result = self
+                .client
+                .post(format!("{peer}/reload"))
+                .header(HOP_HEADER, "1")
+                .timeout(NOTIFY_TIMEOUT)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            match result {
+                Ok(_) => tracing::info!(peer, "notified peer of reload"),
+                Err(e) => tracing::warn!(peer, error = %e, "failed to notify peer of reload"),
+            }
+        }))
+        .await;
+    }
+}
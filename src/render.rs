@@ -1,17 +1,44 @@
 use anyhow::anyhow;
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use arc_swap::ArcSwap;
 use futures::future;
+use serde::Serialize;
 
 use crate::{
     DagFiles, Konf, Value,
     fs::FileProvider,
-    imports::parse_imports,
+    imports::{ImportContext, ImportInfo, parse_extends, parse_imports_for, parse_output_format, parse_output_ttl},
     loader::{LoaderError, MultiLoader},
-    render_helper::resolve_refs_from_deps,
+    metadata::{EXTENDS_KEY, METADATA_KEY},
+    render_helper::{
+        ENV_PREFIX, EnvAccess, UnresolvedPolicy, apply_unresolved_policy, placeholder_path, resolve_refs_collecting, unescape_placeholders,
+        try_resolve_refs_from_deps,
+    },
+    schema::{self, ConfigSchema, SCHEMA_KEY_SUFFIX},
+    graph::{self, GraphReport, OrphanReport},
+    utils::{edit_distance, normalize_path},
 };
 
+/// Maximum number of near-match suggestions returned by [`Dag::resolve_key`].
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Key of the marker replacing a top-level value that failed to render in
+/// [`Dag::get_rendered_isolated`]: `database: { _error: "..." }`.
+pub const ERROR_MARKER_KEY: &str = "_error";
+
+/// A top-level key that failed to render in [`Dag::get_rendered_isolated`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KeyFailure {
+    /// The top-level key that was replaced by an error marker.
+    pub key: String,
+    /// Why it failed, e.g. an unresolved reference or a failed import.
+    pub reason: String,
+}
+
 /// Error type for configuration rendering failures.
 #[derive(Debug, Clone)]
 pub enum RenderError {
@@ -27,6 +54,10 @@ struct DagInner<P: FileProvider> {
     multiloader: Arc<MultiLoader>,
     /// Atomically swappable map of loaded configuration files.
     files: ArcSwap<DagFiles>,
+    /// Context in which conditional imports are evaluated.
+    import_context: ImportContext,
+    /// Compiled `*.schema.json` files, by the key of the config they validate.
+    schemas: ArcSwap<HashMap<String, Arc<ConfigSchema>>>,
 }
 
 /// A directed acyclic graph of configuration files with dependency resolution.
@@ -51,6 +82,27 @@ struct DagInner<P: FileProvider> {
 #[derive(Clone, Debug)]
 pub struct Dag<P: FileProvider> {
     inner: Arc<DagInner<P>>,
+    /// What [`Dag::get_rendered`] does with references left unresolved.
+    unresolved: UnresolvedPolicy,
+    /// Environment variables `${env.NAME}` references may read.
+    env: EnvAccess,
+}
+
+/// Deep-merges `value` over the rendered imports it extends, in order:
+/// later bases win over earlier ones, and `value` wins over all of them.
+fn extend(value: Value, extends: &[String], deps: &HashMap<String, Value>) -> anyhow::Result<Value> {
+    if extends.is_empty() {
+        return Ok(value);
+    }
+    let mut merged = Value::Mapping(crate::Mapping::new());
+    for alias in extends {
+        let base = deps
+            .get(alias)
+            .ok_or_else(|| anyhow!("cannot extend '{alias}': it is not an import alias"))?;
+        merged.merge(base);
+    }
+    merged.merge(&value);
+    Ok(merged)
 }
 
 impl<P: FileProvider> Dag<P> {
@@ -59,21 +111,158 @@ impl<P: FileProvider> Dag<P> {
     /// This will read all files from the provider, parse them, and prepare
     /// them for rendering. The initial load happens synchronously.
     pub async fn new(file_provider: P, multiloader: Arc<MultiLoader>) -> anyhow::Result<Self> {
+        Self::with_context(file_provider, multiloader, ImportContext::default()).await
+    }
+
+    /// Creates a new DAG whose conditional imports are evaluated in `import_context`.
+    pub async fn with_context(
+        file_provider: P,
+        multiloader: Arc<MultiLoader>,
+        import_context: ImportContext,
+    ) -> anyhow::Result<Self> {
         let inner = Arc::new(DagInner {
             file_provider,
             multiloader,
             files: ArcSwap::default(), // Start with an empty HashMap
+            import_context,
+            schemas: ArcSwap::default(),
         });
-        let handle = Self { inner };
+        let handle = Self {
+            inner,
+            unresolved: UnresolvedPolicy::default(),
+            env: EnvAccess::default(),
+        };
         handle.reload().await?;
         Ok(handle)
     }
+
+    /// Applies `policy` to the references [`Dag::get_rendered`] leaves
+    /// unresolved. With [`UnresolvedPolicy::Error`] (strict mode), a typo'd
+    /// reference fails the render instead of shipping as a literal `${...}`.
+    ///
+    /// Only the requested file's output is checked: a reference an import
+    /// leaves unresolved may still be resolved by the file importing it.
+    pub fn with_unresolved_policy(mut self, policy: UnresolvedPolicy) -> Self {
+        self.unresolved = policy;
+        self
+    }
+
+    /// Lets `${env.NAME}` references read the variables `env` allows; none by default.
+    ///
+    /// Already rendered files keep the values they were rendered with.
+    pub fn with_env_access(mut self, env: EnvAccess) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Adds the readable environment variables to the `deps` of a file
+    /// importing `import_infos`, failing if one of them is aliased `env`.
+    fn insert_env(
+        &self,
+        import_infos: &HashMap<String, ImportInfo>,
+        deps: &mut HashMap<String, Value>,
+    ) -> anyhow::Result<()> {
+        if let Some(info) = import_infos.values().find(|info| info.alias == ENV_PREFIX) {
+            return Err(anyhow!(
+                "import '{}' can't be aliased '{ENV_PREFIX}': it is reserved for environment variables",
+                info.path
+            ));
+        }
+        if let Some(variables) = self.env.variables() {
+            deps.insert(ENV_PREFIX.to_string(), variables);
+        }
+        Ok(())
+    }
     /// Returns the fully rendered configuration for the given file path.
     ///
     /// The rendering is lazy and cached - the first call computes the result,
     /// subsequent calls return the cached value. Template variables are resolved
     /// by recursively rendering imported files.
+    ///
+    /// Fails with an error naming the cycle if the file imports itself,
+    /// directly or through other files, and as set by
+    /// [`Dag::with_unresolved_policy`] if references are left unresolved.
+    /// Escaped `$${...}` placeholders come out as literal `${...}`.
     pub async fn get_rendered(&self, file_path: &str) -> anyhow::Result<Value> {
+        self.get_rendered_with_policy(file_path, self.unresolved).await
+    }
+
+    /// Same as [`Dag::get_rendered`], applying `policy` instead of the one
+    /// set by [`Dag::with_unresolved_policy`].
+    pub async fn get_rendered_with_policy(&self, file_path: &str, policy: UnresolvedPolicy) -> anyhow::Result<Value> {
+        let mut rendered = self.render_escaped(file_path).await?;
+        // Unescaping last keeps the literals from being taken for unresolved references
+        apply_unresolved_policy(&mut rendered, policy)?;
+        unescape_placeholders(&mut rendered);
+        Ok(rendered)
+    }
+
+    /// Checks `file_path` for import cycles, then renders it, leaving
+    /// escaped placeholders as is.
+    async fn render_escaped(&self, file_path: &str) -> anyhow::Result<Value> {
+        {
+            let files_snapshot = self.inner.files.load();
+            if let Some(cycle) = self.find_import_cycle(&files_snapshot, file_path) {
+                return Err(anyhow!("circular import: {}", cycle.join(" -> ")));
+            }
+        }
+        self.render_acyclic(file_path).await
+    }
+
+    /// Returns the first import cycle reachable from `start`, as the chain of
+    /// keys being rendered, e.g. `[a, b, a]`.
+    ///
+    /// Rendering a cycle would wait on its own cache cell forever, so this
+    /// runs before rendering. Already rendered files are skipped: their
+    /// imports were checked when they were rendered.
+    fn find_import_cycle(&self, files: &DagFiles, start: &str) -> Option<Vec<String>> {
+        fn visit(
+            dag_inner: &DagInner<impl FileProvider>,
+            files: &DagFiles,
+            key: &str,
+            chain: &mut Vec<String>,
+            done: &mut HashSet<String>,
+        ) -> Option<Vec<String>> {
+            if let Some(start) = chain.iter().position(|k| k == key) {
+                let mut cycle = chain[start..].to_vec();
+                cycle.push(key.to_string());
+                return Some(cycle);
+            }
+            if done.contains(key) {
+                return None;
+            }
+            // Missing files are reported by the render itself
+            let konf = files.get(key)?;
+            if konf.rendered.get().is_some() {
+                return None;
+            }
+
+            let mut imports: Vec<String> = parse_imports_for(&konf.raw, key, &dag_inner.import_context)
+                .into_values()
+                .filter_map(|info| info.resolved_path)
+                .collect();
+            imports.sort();
+
+            chain.push(key.to_string());
+            for import in &imports {
+                if let Some(cycle) = visit(dag_inner, files, import, chain, done) {
+                    return Some(cycle);
+                }
+            }
+            chain.pop();
+            done.insert(key.to_string());
+            None
+        }
+
+        visit(&self.inner, files, start, &mut Vec::new(), &mut HashSet::new())
+    }
+
+    /// Renders a file whose imports were checked for cycles by [`Dag::render_escaped`].
+    ///
+    /// Each file gets a `render` span, nested under the spans of its importers,
+    /// recording its number of dependencies when it is not already cached.
+    #[tracing::instrument(name = "render", skip_all, fields(file = file_path, deps = tracing::field::Empty))]
+    async fn render_acyclic(&self, file_path: &str) -> anyhow::Result<Value> {
         let files_snapshot = self.inner.files.load();
         let konf = files_snapshot
             .get(file_path)
@@ -89,32 +278,38 @@ impl<P: FileProvider> Dag<P> {
 
                 // Parse imports using the new format-aware parser
                 // file_path is used to resolve relative paths (../, ./)
-                let import_infos = parse_imports(&raw_value, file_path);
+                let import_infos = parse_imports_for(&raw_value, file_path, &self.inner.import_context);
 
                 // Collect resolved paths for loading dependencies
                 let resolved_paths: Vec<String> = import_infos
                     .values()
                     .filter_map(|info| info.resolved_path.clone())
                     .collect();
+                tracing::Span::current().record("deps", resolved_paths.len());
 
                 // Load all dependencies by their resolved paths
-                let dep_futures = resolved_paths.iter().map(|path| self.get_rendered(path));
+                let dep_futures = resolved_paths.iter().map(|path| self.render_acyclic(path));
                 let dep_results = future::try_join_all(dep_futures).await?;
 
                 // Build deps_map using aliases as keys (for template resolution)
                 // This allows ${alias.key} to work in templates
-                let deps_map: HashMap<String, Value> = import_infos
+                let mut deps_map: HashMap<String, Value> = import_infos
                     .values()
                     .map(|info| info.alias.clone())
                     .zip(dep_results)
                     .collect();
 
+                let extends = parse_extends(&raw_value);
                 let mut value_to_render = raw_value;
-                resolve_refs_from_deps(&mut value_to_render, &deps_map);
+                self.insert_env(&import_infos, &mut deps_map)?;
+                try_resolve_refs_from_deps(&mut value_to_render, &deps_map)?;
+                // Only references may read the environment, not `extends`
+                deps_map.remove(ENV_PREFIX);
 
                 if let Value::Mapping(ref mut m) = value_to_render {
                     m.remove("<!>");
                 };
+                let value_to_render = extend(value_to_render, &extends, &deps_map)?;
 
                 // The future must resolve to a Result<Value, E>
                 Ok::<_, anyhow::Error>(value_to_render)
@@ -124,6 +319,98 @@ impl<P: FileProvider> Dag<P> {
         Ok(rendered_value.clone())
     }
 
+    /// Renders a configuration, isolating failures per top-level key.
+    ///
+    /// Unlike [`Dag::get_rendered`], a failed import or a placeholder that
+    /// cannot be resolved does not fail the whole render: each affected
+    /// top-level key is replaced with an [`ERROR_MARKER_KEY`] marker and
+    /// reported, while the other keys render normally. The result is not cached.
+    pub async fn get_rendered_isolated(&self, file_path: &str) -> anyhow::Result<(Value, Vec<KeyFailure>)> {
+        let raw_value = {
+            let files_snapshot = self.inner.files.load();
+            files_snapshot
+                .get(file_path)
+                .ok_or_else(|| anyhow!("File not found: {}", file_path))?
+                .raw
+                .clone()
+        };
+
+        // Render imports one by one, remembering the ones that failed
+        let import_infos = parse_imports_for(&raw_value, file_path, &self.inner.import_context);
+        let mut deps_map: HashMap<String, Value> = HashMap::new();
+        let mut failed_imports: HashMap<String, String> = HashMap::new();
+        for info in import_infos.values() {
+            let Some(path) = &info.resolved_path else {
+                continue;
+            };
+            match self.render_escaped(path).await {
+                Ok(value) => {
+                    deps_map.insert(info.alias.clone(), value);
+                }
+                Err(e) => {
+                    failed_imports.insert(info.alias.clone(), format!("import '{path}' failed: {e}"));
+                }
+            }
+        }
+
+        let extends = parse_extends(&raw_value);
+        self.insert_env(&import_infos, &mut deps_map)?;
+        let Value::Mapping(mut map) = raw_value else {
+            let mut value = raw_value;
+            try_resolve_refs_from_deps(&mut value, &deps_map)?;
+            deps_map.remove(ENV_PREFIX);
+            let mut value = extend(value, &extends, &deps_map)?;
+            unescape_placeholders(&mut value);
+            return Ok((value, Vec::new()));
+        };
+        map.remove(METADATA_KEY);
+
+        let mut failures = Vec::new();
+        for (key, value) in map.iter_mut() {
+            let reason = match resolve_refs_collecting(value, &deps_map) {
+                Ok(key_failures) => {
+                    let Some(failure) = key_failures.into_iter().next() else {
+                        continue;
+                    };
+                    // Report the root cause when the reference goes through a failed import
+                    let alias = placeholder_path(&failure.expression)
+                        .and_then(|path| path.split('.').next())
+                        .unwrap_or_default();
+                    failed_imports
+                        .get(alias)
+                        .cloned()
+                        .unwrap_or_else(|| failure.to_string())
+                }
+                Err(e) => e.to_string(),
+            };
+
+            let mut marker = crate::Mapping::new();
+            marker.insert(ERROR_MARKER_KEY.to_string(), Value::String(reason.clone()));
+            *value = Value::Mapping(marker);
+            failures.push(KeyFailure { key: key.clone(), reason });
+        }
+
+        // Inherit from the bases that rendered; a failed one is reported on its own
+        deps_map.remove(ENV_PREFIX);
+        let (bases, failed_bases): (Vec<String>, Vec<String>) =
+            extends.into_iter().partition(|alias| deps_map.contains_key(alias));
+        for alias in failed_bases {
+            let reason = failed_imports
+                .get(&alias)
+                .cloned()
+                .unwrap_or_else(|| format!("cannot extend '{alias}': it is not an import alias"));
+            failures.push(KeyFailure {
+                key: format!("{METADATA_KEY}.{EXTENDS_KEY}"),
+                reason,
+            });
+        }
+        failures.sort_by(|a, b| a.key.cmp(&b.key));
+
+        let mut rendered = extend(Value::Mapping(map), &bases, &deps_map)?;
+        unescape_placeholders(&mut rendered);
+        Ok((rendered, failures))
+    }
+
     /// Reloads all configuration files from the provider.
     ///
     /// This atomically replaces all loaded configurations. Any cached
@@ -131,9 +418,22 @@ impl<P: FileProvider> Dag<P> {
     pub async fn reload(&self) -> Result<(), LoaderError> {
         let paths = self.inner.file_provider.list().await;
         let mut files: DagFiles = HashMap::new();
+        let mut schemas = HashMap::new();
 
         for path in paths {
             if let Some(content) = self.inner.file_provider.load(&path.full_path).await {
+                // `app.schema.json` validates `app` rather than being a config itself
+                if path.ext == "json"
+                    && let Some(key) = path.filename.strip_suffix(SCHEMA_KEY_SUFFIX)
+                {
+                    match schema::compile_schema(&content) {
+                        Ok(compiled) => {
+                            schemas.insert(key.to_string(), Arc::new(compiled));
+                        }
+                        Err(e) => tracing::warn!("ignoring schema {:?}: {e}", &path),
+                    }
+                    continue;
+                }
                 match self.inner.multiloader.load(&path.ext, &content) {
                     Ok(l) => {
                         let k = Konf::new(l);
@@ -147,9 +447,164 @@ impl<P: FileProvider> Dag<P> {
         }
         // Atomically publish the new HashMap
         self.inner.files.store(Arc::new(files));
+        self.inner.schemas.store(Arc::new(schemas));
         Ok(())
     }
 
+    /// Validates the import graph of the loaded files (missing imports, cycles).
+    ///
+    /// With `check_references`, unresolvable `${...}` references are reported too.
+    pub fn check_graph(&self, check_references: bool) -> GraphReport {
+        let files = self.inner.files.load();
+        graph::analyze(
+            files.iter().map(|(k, konf)| (k, &konf.raw)),
+            check_references,
+            &self.inner.import_context,
+        )
+    }
+
+    /// Returns true if a `<key>.schema.json` file was loaded for `key`.
+    pub fn has_schema(&self, key: &str) -> bool {
+        self.inner.schemas.load().contains_key(key)
+    }
+
+    /// Validates a rendered config against its schema, if it has one.
+    ///
+    /// Returns one message per violation; empty when valid or without a schema.
+    pub fn schema_violations(&self, key: &str, value: &Value) -> Vec<String> {
+        match self.inner.schemas.load().get(key) {
+            Some(compiled) => schema::validate(compiled, value),
+            None => Vec::new(),
+        }
+    }
+
+    /// Lists the loaded files that no other file imports.
+    pub fn find_orphans(&self, entrypoint_prefixes: &[String]) -> OrphanReport {
+        let files = self.inner.files.load();
+        graph::find_orphans(
+            files.iter().map(|(k, konf)| (k, &konf.raw)),
+            entrypoint_prefixes,
+            &self.inner.import_context,
+        )
+    }
+
+    /// Renders the import graph of the loaded files in Graphviz DOT.
+    pub fn to_dot(&self) -> String {
+        let files = self.inner.files.load();
+        graph::to_dot(files.iter().map(|(k, konf)| (k, &konf.raw)), &self.inner.import_context)
+    }
+
+    /// Returns all configuration keys currently loaded, sorted.
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.inner.files.load().keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+
+    /// Returns the keys of the loaded files `key` imports, directly or through
+    /// other imports, sorted. Conditional imports are evaluated in the DAG's context.
+    pub fn transitive_imports(&self, key: &str) -> Vec<String> {
+        let files = self.inner.files.load();
+        let mut seen = HashSet::new();
+        let mut pending = vec![key.to_string()];
+        while let Some(current) = pending.pop() {
+            let Some(konf) = files.get(&current) else {
+                continue;
+            };
+            for info in parse_imports_for(&konf.raw, &current, &self.inner.import_context).into_values() {
+                if let Some(import) = info.resolved_path
+                    && import != key
+                    && files.contains_key(&import)
+                    && seen.insert(import.clone())
+                {
+                    pending.push(import);
+                }
+            }
+        }
+        let mut imports: Vec<String> = seen.into_iter().collect();
+        imports.sort();
+        imports
+    }
+
+    /// Returns the keys of the configs under the `dir` folder, recursively, sorted.
+    ///
+    /// `services/api` matches `services/api/db` but not `services/api-v2`.
+    pub fn keys_under(&self, dir: &str) -> Vec<String> {
+        let prefix = format!("{}/", normalize_path(dir));
+        let mut keys: Vec<String> = self
+            .inner
+            .files
+            .load()
+            .keys()
+            .filter(|key| key.starts_with(&prefix))
+            .cloned()
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    /// Resolves a requested path to a loaded configuration key.
+    ///
+    /// The path is normalized first (leading/trailing and duplicate slashes are
+    /// dropped). When `case_insensitive` is set, a key differing only by case
+    /// is accepted as long as it is unambiguous.
+    ///
+    /// On a miss, returns up to five near-matching keys to help the caller.
+    pub fn resolve_key(&self, path: &str, case_insensitive: bool) -> Result<String, Vec<String>> {
+        let normalized = normalize_path(path);
+        let files = self.inner.files.load();
+        if files.contains_key(&normalized) {
+            return Ok(normalized);
+        }
+
+        let lowered = normalized.to_lowercase();
+        let case_matches: Vec<&String> = files
+            .keys()
+            .filter(|key| key.to_lowercase() == lowered)
+            .collect();
+        if case_insensitive && let [key] = case_matches.as_slice() {
+            return Ok((*key).clone());
+        }
+
+        let mut suggestions: Vec<(usize, String)> = files
+            .keys()
+            .filter_map(|key| {
+                let distance = edit_distance(&key.to_lowercase(), &lowered);
+                (distance <= 2).then(|| (distance, key.clone()))
+            })
+            .collect();
+        suggestions.sort();
+        Err(suggestions
+            .into_iter()
+            .map(|(_, key)| key)
+            .take(MAX_SUGGESTIONS)
+            .collect())
+    }
+
+    /// Rough number of bytes held by the loaded and rendered files.
+    pub fn estimated_size(&self) -> usize {
+        self.inner
+            .files
+            .load()
+            .iter()
+            .map(|(key, konf)| {
+                key.capacity()
+                    + konf.raw.estimated_size()
+                    + konf.rendered.get().map_or(0, Value::estimated_size)
+            })
+            .sum()
+    }
+
+    /// Returns the output format `key` declares in its metadata, if any.
+    pub fn output_format(&self, key: &str) -> Option<String> {
+        self.inner.files.load().get(key).and_then(|konf| parse_output_format(&konf.raw))
+    }
+
+    /// Returns the cache TTL, in seconds, the file declares (`<!>: output: ttl:`), if any.
+    pub fn output_ttl(&self, key: &str) -> Option<u64> {
+        self.inner.files.load().get(key).and_then(|konf| parse_output_ttl(&konf.raw))
+    }
+
     /// Returns the raw (unrendered) configuration value for the given file.
     pub fn get_raw(&self, file_path: &str) -> Result<Value, RenderError> {
         let files_snapshot = self.inner.files.load();
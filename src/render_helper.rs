@@ -1,37 +1,142 @@
 use std::collections::HashMap;
+use std::ops::Range;
 use std::sync::OnceLock;
 
-use crate::functions::{registry, FunctionArg, FunctionError};
+use crate::functions::{parse_args, registry, CallArg, FunctionArg, FunctionError};
 use crate::Value;
 
-use regex::{Captures, Regex};
+use regex::Regex;
 
-/// Regex for an exact match, e.g., "${a.b.c}" or "${a.b.c | func}"
-static EXACT_MATCH_RE: OnceLock<Regex> = OnceLock::new();
-/// Regex for finding all occurrences, e.g., in "http://${host}/${path}"
-static INTERPOLATION_RE: OnceLock<Regex> = OnceLock::new();
 /// Regex for parsing placeholder content: path and optional functions
 static PLACEHOLDER_CONTENT_RE: OnceLock<Regex> = OnceLock::new();
-/// Regex for parsing a single function call: name and optional argument
-static FUNCTION_CALL_RE: OnceLock<Regex> = OnceLock::new();
+/// Regex for a single path segment, see [`is_valid_path_segment`]
+static PATH_SEGMENT_RE: OnceLock<Regex> = OnceLock::new();
 
-fn exact_match_re() -> &'static Regex {
-    EXACT_MATCH_RE.get_or_init(|| Regex::new(r"^\$\{(?P<content>[^}]+)\}$").expect("invalid regex"))
+/// Prefix of an escaped placeholder: `$${foo}` renders to the literal `${foo}`.
+const ESCAPED_PLACEHOLDER: &str = "$${";
+
+/// A `${...}` placeholder found in a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placeholder<'a> {
+    /// Byte range of the whole placeholder, from its first `$` to the closing `}`
+    pub range: Range<usize>,
+    /// The expression between the braces
+    pub content: &'a str,
+    /// True for an escaped `$${...}`, which is a literal rather than a reference
+    pub escaped: bool,
 }
 
-fn interpolation_re() -> &'static Regex {
-    INTERPOLATION_RE
-        .get_or_init(|| Regex::new(r"\$\{(?P<content>[^}]+)\}").expect("invalid regex"))
+/// Finds every `${...}` placeholder in `s`, escaped `$${...}` ones included.
+///
+/// A placeholder ends at the first `}` outside quotes that closes its own
+/// `${`, so function arguments can contain braces, e.g. `${a | regex_replace("o{2}", "0")}`.
+///
+/// # Example
+/// ```
+/// use konf_provider::render_helper::placeholders;
+///
+/// let found = placeholders(r#"${a.b | regex_replace("\\d{3}", "-")}/$${c}"#);
+///
+/// assert_eq!(found.len(), 2);
+/// assert_eq!(found[0].content, r#"a.b | regex_replace("\\d{3}", "-")"#);
+/// assert!(!found[0].escaped);
+/// assert_eq!(found[1].content, "c");
+/// assert!(found[1].escaped);
+/// ```
+pub fn placeholders(s: &str) -> Vec<Placeholder<'_>> {
+    let mut found = Vec::new();
+    let mut pos = 0;
+    while let Some(offset) = s[pos..].find("${") {
+        let open = pos + offset;
+        let content_start = open + 2;
+        match closing_brace(s, content_start) {
+            Some(close) if close > content_start => {
+                let escaped = s[..open].ends_with('$');
+                found.push(Placeholder {
+                    range: if escaped { open - 1 } else { open }..close + 1,
+                    content: &s[content_start..close],
+                    escaped,
+                });
+                pos = close + 1;
+            }
+            _ => pos = content_start,
+        }
+    }
+    found
 }
 
-/// Returns the regex for matching template references: ${path.to.value}
+/// Returns the index of the `}` closing a placeholder whose content starts at `start`.
 ///
-/// This regex matches template placeholders like `${some.path}` and captures
-/// the content inside the braces in a named group called "content".
+/// Braces and quotes inside the content are balanced, as in [`split_chain`].
+fn closing_brace(s: &str, start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in s[start..].char_indices() {
+        match (quote, c) {
+            _ if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '{') => depth += 1,
+            (None, '}') if depth == 0 => return Some(start + i),
+            (None, '}') => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the content of `s` if the whole string is a single unescaped placeholder.
+fn exact_placeholder(s: &str) -> Option<&str> {
+    match placeholders(s).as_slice() {
+        [p] if !p.escaped && p.range == (0..s.len()) => Some(p.content),
+        _ => None,
+    }
+}
+
+/// Rebuilds `s` with every placeholder replaced by the output of `replace`.
 ///
-/// Useful for LSP and other tools that need to find template references in text.
-pub fn template_re() -> &'static Regex {
-    interpolation_re()
+/// Returns `None` if `s` has no placeholder.
+fn replace_placeholders(s: &str, mut replace: impl FnMut(&Placeholder) -> String) -> Option<String> {
+    let found = placeholders(s);
+    if found.is_empty() {
+        return None;
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut last = 0;
+    for placeholder in &found {
+        out.push_str(&s[last..placeholder.range.start]);
+        out.push_str(&replace(placeholder));
+        last = placeholder.range.end;
+    }
+    out.push_str(&s[last..]);
+    Some(out)
+}
+
+/// Returns true if `s` contains a `${...}` placeholder that is not escaped.
+pub fn has_placeholder(s: &str) -> bool {
+    placeholders(s).iter().any(|p| !p.escaped)
+}
+
+/// Turns every escaped `$${...}` into the literal `${...}`.
+///
+/// Escapes are kept through rendering, imports included, so that the
+/// literal is never taken for a placeholder; this runs once on the output.
+pub fn unescape_placeholders(value: &mut Value) {
+    match value {
+        Value::String(s) if s.contains(ESCAPED_PLACEHOLDER) => {
+            if let Some(unescaped) = replace_placeholders(s, |p| match p.escaped {
+                true => s[p.range.start + 1..p.range.end].to_string(),
+                false => s[p.range.clone()].to_string(),
+            }) {
+                *s = unescaped;
+            }
+        }
+        Value::Sequence(items) => items.iter_mut().for_each(unescape_placeholders),
+        Value::Mapping(map) => map.values_mut().for_each(unescape_placeholders),
+        _ => {}
+    }
 }
 
 /// A template reference found in a document, with position information.
@@ -72,88 +177,155 @@ pub fn find_template_refs(content: &str) -> Vec<TemplateRef> {
     let mut refs = vec![];
 
     for (line_idx, line) in content.lines().enumerate() {
-        for cap in template_re().captures_iter(line) {
-            if let Some(content_match) = cap.name("content") {
-                let full_match = cap.get(0).unwrap();
-                refs.push(TemplateRef {
-                    path: content_match.as_str().to_string(),
-                    line: line_idx,
-                    col_start: full_match.start(),
-                    col_end: full_match.end(),
-                });
-            }
+        for placeholder in placeholders(line).into_iter().filter(|p| !p.escaped) {
+            refs.push(TemplateRef {
+                path: placeholder.content.to_string(),
+                line: line_idx,
+                col_start: placeholder.range.start,
+                col_end: placeholder.range.end,
+            });
         }
     }
 
     refs
 }
 
+/// Characters of a placeholder path segment; segments are joined by `.`.
+const SEGMENT_CHARS: &str = r"\w/";
+
 fn placeholder_content_re() -> &'static Regex {
     PLACEHOLDER_CONTENT_RE.get_or_init(|| {
         // Matches: "path.to.value" or "path.to.value | func1 | func2:arg"
-        Regex::new(r"^(?P<path>[\w./]+)(?P<funcs>\s*\|.+)?$").expect("invalid regex")
+        Regex::new(&format!(r"^(?P<path>[{SEGMENT_CHARS}.]+)(?P<funcs>\s*\|.+)?$")).expect("invalid regex")
     })
 }
 
-fn function_call_re() -> &'static Regex {
-    FUNCTION_CALL_RE.get_or_init(|| {
-        // Matches: "funcname" or "funcname:\"arg\"" or "funcname:123" or "funcname:true"
-        Regex::new(r#"(?P<name>\w+)(?::(?:"(?P<str_arg>[^"]*)"|(?P<num_arg>-?\d+(?:\.\d+)?)|(?P<bool_arg>true|false)))?"#)
-            .expect("invalid regex")
-    })
+fn path_segment_re() -> &'static Regex {
+    PATH_SEGMENT_RE.get_or_init(|| Regex::new(&format!(r"^[{SEGMENT_CHARS}]+$")).expect("invalid regex"))
+}
+
+/// Returns true if `segment` can be one segment of a `${...}` path: an alias or a key.
+///
+/// # Example
+/// ```
+/// use konf_provider::render_helper::is_valid_path_segment;
+///
+/// assert!(is_valid_path_segment("db_host"));
+/// assert!(is_valid_path_segment("common/database"));
+/// assert!(!is_valid_path_segment("db-host"));
+/// assert!(!is_valid_path_segment("db.host"));
+/// ```
+pub fn is_valid_path_segment(segment: &str) -> bool {
+    path_segment_re().is_match(segment)
 }
 
-/// A parsed function call with name and optional argument.
+/// A parsed function call with its name and arguments.
 #[derive(Debug)]
 struct ParsedFunctionCall {
     name: String,
-    arg: Option<FunctionArg>,
+    /// Literal and reference arguments, in call order
+    args: Vec<CallArg>,
 }
 
-/// Parses a chain of function calls from a string like "| func1 | func2:\"arg\"".
+/// Splits a function chain on `|`, ignoring pipes inside quoted arguments.
+pub fn split_chain(chain: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quote = None;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in chain.char_indices() {
+        match (quote, c) {
+            _ if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '|') => {
+                parts.push(&chain[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&chain[start..]);
+    parts
+}
+
+/// Parses a chain of function calls from a string like `| func1 | func2("arg", 2)`.
+///
+/// A single argument can also be written `func:arg`.
 fn parse_function_chain(chain: &str) -> Result<Vec<ParsedFunctionCall>, FunctionError> {
     let mut functions = Vec::new();
 
-    // Split by pipe and process each function
-    for part in chain.split('|') {
+    for part in split_chain(chain) {
         let part = part.trim();
         if part.is_empty() {
             continue;
         }
 
-        if let Some(caps) = function_call_re().captures(part) {
-            let name = caps.name("name").unwrap().as_str().to_string();
-
-            let arg = if let Some(str_arg) = caps.name("str_arg") {
-                Some(FunctionArg::String(str_arg.as_str().to_string()))
-            } else if let Some(num_arg) = caps.name("num_arg") {
-                let num_str = num_arg.as_str();
-                if num_str.contains('.') {
-                    Some(FunctionArg::Float(num_str.parse().unwrap_or(0.0)))
-                } else {
-                    Some(FunctionArg::Int(num_str.parse().unwrap_or(0)))
-                }
-            } else {
-                caps.name("bool_arg")
-                    .map(|bool_arg| FunctionArg::Boolean(bool_arg.as_str() == "true"))
-            };
-
-            functions.push(ParsedFunctionCall { name, arg });
+        let name_len = part
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(part.len());
+        let (name, rest) = part.split_at(name_len);
+        let malformed = |message: String| FunctionError::MalformedCall {
+            function: if name.is_empty() { part.to_string() } else { name.to_string() },
+            message,
+        };
+        if name.is_empty() {
+            return Err(malformed("expected a function name".to_string()));
         }
+
+        let rest = rest.trim_start();
+        let args = if rest.is_empty() {
+            Vec::new()
+        } else if let Some(list) = rest.strip_prefix('(') {
+            let list = list
+                .strip_suffix(')')
+                .ok_or_else(|| malformed("missing closing ')'".to_string()))?;
+            parse_args(list).map_err(malformed)?
+        } else if let Some(arg) = rest.strip_prefix(':') {
+            let args = parse_args(arg).map_err(malformed)?;
+            if args.len() != 1 {
+                return Err(malformed("':' takes a single argument, use parentheses for more".to_string()));
+            }
+            args
+        } else {
+            return Err(malformed(format!("unexpected '{rest}' after the function name")));
+        };
+
+        functions.push(ParsedFunctionCall {
+            name: name.to_string(),
+            args,
+        });
     }
 
     Ok(functions)
 }
 
-/// Applies a chain of functions to a value.
+/// Applies a chain of functions to a value, resolving reference arguments from `deps`.
 fn apply_function_chain(
     mut value: Value,
     funcs: &[ParsedFunctionCall],
+    deps: &HashMap<String, Value>,
 ) -> Result<Value, FunctionError> {
     let reg = registry();
 
     for func in funcs {
-        let args: Vec<FunctionArg> = func.arg.iter().cloned().collect();
+        let args = func
+            .args
+            .iter()
+            .map(|arg| match arg {
+                CallArg::Literal(literal) => Ok(literal.clone()),
+                CallArg::Reference(reference) => match lookup_path(reference, deps) {
+                    Some(value) => Ok(FunctionArg::Value(value)),
+                    // Missing fallbacks are skipped by `coalesce`
+                    None if func.name == "coalesce" => Ok(FunctionArg::Value(Value::Null)),
+                    None => Err(FunctionError::ExecutionError {
+                        function: func.name.clone(),
+                        message: format!("unresolved reference argument '{reference}'"),
+                    }),
+                },
+            })
+            .collect::<Result<Vec<_>, _>>()?;
         value = reg.execute(&func.name, value, &args)?;
     }
 
@@ -170,118 +342,511 @@ fn resolve_placeholder_expression(
 
     let path = content_caps.name("path")?.as_str();
 
-    // Look up the value
-    let value = lookup_in_deps(path, deps)?;
+    // Parse the function chain, if any
+    let funcs = match content_caps.name("funcs") {
+        Some(chain) => match parse_function_chain(chain.as_str()) {
+            Ok(funcs) => funcs,
+            Err(e) => return Some(Err(e)),
+        },
+        None => Vec::new(),
+    };
 
-    // Check if there are functions to apply
-    let funcs_str = content_caps.name("funcs").map(|m| m.as_str());
+    // Look up the value. An unset environment variable is null when a
+    // `default` or `coalesce` can replace it or `required` reports it, and an
+    // error otherwise. A key missing from an import is only null for
+    // `default`, `required` (so it fails loudly instead of being left
+    // unresolved) and `coalesce`; an alias that is not imported here is left
+    // for the importing file to resolve.
+    let handles_null = |names: &[&str]| funcs.iter().any(|func| names.contains(&func.name.as_str()));
+    let imported = path.split('.').next().is_some_and(|alias| deps.contains_key(alias));
+    let value = match env_var_name(path) {
+        Some(name) => match lookup_path(path, deps) {
+            Some(value) => value,
+            None if handles_null(&["default", "coalesce", "required"]) => Value::Null,
+            None => return Some(Err(FunctionError::MissingEnvVar(name.to_string()))),
+        },
+        None => match lookup_checked(path, deps) {
+            Ok(Some(value)) => value.clone(),
+            Ok(None) if imported && handles_null(&["default", "coalesce", "required"]) => Value::Null,
+            Ok(None) => return None,
+            Err(_) if handles_null(&["default", "coalesce", "required"]) => Value::Null,
+            Err(e) => return Some(Err(e)),
+        },
+    };
 
-    match funcs_str {
-        Some(chain) => {
-            // Parse and apply function chain
-            match parse_function_chain(chain) {
-                Ok(funcs) if funcs.is_empty() => Some(Ok(value.clone())),
-                Ok(funcs) => Some(apply_function_chain(value.clone(), &funcs)),
-                Err(e) => Some(Err(e)),
-            }
+    if funcs.is_empty() {
+        Some(Ok(value))
+    } else {
+        Some(apply_function_chain(value, &funcs, deps))
+    }
+}
+
+/// First path segment reserved for OS environment variables: `${env.DB_PASSWORD}`.
+///
+/// They are resolved against the mapping of [`EnvAccess::variables`], passed
+/// under this alias with the imports; an import can't use it as its alias.
+pub const ENV_PREFIX: &str = "env";
+
+/// Returns the variable name of an `env.NAME` path, or `None` for other paths.
+///
+/// ```
+/// use konf_provider::render_helper::env_var_name;
+///
+/// assert_eq!(env_var_name("env.DB_PASSWORD"), Some("DB_PASSWORD"));
+/// assert_eq!(env_var_name("db.password"), None);
+/// ```
+pub fn env_var_name(path: &str) -> Option<&str> {
+    path.strip_prefix(ENV_PREFIX)?
+        .strip_prefix('.')
+        .filter(|name| !name.is_empty())
+}
+
+/// Prefixes of the server's own variables, never readable through `${env.NAME}`.
+const DENIED_ENV_PREFIXES: &[&str] = &["KONF_", "OTEL_"];
+
+/// Which environment variables `${env.NAME}` references may read.
+///
+/// Nothing is readable by default: each allowed prefix (`--env-prefix APP_`)
+/// opts its variables in. The server's own `KONF_*` and `OTEL_*` variables,
+/// which hold its secrets, are always denied.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvAccess {
+    prefixes: Vec<String>,
+}
+
+impl EnvAccess {
+    /// Allows the variables starting with one of `prefixes`.
+    pub fn with_prefixes(prefixes: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            prefixes: prefixes.into_iter().collect(),
+        }
+    }
+
+    /// Returns true if `${env.NAME}` may read the variable `name`.
+    ///
+    /// ```
+    /// use konf_provider::render_helper::EnvAccess;
+    ///
+    /// let env = EnvAccess::with_prefixes(["APP_".to_string()]);
+    /// assert!(env.allows("APP_DB_PASSWORD"));
+    /// assert!(!env.allows("HOME"));
+    /// assert!(!EnvAccess::with_prefixes([String::new()]).allows("KONF_JWT_SECRET"));
+    /// ```
+    pub fn allows(&self, name: &str) -> bool {
+        !DENIED_ENV_PREFIXES.iter().any(|denied| name.starts_with(denied))
+            && self.prefixes.iter().any(|prefix| name.starts_with(prefix.as_str()))
+    }
+
+    /// Returns the allowed variables that are set, as the mapping `${env.NAME}`
+    /// references are resolved against, or `None` if no variable is allowed.
+    pub fn variables(&self) -> Option<Value> {
+        if self.prefixes.is_empty() {
+            return None;
         }
-        None => Some(Ok(value.clone())),
+        let variables = std::env::vars()
+            .filter(|(name, _)| self.allows(name))
+            .map(|(name, value)| (name, Value::String(value)))
+            .collect();
+        Some(Value::Mapping(variables))
     }
 }
 
+/// Looks up a path in the imports, environment variables (`env.NAME`) included.
+fn lookup_path(path: &str, deps: &HashMap<String, Value>) -> Option<Value> {
+    lookup_in_deps(path, deps).cloned()
+}
+
+/// Returns the referenced path of a placeholder expression, without its function chain.
+///
+/// `"db.host | trim"` yields `Some("db.host")`.
+pub fn placeholder_path(expr: &str) -> Option<&str> {
+    placeholder_content_re()
+        .captures(expr)
+        .and_then(|caps| caps.name("path"))
+        .map(|path| path.as_str())
+}
+
+/// Returns the function calls of a placeholder expression as `(name, argument count)`.
+///
+/// `"db.host | trim | default:\"x\""` yields `[("trim", 0), ("default", 1)]`.
+pub fn placeholder_functions(expr: &str) -> Vec<(String, usize)> {
+    let Some(chain) = placeholder_content_re()
+        .captures(expr)
+        .and_then(|caps| caps.name("funcs"))
+    else {
+        return vec![];
+    };
+    parse_function_chain(chain.as_str())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|call| (call.name, call.args.len()))
+        .collect()
+}
+
+/// Returns the paths a placeholder expression's functions take as arguments.
+///
+/// `"db.host | default(fallback.host)"` yields `["fallback.host"]`.
+pub fn placeholder_reference_args(expr: &str) -> Vec<String> {
+    let Some(chain) = placeholder_content_re()
+        .captures(expr)
+        .and_then(|caps| caps.name("funcs"))
+    else {
+        return vec![];
+    };
+    parse_function_chain(chain.as_str())
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|call| call.args)
+        .filter_map(|arg| match arg {
+            CallArg::Reference(reference) => Some(reference),
+            CallArg::Literal(_) => None,
+        })
+        .collect()
+}
+
 /// Helper to look up a dotted path (e.g., "dependency_file.some.nested.key")
 /// within the pre-rendered dependencies map.
-/// (This function remains unchanged from your original code).
-fn lookup_in_deps<'a>(path: &str, deps: &'a HashMap<String, Value>) -> Option<&'a Value> {
+///
+/// Numeric segments index into sequences: `db.replicas.0.host`.
+pub(crate) fn lookup_in_deps<'a>(path: &str, deps: &'a HashMap<String, Value>) -> Option<&'a Value> {
+    // The first part of the path is the key to the top-level dependency map.
+    match path.split_once('.') {
+        Some((file_key, rest)) => deps.get(file_key)?.get_path(rest),
+        None => deps.get(path),
+    }
+}
+
+/// Like [`lookup_in_deps`], but tells an index past the end of a sequence
+/// apart from a missing key, so it can be reported as an error.
+fn lookup_checked<'a>(path: &str, deps: &'a HashMap<String, Value>) -> Result<Option<&'a Value>, FunctionError> {
     let mut parts = path.split('.');
 
     // The first part of the path is the key to the top-level dependency map.
-    let file_key = parts.next()?;
-
-    // Find the root `Value` for this dependency in our map.
-    let mut current = deps.get(file_key)?;
+    let Some(mut current) = parts.next().and_then(|file_key| deps.get(file_key)) else {
+        return Ok(None);
+    };
 
     // Traverse the rest of the path parts to find the nested value.
     for key in parts {
-        current = current.get(key)?;
+        current = match current {
+            Value::Sequence(seq) => {
+                let Ok(index) = key.parse::<usize>() else {
+                    return Ok(None);
+                };
+                match seq.get(index) {
+                    Some(item) => item,
+                    None => {
+                        return Err(FunctionError::IndexOutOfBounds {
+                            path: path.to_string(),
+                            index,
+                            len: seq.len(),
+                        });
+                    }
+                }
+            }
+            _ => match current.get(key) {
+                Some(value) => value,
+                None => return Ok(None),
+            },
+        };
     }
 
-    Some(current)
+    Ok(Some(current))
 }
 
 /// Helper to stringify a `Value` for interpolation.
-/// Complex types like Mappings and Sequences return None as they can't be
-/// meaningfully embedded in a string.
+/// Scalars are written as-is and null as an empty string. Complex types like
+/// Mappings and Sequences return None as they can't be meaningfully embedded
+/// in a string.
 fn value_to_string(v: &Value) -> Option<String> {
     match v {
         Value::String(s) => Some(s.clone()),
         Value::Int(n) => Some(n.to_string()),
         Value::Float(n) => Some(n.to_string()),
         Value::Boolean(b) => Some(b.to_string()),
-        Value::Null => Some("null".to_string()),
+        Value::Null => Some(String::new()),
         // Sequences and Mappings can't be meaningfully embedded in a string
         Value::Sequence(_) | Value::Mapping(_) => None,
     }
 }
 
+/// A placeholder that could not be resolved while rendering.
+#[derive(Debug)]
+pub struct RefFailure {
+    /// The placeholder content, e.g. `db.host | upper`.
+    pub expression: String,
+    /// The function error, or `None` if the referenced path does not exist.
+    pub error: Option<FunctionError>,
+}
+
+impl std::fmt::Display for RefFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.error {
+            Some(e) => write!(f, "'${{{}}}': {e}", self.expression),
+            None => write!(f, "unresolved reference '${{{}}}'", self.expression),
+        }
+    }
+}
+
+/// Maximum number of resolution passes used by [`resolve_refs_collecting`].
+pub const MAX_RESOLVE_PASSES: usize = 8;
+
+/// Placeholders kept producing new placeholders after the maximum number of
+/// passes, e.g. a value that expands to a reference to itself plus a suffix.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("references did not converge after {passes} passes: {}", .expressions.join(", "))]
+pub struct ConvergenceError {
+    /// Number of passes performed.
+    pub passes: usize,
+    /// Placeholders still present after the last pass.
+    pub expressions: Vec<String>,
+}
+
+/// Error returned by [`try_resolve_refs_from_deps`].
+#[derive(Debug, thiserror::Error)]
+pub enum ResolveError {
+    /// References kept expanding past the maximum number of passes.
+    #[error(transparent)]
+    Convergence(#[from] ConvergenceError),
+    /// A function in a placeholder's chain failed (unknown function, wrong
+    /// argument, unsupported type...).
+    #[error("{0}")]
+    Function(RefFailure),
+    /// Placeholders were left unresolved under [`UnresolvedPolicy::Error`].
+    #[error(
+        "unresolved references: {}",
+        .0.iter().map(|e| format!("'${{{e}}}'")).collect::<Vec<_>>().join(", ")
+    )]
+    Unresolved(Vec<String>),
+}
+
+/// What happens to placeholders that are still unresolved once a config is rendered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnresolvedPolicy {
+    /// Keep the literal `${...}` placeholder.
+    #[default]
+    Leave,
+    /// Replace the value holding the placeholder with null.
+    Null,
+    /// Remove the value holding the placeholder from its mapping or sequence.
+    Omit,
+    /// Fail the render.
+    Error,
+}
+
+impl std::str::FromStr for UnresolvedPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "leave" => Ok(Self::Leave),
+            "null" => Ok(Self::Null),
+            "omit" => Ok(Self::Omit),
+            "error" => Ok(Self::Error),
+            other => Err(format!(
+                "unknown unresolved policy '{other}' (expected one of: leave, null, omit, error)"
+            )),
+        }
+    }
+}
+
+/// Applies `policy` to the placeholders left in a rendered value.
+///
+/// This runs on the final output rather than on each file: a placeholder an
+/// import can't resolve may still be resolved by the file importing it.
+/// A string interpolating an unresolved placeholder (`http://${db.host}`)
+/// counts as unresolved as a whole. Omitting the root value leaves null.
+pub fn apply_unresolved_policy(value: &mut Value, policy: UnresolvedPolicy) -> Result<(), ResolveError> {
+    match policy {
+        UnresolvedPolicy::Leave => Ok(()),
+        UnresolvedPolicy::Error => {
+            let remaining = collect_placeholders(value);
+            if remaining.is_empty() {
+                Ok(())
+            } else {
+                Err(ResolveError::Unresolved(remaining))
+            }
+        }
+        UnresolvedPolicy::Null | UnresolvedPolicy::Omit => {
+            if strip_unresolved(value, policy) {
+                *value = Value::Null;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Nulls out unresolved values, or returns true if `value` should be omitted.
+fn strip_unresolved(value: &mut Value, policy: UnresolvedPolicy) -> bool {
+    match value {
+        Value::String(s) if has_placeholder(s) => {
+            if policy == UnresolvedPolicy::Omit {
+                return true;
+            }
+            *value = Value::Null;
+            false
+        }
+        Value::Sequence(items) => {
+            items.retain_mut(|item| !strip_unresolved(item, policy));
+            false
+        }
+        Value::Mapping(map) => {
+            map.retain(|_, item| !strip_unresolved(item, policy));
+            false
+        }
+        _ => false,
+    }
+}
+
 /// Traverses a `serde_yaml::Value` and replaces any `"${path}"` strings
 /// with the corresponding values found in the `deps` map.
 /// Supports function chains like `${path.to.value | trim | upper}`.
+///
+/// Placeholders that cannot be resolved are left unchanged. Function errors
+/// and non-convergent references are logged; use [`try_resolve_refs_from_deps`]
+/// to fail on them.
 pub fn resolve_refs_from_deps(value: &mut Value, deps: &HashMap<String, Value>) {
+    if let Err(e) = try_resolve_refs_from_deps(value, deps) {
+        tracing::warn!("{e}");
+    }
+}
+
+/// Same as [`resolve_refs_from_deps`], but fails on the first function error
+/// or when references don't converge.
+///
+/// Placeholders whose path does not exist are still left unchanged.
+pub fn try_resolve_refs_from_deps(
+    value: &mut Value,
+    deps: &HashMap<String, Value>,
+) -> Result<(), ResolveError> {
+    match resolve_refs_collecting(value, deps)?
+        .into_iter()
+        .find(|failure| failure.error.is_some())
+    {
+        Some(failure) => Err(ResolveError::Function(failure)),
+        None => Ok(()),
+    }
+}
+
+/// Same as [`try_resolve_refs_from_deps`], but returns the placeholders that
+/// could not be resolved.
+pub fn resolve_refs_collecting(
+    value: &mut Value,
+    deps: &HashMap<String, Value>,
+) -> Result<Vec<RefFailure>, ConvergenceError> {
+    resolve_refs_passes(value, deps, MAX_RESOLVE_PASSES)
+}
+
+/// Resolves placeholders in passes until a pass replaces nothing.
+///
+/// A resolved value may itself contain `${...}` (e.g. a string an import could
+/// not resolve in its own context), which the next pass resolves against
+/// `deps`. If placeholders are still being replaced after `max_passes`, the
+/// references don't converge and an error is returned.
+pub fn resolve_refs_passes(
+    value: &mut Value,
+    deps: &HashMap<String, Value>,
+    max_passes: usize,
+) -> Result<Vec<RefFailure>, ConvergenceError> {
+    for _ in 0..max_passes {
+        let mut failures = Vec::new();
+        if !resolve_refs(value, deps, &mut failures) {
+            return Ok(failures);
+        }
+    }
+    Err(ConvergenceError {
+        passes: max_passes,
+        expressions: collect_placeholders(value),
+    })
+}
+
+/// Collects the content of every `${...}` placeholder in string values.
+pub fn collect_placeholders(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(s) => placeholders(s)
+            .into_iter()
+            .filter(|p| !p.escaped)
+            .map(|p| p.content.trim().to_string())
+            .collect(),
+        Value::Sequence(items) => items.iter().flat_map(collect_placeholders).collect(),
+        Value::Mapping(map) => map.values().flat_map(collect_placeholders).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Runs one resolution pass, returning true if any placeholder was replaced.
+fn resolve_refs(value: &mut Value, deps: &HashMap<String, Value>, failures: &mut Vec<RefFailure>) -> bool {
     match value {
         Value::String(s) => {
             // Case 1: The entire string is a single placeholder, like "${a.b.c}" or "${a.b.c | func}".
             // In this case, we replace the string with the referenced value, preserving its type.
-            if let Some(caps) = exact_match_re().captures(s) {
-                if let Some(content) = caps.name("content")
-                    && let Some(result) = resolve_placeholder_expression(content.as_str(), deps)
-                {
-                    match result {
-                        Ok(replacement) => {
-                            *value = replacement;
-                        }
-                        Err(e) => {
-                            // Log error but leave placeholder unchanged
-                            tracing::warn!("Function error in placeholder: {}", e);
-                        }
+            if let Some(content) = exact_placeholder(s) {
+                match resolve_placeholder_expression(content, deps) {
+                    Some(Ok(replacement)) => {
+                        *value = replacement;
+                        return true;
                     }
+                    // Leave placeholder unchanged
+                    result => failures.push(RefFailure {
+                        expression: content.to_string(),
+                        error: result.and_then(|r| r.err()),
+                    }),
                 }
                 // Stop processing to avoid falling through to interpolation logic.
-                return;
+                return false;
             }
 
             // Case 2: The string contains one or more placeholders for interpolation,
             // like "http://${server.host}:${server.port}/path".
             // The result will always be a new string.
-            let new_s = interpolation_re().replace_all(s, |caps: &Captures| {
-                // Get the content from the "content" capture group.
-                caps.name("content")
-                    .and_then(|content| resolve_placeholder_expression(content.as_str(), deps))
-                    .and_then(|result| result.ok())
-                    .and_then(|v| value_to_string(&v))
-                    .unwrap_or_else(|| caps[0].to_string()) // If lookup or conversion fails, leave the placeholder unchanged.
+            let new_s = replace_placeholders(s, |placeholder| {
+                let original = s[placeholder.range.clone()].to_string();
+                // Escaped placeholders are kept until the output is unescaped
+                if placeholder.escaped {
+                    return original;
+                }
+                let content = placeholder.content;
+                match resolve_placeholder_expression(content, deps) {
+                    Some(Ok(v)) => {
+                        if let Some(s) = value_to_string(&v) {
+                            return s;
+                        }
+                        failures.push(RefFailure {
+                            expression: content.to_string(),
+                            error: Some(FunctionError::UnsupportedType {
+                                function: "interpolation".to_string(),
+                                got: crate::functions::value_type_name(&v),
+                            }),
+                        });
+                    }
+                    result => failures.push(RefFailure {
+                        expression: content.to_string(),
+                        error: result.and_then(|r| r.err()),
+                    }),
+                }
+                // If lookup or conversion fails, leave the placeholder unchanged.
+                original
             });
 
-            // If replace_all found and replaced something, it returns an Owned Cow.
-            // We update the original value only if a change was made.
-            if let std::borrow::Cow::Owned(owned_s) = new_s {
-                *value = Value::String(owned_s);
-            }
-        }
-        Value::Sequence(arr) => {
-            // Recurse for each item in the sequence.
-            for v in arr {
-                resolve_refs_from_deps(v, deps);
-            }
-        }
-        Value::Mapping(obj) => {
-            // Recurse for each value in the map.
-            for (_k, v) in obj.iter_mut() {
-                resolve_refs_from_deps(v, deps);
+            // replace_placeholders returns None when there was nothing to replace.
+            if let Some(new_s) = new_s {
+                // Placeholders that failed are written back unchanged
+                let changed = new_s != *s;
+                *value = Value::String(new_s);
+                return changed;
             }
+            false
         }
+        // Recurse for each item in the sequence, without short-circuiting.
+        Value::Sequence(arr) => arr
+            .iter_mut()
+            .fold(false, |changed, v| resolve_refs(v, deps, failures) | changed),
+        // Recurse for each value in the map.
+        Value::Mapping(obj) => obj
+            .values_mut()
+            .fold(false, |changed, v| resolve_refs(v, deps, failures) | changed),
         // Other types (Number, Bool, Null) don't have refs, so we do nothing.
-        _ => {}
+        _ => false,
     }
 }
 
@@ -303,7 +868,7 @@ mod tests {
         let funcs = parse_function_chain("| trim").unwrap();
         assert_eq!(funcs.len(), 1);
         assert_eq!(funcs[0].name, "trim");
-        assert!(funcs[0].arg.is_none());
+        assert!(funcs[0].args.is_empty());
     }
 
     #[test]
@@ -320,7 +885,7 @@ mod tests {
         let funcs = parse_function_chain(r#"| default:"fallback""#).unwrap();
         assert_eq!(funcs.len(), 1);
         assert_eq!(funcs[0].name, "default");
-        assert!(matches!(&funcs[0].arg, Some(FunctionArg::String(s)) if s == "fallback"));
+        assert!(matches!(funcs[0].args.first(), Some(CallArg::Literal(FunctionArg::String(s))) if s == "fallback"));
     }
 
     #[test]
@@ -328,25 +893,26 @@ mod tests {
         let funcs = parse_function_chain("| someFunc:42").unwrap();
         assert_eq!(funcs.len(), 1);
         assert_eq!(funcs[0].name, "someFunc");
-        assert!(matches!(funcs[0].arg, Some(FunctionArg::Int(42))));
+        assert!(matches!(funcs[0].args.first(), Some(CallArg::Literal(FunctionArg::Int(42)))));
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_parse_function_chain_with_float_arg() {
         let funcs = parse_function_chain("| someFunc:3.14").unwrap();
         assert_eq!(funcs.len(), 1);
         assert_eq!(funcs[0].name, "someFunc");
-        assert!(matches!(funcs[0].arg, Some(FunctionArg::Float(f)) if (f - 3.14).abs() < 0.001));
+        assert!(matches!(funcs[0].args.first(), Some(CallArg::Literal(FunctionArg::Float(f))) if (f - 3.14).abs() < 0.001));
     }
 
     #[test]
     fn test_parse_function_chain_with_bool_arg() {
         let funcs = parse_function_chain("| someFunc:true").unwrap();
         assert_eq!(funcs.len(), 1);
-        assert!(matches!(funcs[0].arg, Some(FunctionArg::Boolean(true))));
+        assert!(matches!(funcs[0].args.first(), Some(CallArg::Literal(FunctionArg::Boolean(true)))));
 
         let funcs = parse_function_chain("| someFunc:false").unwrap();
-        assert!(matches!(funcs[0].arg, Some(FunctionArg::Boolean(false))));
+        assert!(matches!(funcs[0].args.first(), Some(CallArg::Literal(FunctionArg::Boolean(false)))));
     }
 
     #[test]
@@ -379,6 +945,51 @@ mod tests {
         assert_eq!(value, Value::String("hello".to_string()));
     }
 
+    #[test]
+    fn test_resolve_refs_two_level_indirection() {
+        // Imports whose values still hold references they couldn't resolve themselves
+        let mut deps = HashMap::new();
+        deps.insert(
+            "a".to_string(),
+            Value::Mapping(make_mapping(vec![("x", Value::String("${b.y}".to_string()))])),
+        );
+        deps.insert(
+            "b".to_string(),
+            Value::Mapping(make_mapping(vec![("y", Value::String("db-${c.z}".to_string()))])),
+        );
+        deps.insert(
+            "c".to_string(),
+            Value::Mapping(make_mapping(vec![("z", Value::Int(1))])),
+        );
+
+        let mut value = Value::String("host: ${a.x}".to_string());
+        let failures = resolve_refs_collecting(&mut value, &deps).unwrap();
+        assert!(failures.is_empty());
+        assert_eq!(value, Value::String("host: db-1".to_string()));
+    }
+
+    #[test]
+    fn test_resolve_refs_non_convergent_errors() {
+        let mut deps = HashMap::new();
+        deps.insert(
+            "a".to_string(),
+            Value::Mapping(make_mapping(vec![("x", Value::String("${a.x}!".to_string()))])),
+        );
+
+        let mut value = Value::String("${a.x}".to_string());
+        let error = resolve_refs_passes(&mut value, &deps, 4).unwrap_err();
+        assert_eq!(error.passes, 4);
+        assert_eq!(error.expressions, vec!["a.x"]);
+
+        // A reference that expands to itself never converges either
+        deps.insert(
+            "b".to_string(),
+            Value::Mapping(make_mapping(vec![("x", Value::String("${b.x}".to_string()))])),
+        );
+        let mut value = Value::String("${b.x}".to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_err());
+    }
+
     #[test]
     fn test_resolve_refs_with_chained_functions() {
         let mut deps = HashMap::new();
@@ -394,6 +1005,271 @@ mod tests {
         assert_eq!(value, Value::String("HELLO".to_string()));
     }
 
+    #[test]
+    fn test_try_resolve_refs_fails_on_function_errors() {
+        let mut deps = HashMap::new();
+        deps.insert(
+            "base".to_string(),
+            Value::Mapping(make_mapping(vec![
+                ("name", Value::String("hello".to_string())),
+                ("port", Value::Int(8080)),
+            ])),
+        );
+
+        let mut value = Value::String("${base.name | trim | shout}".to_string());
+        let error = try_resolve_refs_from_deps(&mut value, &deps).unwrap_err();
+        assert!(matches!(
+            &error,
+            ResolveError::Function(RefFailure { error: Some(FunctionError::UnknownFunction(name)), .. }) if name == "shout"
+        ));
+        assert_eq!(error.to_string(), "'${base.name | trim | shout}': unknown function: shout");
+
+        let mut value = Value::String("port ${base.port | upper}".to_string());
+        let error = try_resolve_refs_from_deps(&mut value, &deps).unwrap_err();
+        assert!(matches!(
+            error,
+            ResolveError::Function(RefFailure { error: Some(FunctionError::UnsupportedType { got: "int", .. }), .. })
+        ));
+
+        // Missing paths are not function errors and stay unchanged
+        let mut value = Value::String("${base.missing | upper}".to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String("${base.missing | upper}".to_string()));
+    }
+
+    #[test]
+    fn test_resolve_refs_indexes_sequences() {
+        let server = |host: &str| Value::Mapping(make_mapping(vec![("host", Value::String(host.to_string()))]));
+        let mut deps = HashMap::new();
+        deps.insert(
+            "a".to_string(),
+            Value::Mapping(make_mapping(vec![
+                ("servers", Value::Sequence(vec![server("s0.internal"), server("s1.internal")])),
+                ("ports", Value::Sequence(vec![Value::Int(80), Value::Int(443)])),
+            ])),
+        );
+
+        let mut value = Value::String("${a.servers.0.host}".to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String("s0.internal".to_string()));
+
+        let mut value = Value::String("https://${a.servers.1.host}:${a.ports.1}".to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String("https://s1.internal:443".to_string()));
+
+        // Non-numeric segments don't index sequences: left unresolved
+        let mut value = Value::String("${a.servers.first.host}".to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String("${a.servers.first.host}".to_string()));
+
+        let mut value = Value::String("${a.servers.5.host}".to_string());
+        let error = try_resolve_refs_from_deps(&mut value, &deps).unwrap_err();
+        assert!(matches!(
+            &error,
+            ResolveError::Function(RefFailure { error: Some(FunctionError::IndexOutOfBounds { index: 5, len: 2, .. }), .. })
+        ));
+        assert_eq!(
+            error.to_string(),
+            "'${a.servers.5.host}': index 5 out of bounds in 'a.servers.5.host' (sequence of 2)"
+        );
+
+        let mut value = Value::String(r#"${a.servers.5.host | default:"none"}"#.to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String("none".to_string()));
+    }
+
+    #[test]
+    fn test_resolve_refs_required_fails_on_missing_values() {
+        let mut deps = HashMap::new();
+        deps.insert(
+            "db".to_string(),
+            Value::Mapping(make_mapping(vec![
+                ("password", Value::Null),
+                ("user", Value::String("app".to_string())),
+            ])),
+        );
+
+        let mut value = Value::String("${db.user | required}".to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String("app".to_string()));
+
+        let mut value = Value::String(r#"${db.password | required("DB password must be set")}"#.to_string());
+        let error = try_resolve_refs_from_deps(&mut value, &deps).unwrap_err();
+        assert!(error.to_string().contains("DB password must be set"), "{error}");
+
+        // A key missing from an import fails too, instead of staying unresolved
+        let mut value = Value::String("${db.host | required}".to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_err());
+
+        // An alias that is not imported here may still be resolved by an importing file
+        let mut value = Value::String("${other.host | required}".to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String("${other.host | required}".to_string()));
+    }
+
+    #[test]
+    fn test_resolve_refs_with_replace_functions() {
+        let mut deps = HashMap::new();
+        deps.insert(
+            "a".to_string(),
+            Value::Mapping(make_mapping(vec![("email", Value::String("ops@example".to_string()))])),
+        );
+
+        let mut value = Value::String(r#"${a.email | replace("@", " at ")}"#.to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String("ops at example".to_string()));
+
+        let mut value = Value::String(r#"${a.email | regex_replace("(\\w+)@(\\w+)", "$2/$1")}"#.to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String("example/ops".to_string()));
+
+        // Quantifier braces don't end the placeholder
+        let mut value = Value::String(r#"${a.email | regex_replace("o{1}", "0")}"#.to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String("0ps@example".to_string()));
+
+        let mut value = Value::String(r#"mail: ${a.email | regex_replace("^\\w{3}", "dev")}!"#.to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String("mail: dev@example!".to_string()));
+    }
+
+    #[test]
+    fn test_placeholders_balance_braces_and_quotes() {
+        let contents = |s| placeholders(s).into_iter().map(|p| p.content).collect::<Vec<_>>();
+        assert_eq!(contents(r#"${a | replace("}", "{")}-${b}"#), vec![r#"a | replace("}", "{")"#, "b"]);
+        assert_eq!(contents("${a | f({x})}"), vec!["a | f({x})"]);
+        // An unterminated placeholder is left alone, the next one is still found
+        assert_eq!(contents("${a ${b}"), vec!["b"]);
+        assert_eq!(contents("${} ${'open}"), Vec::<&str>::new());
+        assert_eq!(exact_placeholder("${a}${b}"), None);
+        assert_eq!(exact_placeholder(r#"${a | regex_replace("o{1}", "0")}"#), Some(r#"a | regex_replace("o{1}", "0")"#));
+    }
+
+    #[test]
+    fn test_resolve_refs_interpolates_structured_values_through_to_json() {
+        let mut deps = HashMap::new();
+        deps.insert(
+            "a".to_string(),
+            Value::Mapping(make_mapping(vec![("tags", Value::Sequence(vec![Value::String("x".to_string())]))])),
+        );
+
+        let mut value = Value::String("tags=${a.tags | to_json}".to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String(r#"tags=["x"]"#.to_string()));
+    }
+
+    #[test]
+    fn test_resolve_refs_coalesce_falls_back_through_references() {
+        let mut deps = HashMap::new();
+        deps.insert(
+            "primary".to_string(),
+            Value::Mapping(make_mapping(vec![("host", Value::Null)])),
+        );
+        deps.insert(
+            "backup".to_string(),
+            Value::Mapping(make_mapping(vec![("host", Value::String("backup.internal".to_string()))])),
+        );
+
+        let mut value = Value::String(r#"${primary.host | coalesce(backup.host, "localhost")}"#.to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String("backup.internal".to_string()));
+
+        // Missing keys and references fall through to the literal
+        let mut value = Value::String(r#"${primary.port | coalesce(backup.port, "5432")}"#.to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String("5432".to_string()));
+
+        // Quoted arguments are literals, not references
+        let mut value = Value::String(r#"${primary.host | coalesce("backup.host")}"#.to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String("backup.host".to_string()));
+    }
+
+    #[test]
+    fn test_resolve_refs_from_env() {
+        // SAFETY: the variable names are unique to this test
+        unsafe {
+            std::env::set_var("APP_TEST_RENDER_PASSWORD", "s3cret");
+            std::env::remove_var("APP_TEST_RENDER_UNSET");
+        }
+        let env = EnvAccess::with_prefixes(["APP_TEST_RENDER_".to_string()]);
+        let deps = HashMap::from([(ENV_PREFIX.to_string(), env.variables().unwrap())]);
+
+        let mut value = Value::String("${env.APP_TEST_RENDER_PASSWORD}".to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String("s3cret".to_string()));
+
+        let mut value = Value::String("pw=${env.APP_TEST_RENDER_PASSWORD | upper}".to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String("pw=S3CRET".to_string()));
+
+        // An unset variable falls back to a default...
+        let mut value = Value::String(r#"${env.APP_TEST_RENDER_UNSET | default("dev")}"#.to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String("dev".to_string()));
+
+        // ...and fails the render otherwise
+        let mut value = Value::String("${env.APP_TEST_RENDER_UNSET}".to_string());
+        let error = try_resolve_refs_from_deps(&mut value, &deps).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "'${env.APP_TEST_RENDER_UNSET}': environment variable 'APP_TEST_RENDER_UNSET' is not set or not allowed (see --env-prefix)"
+        );
+        unsafe { std::env::remove_var("APP_TEST_RENDER_PASSWORD") };
+    }
+
+    #[test]
+    fn test_env_access_is_opt_in_and_denies_server_variables() {
+        // SAFETY: the variable names are unique to this test
+        unsafe {
+            std::env::set_var("KONF_TEST_ENV_SECRET", "s3cret");
+            std::env::set_var("APP_TEST_ENV_DENIED", "visible");
+        }
+        assert_eq!(EnvAccess::default().variables(), None, "nothing is readable by default");
+
+        // Even an empty prefix, allowing everything else, keeps the server's variables out
+        let env = EnvAccess::with_prefixes([String::new()]);
+        assert!(!env.allows("KONF_TEST_ENV_SECRET") && !env.allows("OTEL_EXPORTER_OTLP_ENDPOINT"));
+        let deps = HashMap::from([(ENV_PREFIX.to_string(), env.variables().unwrap())]);
+        let mut value = Value::String("${env.KONF_TEST_ENV_SECRET}".to_string());
+        let error = try_resolve_refs_from_deps(&mut value, &deps).unwrap_err();
+        assert!(error.to_string().contains("not set or not allowed"), "{error}");
+        let mut value = Value::String("${env.APP_TEST_ENV_DENIED}".to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+
+        let env = EnvAccess::with_prefixes(["OTHER_".to_string()]);
+        let deps = HashMap::from([(ENV_PREFIX.to_string(), env.variables().unwrap())]);
+        let mut value = Value::String("${env.APP_TEST_ENV_DENIED}".to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_err(), "outside the prefixes");
+        unsafe {
+            std::env::remove_var("KONF_TEST_ENV_SECRET");
+            std::env::remove_var("APP_TEST_ENV_DENIED");
+        }
+    }
+
+    #[test]
+    fn test_resolve_refs_interpolates_scalars() {
+        let mut deps = HashMap::new();
+        deps.insert(
+            "db".to_string(),
+            Value::Mapping(make_mapping(vec![
+                ("port", Value::Int(5432)),
+                ("ssl", Value::Boolean(true)),
+                ("ratio", Value::Float(0.5)),
+                ("schema", Value::Null),
+            ])),
+        );
+
+        let mut value = Value::String("http://host:${db.port}/db?ssl=${db.ssl}&r=${db.ratio}&s=${db.schema}".to_string());
+        resolve_refs_from_deps(&mut value, &deps);
+        assert_eq!(value, Value::String("http://host:5432/db?ssl=true&r=0.5&s=".to_string()));
+
+        // An exact match keeps the typed value
+        let mut value = Value::String("${db.port}".to_string());
+        resolve_refs_from_deps(&mut value, &deps);
+        assert_eq!(value, Value::Int(5432));
+    }
+
     #[test]
     fn test_resolve_refs_interpolation_with_function() {
         let mut deps = HashMap::new();
@@ -433,6 +1309,136 @@ mod tests {
         let mut value = Value::String(r#"${base.null_val | default:"fallback"}"#.to_string());
         resolve_refs_from_deps(&mut value, &deps);
         assert_eq!(value, Value::String("fallback".to_string()));
+
+        // Default applied when the imported alias lacks the key
+        let mut value = Value::String(r#"${base.missing | default("localhost")}"#.to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String("localhost".to_string()));
+        let mut value = Value::String(r#"http://${base.missing | default("localhost")}:80"#.to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String("http://localhost:80".to_string()));
+
+        // An alias not imported here is still left for the importing file
+        let mut value = Value::String(r#"${other.missing | default("localhost")}"#.to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_ok());
+        assert_eq!(value, Value::String(r#"${other.missing | default("localhost")}"#.to_string()));
+    }
+
+    #[test]
+    fn test_resolve_refs_to_int_yields_number() {
+        let mut deps = HashMap::new();
+        deps.insert(
+            "svc".to_string(),
+            Value::Mapping(make_mapping(vec![
+                ("port", Value::String("8080".to_string())),
+            ])),
+        );
+
+        let mut value = Value::String("${svc.port | to_int}".to_string());
+        resolve_refs_from_deps(&mut value, &deps);
+        assert_eq!(value, Value::Int(8080));
+    }
+
+    fn tenant_deps(tenant: &str) -> HashMap<String, Value> {
+        let mut deps = HashMap::new();
+        deps.insert(
+            "config".to_string(),
+            Value::Mapping(make_mapping(vec![(
+                "map",
+                Value::Mapping(make_mapping(vec![
+                    ("acme", Value::String("db-acme".to_string())),
+                ])),
+            )])),
+        );
+        deps.insert(
+            "request".to_string(),
+            Value::Mapping(make_mapping(vec![
+                ("tenant", Value::String(tenant.to_string())),
+            ])),
+        );
+        deps
+    }
+
+    #[test]
+    fn test_resolve_refs_with_reference_argument() {
+        let deps = tenant_deps("acme");
+
+        let mut value = Value::String("${config.map | get(request.tenant)}".to_string());
+        resolve_refs_from_deps(&mut value, &deps);
+        assert_eq!(value, Value::String("db-acme".to_string()));
+
+        let mut value = Value::String("${config.map | get:request.tenant}".to_string());
+        resolve_refs_from_deps(&mut value, &deps);
+        assert_eq!(value, Value::String("db-acme".to_string()));
+    }
+
+    #[test]
+    fn test_resolve_refs_with_absent_dynamic_key() {
+        let deps = tenant_deps("initech");
+
+        let mut value = Value::String("${config.map | get(request.tenant) | default:\"db-shared\"}".to_string());
+        resolve_refs_from_deps(&mut value, &deps);
+        assert_eq!(value, Value::String("db-shared".to_string()));
+    }
+
+    #[test]
+    fn test_parse_function_chain_with_argument_list() {
+        let funcs = parse_function_chain(r#"| splitn("|", 2) | default("a, b")"#).unwrap();
+        assert_eq!(funcs.len(), 2);
+        assert_eq!(
+            funcs[0].args,
+            vec![
+                CallArg::Literal(FunctionArg::String("|".to_string())),
+                CallArg::Literal(FunctionArg::Int(2)),
+            ]
+        );
+        assert_eq!(funcs[1].args, vec![CallArg::Literal(FunctionArg::String("a, b".to_string()))]);
+    }
+
+    #[test]
+    fn test_parse_function_chain_malformed() {
+        for chain in [r#"| default("x""#, "| default(1,)", r#"| default:"a", "b""#, "| default x", "| (1)"] {
+            let error = parse_function_chain(chain).unwrap_err();
+            assert!(matches!(error, FunctionError::MalformedCall { .. }), "{chain}: {error}");
+        }
+    }
+
+    #[test]
+    fn test_resolve_refs_with_argument_list() {
+        let mut deps = HashMap::new();
+        deps.insert(
+            "db".to_string(),
+            Value::Mapping(make_mapping(vec![
+                ("host", Value::Null),
+                ("dsn", Value::String("user=app=admin".to_string())),
+            ])),
+        );
+
+        let mut value = Value::String(r#"${db.host | default("localhost")}"#.to_string());
+        resolve_refs_from_deps(&mut value, &deps);
+        assert_eq!(value, Value::String("localhost".to_string()));
+
+        let mut value = Value::String(r#"${db.dsn | splitn("=", 2)}"#.to_string());
+        resolve_refs_from_deps(&mut value, &deps);
+        assert_eq!(
+            value,
+            Value::Sequence(vec![
+                Value::String("user".to_string()),
+                Value::String("app=admin".to_string()),
+            ])
+        );
+
+        // A malformed argument list fails instead of being ignored
+        let mut value = Value::String(r#"${db.host | default("localhost"}"#.to_string());
+        assert!(try_resolve_refs_from_deps(&mut value, &deps).is_err());
+    }
+
+    #[test]
+    fn test_parse_function_chain_with_reference_arg() {
+        let funcs = parse_function_chain("| get(request.tenant) | default:true").unwrap();
+        assert_eq!(funcs.len(), 2);
+        assert_eq!(funcs[0].args, vec![CallArg::Reference("request.tenant".to_string())]);
+        assert_eq!(funcs[1].args, vec![CallArg::Literal(FunctionArg::Boolean(true))]);
     }
 
     #[test]
@@ -444,6 +1450,112 @@ mod tests {
         assert_eq!(value, Value::String("${unknown.path}".to_string()));
     }
 
+    fn unresolved_config() -> Value {
+        let mut value = Value::Mapping(make_mapping(vec![
+            ("host", Value::String("${db.host}".to_string())),
+            ("url", Value::String("http://${db.hots}:${db.port}".to_string())),
+            (
+                "replicas",
+                Value::Sequence(vec![Value::String("${db.replica}".to_string()), Value::Int(1)]),
+            ),
+        ]));
+        let deps = HashMap::from([(
+            "db".to_string(),
+            Value::Mapping(make_mapping(vec![
+                ("host", Value::String("localhost".to_string())),
+                ("port", Value::Int(5432)),
+            ])),
+        )]);
+        resolve_refs_from_deps(&mut value, &deps);
+        value
+    }
+
+    #[test]
+    fn test_unresolved_policy_leave() {
+        let mut value = unresolved_config();
+        apply_unresolved_policy(&mut value, UnresolvedPolicy::Leave).unwrap();
+        assert_eq!(value.get("host"), Some(&Value::String("localhost".to_string())));
+        assert_eq!(value.get("url"), Some(&Value::String("http://${db.hots}:5432".to_string())));
+    }
+
+    #[test]
+    fn test_unresolved_policy_null() {
+        let mut value = unresolved_config();
+        apply_unresolved_policy(&mut value, UnresolvedPolicy::Null).unwrap();
+        assert_eq!(value.get("host"), Some(&Value::String("localhost".to_string())));
+        assert_eq!(value.get("url"), Some(&Value::Null));
+        assert_eq!(value.get("replicas"), Some(&Value::Sequence(vec![Value::Null, Value::Int(1)])));
+    }
+
+    #[test]
+    fn test_unresolved_policy_omit() {
+        let mut value = unresolved_config();
+        apply_unresolved_policy(&mut value, UnresolvedPolicy::Omit).unwrap();
+        assert_eq!(value.get("host"), Some(&Value::String("localhost".to_string())));
+        assert_eq!(value.get("url"), None);
+        assert_eq!(value.get("replicas"), Some(&Value::Sequence(vec![Value::Int(1)])));
+
+        let mut root = Value::String("${missing}".to_string());
+        apply_unresolved_policy(&mut root, UnresolvedPolicy::Omit).unwrap();
+        assert_eq!(root, Value::Null);
+    }
+
+    #[test]
+    fn test_unresolved_policy_error() {
+        let mut value = unresolved_config();
+        let error = apply_unresolved_policy(&mut value, UnresolvedPolicy::Error).unwrap_err();
+        let message = error.to_string();
+        assert!(message.starts_with("unresolved references: "), "{message}");
+        assert!(message.contains("'${db.hots}'") && message.contains("'${db.replica}'"), "{message}");
+
+        let mut resolved = Value::Int(1);
+        assert!(apply_unresolved_policy(&mut resolved, UnresolvedPolicy::Error).is_ok());
+    }
+
+    /// Resolves `template` against `db.host = localhost`, then unescapes it
+    fn render_escaped(template: &str) -> Result<Value, ResolveError> {
+        let mut value = Value::String(template.to_string());
+        let deps = HashMap::from([(
+            "db".to_string(),
+            Value::Mapping(make_mapping(vec![("host", Value::String("localhost".to_string()))])),
+        )]);
+        try_resolve_refs_from_deps(&mut value, &deps)?;
+        apply_unresolved_policy(&mut value, UnresolvedPolicy::Error)?;
+        unescape_placeholders(&mut value);
+        Ok(value)
+    }
+
+    #[test]
+    fn test_escaped_placeholder_is_literal() {
+        assert_eq!(render_escaped("$${foo}").unwrap(), Value::String("${foo}".to_string()));
+        assert_eq!(render_escaped("$${db.host}").unwrap(), Value::String("${db.host}".to_string()));
+    }
+
+    #[test]
+    fn test_escaped_placeholder_next_to_reference() {
+        assert_eq!(
+            render_escaped("${db.host}:$${PORT}/${db.host}").unwrap(),
+            Value::String("localhost:${PORT}/localhost".to_string())
+        );
+        // Only the escape itself is dropped, once
+        assert_eq!(render_escaped("$$${db.host}").unwrap(), Value::String("$${db.host}".to_string()));
+    }
+
+    #[test]
+    fn test_lone_dollar_is_untouched() {
+        assert_eq!(
+            render_escaped("costs $5, $$ or ${db.host}$").unwrap(),
+            Value::String("costs $5, $$ or localhost$".to_string())
+        );
+        assert!(find_template_refs("a: $${foo}\nb: ${bar}").iter().all(|r| r.path == "bar"));
+    }
+
+    #[test]
+    fn test_unresolved_policy_from_str() {
+        assert_eq!("omit".parse(), Ok(UnresolvedPolicy::Omit));
+        assert!("drop".parse::<UnresolvedPolicy>().is_err());
+    }
+
     #[test]
     fn test_resolve_refs_preserves_type() {
         let mut deps = HashMap::new();
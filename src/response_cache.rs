@@ -0,0 +1,198 @@
+//! Bounded cache of serialized responses.
+//!
+//! Rendered configs are cached by the DAG, but serialization still runs on
+//! every request. With a capacity set, the final response body is kept per
+//! commit, path, format and token, so a warm repeated request is a map lookup.
+//! The cache is cleared on reload. Responses carrying `_meta` are not cached,
+//! since their render time would be frozen.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use sha2::{Digest, Sha256};
+
+use crate::metrics;
+
+/// Identifies a cached response.
+///
+/// The token is part of the key so that a response is only ever served to a
+/// token that was authorized when it was cached.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResponseKey {
+    /// Commit the config was rendered at (empty in local mode).
+    pub commit: String,
+    /// Requested config path.
+    pub path: String,
+    /// Output format.
+    pub format: String,
+    /// Hash of the token the response was authorized for, see [`token_scope`]
+    /// (empty in local mode).
+    pub scope: String,
+}
+
+/// Returns the [`ResponseKey::scope`] of a token: its SHA-256, so that
+/// tokens aren't kept in memory for as long as their responses are cached.
+pub fn token_scope(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    bodies: HashMap<ResponseKey, String>,
+    /// Insertion order, oldest first, used for eviction.
+    order: VecDeque<ResponseKey>,
+}
+
+/// Bounded response cache, shared across worker threads.
+///
+/// When full, the oldest entry is evicted. A cache without capacity is
+/// disabled: lookups miss without being counted and inserts are ignored.
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    capacity: usize,
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl ResponseCache {
+    /// Creates a cache holding up to `capacity` responses, or a disabled one if `None`.
+    pub fn new(capacity: Option<usize>) -> Self {
+        Self {
+            capacity: capacity.unwrap_or(0),
+            entries: Arc::default(),
+        }
+    }
+
+    /// Returns true if responses are cached.
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Looks up a cached response, recording a hit or miss.
+    pub fn get(&self, key: &ResponseKey) -> Option<String> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let body = self.lock().bodies.get(key).cloned();
+        metrics::record_response_cache(body.is_some());
+        body
+    }
+
+    /// Stores a response, evicting the oldest one if the cache is full.
+    pub fn insert(&self, key: ResponseKey, body: &str) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut entries = self.lock();
+        if entries.bodies.insert(key.clone(), body.to_string()).is_some() {
+            return;
+        }
+        entries.order.push_back(key);
+        while entries.order.len() > self.capacity {
+            if let Some(oldest) = entries.order.pop_front() {
+                entries.bodies.remove(&oldest);
+            }
+        }
+    }
+
+    /// Drops every cached response.
+    pub fn clear(&self) {
+        let mut entries = self.lock();
+        entries.bodies.clear();
+        entries.order.clear();
+    }
+
+    /// Drops the cached responses rendered at `commit`.
+    pub fn evict_commit(&self, commit: &str) {
+        let mut entries = self.lock();
+        entries.order.retain(|key| key.commit != commit);
+        entries.bodies.retain(|key, _| key.commit != commit);
+    }
+
+    /// Returns the number of cached responses.
+    pub fn len(&self) -> usize {
+        self.lock().bodies.len()
+    }
+
+    /// Returns true if no response is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Entries> {
+        // A panic while holding the lock can't leave the maps inconsistent enough to matter
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(path: &str) -> ResponseKey {
+        ResponseKey {
+            commit: String::new(),
+            path: path.to_string(),
+            format: "json".to_string(),
+            scope: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_oldest_entry_is_evicted() {
+        let cache = ResponseCache::new(Some(2));
+        cache.insert(key("a"), "1");
+        cache.insert(key("b"), "2");
+        cache.insert(key("c"), "3");
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&key("a")), None);
+        assert_eq!(cache.get(&key("c")).as_deref(), Some("3"));
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_scope_is_part_of_the_key() {
+        let cache = ResponseCache::new(Some(4));
+        cache.insert(key("a"), "1");
+
+        let other_token = ResponseKey {
+            scope: "other".to_string(),
+            ..key("a")
+        };
+        assert_eq!(cache.get(&other_token), None);
+    }
+
+    #[test]
+    fn test_token_scope_hides_the_token() {
+        let scope = token_scope("secret-token");
+        assert_eq!(scope, token_scope("secret-token"));
+        assert_ne!(scope, token_scope("other-token"));
+        assert!(!scope.contains("secret"));
+    }
+
+    #[test]
+    fn test_evict_commit_keeps_other_commits() {
+        let cache = ResponseCache::new(Some(4));
+        let at = |commit: &str| ResponseKey {
+            commit: commit.to_string(),
+            ..key("a")
+        };
+        cache.insert(at("abc"), "1");
+        cache.insert(at("def"), "2");
+
+        cache.evict_commit("abc");
+        assert_eq!(cache.get(&at("abc")), None);
+        assert_eq!(cache.get(&at("def")).as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn test_disabled_cache_stores_nothing() {
+        let cache = ResponseCache::new(None);
+        cache.insert(key("a"), "1");
+
+        assert!(cache.is_empty());
+        assert_eq!(cache.get(&key("a")), None);
+    }
+}
@@ -0,0 +1,141 @@
+//! JSON Schema inference and validation for rendered configuration values.
+//!
+//! The inferred schema is approximate: it records the types and keys observed
+//! in a single rendered config. It is meant to bootstrap a schema that teams
+//! then refine by hand, not to replace one.
+//!
+//! Schemas committed next to a config (`app.schema.json` for `app`) are
+//! compiled with [`compile_schema`] and checked with [`validate`].
+
+use serde_json::{Map, Value as Json, json};
+
+use crate::{Value, writer::json::to_json};
+
+/// JSON Schema dialect emitted by [`infer_schema`].
+pub const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Suffix of a schema file's key: `app.schema.json` is loaded as `app.schema`
+/// and validates the config `app`.
+pub const SCHEMA_KEY_SUFFIX: &str = ".schema";
+
+/// A compiled JSON Schema.
+pub type ConfigSchema = jsonschema::Validator;
+
+/// Compiles a JSON Schema document.
+pub fn compile_schema(source: &str) -> Result<ConfigSchema, String> {
+    let schema: Json = serde_json::from_str(source).map_err(|e| format!("invalid JSON: {e}"))?;
+    jsonschema::validator_for(&schema).map_err(|e| format!("invalid schema: {e}"))
+}
+
+/// Validates a rendered value, returning one message per violation.
+///
+/// Each message is prefixed with the JSON pointer of the offending value.
+pub fn validate(schema: &ConfigSchema, value: &Value) -> Vec<String> {
+    let instance = to_json(value);
+    schema
+        .iter_errors(&instance)
+        .map(|error| {
+            let path = error.instance_path.to_string();
+            let path = if path.is_empty() { "/" } else { &path };
+            format!("{path}: {error}")
+        })
+        .collect()
+}
+
+/// Infers a JSON Schema document describing `value`.
+///
+/// Every key observed in a mapping is marked as required. Sequence items are
+/// described by a single schema merged from all observed elements.
+pub fn infer_schema(value: &Value) -> Json {
+    let mut schema = infer(value);
+    if let Json::Object(map) = &mut schema {
+        map.insert("$schema".to_string(), Json::String(SCHEMA_DIALECT.to_string()));
+    }
+    schema
+}
+
+fn infer(value: &Value) -> Json {
+    match value {
+        Value::String(_) => json!({ "type": "string" }),
+        Value::Int(_) => json!({ "type": "integer" }),
+        Value::Float(_) => json!({ "type": "number" }),
+        Value::Boolean(_) => json!({ "type": "boolean" }),
+        Value::Null => json!({ "type": "null" }),
+        Value::Sequence(items) => {
+            let items = items
+                .iter()
+                .map(infer)
+                .reduce(merge)
+                .unwrap_or_else(|| json!({}));
+            json!({ "type": "array", "items": items })
+        }
+        Value::Mapping(map) => {
+            let mut required: Vec<&String> = map.keys().collect();
+            required.sort();
+            let properties: Map<String, Json> =
+                map.iter().map(|(k, v)| (k.clone(), infer(v))).collect();
+            json!({ "type": "object", "properties": properties, "required": required })
+        }
+    }
+}
+
+/// Merges two inferred schemas into one accepting both.
+///
+/// Objects merge their properties (keys are only required if required by
+/// both), integers widen to numbers, anything else becomes an `anyOf`.
+fn merge(a: Json, b: Json) -> Json {
+    if a == b {
+        return a;
+    }
+    let type_of = |s: &Json| s.get("type").and_then(Json::as_str).map(str::to_string);
+    match (type_of(&a).as_deref(), type_of(&b).as_deref()) {
+        (Some("object"), Some("object")) => merge_objects(a, b),
+        (Some("integer" | "number"), Some("integer" | "number")) => json!({ "type": "number" }),
+        (Some("array"), Some("array")) => {
+            let items = merge(a["items"].clone(), b["items"].clone());
+            json!({ "type": "array", "items": items })
+        }
+        _ => {
+            let mut variants = Vec::new();
+            for schema in [a, b] {
+                match schema {
+                    Json::Object(ref map) if map.contains_key("anyOf") => {
+                        variants.extend(map["anyOf"].as_array().cloned().unwrap_or_default())
+                    }
+                    other => variants.push(other),
+                }
+            }
+            let mut unique: Vec<Json> = Vec::new();
+            for variant in variants {
+                if !unique.contains(&variant) {
+                    unique.push(variant);
+                }
+            }
+            json!({ "anyOf": unique })
+        }
+    }
+}
+
+fn merge_objects(mut a: Json, b: Json) -> Json {
+    let required_in = |s: &Json, key: &str| {
+        s["required"]
+            .as_array()
+            .is_some_and(|r| r.iter().any(|k| k == key))
+    };
+    let mut properties = a["properties"].as_object().cloned().unwrap_or_default();
+    for (key, schema) in b["properties"].as_object().cloned().unwrap_or_default() {
+        let merged = match properties.remove(&key) {
+            Some(existing) => merge(existing, schema),
+            None => schema,
+        };
+        properties.insert(key, merged);
+    }
+    let required: Vec<&String> = properties
+        .keys()
+        .filter(|key| required_in(&a, key) && required_in(&b, key))
+        .collect();
+    let required = json!(required);
+    a["properties"] = Json::Object(properties);
+    a["required"] = required;
+    a
+}
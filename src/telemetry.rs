@@ -26,7 +26,7 @@ impl Default for TelemetryConfig {
 pub fn init_tracing(config: TelemetryConfig) -> Option<TracerProvider> {
     let env_filter = tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| {
         format!(
-            "{}=debug,tower_http=debug,axum::rejection=trace",
+            "{}=debug,konf_provider=info,tower_http=debug,axum::rejection=trace",
             env!("CARGO_CRATE_NAME")
         )
         .into()
@@ -4,11 +4,24 @@ use xitca_web::{
     WebContext,
     error::{Error, MatchError},
     handler::{Responder, html::Html},
-    http::{StatusCode, WebResponse},
+    http::{
+        HeaderMap, StatusCode, WebResponse,
+        header::{ACCEPT, CACHE_CONTROL, CONTENT_TYPE, ETAG, HeaderValue, IF_NONE_MATCH},
+    },
     service::Service,
 };
 
-use crate::Value;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    Value,
+    loader::MultiLoader,
+    metadata::METADATA_KEY,
+    render::KeyFailure,
+    validate::ValidationReport,
+    writer::{MultiWriter, k8s::resource_name},
+};
 
 // a custom error type. must implement following traits:
 // std::fmt::{Debug, Display} for formatting
@@ -72,6 +85,14 @@ where
                 // handle typed error.
             }
 
+            // GetError picks its body format from the request's Accept header, which
+            // is only available here: outer middlewares respond without the request.
+            if let Some(e) = e.upcast().downcast_ref::<GetError>() {
+                tracing::error!("{e}");
+                let Ok(res) = e.call(ctx).await;
+                return Ok(res);
+            }
+
             // type casting can also be used to handle xitca-web's "internal" error types for overriding
             // default error behavior.
             // *. "internal" means these error types have their default error formatter and http response generator.
@@ -93,12 +114,12 @@ where
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum GetError {
     /// The requested commit hash was not found in the repository
     CommitNotFound { commit: String },
-    /// The requested config file was not found
-    ConfigNotFound { path: String },
+    /// The requested config file was not found; `suggestions` lists near-matching keys
+    ConfigNotFound { path: String, suggestions: Vec<String> },
     /// Failed to render the configuration (e.g., missing imports, circular deps)
     RenderError { path: String, reason: String },
     /// Failed to initialize the DAG for a commit
@@ -111,6 +132,14 @@ pub enum GetError {
     Unauthorized { reason: String },
     /// Token is valid but not authorized for this resource
     Forbidden { path: String },
+    /// The request is valid but its parts conflict (e.g. colliding env variables)
+    Conflict { reason: String },
+    /// The server is saturated and rejected the request (e.g. concurrency limit reached)
+    Unavailable { reason: String },
+    /// The rendered config violates its JSON Schema
+    ValidationFailed { path: String, errors: Vec<String> },
+    /// No writer produces a media type the `Accept` header allows
+    NotAcceptable { accept: String },
 }
 
 impl fmt::Display for GetError {
@@ -119,8 +148,12 @@ impl fmt::Display for GetError {
             GetError::CommitNotFound { commit } => {
                 write!(f, "commit not found: '{commit}'")
             }
-            GetError::ConfigNotFound { path } => {
-                write!(f, "config file not found: '{path}'")
+            GetError::ConfigNotFound { path, suggestions } => {
+                write!(f, "config file not found: '{path}'")?;
+                if !suggestions.is_empty() {
+                    write!(f, " (did you mean: {}?)", suggestions.join(", "))?;
+                }
+                Ok(())
             }
             GetError::RenderError { path, reason } => {
                 write!(f, "failed to render config '{path}': {reason}")
@@ -140,6 +173,22 @@ impl fmt::Display for GetError {
             GetError::Forbidden { path } => {
                 write!(f, "forbidden: not authorized to access '{path}'")
             }
+            GetError::Conflict { reason } => {
+                write!(f, "conflict: {reason}")
+            }
+            GetError::Unavailable { reason } => {
+                write!(f, "service unavailable: {reason}")
+            }
+            GetError::ValidationFailed { path, errors } => {
+                write!(f, "config '{path}' violates its schema:")?;
+                for error in errors {
+                    write!(f, "\n- {error}")?;
+                }
+                Ok(())
+            }
+            GetError::NotAcceptable { accept } => {
+                write!(f, "not acceptable: no output format matches '{accept}'")
+            }
         }
     }
 }
@@ -154,13 +203,10 @@ impl From<GetError> for Error {
     }
 }
 
-// response generator of GetError. Returns appropriate HTTP status codes with error message body.
-impl<'r, C> Service<WebContext<'r, C>> for GetError {
-    type Response = WebResponse;
-    type Error = Infallible;
-
-    async fn call(&self, ctx: WebContext<'r, C>) -> Result<Self::Response, Self::Error> {
-        let status = match self {
+impl GetError {
+    /// HTTP status the error is answered with.
+    pub fn status(&self) -> StatusCode {
+        match self {
             GetError::CommitNotFound { .. } => StatusCode::NOT_FOUND,
             GetError::ConfigNotFound { .. } => StatusCode::NOT_FOUND,
             GetError::RenderError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
@@ -169,20 +215,248 @@ impl<'r, C> Service<WebContext<'r, C>> for GetError {
             GetError::InternalError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             GetError::Unauthorized { .. } => StatusCode::UNAUTHORIZED,
             GetError::Forbidden { .. } => StatusCode::FORBIDDEN,
+            GetError::Conflict { .. } => StatusCode::CONFLICT,
+            GetError::Unavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            GetError::ValidationFailed { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            GetError::NotAcceptable { .. } => StatusCode::NOT_ACCEPTABLE,
+        }
+    }
+
+    /// Stable, machine-readable identifier of the variant, sent as `code` in
+    /// JSON error bodies. Unlike messages, codes never change.
+    pub fn code(&self) -> &'static str {
+        match self {
+            GetError::CommitNotFound { .. } => "commit_not_found",
+            GetError::ConfigNotFound { .. } => "config_not_found",
+            GetError::RenderError { .. } => "render_error",
+            GetError::DagInitError { .. } => "dag_init_error",
+            GetError::BadRequest { .. } => "bad_request",
+            GetError::InternalError { .. } => "internal_error",
+            GetError::Unauthorized { .. } => "unauthorized",
+            GetError::Forbidden { .. } => "forbidden",
+            GetError::Conflict { .. } => "conflict",
+            GetError::Unavailable { .. } => "unavailable",
+            GetError::ValidationFailed { .. } => "validation_failed",
+            GetError::NotAcceptable { .. } => "not_acceptable",
+        }
+    }
+
+    /// JSON error body: the message as `error`, the variant's [`code`](Self::code),
+    /// and the commit, path or details the variant carries.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut body = serde_json::json!({ "error": self.to_string(), "code": self.code() });
+        let extra = match self {
+            GetError::CommitNotFound { commit } | GetError::DagInitError { commit, .. } => {
+                serde_json::json!({ "commit": commit })
+            }
+            GetError::ConfigNotFound { path, suggestions } => {
+                serde_json::json!({ "path": path, "suggestions": suggestions })
+            }
+            GetError::RenderError { path, .. } | GetError::Forbidden { path } => serde_json::json!({ "path": path }),
+            GetError::ValidationFailed { path, errors } => serde_json::json!({ "path": path, "errors": errors }),
+            GetError::NotAcceptable { accept } => serde_json::json!({ "accept": accept }),
+            _ => return body,
         };
-        // Include the error message in the response body
-        (self.to_string(), status)
+        if let (Some(body), serde_json::Value::Object(extra)) = (body.as_object_mut(), extra) {
+            body.extend(extra);
+        }
+        body
+    }
+}
+
+/// Returns true if the `Accept` header explicitly asks for JSON
+/// (`application/json` or a `+json` type), so errors are answered in JSON.
+pub fn accepts_json(headers: &HeaderMap) -> bool {
+    let Some(accept) = headers.get(ACCEPT).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    accept.split(',').any(|range| {
+        let mut parts = range.split(';').map(str::trim);
+        let media_type = parts.next().unwrap_or_default().to_ascii_lowercase();
+        let q = parts
+            .find_map(|param| param.strip_prefix("q="))
+            .and_then(|q| q.parse::<f32>().ok())
+            .unwrap_or(1.0);
+        q > 0.0 && (media_type == "application/json" || media_type.ends_with("+json"))
+    })
+}
+
+// response generator of GetError. Returns appropriate HTTP status codes with error message body,
+// as JSON when the request accepts it.
+impl<'r, C> Service<WebContext<'r, C>> for GetError {
+    type Response = WebResponse;
+    type Error = Infallible;
+
+    async fn call(&self, ctx: WebContext<'r, C>) -> Result<Self::Response, Self::Error> {
+        if !accepts_json(ctx.req().headers()) {
+            // Include the error message in the response body
+            return (self.to_string(), self.status())
+                .respond(ctx)
+                .await
+                .map_err(|_| unreachable!());
+        }
+        let mut res = (self.to_json().to_string(), self.status())
             .respond(ctx)
             .await
-            .map_err(|_| unreachable!())
+            .map_err(|_| unreachable!())?;
+        res.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        Ok(res)
+    }
+}
+
+/// A serialized config, sent with its writer's `Content-Type` and the
+/// `Cache-Control` header its `ttl` hint asks for.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderedConfig {
+    pub body: String,
+    /// MIME type of `body`; `text/plain` when unset.
+    pub content_type: Option<&'static str>,
+    /// `max-age` in seconds, from the config's `<!>: output: ttl:` hint.
+    pub ttl: Option<u64>,
+    /// Strong validator sent as the `ETag` header.
+    pub etag: Option<String>,
+    /// Respond `304 Not Modified` without a body: the client's copy matches `etag`.
+    pub not_modified: bool,
+}
+
+impl RenderedConfig {
+    /// A `304 Not Modified` response for a client already holding `etag`.
+    pub fn not_modified(etag: String, ttl: Option<u64>) -> Self {
+        Self {
+            ttl,
+            etag: Some(etag),
+            not_modified: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// Returns a strong, quoted ETag identifying a response built from `parts`.
+pub fn etag(parts: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part.as_bytes());
+        // Separator, so ("ab", "c") and ("a", "bc") differ
+        hasher.update([0]);
     }
+    format!("\"{}\"", hex::encode(&hasher.finalize()[..16]))
+}
+
+/// Header carrying the caller's request id, recorded on the request's trace span.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Returns the `X-Request-Id` of the request, or an empty string.
+pub fn request_id(headers: &HeaderMap) -> &str {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+}
+
+/// Returns true if the request's `If-None-Match` header matches `etag`.
+///
+/// The header may list several tags or be `*`; weak tags (`W/"..."`) match
+/// their strong counterpart, as the weak comparison of RFC 9110 requires.
+pub fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    let Some(value) = headers.get(IF_NONE_MATCH).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    value.split(',').map(str::trim).any(|tag| {
+        let tag = tag.strip_prefix("W/").unwrap_or(tag);
+        tag == "*" || tag == etag
+    })
 }
 
+impl<'r, C, B> Responder<WebContext<'r, C, B>> for RenderedConfig {
+    type Response = WebResponse;
+    type Error = Error;
+
+    async fn respond(self, ctx: WebContext<'r, C, B>) -> Result<Self::Response, Self::Error> {
+        let mut res = self.body.respond(ctx).await?;
+        if self.not_modified {
+            *res.status_mut() = StatusCode::NOT_MODIFIED;
+            res.headers_mut().remove(CONTENT_TYPE);
+        }
+        if let Some(etag) = &self.etag {
+            let value = HeaderValue::from_str(etag).expect("quoted hex is a valid header value");
+            res.headers_mut().insert(ETAG, value);
+        }
+        if let Some(content_type) = self.content_type {
+            res.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+        }
+        if let Some(ttl) = self.ttl {
+            let value = HeaderValue::from_str(&format!("max-age={ttl}")).expect("digits are a valid header value");
+            res.headers_mut().insert(CACHE_CONTROL, value);
+        }
+        Ok(res)
+    }
+}
+
+/// Readiness of the server, returned by `GET /ready`: `200 OK` when every
+/// config passes validation, `503 Service Unavailable` otherwise.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct Readiness {
+    pub ready: bool,
+    /// Configs that failed, in key order.
+    pub failures: Vec<ReadinessFailure>,
+}
+
+/// A config failing the readiness check.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ReadinessFailure {
+    pub path: String,
+    /// One message per problem, as reported by `konf validate`.
+    pub reasons: Vec<String>,
+}
+
+impl Readiness {
+    /// Not ready, without reaching the configs, e.g. when they can't be loaded.
+    pub fn unavailable(reason: String) -> Self {
+        Self {
+            ready: false,
+            failures: vec![ReadinessFailure {
+                path: String::new(),
+                reasons: vec![reason],
+            }],
+        }
+    }
+}
+
+impl From<ValidationReport> for Readiness {
+    fn from(report: ValidationReport) -> Self {
+        Self {
+            ready: report.is_ok(),
+            failures: report
+                .invalid
+                .into_iter()
+                .map(|(path, reasons)| ReadinessFailure { path, reasons })
+                .collect(),
+        }
+    }
+}
+
+impl<'r, C, B> Responder<WebContext<'r, C, B>> for Readiness {
+    type Response = WebResponse;
+    type Error = Error;
+
+    async fn respond(self, ctx: WebContext<'r, C, B>) -> Result<Self::Response, Self::Error> {
+        let status = if self.ready {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+        let body = serde_json::to_string(&self).expect("readiness serializes to JSON");
+        let mut res = (body, status).respond(ctx).await?;
+        res.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        Ok(res)
+    }
+}
 
 pub fn get_conf_strings(value: &Value, key: &str) -> Vec<String> {
-    const MAIN_KEY: &str = "<!>";
     value
-        .get(MAIN_KEY)
+        .get(METADATA_KEY)
         .and_then(|main_value| main_value.as_mapping())
         .and_then(|main_map| main_map.get(key))
         .and_then(|import_value| import_value.as_sequence())
@@ -193,4 +467,157 @@ pub fn get_conf_strings(value: &Value, key: &str) -> Vec<String> {
                 .collect()
         })
         .unwrap_or_default()
-}
\ No newline at end of file
+}
+
+/// Parses a JSON or YAML request body into a `Value` to be merged over a rendered config.
+///
+/// YAML is a superset of JSON, so both are handled by the YAML loader.
+pub fn parse_overlay(multiloader: &MultiLoader, body: &str) -> Result<Value, GetError> {
+    multiloader
+        .load("yaml", body)
+        .map_err(|e| GetError::BadRequest {
+            reason: format!("invalid overlay body: {e}"),
+        })
+}
+
+/// Picks the output format for a request that doesn't name one from its
+/// `Accept` header, using the default format when the header is absent.
+pub fn negotiate_format<'a>(writer: &'a MultiWriter, headers: &HeaderMap) -> Result<&'a str, GetError> {
+    let Some(accept) = headers.get(ACCEPT) else {
+        return Ok(&writer.default_format);
+    };
+    let accept = accept.to_str().unwrap_or_default();
+    writer
+        .format_for_content_type(accept)
+        .ok_or_else(|| GetError::NotAcceptable {
+            accept: accept.to_string(),
+        })
+}
+
+/// Key under which [`with_render_meta`] records where a rendered config came from.
+pub const RENDER_META_KEY: &str = "_meta";
+
+/// Adds a `_meta` mapping to a rendered config, recording its source key, the
+/// commit it was rendered from (git mode) and the render time (Unix seconds).
+///
+/// Values that are not mappings are returned unchanged.
+pub fn with_render_meta(mut value: Value, source: &str, commit: Option<&str>) -> Value {
+    let Value::Mapping(map) = &mut value else {
+        return value;
+    };
+    let rendered_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+
+    let mut meta = crate::Mapping::new();
+    meta.insert("source".to_string(), Value::String(source.to_string()));
+    if let Some(commit) = commit {
+        meta.insert("commit".to_string(), Value::String(commit.to_string()));
+    }
+    meta.insert("rendered_at".to_string(), Value::Int(rendered_at));
+    map.insert(RENDER_META_KEY.to_string(), Value::Mapping(meta));
+    value
+}
+
+/// Query string options of the render endpoints.
+#[derive(Debug, Default, Deserialize)]
+pub struct RenderQuery {
+    /// Isolate failures per top-level key instead of failing the whole render.
+    #[serde(default)]
+    pub isolate: bool,
+    /// Report isolated failures under a `_diagnostics` key (implies `isolate`).
+    #[serde(default)]
+    pub diagnostics: bool,
+    /// Validate the rendered config against its `*.schema.json` file.
+    #[serde(default)]
+    pub validate: bool,
+    /// Resource name of `configmap` and `secret` manifests, instead of the
+    /// config path's last segment.
+    pub name: Option<String>,
+    /// Indent JSON output instead of writing it on a single line.
+    #[serde(default)]
+    pub pretty: bool,
+}
+
+impl RenderQuery {
+    /// Returns true if the render should isolate failures per top-level key.
+    pub fn isolated(&self) -> bool {
+        self.isolate || self.diagnostics
+    }
+
+    /// Returns true if the config should be written as indented JSON.
+    pub fn pretty_json(&self, format: &str) -> bool {
+        self.pretty && format == "json"
+    }
+
+    /// Returns the document name to write the config at `path` under.
+    pub fn document_name(&self, path: &str) -> String {
+        self.name.clone().unwrap_or_else(|| resource_name(path))
+    }
+}
+
+/// Key under which [`with_diagnostics`] lists the keys that failed to render.
+pub const DIAGNOSTICS_KEY: &str = "_diagnostics";
+
+/// Adds a `_diagnostics` list of `{key, reason}` entries to a rendered config.
+///
+/// Values that are not mappings are returned unchanged.
+pub fn with_diagnostics(mut value: Value, failures: &[KeyFailure]) -> Value {
+    if let Value::Mapping(map) = &mut value {
+        let entries = failures
+            .iter()
+            .map(|failure| {
+                let mut entry = crate::Mapping::new();
+                entry.insert("key".to_string(), Value::String(failure.key.clone()));
+                entry.insert("reason".to_string(), Value::String(failure.reason.clone()));
+                Value::Mapping(entry)
+            })
+            .collect();
+        map.insert(DIAGNOSTICS_KEY.to_string(), Value::Sequence(entries));
+    }
+    value
+}
+
+/// Normalizes a requested config path: drops leading, trailing and duplicate slashes.
+///
+/// ```
+/// use konf_provider::utils::normalize_path;
+///
+/// assert_eq!(normalize_path("/common//database/"), "common/database");
+/// ```
+pub fn normalize_path(path: &str) -> String {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Splits a `/value` path into the config path and the dotted key within it,
+/// at the first `.` of the last segment.
+///
+/// ```
+/// use konf_provider::utils::split_key_path;
+///
+/// assert_eq!(split_key_path("common/database.pool.size"), Some(("common/database", "pool.size")));
+/// assert_eq!(split_key_path("v1.2/app"), None);
+/// ```
+pub fn split_key_path(path: &str) -> Option<(&str, &str)> {
+    let segment_start = path.rfind('/').map_or(0, |i| i + 1);
+    let dot = segment_start + path[segment_start..].find('.')?;
+    Some((&path[..dot], &path[dot + 1..]))
+}
+
+/// Levenshtein distance between two strings, used to suggest near-matching keys.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
@@ -0,0 +1,53 @@
+//! Whole-folder validation, the CLI counterpart of the LSP diagnostics.
+//!
+//! Each config is checked with the real renderer: it must render (no missing
+//! import or import cycle), every `${...}` reference must resolve, and the
+//! result must match its `*.schema.json` when it has one.
+
+use crate::{fs::FileProvider, graph::GraphProblem, render::Dag};
+
+/// Outcome of a [`validate_all`] run.
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    /// Keys that passed every check, in order.
+    pub valid: Vec<String>,
+    /// Keys that failed, with one message per problem.
+    pub invalid: Vec<(String, Vec<String>)>,
+}
+
+impl ValidationReport {
+    /// Returns true if every config passed.
+    pub fn is_ok(&self) -> bool {
+        self.invalid.is_empty()
+    }
+}
+
+/// Validates each of `keys`, collecting every problem instead of stopping at the first one.
+pub async fn validate_all<P: FileProvider>(dag: &Dag<P>, keys: &[String]) -> ValidationReport {
+    let graph = dag.check_graph(true);
+    let mut report = ValidationReport::default();
+
+    for key in keys {
+        let problems = match dag.get_rendered(key).await {
+            Ok(rendered) => graph
+                .problems
+                .iter()
+                .filter_map(|problem| match problem {
+                    GraphProblem::UnresolvedReference { file, reference } if file == key => {
+                        Some(format!("unresolved reference '${{{reference}}}'"))
+                    }
+                    _ => None,
+                })
+                .chain(dag.schema_violations(key, &rendered))
+                .collect(),
+            Err(e) => vec![format!("{e:#}")],
+        };
+
+        if problems.is_empty() {
+            report.valid.push(key.clone());
+        } else {
+            report.invalid.push((key.clone(), problems));
+        }
+    }
+    report
+}
@@ -0,0 +1,48 @@
+//! Reloads configs when files under the local folder change (`--watch`).
+//!
+//! Editors often write a file in several steps (truncate, write, rename), so
+//! events are debounced: a reload fires once no change has been seen for the
+//! debounce delay, however many events came before it.
+
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+
+/// Delay without changes after which a burst of events triggers a reload.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Watches `folder` recursively, calling `on_change` with the first changed
+/// path of each debounced burst of changes.
+///
+/// Must be called within a Tokio runtime, on which `on_change` runs.
+/// Watching stops when the returned watcher is dropped.
+pub fn watch_folder<F, Fut>(folder: &Path, debounce: Duration, on_change: F) -> notify::Result<RecommendedWatcher>
+where
+    F: Fn(PathBuf) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send,
+{
+    let (tx, mut rx) = mpsc::unbounded_channel::<PathBuf>();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+        // Reads don't change anything
+        Ok(event) if matches!(event.kind, EventKind::Access(_)) => {}
+        Ok(event) => {
+            for path in event.paths {
+                let _ = tx.send(path);
+            }
+        }
+        Err(e) => tracing::warn!("file watcher error: {e}"),
+    })?;
+    watcher.watch(folder, RecursiveMode::Recursive)?;
+
+    tokio::spawn(async move {
+        while let Some(path) = rx.recv().await {
+            // Wait for the burst to settle, dropping the events it contains
+            while let Ok(Some(_)) = tokio::time::timeout(debounce, rx.recv()).await {}
+            on_change(path).await;
+        }
+    });
+    Ok(watcher)
+}
@@ -0,0 +1,54 @@
+//! Verification of push webhooks (`POST /webhook`, git mode).
+//!
+//! GitHub (and Gitea or Forgejo) sign the payload with HMAC-SHA256 keyed by
+//! the shared secret and send `X-Hub-Signature-256: sha256=<hex>`; GitLab
+//! sends the secret itself in `X-Gitlab-Token`. Either is accepted, and both
+//! are compared in constant time.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use xitca_web::http::HeaderMap;
+
+use crate::utils::GetError;
+
+/// Header carrying the HMAC-SHA256 signature of the payload (GitHub style).
+pub const SIGNATURE_HEADER: &str = "x-hub-signature-256";
+/// Header carrying the shared secret itself (GitLab style).
+pub const GITLAB_TOKEN_HEADER: &str = "x-gitlab-token";
+
+fn mac(secret: &str, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac
+}
+
+/// Returns the `X-Hub-Signature-256` value of `body` signed with `secret`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    format!("sha256={}", hex::encode(mac(secret, body).finalize().into_bytes()))
+}
+
+/// Checks that a webhook request was sent by someone knowing `secret`.
+pub fn verify(secret: &str, headers: &HeaderMap, body: &[u8]) -> Result<(), GetError> {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    let valid = if let Some(signature) = header(SIGNATURE_HEADER) {
+        signature
+            .strip_prefix("sha256=")
+            .and_then(|hex| hex::decode(hex).ok())
+            .is_some_and(|signature| mac(secret, body).verify_slice(&signature).is_ok())
+    } else if let Some(token) = header(GITLAB_TOKEN_HEADER) {
+        token.as_bytes().ct_eq(secret.as_bytes()).into()
+    } else {
+        return Err(GetError::Unauthorized {
+            reason: format!("missing {SIGNATURE_HEADER} or {GITLAB_TOKEN_HEADER} header"),
+        });
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(GetError::Unauthorized {
+            reason: "webhook signature mismatch".to_string(),
+        })
+    }
+}
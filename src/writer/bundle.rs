@@ -0,0 +1,95 @@
+//! Combining several rendered configs into a single env file.
+
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::{
+    Value,
+    writer::{docker_env::DockerEnvVarWriter, env::EnvVarWriter},
+};
+
+/// One config in a bundle, with an optional variable prefix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundlePart {
+    /// Prefix prepended (uppercased, `_`-separated) to every variable of this config.
+    pub prefix: Option<String>,
+    /// Config path, e.g. `common/database`.
+    pub path: String,
+}
+
+/// Errors that can occur while building a bundle.
+#[derive(Debug, Error)]
+pub enum BundleError {
+    #[error("variable '{key}' is defined with different values by '{first}' and '{second}'")]
+    Collision {
+        key: String,
+        first: String,
+        second: String,
+    },
+
+    #[error("unsupported bundle format '{0}' (expected env or docker-env)")]
+    UnsupportedFormat(String),
+}
+
+/// Parses a comma-separated bundle spec like `db:common/database,a`.
+///
+/// Each item is a config path, optionally preceded by `prefix:`.
+pub fn parse_bundle_spec(spec: &str) -> Vec<BundlePart> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| match item.split_once(':') {
+            Some((prefix, path)) => BundlePart {
+                prefix: Some(prefix.to_string()).filter(|p| !p.is_empty()),
+                path: path.to_string(),
+            },
+            None => BundlePart {
+                prefix: None,
+                path: item.to_string(),
+            },
+        })
+        .collect()
+}
+
+/// Flattens each rendered config to env variables and joins them in one body.
+///
+/// Variables are written in config order, sorted by key within each config.
+/// A variable defined by several configs with the same value is written once;
+/// with different values it is a [`BundleError::Collision`].
+pub fn env_bundle(format: &str, parts: &[(BundlePart, Value)]) -> Result<String, BundleError> {
+    let flatten = |v: &Value| match format {
+        "env" => Ok(EnvVarWriter::default().entries(v)),
+        "docker-env" => Ok(DockerEnvVarWriter {}.entries(v)),
+        other => Err(BundleError::UnsupportedFormat(other.to_string())),
+    };
+
+    let mut seen: HashMap<String, (&str, String)> = HashMap::new();
+    let mut lines = Vec::new();
+    for (part, value) in parts {
+        let value = match &part.prefix {
+            Some(prefix) => Value::Mapping(HashMap::from([(prefix.clone(), value.clone())])),
+            None => value.clone(),
+        };
+        let mut entries = flatten(&value)?;
+        entries.sort();
+
+        for (key, val) in entries {
+            match seen.get(&key) {
+                Some((_, existing)) if *existing == val => continue,
+                Some((first, _)) => {
+                    return Err(BundleError::Collision {
+                        key,
+                        first: first.to_string(),
+                        second: part.path.clone(),
+                    });
+                }
+                None => {
+                    lines.push(format!("{key}={val}"));
+                    seen.insert(key, (&part.path, val));
+                }
+            }
+        }
+    }
+    Ok(lines.join("\n"))
+}
@@ -8,15 +8,38 @@ impl ValueWriter for DockerEnvVarWriter {
         "docker-env"
     }
 
+    fn content_type(&self) -> &'static str {
+        "text/plain; charset=utf-8"
+    }
+
     fn to_str(&self, v: &Value) -> Result<String, WriterError> {
-        let mut lines = Vec::new();
-        flatten_to_env("", v, &mut lines);
+        let entries = self.entries(v);
+        if let Some((key, _)) = entries.iter().find(|(key, _)| !is_env_var_name(key)) {
+            return Err(WriterError {
+                format: "docker-env",
+                message: format!("'{key}' is not a valid environment variable name"),
+            });
+        }
+        let lines: Vec<String> = entries
+            .into_iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect();
         Ok(lines.join("\n"))
     }
 }
 
+/// Returns true if `key` is a valid shell variable name: ASCII letters,
+/// digits and `_`, not starting with a digit.
+fn is_env_var_name(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
 /// Recursively traverses the Value structure to flatten it into environment variable format.
-fn flatten_to_env(prefix: &str, value: &Value, lines: &mut Vec<String>) {
+///
+/// Null values are flattened to `None`.
+fn flatten_to_env(prefix: &str, value: &Value, entries: &mut Vec<(String, Option<String>)>) {
     match value {
         Value::Mapping(map) => {
             for (key, val) in map {
@@ -26,32 +49,31 @@ fn flatten_to_env(prefix: &str, value: &Value, lines: &mut Vec<String>) {
                 } else {
                     format!("{}_{}", prefix, key)
                 };
-                flatten_to_env(&new_prefix, val, lines);
+                flatten_to_env(&new_prefix, val, entries);
             }
         }
         Value::Sequence(seq) => {
             for (index, item) in seq.iter().enumerate() {
                 // Append the index to the prefix for sequence items
                 let new_prefix = format!("{}_{}", prefix, index);
-                flatten_to_env(&new_prefix, item, lines);
+                flatten_to_env(&new_prefix, item, entries);
             }
         }
         // Base cases for the recursion: primitive values
         Value::String(s) => {
-            lines.push(format!("{}={}", prefix.to_uppercase(), s));
+            entries.push((prefix.to_uppercase(), Some(s.clone())));
         }
         Value::Int(n) => {
-            lines.push(format!("{}={}", prefix.to_uppercase(), n));
+            entries.push((prefix.to_uppercase(), Some(n.to_string())));
         }
         Value::Float(n) => {
-            lines.push(format!("{}={}", prefix.to_uppercase(), n));
+            entries.push((prefix.to_uppercase(), Some(n.to_string())));
         }
         Value::Boolean(b) => {
-            lines.push(format!("{}={}", prefix.to_uppercase(), b));
+            entries.push((prefix.to_uppercase(), Some(b.to_string())));
         }
         Value::Null => {
-            // Represent null as an empty string
-            lines.push(format!("{}=\"\"", prefix.to_uppercase()));
+            entries.push((prefix.to_uppercase(), None));
         }
     }
 }
@@ -60,4 +82,21 @@ impl DockerEnvVarWriter {
     pub fn new_boxed() -> Box<Self> {
         Box::new(Self{})
     }
+
+    /// Returns the flattened `(KEY, value)` pairs, formatted as in `to_str`.
+    pub fn entries(&self, v: &Value) -> Vec<(String, String)> {
+        self.raw_entries(v)
+            .into_iter()
+            // Represent null as an empty string
+            .map(|(key, value)| (key, value.unwrap_or_else(|| "\"\"".to_string())))
+            .collect()
+    }
+
+    /// Returns the flattened `(KEY, value)` pairs with values unformatted,
+    /// `None` standing for null.
+    pub fn raw_entries(&self, v: &Value) -> Vec<(String, Option<String>)> {
+        let mut entries = Vec::new();
+        flatten_to_env("", v, &mut entries);
+        entries
+    }
 }
@@ -1,22 +1,32 @@
-use crate::{writer::{ValueWriter, WriterError}, Value};
+use crate::{writer::{SequenceEncoding, ValueWriter, WriterError}, Value};
 
-#[derive(Debug)]
-pub struct EnvVarWriter {}
+#[derive(Debug, Default)]
+pub struct EnvVarWriter {
+    /// How sequences of scalars are written.
+    pub sequence_encoding: SequenceEncoding,
+}
 
 impl ValueWriter for EnvVarWriter {
     fn ext(&self) -> &'static str {
         "env"
     }
 
+    fn content_type(&self) -> &'static str {
+        "text/plain; charset=utf-8"
+    }
+
     fn to_str(&self, v: &Value) -> Result<String, WriterError> {
-        let mut lines = Vec::new();
-        flatten_to_env("", v, &mut lines);
+        let lines: Vec<String> = self
+            .entries(v)
+            .into_iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect();
         Ok(lines.join("\n"))
     }
 }
 
 /// Recursively traverses the Value structure to flatten it into environment variable format.
-fn flatten_to_env(prefix: &str, value: &Value, lines: &mut Vec<String>) {
+fn flatten_to_env(prefix: &str, value: &Value, sequences: &SequenceEncoding, entries: &mut Vec<(String, String)>) {
     match value {
         Value::Mapping(map) => {
             for (key, val) in map {
@@ -26,38 +36,54 @@ fn flatten_to_env(prefix: &str, value: &Value, lines: &mut Vec<String>) {
                 } else {
                     format!("{}_{}", prefix, key)
                 };
-                flatten_to_env(&new_prefix, val, lines);
+                flatten_to_env(&new_prefix, val, sequences, entries);
             }
         }
         Value::Sequence(seq) => {
+            if let Some(joined) = sequences.join(seq) {
+                entries.push((prefix.to_uppercase(), format!("\"{}\"", joined)));
+                return;
+            }
             for (index, item) in seq.iter().enumerate() {
                 // Append the index to the prefix for sequence items
                 let new_prefix = format!("{}_{}", prefix, index);
-                flatten_to_env(&new_prefix, item, lines);
+                flatten_to_env(&new_prefix, item, sequences, entries);
             }
         }
         // Base cases for the recursion: primitive values
         Value::String(s) => {
-            lines.push(format!("{}=\"{}\"", prefix.to_uppercase(), s));
+            entries.push((prefix.to_uppercase(), format!("\"{}\"", s)));
         }
         Value::Int(n) => {
-            lines.push(format!("{}={}", prefix.to_uppercase(), n));
+            entries.push((prefix.to_uppercase(), n.to_string()));
         }
         Value::Float(n) => {
-            lines.push(format!("{}={}", prefix.to_uppercase(), n));
+            entries.push((prefix.to_uppercase(), n.to_string()));
         }
         Value::Boolean(b) => {
-            lines.push(format!("{}={}", prefix.to_uppercase(), b));
+            entries.push((prefix.to_uppercase(), b.to_string()));
         }
         Value::Null => {
             // Represent null as an empty string
-            lines.push(format!("{}=\"\"", prefix.to_uppercase()));
+            entries.push((prefix.to_uppercase(), "\"\"".to_string()));
         }
     }
 }
 
 impl EnvVarWriter {
     pub fn new_boxed() -> Box<Self> {
-        Box::new(Self{})
+        Box::new(Self::default())
+    }
+
+    /// Returns the flattened `(KEY, value)` pairs, with values quoted as in `to_str`.
+    pub fn entries(&self, v: &Value) -> Vec<(String, String)> {
+        let mut entries = Vec::new();
+        flatten_to_env("", v, &self.sequence_encoding, &mut entries);
+        entries
+    }
+
+    /// Creates a writer using the given sequence encoding.
+    pub fn with_sequence_encoding(sequence_encoding: SequenceEncoding) -> Self {
+        Self { sequence_encoding }
     }
 }
@@ -0,0 +1,68 @@
+use crate::{writer::{ValueWriter, WriterError}, Value};
+
+/// Writes YAML shaped for use as Helm chart values.
+///
+/// Unlike the plain YAML writer:
+/// - keys with a `null` value are omitted, since Helm treats `null` as
+///   "delete the chart default" rather than as a value;
+/// - mappings are always emitted as `{}` when empty, including the document
+///   itself, and keys are sorted so the output diffs cleanly;
+/// - the top level must be a mapping, as Helm rejects any other values file.
+///
+/// Nulls inside sequences are kept, since they are positional.
+#[derive(Debug)]
+pub struct HelmValuesWriter {}
+
+impl ValueWriter for HelmValuesWriter {
+    fn ext(&self) -> &'static str {
+        "helm-values"
+    }
+
+    fn content_type(&self) -> &'static str {
+        "application/yaml"
+    }
+
+    fn to_str(&self, v: &Value) -> Result<String, WriterError> {
+        let values = match v {
+            Value::Mapping(_) => to_helm_yaml(v),
+            Value::Null => serde_yaml::Value::Mapping(serde_yaml::Mapping::new()),
+            _ => {
+                return Err(WriterError {
+                    format: "helm-values",
+                    message: "Helm values must be a mapping at the top level".to_string(),
+                });
+            }
+        };
+        serde_yaml::to_string(&values).map_err(|e| WriterError {
+            format: "helm-values",
+            message: e.to_string(),
+        })
+    }
+}
+
+/// Converts to serde_yaml, dropping null-valued keys and sorting mapping keys.
+fn to_helm_yaml(value: &Value) -> serde_yaml::Value {
+    match value {
+        Value::Mapping(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let mut yaml_map = serde_yaml::Mapping::new();
+            for key in keys {
+                let value = &map[key];
+                if matches!(value, Value::Null) {
+                    continue;
+                }
+                yaml_map.insert(serde_yaml::Value::String(key.clone()), to_helm_yaml(value));
+            }
+            serde_yaml::Value::Mapping(yaml_map)
+        }
+        Value::Sequence(seq) => serde_yaml::Value::Sequence(seq.iter().map(to_helm_yaml).collect()),
+        scalar => super::yaml::to_yaml(scalar),
+    }
+}
+
+impl HelmValuesWriter {
+    pub fn new_boxed() -> Box<Self> {
+        Box::new(Self {})
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{writer::{ValueWriter, WriterError}, Value};
+
+/// Writes INI files.
+///
+/// Top-level keys holding a mapping become `[section]` blocks; the remaining
+/// top-level keys are written first, before any section header, as the global
+/// section. Mappings nested deeper than a section are flattened into dotted
+/// keys (`pool.max=10`) and sequences into indexed keys (`hosts[0]=a`).
+///
+/// Keys are sorted so the output diffs cleanly. Strings are written bare
+/// unless they would be misread (surrounding whitespace, comment characters,
+/// quotes or line breaks), in which case they are double-quoted.
+#[derive(Debug)]
+pub struct IniWriter {}
+
+impl ValueWriter for IniWriter {
+    fn ext(&self) -> &'static str {
+        "ini"
+    }
+
+    fn content_type(&self) -> &'static str {
+        "text/plain; charset=utf-8"
+    }
+
+    fn to_str(&self, v: &Value) -> Result<String, WriterError> {
+        let map = match v {
+            Value::Mapping(map) => map,
+            Value::Null => return Ok(String::new()),
+            _ => {
+                return Err(WriterError {
+                    format: "ini",
+                    message: "INI output must be a mapping at the top level".to_string(),
+                });
+            }
+        };
+        let mut keys: Vec<&String> = map.keys().collect();
+        keys.sort();
+
+        let mut ini = String::new();
+        for key in &keys {
+            let value = &map[*key];
+            if !matches!(value, Value::Mapping(_)) {
+                write_entries(value, key, &mut ini);
+            }
+        }
+        for key in &keys {
+            let Value::Mapping(section) = &map[*key] else {
+                continue;
+            };
+            if !ini.is_empty() {
+                ini.push('\n');
+            }
+            ini.push_str(&format!("[{key}]\n"));
+            let mut section_keys: Vec<&String> = section.keys().collect();
+            section_keys.sort();
+            for section_key in section_keys {
+                write_entries(&section[section_key], section_key, &mut ini);
+            }
+        }
+        Ok(ini)
+    }
+}
+
+/// Writes `value` as one or more `key=value` lines, flattening nested values.
+fn write_entries(value: &Value, prefix: &str, ini: &mut String) {
+    match value {
+        Value::Mapping(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            for key in keys {
+                write_entries(&map[key], &format!("{prefix}.{key}"), ini);
+            }
+        }
+        Value::Sequence(seq) => {
+            for (index, item) in seq.iter().enumerate() {
+                write_entries(item, &format!("{prefix}[{index}]"), ini);
+            }
+        }
+        Value::String(s) => ini.push_str(&format!("{prefix}={}\n", quote(s))),
+        Value::Int(n) => ini.push_str(&format!("{prefix}={n}\n")),
+        Value::Float(n) => ini.push_str(&format!("{prefix}={n}\n")),
+        Value::Boolean(b) => ini.push_str(&format!("{prefix}={b}\n")),
+        // INI has no null, so it's written as an empty value
+        Value::Null => ini.push_str(&format!("{prefix}=\n")),
+    }
+}
+
+/// Double-quotes `s` when writing it bare would change how it's read back.
+fn quote(s: &str) -> String {
+    let needs_quotes = s.trim() != s
+        || s.contains(['"', ';', '#', '\n', '\r']);
+    if !needs_quotes {
+        return s.to_string();
+    }
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r");
+    format!("\"{escaped}\"")
+}
+
+impl IniWriter {
+    pub fn new_boxed() -> Box<Self> {
+        Box::new(Self {})
+    }
+}
@@ -1,14 +1,28 @@
 use crate::{writer::{ValueWriter, WriterError}, Value};
 
-#[derive(Debug)]
-pub struct JsonWriter {}
+/// Writes JSON, with object keys in sorted order (`serde_json::Map` is a
+/// `BTreeMap`), so the same value always gives the same output.
+#[derive(Debug, Default)]
+pub struct JsonWriter {
+    /// Indent the output instead of writing it on a single line.
+    pub pretty: bool,
+}
 
 impl ValueWriter for JsonWriter {
     fn ext(&self) -> &'static str {
         "json"
     }
+    fn content_type(&self) -> &'static str {
+        "application/json"
+    }
     fn to_str(&self, v: &Value) -> Result<String, WriterError> {
-        serde_json::to_string(&to_json(v)).map_err(|e| WriterError {
+        let json = to_json(v);
+        let result = if self.pretty {
+            serde_json::to_string_pretty(&json)
+        } else {
+            serde_json::to_string(&json)
+        };
+        result.map_err(|e| WriterError {
             format: "json",
             message: e.to_string(),
         })
@@ -42,6 +56,11 @@ pub fn to_json(value: &Value) -> serde_json::Value {
 
 impl JsonWriter {
     pub fn new_boxed() -> Box<Self> {
-        Box::new(Self{})
+        Box::new(Self::default())
+    }
+
+    /// A writer indenting its output, for humans reading it.
+    pub fn pretty() -> Self {
+        Self { pretty: true }
     }
 }
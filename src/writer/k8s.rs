@@ -0,0 +1,134 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::{
+    writer::{docker_env::DockerEnvVarWriter, ValueWriter, WriterError},
+    Value,
+};
+
+/// Resource name used when a manifest is written without one.
+pub const DEFAULT_RESOURCE_NAME: &str = "config";
+
+/// Writes a `v1/ConfigMap` manifest whose `data` holds the flattened config.
+///
+/// Keys are flattened as for `docker-env` (`DATABASE_HOST`) and every value
+/// is written as a string, as Kubernetes requires; null becomes `""`.
+#[derive(Debug)]
+pub struct ConfigMapWriter {}
+
+impl ValueWriter for ConfigMapWriter {
+    fn ext(&self) -> &'static str {
+        "configmap"
+    }
+
+    fn content_type(&self) -> &'static str {
+        "application/yaml"
+    }
+
+    fn to_str(&self, v: &Value) -> Result<String, WriterError> {
+        self.to_str_named(v, DEFAULT_RESOURCE_NAME)
+    }
+
+    fn to_str_named(&self, v: &Value, name: &str) -> Result<String, WriterError> {
+        manifest("configmap", "ConfigMap", name, v, |value| value)
+    }
+}
+
+impl ConfigMapWriter {
+    pub fn new_boxed() -> Box<Self> {
+        Box::new(Self {})
+    }
+}
+
+/// Writes a `v1/Secret` manifest (type `Opaque`) whose `data` holds the
+/// flattened config, base64-encoded.
+#[derive(Debug)]
+pub struct SecretWriter {}
+
+impl ValueWriter for SecretWriter {
+    fn ext(&self) -> &'static str {
+        "secret"
+    }
+
+    fn content_type(&self) -> &'static str {
+        "application/yaml"
+    }
+
+    fn to_str(&self, v: &Value) -> Result<String, WriterError> {
+        self.to_str_named(v, DEFAULT_RESOURCE_NAME)
+    }
+
+    fn to_str_named(&self, v: &Value, name: &str) -> Result<String, WriterError> {
+        manifest("secret", "Secret", name, v, |value| STANDARD.encode(value))
+    }
+}
+
+impl SecretWriter {
+    pub fn new_boxed() -> Box<Self> {
+        Box::new(Self {})
+    }
+}
+
+/// Returns the default resource name for the config at `path`: its last
+/// segment, lowercased, with characters Kubernetes rejects replaced by `-`.
+///
+/// ```
+/// use konf_provider::writer::k8s::resource_name;
+///
+/// assert_eq!(resource_name("services/api/Config_v2"), "config-v2");
+/// ```
+pub fn resource_name(path: &str) -> String {
+    let segment = path.trim_matches('/').rsplit('/').next().unwrap_or_default();
+    let name: String = segment
+        .chars()
+        .map(|c| match c.to_ascii_lowercase() {
+            c @ ('a'..='z' | '0'..='9' | '-' | '.') => c,
+            _ => '-',
+        })
+        .collect();
+    let name = name.trim_matches(['-', '.']);
+    if name.is_empty() {
+        DEFAULT_RESOURCE_NAME.to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Builds a manifest of `kind` named `name`, with `encode` applied to each
+/// flattened value of `v`.
+fn manifest(
+    format: &'static str,
+    kind: &str,
+    name: &str,
+    v: &Value,
+    encode: impl Fn(String) -> String,
+) -> Result<String, WriterError> {
+    if !matches!(v, Value::Mapping(_) | Value::Null) {
+        return Err(WriterError {
+            format,
+            message: format!("a {kind} must be built from a mapping"),
+        });
+    }
+    let mut entries = DockerEnvVarWriter {}.raw_entries(v);
+    entries.sort();
+
+    let mut data = serde_yaml::Mapping::new();
+    for (key, value) in entries {
+        data.insert(key.into(), encode(value.unwrap_or_default()).into());
+    }
+    let mut metadata = serde_yaml::Mapping::new();
+    metadata.insert("name".into(), name.into());
+
+    let mut manifest = serde_yaml::Mapping::new();
+    manifest.insert("apiVersion".into(), "v1".into());
+    manifest.insert("kind".into(), kind.into());
+    manifest.insert("metadata".into(), metadata.into());
+    if kind == "Secret" {
+        manifest.insert("type".into(), "Opaque".into());
+    }
+    manifest.insert("data".into(), data.into());
+
+    serde_yaml::to_string(&manifest).map_err(|e| WriterError {
+        format,
+        message: e.to_string(),
+    })
+}
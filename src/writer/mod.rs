@@ -1,9 +1,15 @@
 pub mod env;
 pub mod json;
 pub mod properties;
+pub mod shell;
 pub mod toml;
 pub mod yaml;
 pub mod docker_env;
+pub mod helm;
+pub mod ini;
+pub mod k8s;
+pub mod xml;
+pub mod bundle;
 use std::fmt::Debug;
 
 use crate::Value;
@@ -12,8 +18,18 @@ use crate::Value;
 pub trait ValueWriter: Debug + Send + Sync {
     /// Returns the format extension this writer handles (e.g., "json", "yaml").
     fn ext(&self) -> &'static str;
+    /// Returns the MIME type of the serialized output (e.g., "application/json").
+    fn content_type(&self) -> &'static str;
     /// Serializes a `Value` to a string representation.
     fn to_str(&self, v: &Value) -> Result<String, WriterError>;
+    /// Serializes a `Value` into a document named `name`.
+    ///
+    /// Only formats that embed a name (Kubernetes manifests) use it; the
+    /// others ignore it.
+    fn to_str_named(&self, v: &Value, name: &str) -> Result<String, WriterError> {
+        let _ = name;
+        self.to_str(v)
+    }
 }
 
 /// Error type for serialization failures.
@@ -31,14 +47,183 @@ impl std::fmt::Display for WriterError {
 
 impl std::error::Error for WriterError {}
 
+/// How flat key/value writers (env, properties) encode sequences of scalars.
+///
+/// Sequences containing mappings or sequences are always indexed, since they
+/// can't be represented as a single value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum SequenceEncoding {
+    /// One entry per element, suffixed with its index (`FOO_0`, `foo[0]`).
+    #[default]
+    Indexed,
+    /// A single entry with all elements joined by the separator (`FOO=a,b`).
+    ///
+    /// A separator or backslash inside an element is escaped with a
+    /// backslash, so `["a,b", "c"]` joined by `,` is `a\,b,c`.
+    Joined(String),
+}
+
+impl SequenceEncoding {
+    /// Joins sequences by `separator` if one is given and not empty, indexes them otherwise.
+    pub fn from_separator(separator: Option<String>) -> Self {
+        match separator {
+            Some(separator) if !separator.is_empty() => SequenceEncoding::Joined(separator),
+            _ => SequenceEncoding::Indexed,
+        }
+    }
+
+    /// Returns the joined representation of `seq` when this encoding joins
+    /// sequences and every element is a scalar, `None` otherwise.
+    pub fn join(&self, seq: &[Value]) -> Option<String> {
+        let SequenceEncoding::Joined(separator) = self else {
+            return None;
+        };
+        let items = seq
+            .iter()
+            .map(|item| match item {
+                Value::String(s) => Some(escape_separator(s, separator)),
+                Value::Int(n) => Some(escape_separator(&n.to_string(), separator)),
+                Value::Float(n) => Some(escape_separator(&n.to_string(), separator)),
+                Value::Boolean(b) => Some(escape_separator(&b.to_string(), separator)),
+                Value::Null => Some(String::new()),
+                Value::Sequence(_) | Value::Mapping(_) => None,
+            })
+            .collect::<Option<Vec<String>>>()?;
+        Some(items.join(separator))
+    }
+}
+
+/// Backslash-escapes every `separator` and backslash in a joined element.
+fn escape_separator(element: &str, separator: &str) -> String {
+    let mut escaped = String::with_capacity(element.len());
+    let mut rest = element;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix(separator) {
+            escaped.push('\\');
+            escaped.push_str(separator);
+            rest = after;
+        } else {
+            if c == '\\' {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    escaped
+}
+
+/// Format used by [`MultiWriter`] when a request does not specify one.
+pub const DEFAULT_FORMAT: &str = "yaml";
+
+/// Requested format that selects the one a file declares under `<!>: output: format:`.
+pub const AUTO_FORMAT: &str = "_";
+
 #[derive(Debug)]
 pub struct MultiWriter {
     pub loaders: Vec<Box<dyn ValueWriter>>,
+    /// Format used for requests that don't specify one.
+    pub default_format: String,
 }
 
 impl MultiWriter {
     pub fn new(loaders: Vec<Box<dyn ValueWriter>>) -> Self {
-        Self { loaders }
+        Self {
+            loaders,
+            default_format: DEFAULT_FORMAT.to_string(),
+        }
+    }
+
+    /// Sets the format used for requests that don't specify one.
+    ///
+    /// Returns `None` if no writer handles `format`.
+    pub fn with_default_format(mut self, format: &str) -> Option<Self> {
+        if !self.supports(format) {
+            return None;
+        }
+        self.default_format = format.to_string();
+        Some(self)
+    }
+
+    /// Returns the format to use for an auto-format request: the one the file
+    /// `declared` if a writer handles it, the default format otherwise.
+    pub fn auto_format<'a>(&'a self, declared: Option<&'a str>) -> &'a str {
+        match declared {
+            Some(format) if self.supports(format) => format,
+            Some(format) => {
+                tracing::warn!("declared output format '{format}' is unknown, using '{}'", self.default_format);
+                &self.default_format
+            }
+            None => &self.default_format,
+        }
+    }
+
+    /// Returns true if a writer handles `ext`.
+    pub fn supports(&self, ext: &str) -> bool {
+        self.loaders.iter().any(|l| l.ext() == ext)
+    }
+
+    /// Returns the extensions of all registered writers.
+    pub fn formats(&self) -> Vec<&'static str> {
+        self.loaders.iter().map(|l| l.ext()).collect()
+    }
+
+    /// Returns the MIME type of the writer handling `ext`.
+    pub fn content_type(&self, ext: &str) -> Option<&'static str> {
+        self.loaders
+            .iter()
+            .find(|e| ext == e.ext())
+            .map(|l| l.content_type())
+    }
+
+    /// Returns the format of the writer best matching an `Accept` header value.
+    ///
+    /// Media ranges are tried by decreasing `q` weight and the first one a
+    /// writer produces wins; when several writers share a type (`text/plain`),
+    /// the one registered first is used. `*/*` selects the default format.
+    /// Returns `None` if no range matches a writer.
+    pub fn format_for_content_type(&self, accept: &str) -> Option<&str> {
+        let mut ranges: Vec<(&str, f32)> = accept
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';').map(str::trim);
+                let media_type = parts.next().filter(|m| !m.is_empty())?;
+                let q = parts
+                    .find_map(|param| param.strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.parse::<f32>().ok())?;
+                (q > 0.0).then_some((media_type, q))
+            })
+            .collect();
+        // Stable, so ranges of equal weight keep the client's order
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        ranges.into_iter().find_map(|(media_type, _)| {
+            let media_type = media_type.to_ascii_lowercase();
+            if media_type == "*/*" {
+                return Some(self.default_format.as_str());
+            }
+            let media_type = match media_type.as_str() {
+                "text/yaml" | "text/x-yaml" | "application/x-yaml" => "application/yaml",
+                other => other,
+            };
+            self.loaders
+                .iter()
+                .find(|l| {
+                    let produced = l.content_type().split(';').next().unwrap_or_default();
+                    match media_type.strip_suffix("/*") {
+                        Some(main_type) => produced.split('/').next() == Some(main_type),
+                        None => produced == media_type,
+                    }
+                })
+                .map(|l| l.ext())
+        })
+    }
+
+    /// Like [`MultiWriter::write`], picking the format from an `Accept` header
+    /// value as [`MultiWriter::format_for_content_type`] does.
+    pub fn write_for_content_type(&self, accept: &str, content: &Value) -> Option<Result<String, WriterError>> {
+        let ext = self.format_for_content_type(accept)?;
+        self.write(ext, content)
     }
 
     pub fn write(&self, ext: &str, content: &Value) -> Option<Result<String, WriterError>> {
@@ -47,4 +232,12 @@ impl MultiWriter {
             .find(|e| ext == e.ext())
             .map(|l| l.to_str(content))
     }
+
+    /// Like [`MultiWriter::write`], naming the document `name` for formats that embed one.
+    pub fn write_named(&self, ext: &str, content: &Value, name: &str) -> Option<Result<String, WriterError>> {
+        self.loaders
+            .iter()
+            .find(|e| ext == e.ext())
+            .map(|l| l.to_str_named(content, name))
+    }
 }
@@ -1,21 +1,33 @@
-use crate::{Value, writer::{ValueWriter, WriterError}};
+use crate::{Value, writer::{SequenceEncoding, ValueWriter, WriterError}};
 
-#[derive(Debug)]
-pub struct PropertiesWriter {}
+#[derive(Debug, Default)]
+pub struct PropertiesWriter {
+    /// How sequences of scalars are written.
+    pub sequence_encoding: SequenceEncoding,
+}
 
 impl ValueWriter for PropertiesWriter {
     fn ext(&self) -> &'static str {
         "properties"
     }
 
+    fn content_type(&self) -> &'static str {
+        "text/plain; charset=utf-8"
+    }
+
     fn to_str(&self, v: &Value) -> Result<String, WriterError> {
         let mut properties = String::new();
-        write_properties(v, "", &mut properties);
+        write_properties(v, "", &self.sequence_encoding, &mut properties);
         Ok(properties)
     }
 }
 
-fn write_properties(value: &Value, prefix: &str, properties: &mut String) {
+fn write_properties(
+    value: &Value,
+    prefix: &str,
+    sequences: &SequenceEncoding,
+    properties: &mut String,
+) {
     match value {
         Value::Mapping(map) => {
             for (key, val) in map {
@@ -24,38 +36,80 @@ fn write_properties(value: &Value, prefix: &str, properties: &mut String) {
                 } else {
                     format!("{}.{}", prefix, key)
                 };
-                write_properties(val, &new_prefix, properties);
+                write_properties(val, &new_prefix, sequences, properties);
             }
         }
         Value::Sequence(seq) => {
+            if let Some(joined) = sequences.join(seq) {
+                write_entry(prefix, &joined, properties);
+                return;
+            }
             for (index, val) in seq.iter().enumerate() {
                 let new_prefix = format!("{}[{}]", prefix, index);
-                write_properties(val, &new_prefix, properties);
+                write_properties(val, &new_prefix, sequences, properties);
             }
         }
-        Value::String(s) => {
-            properties.push_str(&format!("{}=\"{}\"\n", prefix, s));
-        }
-        Value::Int(n) => {
-            properties.push_str(&format!("{}={}\n", prefix, n));
-        }
-        Value::Float(n) => {
-            properties.push_str(&format!("{}={}\n", prefix, n));
-        }
-        Value::Boolean(b) => {
-            properties.push_str(&format!("{}={}\n", prefix, b));
-        }
+        Value::String(s) => write_entry(prefix, s, properties),
+        Value::Int(n) => write_entry(prefix, &n.to_string(), properties),
+        Value::Float(n) => write_entry(prefix, &n.to_string(), properties),
+        Value::Boolean(b) => write_entry(prefix, &b.to_string(), properties),
         Value::Null => {
             // Java properties files don't have a concept of null,
             // so we can either ignore it or write an empty string.
             // Here, we'll write an empty string.
-            properties.push_str(&format!("{}=\n", prefix));
+            write_entry(prefix, "", properties);
+        }
+    }
+}
+
+/// Writes a `key=value` line, both escaped as `java.util.Properties.store` does.
+fn write_entry(key: &str, value: &str, properties: &mut String) {
+    properties.push_str(&escape(key, true));
+    properties.push('=');
+    properties.push_str(&escape(value, false));
+    properties.push('\n');
+}
+
+/// Escapes `s` following `java.util.Properties.store`.
+///
+/// Backslashes, line breaks, tabs, form feeds and the separator and comment
+/// characters (`=`, `:`, `#`, `!`) are backslash-escaped. Spaces are escaped
+/// everywhere in a key but only first in a value, where it would otherwise be
+/// trimmed. Characters outside printable ASCII are written as `\uXXXX` UTF-16
+/// escapes, so the file is plain ASCII whatever encoding it is read with.
+fn escape(s: &str, is_key: bool) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for (index, c) in s.chars().enumerate() {
+        match c {
+            ' ' if is_key || index == 0 => escaped.push_str("\\ "),
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\x0c' => escaped.push_str("\\f"),
+            '=' | ':' | '#' | '!' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            _ => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    escaped.push_str(&format!("\\u{unit:04X}"));
+                }
+            }
         }
     }
+    escaped
 }
 
 impl PropertiesWriter {
     pub fn new_boxed() -> Box<Self> {
-        Box::new(Self {})
+        Box::new(Self::default())
+    }
+
+    /// Creates a writer using the given sequence encoding.
+    pub fn with_sequence_encoding(sequence_encoding: SequenceEncoding) -> Self {
+        Self { sequence_encoding }
     }
 }
@@ -0,0 +1,62 @@
+use crate::{writer::{docker_env::DockerEnvVarWriter, ValueWriter, WriterError}, Value};
+
+/// Writes `export KEY='value'` lines meant to be `source`d by a POSIX shell.
+///
+/// Keys are flattened as for `docker-env`. Values are single-quoted, so `$`,
+/// backticks, backslashes and spaces are taken literally; an embedded `'` is
+/// written as `'\''`. Characters that are not valid in a variable name are
+/// replaced by `_` in keys.
+#[derive(Debug)]
+pub struct ShellWriter {}
+
+impl ValueWriter for ShellWriter {
+    fn ext(&self) -> &'static str {
+        "sh"
+    }
+
+    fn content_type(&self) -> &'static str {
+        "text/plain; charset=utf-8"
+    }
+
+    fn to_str(&self, v: &Value) -> Result<String, WriterError> {
+        let lines: Vec<String> = DockerEnvVarWriter {}
+            .raw_entries(v)
+            .into_iter()
+            .map(|(key, value)| {
+                let value = value.unwrap_or_default();
+                format!("export {}={}", variable_name(&key), quote(&value))
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    }
+}
+
+/// Single-quotes `value` for a POSIX shell.
+///
+/// ```
+/// use konf_provider::writer::shell::quote;
+///
+/// assert_eq!(quote("it's $HOME"), r"'it'\''s $HOME'");
+/// ```
+pub fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Makes `key` a valid shell variable name.
+fn variable_name(key: &str) -> String {
+    let name: String = key
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) || name.is_empty() {
+        format!("_{name}")
+    } else {
+        name
+    }
+}
+
+impl ShellWriter {
+    pub fn new_boxed() -> Box<Self> {
+        Box::new(Self {})
+    }
+}
@@ -9,6 +9,10 @@ impl ValueWriter for TomlWriter {
         "toml"
     }
 
+    fn content_type(&self) -> &'static str {
+        "application/toml"
+    }
+
     fn to_str(&self, v: &Value) -> Result<String, WriterError> {
         const ROOT_KEY: &str = "root";
         let toml_value = to_toml(v);
@@ -0,0 +1,109 @@
+use crate::{writer::{ValueWriter, WriterError}, Value};
+
+/// Element name used for sequence items that have no key of their own.
+const ITEM_ELEMENT: &str = "item";
+
+/// Writes XML documents for consumers that can't read any other format.
+///
+/// The document is wrapped in a single root element. Mapping keys become
+/// nested elements (sorted, so the output diffs cleanly), a sequence becomes
+/// one element per item repeating its key, and scalars become text content.
+/// `null` is written as an empty element. Items of a sequence that isn't
+/// under a key (at the top level, or nested in another sequence) are written
+/// as `<item>` elements.
+#[derive(Debug)]
+pub struct XmlWriter {
+    /// Name of the document's root element.
+    pub root: String,
+}
+
+impl Default for XmlWriter {
+    fn default() -> Self {
+        Self {
+            root: "root".to_string(),
+        }
+    }
+}
+
+impl ValueWriter for XmlWriter {
+    fn ext(&self) -> &'static str {
+        "xml"
+    }
+
+    fn content_type(&self) -> &'static str {
+        "application/xml"
+    }
+
+    fn to_str(&self, v: &Value) -> Result<String, WriterError> {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        write_element(&self.root, v, 0, &mut xml);
+        Ok(xml)
+    }
+}
+
+/// Writes `value` as a `<name>` element indented by `depth` levels.
+fn write_element(name: &str, value: &Value, depth: usize, xml: &mut String) {
+    let indent = "  ".repeat(depth);
+    match value {
+        Value::Mapping(map) if !map.is_empty() => {
+            xml.push_str(&format!("{indent}<{name}>\n"));
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            for key in keys {
+                write_child(key, &map[key], depth + 1, xml);
+            }
+            xml.push_str(&format!("{indent}</{name}>\n"));
+        }
+        Value::Sequence(seq) if !seq.is_empty() => {
+            xml.push_str(&format!("{indent}<{name}>\n"));
+            for item in seq {
+                write_element(ITEM_ELEMENT, item, depth + 1, xml);
+            }
+            xml.push_str(&format!("{indent}</{name}>\n"));
+        }
+        Value::Mapping(_) | Value::Sequence(_) | Value::Null => {
+            xml.push_str(&format!("{indent}<{name}/>\n"));
+        }
+        Value::String(s) => xml.push_str(&format!("{indent}<{name}>{}</{name}>\n", escape(s))),
+        Value::Int(n) => xml.push_str(&format!("{indent}<{name}>{n}</{name}>\n")),
+        Value::Float(n) => xml.push_str(&format!("{indent}<{name}>{n}</{name}>\n")),
+        Value::Boolean(b) => xml.push_str(&format!("{indent}<{name}>{b}</{name}>\n")),
+    }
+}
+
+/// Writes the element(s) for a mapping entry.
+///
+/// A sequence is written as one `<name>` element per item rather than being
+/// wrapped in a single element, so an empty one writes nothing.
+fn write_child(name: &str, value: &Value, depth: usize, xml: &mut String) {
+    match value {
+        Value::Sequence(seq) => {
+            for item in seq {
+                write_element(name, item, depth, xml);
+            }
+        }
+        _ => write_element(name, value, depth, xml),
+    }
+}
+
+/// Escapes the characters that are special in XML text.
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+impl XmlWriter {
+    pub fn new_boxed() -> Box<Self> {
+        Box::new(Self::default())
+    }
+
+    /// Creates a writer whose document root element is `root`.
+    pub fn with_root(root: &str) -> Self {
+        Self {
+            root: root.to_string(),
+        }
+    }
+}
@@ -1,14 +1,30 @@
+use std::collections::BTreeMap;
+
 use crate::{writer::{ValueWriter, WriterError}, Value};
 
 #[derive(Debug)]
-pub struct YamlWriter {}
+pub struct YamlWriter {
+    /// Write mapping keys in sorted order, so the same value always gives the
+    /// same output. Otherwise they follow the (random) order of `Value::Mapping`.
+    pub sorted: bool,
+}
+
+impl Default for YamlWriter {
+    fn default() -> Self {
+        Self { sorted: true }
+    }
+}
 
 impl ValueWriter for YamlWriter {
     fn ext(&self) -> &'static str {
         "yaml"
     }
+    fn content_type(&self) -> &'static str {
+        "application/yaml"
+    }
     fn to_str(&self, v: &Value) -> Result<String, WriterError> {
-        serde_yaml::to_string(&to_yaml(v)).map_err(|e| WriterError {
+        let yaml = if self.sorted { to_sorted_yaml(v) } else { to_yaml(v) };
+        serde_yaml::to_string(&yaml).map_err(|e| WriterError {
             format: "yaml",
             message: e.to_string(),
         })
@@ -37,8 +53,25 @@ pub fn to_yaml(value: &Value) -> serde_yaml::Value {
     }
 }
 
+/// Like [`to_yaml`], with the keys of every mapping in sorted order.
+pub fn to_sorted_yaml(value: &Value) -> serde_yaml::Value {
+    match value {
+        Value::Sequence(seq) => serde_yaml::Value::Sequence(seq.iter().map(to_sorted_yaml).collect()),
+        Value::Mapping(map) => {
+            // `serde_yaml::Mapping` keeps insertion order
+            let sorted: BTreeMap<&String, &Value> = map.iter().collect();
+            let mut yaml_map = serde_yaml::Mapping::new();
+            for (key, value) in sorted {
+                yaml_map.insert(serde_yaml::Value::String(key.clone()), to_sorted_yaml(value));
+            }
+            serde_yaml::Value::Mapping(yaml_map)
+        }
+        scalar => to_yaml(scalar),
+    }
+}
+
 impl YamlWriter {
     pub fn new_boxed() -> Box<Self> {
-        Box::new(Self{})
+        Box::new(Self::default())
     }
 }
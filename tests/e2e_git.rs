@@ -13,12 +13,27 @@
 
 use std::sync::Arc;
 
+use arc_swap::ArcSwap;
+use dashmap::DashMap;
 use konf_provider::{
-    fs::git::{clone_or_update, get_git_directory, is_valid_commit_hash, is_valid_git_url, list_all_commit_hashes, Creds, GitFileProvider},
+    authorizer::jwt::JwtValidator,
+    config::{GitAppState, RepoConfig},
+    dag_cache::DagCacheLimits,
+    fs::git::{changed_keys, clone_or_update, get_git_directory, is_valid_commit_hash, is_valid_git_url, list_all_commit_hashes, list_refs, Creds, GitFileProvider},
+    git_routes,
+    imports::ImportContext,
+    in_flight::InFlight,
     loader::MultiLoader,
     loaders::yaml::YamlLoader,
+    peers::PeerNotifier,
     render::Dag,
+    response_cache::ResponseCache,
+    utils::{GetError, RenderQuery},
+    webhook,
+    writer::{MultiWriter, json::JsonWriter, yaml::YamlWriter},
 };
+use xitca_web::handler::{json::Json, params::Params, query::Query, state::StateRef};
+use xitca_web::http::{HeaderMap, HeaderValue};
 
 struct TestEnv {
     repo_url: String,
@@ -112,6 +127,512 @@ fn test_git_directory_is_deterministic() {
     assert_ne!(dir1, dir3, "Different URLs should produce different directories");
 }
 
+/// Writes `files` into the work tree of `repo`, removes `removed`, and commits.
+fn commit_files(repo: &git2::Repository, files: &[(&str, &str)], removed: &[&str]) -> String {
+    let workdir = repo.workdir().unwrap();
+    let mut index = repo.index().unwrap();
+    for (path, content) in files {
+        let full = workdir.join(path);
+        std::fs::create_dir_all(full.parent().unwrap()).unwrap();
+        std::fs::write(full, content).unwrap();
+        index.add_path(std::path::Path::new(path)).unwrap();
+    }
+    for path in removed {
+        std::fs::remove_file(workdir.join(path)).unwrap();
+        index.remove_path(std::path::Path::new(path)).unwrap();
+    }
+    index.write().unwrap();
+
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = git2::Signature::now("test", "test@example.com").unwrap();
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    repo.commit(Some("HEAD"), &signature, &signature, "update", &tree, &parents)
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn test_changed_keys_between_commits() {
+    let dir = std::env::temp_dir().join(format!("konf-changes-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let repo = git2::Repository::init(&dir).unwrap();
+
+    let first = commit_files(
+        &repo,
+        &[
+            ("common/database.yaml", "host: localhost"),
+            ("services/api.yaml", "port: 8080"),
+            ("legacy.yaml", "old: true"),
+        ],
+        &[],
+    );
+    let second = commit_files(
+        &repo,
+        &[("common/database.yaml", "host: db.internal"), ("services/worker.yaml", "port: 9090")],
+        &["legacy.yaml"],
+    );
+
+    let changes = changed_keys(&dir, &first, &second).unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert_eq!(changes.modified, vec!["common/database"]);
+    assert_eq!(changes.added, vec!["services/worker"]);
+    assert_eq!(changes.removed, vec!["legacy"]);
+}
+
+/// Git mode state serving the local clone of `repo_url`, with `admin` as admin token.
+fn local_git_state(repo_url: &str) -> GitAppState<GitFileProvider> {
+    let metrics = metrics_exporter_prometheus::PrometheusBuilder::new().build_recorder().handle();
+    GitAppState {
+        dag: DashMap::new(),
+        dag_cache: DagCacheLimits::default(),
+        dag_builds: InFlight::new(),
+        writer: Arc::new(MultiWriter::new(vec![JsonWriter::new_boxed()])),
+        commits: ArcSwap::from(Arc::new(list_all_commit_hashes(repo_url).unwrap())),
+        refs: ArcSwap::from(Arc::new(list_refs(repo_url).unwrap())),
+        multiloader: Arc::new(MultiLoader::new(vec![Box::new(YamlLoader {})])),
+        repo_config: RepoConfig {
+            url: repo_url.to_string(),
+            branch: "master".to_string(),
+            creds: None,
+        },
+        metrics: Arc::new(metrics),
+        admin_tokens: vec!["admin".to_string()],
+        jwt: None,
+        inherit_auth: false,
+        peers: PeerNotifier::new(Vec::new()),
+        webhook_secret: None,
+        case_insensitive_paths: false,
+        render_meta: false,
+        import_context: ImportContext::default(),
+        response_cache: ResponseCache::new(None),
+        strict_schemas: false,
+        unresolved: Default::default(),
+        env: Default::default(),
+    }
+}
+
+fn token_header(token: &'static str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert("token", HeaderValue::from_static(token));
+    headers
+}
+
+#[tokio::test]
+async fn test_cache_stats_lists_served_commit() {
+    let repo_url = format!("https://example.com/konf-cache-stats-{}.git", std::process::id());
+    let dir = get_git_directory(&repo_url);
+    let _ = std::fs::remove_dir_all(&dir);
+    let repo = git2::Repository::init(&dir).unwrap();
+    let commit = commit_files(&repo, &[("app.yaml", "port: 8080")], &[]);
+    let state = local_git_state(&repo_url);
+
+    let served = git_routes::get_data(
+        token_header("admin"),
+        Params((commit.clone(), "json".to_string(), "app".to_string())),
+        Query(RenderQuery::default()),
+        StateRef(&state),
+    )
+    .await;
+    let stats = git_routes::get_cache_stats(token_header("admin"), StateRef(&state)).await;
+    let forbidden = git_routes::get_cache_stats(token_header("reader"), StateRef(&state)).await;
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert!(served.is_ok(), "Commit should be served: {:?}", served.err());
+    let Json(stats) = stats.expect("Admin token should read cache stats");
+    assert_eq!(stats.entries, 1);
+    assert_eq!(stats.commits[0].commit, commit);
+    assert!(stats.commits[0].last_access > 0);
+    assert!(stats.estimated_bytes > 0);
+    assert!(matches!(forbidden, Err(GetError::Forbidden { .. })));
+}
+
+#[tokio::test]
+async fn test_rebuild_refreshes_commit_entry() {
+    let repo_url = format!("https://example.com/konf-rebuild-{}.git", std::process::id());
+    let dir = get_git_directory(&repo_url);
+    let _ = std::fs::remove_dir_all(&dir);
+    let repo = git2::Repository::init(&dir).unwrap();
+    let commit = commit_files(&repo, &[("app.yaml", "port: 8080")], &[]);
+    let state = local_git_state(&repo_url);
+    let rebuild = |commit: &str| {
+        let query = git_routes::RebuildQuery { commit: commit.to_string() };
+        git_routes::rebuild_commit(token_header("admin"), Query(query), StateRef(&state))
+    };
+
+    let Json(first) = rebuild(&commit).await.expect("Rebuild should build the entry");
+    // Build timestamps have a one second resolution
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    let Json(second) = rebuild(&commit).await.expect("Rebuild should succeed");
+    let missing = rebuild(&"0".repeat(40)).await;
+    let forbidden = git_routes::rebuild_commit(
+        token_header("reader"),
+        Query(git_routes::RebuildQuery { commit: commit.clone() }),
+        StateRef(&state),
+    )
+    .await;
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert_eq!(second.commit, commit);
+    assert!(second.built_at > first.built_at, "{} <= {}", second.built_at, first.built_at);
+    assert_eq!(state.dag.len(), 1);
+    assert!(matches!(missing, Err(GetError::CommitNotFound { .. })));
+    assert!(matches!(forbidden, Err(GetError::Forbidden { .. })));
+}
+
+#[tokio::test]
+async fn test_get_data_answers_matching_etag_with_not_modified() {
+    let repo_url = format!("https://example.com/konf-etag-{}.git", std::process::id());
+    let dir = get_git_directory(&repo_url);
+    let _ = std::fs::remove_dir_all(&dir);
+    let repo = git2::Repository::init(&dir).unwrap();
+    let commit = commit_files(&repo, &[("app.yaml", "port: 8080")], &[]);
+    let mut state = local_git_state(&repo_url);
+    state.writer = Arc::new(MultiWriter::new(vec![JsonWriter::new_boxed(), YamlWriter::new_boxed()]));
+    let get = |format: &str, token: &'static str, if_none_match: Option<&str>| {
+        let mut headers = token_header(token);
+        if let Some(etag) = if_none_match {
+            headers.insert("if-none-match", HeaderValue::from_str(etag).unwrap());
+        }
+        git_routes::get_data(
+            headers,
+            Params((commit.clone(), format.to_string(), "app".to_string())),
+            Query(RenderQuery::default()),
+            StateRef(&state),
+        )
+    };
+
+    let first = get("json", "admin", None).await.expect("Config should render");
+    let etag = first.etag.clone().expect("Git responses carry an ETag");
+    let revalidated = get("json", "admin", Some(&etag)).await.unwrap();
+    let stale = get("json", "admin", Some("\"0\"")).await.unwrap();
+    let yaml = get("yaml", "admin", None).await.unwrap();
+    let forbidden = get("json", "reader", Some(&etag)).await;
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert!(!first.not_modified);
+    assert!(revalidated.not_modified);
+    assert!(revalidated.body.is_empty());
+    assert_eq!(revalidated.etag, Some(etag.clone()));
+    assert!(!stale.not_modified);
+    assert_eq!(stale.body, first.body);
+    assert_ne!(yaml.etag, Some(etag), "ETags vary by output format");
+    assert!(matches!(forbidden, Err(GetError::Forbidden { .. })));
+}
+
+#[tokio::test]
+async fn test_cached_response_is_not_served_to_an_expired_jwt() {
+    let repo_url = format!("https://example.com/konf-jwt-cache-{}.git", std::process::id());
+    let dir = get_git_directory(&repo_url);
+    let _ = std::fs::remove_dir_all(&dir);
+    let repo = git2::Repository::init(&dir).unwrap();
+    let commit = commit_files(&repo, &[("app.yaml", "<!>:\n  auth:\n    - claims:\n        aud: konf\nport: 8080")], &[]);
+    let mut state = local_git_state(&repo_url);
+    state.jwt = Some(Arc::new(JwtValidator::hmac(b"secret").with_leeway(0)));
+    state.response_cache = ResponseCache::new(Some(8));
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+    let claims = serde_json::json!({"exp": now + 1, "aud": "konf"});
+    let jwt = jsonwebtoken::encode(&jsonwebtoken::Header::default(), &claims, &jsonwebtoken::EncodingKey::from_secret(b"secret")).unwrap();
+    let get = || {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", HeaderValue::from_str(&format!("Bearer {jwt}")).unwrap());
+        git_routes::get_data(
+            headers,
+            Params((commit.clone(), "json".to_string(), "app".to_string())),
+            Query(RenderQuery::default()),
+            StateRef(&state),
+        )
+    };
+
+    let fresh = get().await;
+    tokio::time::sleep(std::time::Duration::from_millis(2100)).await;
+    let expired = get().await;
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert!(fresh.is_ok(), "Valid JWT should be served: {:?}", fresh.err());
+    assert_eq!(state.response_cache.len(), 1);
+    assert!(matches!(expired, Err(GetError::Forbidden { .. })), "{expired:?}");
+}
+
+#[tokio::test]
+async fn test_get_data_resolves_branch_and_tag_names() {
+    let repo_url = format!("https://example.com/konf-refs-{}.git", std::process::id());
+    let dir = get_git_directory(&repo_url);
+    let _ = std::fs::remove_dir_all(&dir);
+    let repo = git2::Repository::init(&dir).unwrap();
+    let first = commit_files(&repo, &[("app.yaml", "port: 8080")], &[]);
+    let signature = git2::Signature::now("test", "test@example.com").unwrap();
+    let first_commit = repo.find_object(git2::Oid::from_str(&first).unwrap(), None).unwrap();
+    repo.tag("v1.0.0", &first_commit, &signature, "first release", false).unwrap();
+    let second = commit_files(&repo, &[("app.yaml", "port: 9090")], &[]);
+    repo.branch("release", &repo.find_commit(git2::Oid::from_str(&second).unwrap()).unwrap(), false)
+        .unwrap();
+    let state = local_git_state(&repo_url);
+    let get = |commit: &str| {
+        git_routes::get_data(
+            token_header("admin"),
+            Params((commit.to_string(), "json".to_string(), "app".to_string())),
+            Query(RenderQuery::default()),
+            StateRef(&state),
+        )
+    };
+
+    let refs = list_refs(&repo_url).unwrap();
+    let branch = get("release").await.expect("Branch should resolve");
+    let tag = get("v1.0.0").await.expect("Annotated tag should resolve to its commit");
+    let by_hash = get(&first).await.unwrap();
+    let unknown = get("no-such-branch").await;
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert_eq!(refs.get("release"), Some(&second));
+    assert_eq!(refs.get("v1.0.0"), Some(&first));
+    assert!(branch.body.contains("9090"));
+    assert!(tag.body.contains("8080"));
+    assert_eq!(tag.etag, by_hash.etag, "A ref and its commit share the ETag");
+    assert!(matches!(unknown, Err(GetError::CommitNotFound { commit }) if commit == "no-such-branch"));
+}
+
+#[tokio::test]
+async fn test_get_data_accepts_bearer_or_token_header() {
+    let repo_url = format!("https://example.com/konf-bearer-{}.git", std::process::id());
+    let dir = get_git_directory(&repo_url);
+    let _ = std::fs::remove_dir_all(&dir);
+    let repo = git2::Repository::init(&dir).unwrap();
+    let commit = commit_files(&repo, &[("app.yaml", "<!>:\n  auth:\n    - reader\nport: 8080")], &[]);
+    let state = local_git_state(&repo_url);
+    let get = |headers: HeaderMap| {
+        git_routes::get_data(
+            headers,
+            Params((commit.clone(), "json".to_string(), "app".to_string())),
+            Query(RenderQuery::default()),
+            StateRef(&state),
+        )
+    };
+    let bearer = |value: &'static str| {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", HeaderValue::from_static(value));
+        headers
+    };
+
+    let bearer_only = get(bearer("Bearer reader")).await;
+    let custom_only = get(token_header("reader")).await;
+    let mut both = bearer("bearer reader");
+    both.insert("token", HeaderValue::from_static("someone-else"));
+    let bearer_preferred = get(both).await;
+    let neither = get(HeaderMap::new()).await;
+    let basic_only = get(bearer("Basic cmVhZGVy")).await;
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert!(bearer_only.is_ok(), "Bearer token should be accepted: {:?}", bearer_only.err());
+    assert!(custom_only.is_ok(), "token header should still be accepted: {:?}", custom_only.err());
+    assert!(bearer_preferred.is_ok(), "Bearer token should win over the token header");
+    assert!(matches!(neither, Err(GetError::Unauthorized { reason }) if reason.contains("Authorization")));
+    assert!(matches!(basic_only, Err(GetError::Unauthorized { .. })), "Only the Bearer scheme carries a token");
+}
+
+#[tokio::test]
+async fn test_inherit_auth_requires_access_to_imports() {
+    let repo_url = format!("https://example.com/konf-inherit-auth-{}.git", std::process::id());
+    let dir = get_git_directory(&repo_url);
+    let _ = std::fs::remove_dir_all(&dir);
+    let repo = git2::Repository::init(&dir).unwrap();
+    let commit = commit_files(
+        &repo,
+        &[
+            ("common/secret.yaml", "<!>:\n  auth:\n    - ops\npassword: hunter2"),
+            ("common/shared.yaml", "region: eu"),
+            (
+                "app.yaml",
+                "<!>:\n  auth:\n    - app\n    - ops\n  import:\n    common/secret: secret\n    common/shared: shared\n\
+                 password: ${secret.password}\nregion: ${shared.region}",
+            ),
+        ],
+        &[],
+    );
+    let lenient_state = local_git_state(&repo_url);
+    let mut strict_state = local_git_state(&repo_url);
+    strict_state.inherit_auth = true;
+    let params = || Params((commit.clone(), "json".to_string(), "app".to_string()));
+
+    let lenient = git_routes::get_data(token_header("app"), params(), Query(RenderQuery::default()), StateRef(&lenient_state)).await;
+    let strict_app = git_routes::get_data(token_header("app"), params(), Query(RenderQuery::default()), StateRef(&strict_state)).await;
+    let strict_ops = git_routes::get_data(token_header("ops"), params(), Query(RenderQuery::default()), StateRef(&strict_state)).await;
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert!(lenient.is_ok(), "Imports don't restrict access by default");
+    assert!(
+        matches!(strict_app, Err(GetError::Forbidden { path }) if path == "app"),
+        "The restricted import tightens access on the importer"
+    );
+    let body = strict_ops.expect("A token authorized for every restricted file reads the config").body;
+    assert!(body.contains("hunter2"), "Unrestricted imports don't need a grant");
+}
+
+#[tokio::test]
+async fn test_webhook_reloads_commits_when_signed() {
+    let upstream_dir = std::env::temp_dir().join(format!("konf-webhook-upstream-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&upstream_dir);
+    let upstream = git2::Repository::init(&upstream_dir).unwrap();
+    commit_files(&upstream, &[("app.yaml", "port: 8080")], &[]);
+    let branch = upstream.head().unwrap().shorthand().unwrap().to_string();
+    let repo_url = upstream_dir.to_str().unwrap().to_string();
+    let _ = std::fs::remove_dir_all(get_git_directory(&repo_url));
+    clone_or_update(&repo_url, &branch, &None).await.unwrap();
+
+    let mut state = local_git_state(&repo_url);
+    state.repo_config.branch = branch;
+    state.webhook_secret = Some("s3cret".to_string());
+    let pushed = commit_files(&upstream, &[("app.yaml", "port: 9090")], &[]);
+    let body = r#"{"ref":"refs/heads/main"}"#.to_string();
+    let post = |signature: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(webhook::SIGNATURE_HEADER, HeaderValue::from_str(signature).unwrap());
+        git_routes::webhook(headers, StateRef(&state), body.clone())
+    };
+
+    let forged = post(&webhook::sign("wrong", body.as_bytes())).await;
+    let still_stale = state.commits.load().contains(&pushed);
+    let signed = post(&webhook::sign("s3cret", body.as_bytes())).await;
+    let unsigned = git_routes::webhook(HeaderMap::new(), StateRef(&state), body.clone()).await;
+    let _ = std::fs::remove_dir_all(get_git_directory(&repo_url));
+    let _ = std::fs::remove_dir_all(&upstream_dir);
+
+    assert!(matches!(forged, Err(GetError::Unauthorized { .. })));
+    assert!(!still_stale, "A rejected webhook must not reload");
+    assert_eq!(signed.unwrap(), "OK");
+    assert!(state.commits.load().contains(&pushed), "The pushed commit should be known after the webhook");
+    assert!(matches!(unsigned, Err(GetError::Unauthorized { .. })));
+}
+
+#[tokio::test]
+async fn test_diff_compares_rendered_config_across_commits() {
+    let repo_url = format!("https://example.com/konf-diff-{}.git", std::process::id());
+    let dir = get_git_directory(&repo_url);
+    let _ = std::fs::remove_dir_all(&dir);
+    let repo = git2::Repository::init(&dir).unwrap();
+    let first = commit_files(
+        &repo,
+        &[
+            ("common/db.yaml", "host: localhost"),
+            ("app.yaml", "<!>:\n  import:\n    common/db: db\ndb_host: ${db.host}\nport: 8080"),
+        ],
+        &[],
+    );
+    let second = commit_files(&repo, &[("common/db.yaml", "host: db.internal")], &[]);
+    let state = local_git_state(&repo_url);
+    let diff = |from: &str, to: &str, path: &str| {
+        git_routes::get_diff(
+            token_header("admin"),
+            Params((from.to_string(), to.to_string(), "json".to_string(), path.to_string())),
+            StateRef(&state),
+        )
+    };
+
+    let changed = diff(&first, &second, "app").await;
+    let missing = diff(&first, &second, "nope").await;
+    let unknown_commit = diff(&first, &"0".repeat(40), "app").await;
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let changed = changed.expect("Diff should render both commits");
+    let body: serde_json::Value = serde_json::from_str(&changed.body).unwrap();
+    assert_eq!(body["changed"]["db_host"]["from"], "localhost");
+    assert_eq!(body["changed"]["db_host"]["to"], "db.internal");
+    assert_eq!(body["added"], serde_json::json!({}));
+    assert!(body["changed"].get("port").is_none());
+    assert!(matches!(missing, Err(GetError::ConfigNotFound { .. })));
+    assert!(matches!(unknown_commit, Err(GetError::CommitNotFound { .. })));
+}
+
+#[tokio::test]
+async fn test_diff_files_lists_changed_keys() {
+    let repo_url = format!("https://example.com/konf-diff-files-{}.git", std::process::id());
+    let dir = get_git_directory(&repo_url);
+    let _ = std::fs::remove_dir_all(&dir);
+    let repo = git2::Repository::init(&dir).unwrap();
+    let first = commit_files(&repo, &[("common/db.yaml", "host: localhost"), ("app.yaml", "port: 8080")], &[]);
+    let second = commit_files(&repo, &[("common/db.yaml", "host: db.internal")], &[]);
+    let state = local_git_state(&repo_url);
+    let query = |from: &str, to: &str| {
+        Query(git_routes::ChangesQuery {
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+    };
+
+    let changes = git_routes::get_changes(token_header("admin"), query(&first, &second), StateRef(&state)).await;
+    let unknown_commit = git_routes::get_changes(token_header("admin"), query(&first, &"0".repeat(40)), StateRef(&state)).await;
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let changes = changes.expect("Both commits should be compared").0;
+    assert_eq!(changes.modified, vec!["common/db"]);
+    assert!(changes.added.is_empty() && changes.removed.is_empty());
+    assert!(matches!(unknown_commit, Err(GetError::CommitNotFound { .. })));
+}
+
+#[tokio::test]
+async fn test_dag_cache_evicts_least_recently_served_commit() {
+    let repo_url = format!("https://example.com/konf-dag-cache-{}.git", std::process::id());
+    let dir = get_git_directory(&repo_url);
+    let _ = std::fs::remove_dir_all(&dir);
+    let repo = git2::Repository::init(&dir).unwrap();
+    let first = commit_files(&repo, &[("app.yaml", "port: 1")], &[]);
+    let second = commit_files(&repo, &[("app.yaml", "port: 2")], &[]);
+    let third = commit_files(&repo, &[("app.yaml", "port: 3")], &[]);
+    let mut state = local_git_state(&repo_url);
+    state.dag_cache = DagCacheLimits {
+        capacity: Some(2),
+        ttl: None,
+    };
+    let serve = |commit: &str| {
+        git_routes::get_data(
+            token_header("admin"),
+            Params((commit.to_string(), "json".to_string(), "app".to_string())),
+            Query(RenderQuery::default()),
+            StateRef(&state),
+        )
+    };
+
+    for commit in [&first, &second, &first, &third] {
+        serve(commit).await.expect("Config should render");
+        // Access times have a millisecond resolution
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert_eq!(state.dag.len(), 2);
+    assert!(state.dag.contains_key(&first), "Recently served commits are kept");
+    assert!(!state.dag.contains_key(&second), "The least recently served commit is evicted");
+    assert!(state.dag.contains_key(&third));
+}
+
+#[tokio::test]
+async fn test_concurrent_requests_share_one_dag_build() {
+    let repo_url = format!("https://example.com/konf-in-flight-{}.git", std::process::id());
+    let dir = get_git_directory(&repo_url);
+    let _ = std::fs::remove_dir_all(&dir);
+    let repo = git2::Repository::init(&dir).unwrap();
+    let commit = commit_files(&repo, &[("app.yaml", "port: 8080")], &[]);
+    let state = local_git_state(&repo_url);
+
+    let requests = (0..8).map(|_| {
+        git_routes::get_data(
+            token_header("admin"),
+            Params((commit.clone(), "json".to_string(), "app".to_string())),
+            Query(RenderQuery::default()),
+            StateRef(&state),
+        )
+    });
+    let responses = futures::future::join_all(requests).await;
+    let _ = std::fs::remove_dir_all(&dir);
+
+    for response in responses {
+        assert_eq!(response.expect("Config should render").body, r#"{"port":8080}"#);
+    }
+    assert_eq!(state.dag.len(), 1);
+    assert!(state.dag_builds.is_empty(), "Finished builds leave the in-flight map");
+}
+
 // ============================================================================
 // E2E tests (require valid credentials - run with --ignored)
 // ============================================================================
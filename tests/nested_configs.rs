@@ -38,6 +38,34 @@ async fn test_list_nested_files() {
     assert!(redis.is_ok(), "Should find common/redis");
 }
 
+#[tokio::test]
+async fn test_keys_under_directory() {
+    let dag = Dag::new(BasicFsFileProvider::new(example_folder()), create_multiloader())
+        .await
+        .expect("Failed to create DAG");
+
+    assert_eq!(dag.keys_under("services/api"), vec!["services/api/config", "services/api/config_v2"]);
+    assert_eq!(dag.keys_under("/services/"), vec![
+        "services/api/config",
+        "services/api/config_v2",
+        "services/worker/config",
+    ]);
+    assert!(dag.keys_under("services/ap").is_empty(), "Prefixes match whole folder names");
+}
+
+#[tokio::test]
+async fn test_transitive_imports_follow_the_graph() {
+    let fs = konf_provider::fs::memory::MemoryFileProvider::new()
+        .with_file("app.yaml", "<!>:\n  import:\n    services/db:\nkey: value")
+        .with_file("services/db.yaml", "<!>:\n  import:\n    ../common/secret:\n    missing:\nkey: value")
+        .with_file("common/secret.yaml", "<!>:\n  import:\n    app:\nkey: value");
+    let dag = Dag::new(fs, create_multiloader()).await.expect("Failed to create DAG");
+
+    assert_eq!(dag.transitive_imports("app"), vec!["common/secret", "services/db"]);
+    assert_eq!(dag.transitive_imports("services/db"), vec!["app", "common/secret"]);
+    assert!(dag.transitive_imports("unknown").is_empty());
+}
+
 #[tokio::test]
 async fn test_nested_imports() {
     let provider = BasicFsFileProvider::new(example_folder());
@@ -326,3 +354,637 @@ async fn test_new_import_format_with_relative_paths() {
         panic!("Config should be a mapping");
     }
 }
+
+#[tokio::test]
+async fn test_resolve_key_trailing_slash() {
+    let provider = BasicFsFileProvider::new(example_folder());
+    let dag = Dag::new(provider, create_multiloader())
+        .await
+        .expect("Failed to create DAG");
+
+    assert_eq!(dag.resolve_key("common/database/", false).unwrap(), "common/database");
+    assert_eq!(dag.resolve_key("/common//database", false).unwrap(), "common/database");
+}
+
+#[tokio::test]
+async fn test_resolve_key_case_variations() {
+    let provider = BasicFsFileProvider::new(example_folder());
+    let dag = Dag::new(provider, create_multiloader())
+        .await
+        .expect("Failed to create DAG");
+
+    // Case-sensitive lookups miss, but suggest the right key
+    let suggestions = dag.resolve_key("Common/Database", false).unwrap_err();
+    assert_eq!(suggestions.first().map(String::as_str), Some("common/database"));
+
+    // Case-insensitive lookups resolve to the loaded key
+    assert_eq!(dag.resolve_key("Common/Database/", true).unwrap(), "common/database");
+}
+
+#[tokio::test]
+async fn test_resolve_key_suggests_near_matches() {
+    let provider = BasicFsFileProvider::new(example_folder());
+    let dag = Dag::new(provider, create_multiloader())
+        .await
+        .expect("Failed to create DAG");
+
+    let suggestions = dag.resolve_key("common/databse", true).unwrap_err();
+    assert_eq!(suggestions, vec!["common/database".to_string()]);
+
+    let suggestions = dag.resolve_key("totally/unrelated", true).unwrap_err();
+    assert!(suggestions.is_empty());
+}
+
+#[tokio::test]
+async fn test_example_import_graph_is_valid() {
+    let provider = BasicFsFileProvider::new(example_folder());
+    let dag = Dag::new(provider, create_multiloader())
+        .await
+        .expect("Failed to create DAG");
+
+    let report = dag.check_graph(true);
+    assert!(report.is_ok(), "Unexpected problems: {:?}", report.problems);
+    assert!(report.imports > 0);
+}
+
+#[tokio::test]
+async fn test_export_writes_nested_files() {
+    use konf_provider::export::export_all;
+    use konf_provider::writer::{MultiWriter, json::JsonWriter};
+
+    let provider = BasicFsFileProvider::new(example_folder());
+    let dag = Dag::new(provider, create_multiloader())
+        .await
+        .expect("Failed to create DAG");
+    let writer = MultiWriter::new(vec![JsonWriter::new_boxed()]);
+    let out = std::env::temp_dir().join(format!("konf-export-{}", std::process::id()));
+
+    let summary = export_all(&dag, &writer, "json", &out, false)
+        .await
+        .expect("Export failed");
+
+    assert!(summary.failed.is_empty(), "Unexpected failures: {:?}", summary.failed);
+    assert_eq!(summary.written.len(), dag.keys().len());
+    assert!(out.join("services/api/config.json").is_file());
+    assert!(out.join("common/database.json").is_file());
+
+    let unknown = export_all(&dag, &writer, "xml", &out, false).await;
+    assert!(unknown.is_err());
+
+    let _ = std::fs::remove_dir_all(&out);
+}
+
+#[tokio::test]
+async fn test_validate_all_example_folder() {
+    use konf_provider::validate::validate_all;
+
+    let dag = Dag::new(BasicFsFileProvider::new(example_folder()), create_multiloader())
+        .await
+        .expect("Failed to create DAG");
+
+    let report = validate_all(&dag, &dag.keys()).await;
+    assert!(report.is_ok(), "Unexpected problems: {:?}", report.invalid);
+    assert_eq!(report.valid, dag.keys());
+}
+
+#[tokio::test]
+async fn test_validate_all_reports_each_broken_config() {
+    use konf_provider::validate::validate_all;
+
+    let fs = konf_provider::fs::memory::MemoryFileProvider::new()
+        .with_file("db.yaml", "host: localhost")
+        .with_file("app.yaml", "<!>:\n  import:\n    db:\nhost: ${db.host}")
+        .with_file("typo.yaml", "<!>:\n  import:\n    db:\nhost: ${db.hots}")
+        .with_file("ghost.yaml", "<!>:\n  import:\n    missing:\nkey: value")
+        .with_file("ping.yaml", "<!>:\n  import:\n    pong:\nkey: value")
+        .with_file("pong.yaml", "<!>:\n  import:\n    ping:\nkey: value");
+    let dag = Dag::new(fs, create_multiloader()).await.expect("Failed to create DAG");
+
+    let report = validate_all(&dag, &dag.keys()).await;
+
+    assert!(!report.is_ok());
+    assert_eq!(report.valid, vec!["app", "db"]);
+    let invalid: Vec<&str> = report.invalid.iter().map(|(key, _)| key.as_str()).collect();
+    assert_eq!(invalid, vec!["ghost", "ping", "pong", "typo"]);
+    let problems = |key: &str| report.invalid.iter().find(|(k, _)| k == key).unwrap().1.join("\n");
+    assert!(problems("ghost").contains("missing"), "{}", problems("ghost"));
+    assert!(problems("ping").contains("circular import: ping -> pong -> ping"), "{}", problems("ping"));
+    assert_eq!(problems("typo"), "unresolved reference '${db.hots}'");
+}
+
+#[tokio::test]
+async fn test_example_graph_as_dot() {
+    let dag = Dag::new(BasicFsFileProvider::new(example_folder()), create_multiloader())
+        .await
+        .expect("Failed to create DAG");
+
+    let dot = dag.to_dot();
+
+    assert!(dot.starts_with("digraph konf {\n"), "{}", dot);
+    assert!(dot.contains("\"services/api/config\" -> \"common/database\";"), "{}", dot);
+    assert!(dot.contains("\"services/api/config\" -> \"common/redis\";"), "{}", dot);
+    assert!(dot.contains("    \"common/redis\";\n"), "every file is a node: {}", dot);
+    assert!(!dot.contains("color=red"), "the example has no cycle: {}", dot);
+}
+
+/// Span recorded by [`SpanRecorder`]: name, fields and parent span name.
+type RecordedSpan = (String, std::collections::HashMap<String, String>, Option<String>);
+
+/// Layer recording every span created while it is the default subscriber.
+#[derive(Clone, Default)]
+struct SpanRecorder(Arc<std::sync::Mutex<std::collections::BTreeMap<u64, RecordedSpan>>>);
+
+struct FieldVisitor<'a>(&'a mut std::collections::HashMap<String, String>);
+
+impl tracing::field::Visit for FieldVisitor<'_> {
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name().to_string(), format!("{value:?}"));
+    }
+}
+
+impl<S> tracing_subscriber::Layer<S> for SpanRecorder
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    fn on_new_span(
+        &self,
+        attrs: &tracing::span::Attributes<'_>,
+        id: &tracing::span::Id,
+        ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let mut fields = std::collections::HashMap::new();
+        attrs.record(&mut FieldVisitor(&mut fields));
+        let parent = ctx.span(id).and_then(|span| span.parent()).map(|parent| parent.name().to_string());
+        self.0
+            .lock()
+            .unwrap()
+            .insert(id.into_u64(), (attrs.metadata().name().to_string(), fields, parent));
+    }
+
+    fn on_record(
+        &self,
+        id: &tracing::span::Id,
+        values: &tracing::span::Record<'_>,
+        _ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        if let Some((_, fields, _)) = self.0.lock().unwrap().get_mut(&id.into_u64()) {
+            values.record(&mut FieldVisitor(fields));
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_render_spans_follow_imports() {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let fs = konf_provider::fs::memory::MemoryFileProvider::new()
+        .with_file("db.yaml", "host: localhost")
+        .with_file("app.yaml", "<!>:\n  import:\n    db:\nhost: ${db.host}");
+    let dag = Dag::new(fs, create_multiloader()).await.expect("Failed to create DAG");
+
+    let recorder = SpanRecorder::default();
+    let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(recorder.clone()));
+    dag.get_rendered("app").await.expect("Render failed");
+
+    let spans: Vec<RecordedSpan> = recorder.0.lock().unwrap().values().cloned().collect();
+    let render = |file: &str| {
+        spans
+            .iter()
+            .find(|(name, fields, _)| name == "render" && fields.get("file").map(String::as_str) == Some(file))
+            .unwrap_or_else(|| panic!("no render span for {file}: {spans:?}"))
+    };
+    assert_eq!(render("app").1.get("deps").map(String::as_str), Some("1"));
+    assert_eq!(render("app").2, None);
+    assert_eq!(render("db").1.get("deps").map(String::as_str), Some("0"));
+    assert_eq!(render("db").2.as_deref(), Some("render"), "imports render under their importer");
+}
+
+fn broken_reference_provider() -> konf_provider::fs::memory::MemoryFileProvider {
+    konf_provider::fs::memory::MemoryFileProvider::new()
+        .with_file("db.yaml", "host: localhost\nport: 5432")
+        .with_file(
+            "dashboard.yaml",
+            "<!>:\n  import:\n    db: db\n    ghost: ghost\nhost: ${db.host}\nurl: \"postgres://${db.host}:${db.port}\"\nghost_host: ${ghost.host}\ntypo: ${db.hots}\n",
+        )
+}
+
+#[tokio::test]
+async fn test_isolated_render_serves_healthy_keys() {
+    use konf_provider::render::ERROR_MARKER_KEY;
+
+    let dag = Dag::new(broken_reference_provider(), create_multiloader())
+        .await
+        .expect("Failed to create DAG");
+
+    // A missing import fails the whole normal render
+    assert!(dag.get_rendered("dashboard").await.is_err());
+
+    let (rendered, failures) = dag
+        .get_rendered_isolated("dashboard")
+        .await
+        .expect("Isolated render should succeed");
+
+    assert_eq!(rendered.get("host"), Some(&Value::String("localhost".to_string())));
+    assert_eq!(
+        rendered.get("url"),
+        Some(&Value::String("postgres://localhost:5432".to_string()))
+    );
+    assert!(rendered.get("<!>").is_none());
+
+    let failed_keys: Vec<&str> = failures.iter().map(|f| f.key.as_str()).collect();
+    assert_eq!(failed_keys, vec!["ghost_host", "typo"]);
+    assert!(failures[0].reason.contains("ghost"), "{}", failures[0].reason);
+    assert!(failures[1].reason.contains("db.hots"), "{}", failures[1].reason);
+
+    let marker = rendered.get("typo").and_then(|v| v.get(ERROR_MARKER_KEY));
+    assert!(matches!(marker, Some(Value::String(_))));
+}
+
+#[tokio::test]
+async fn test_isolated_render_without_failures() {
+    let provider = BasicFsFileProvider::new(example_folder());
+    let dag = Dag::new(provider, create_multiloader())
+        .await
+        .expect("Failed to create DAG");
+
+    let (isolated, failures) = dag.get_rendered_isolated("services/api/config").await.unwrap();
+    let rendered = dag.get_rendered("services/api/config").await.unwrap();
+
+    assert!(failures.is_empty(), "Unexpected failures: {:?}", failures);
+    assert_eq!(isolated, rendered);
+}
+
+#[tokio::test]
+async fn test_find_orphans_reports_unused_file() {
+    let provider = konf_provider::fs::memory::MemoryFileProvider::new()
+        .with_file("common/database.yaml", "host: localhost")
+        .with_file("common/legacy.yaml", "host: old-host")
+        .with_file(
+            "services/api/config.yaml",
+            "<!>:\n  import:\n    common/database: db\nurl: ${db.host}",
+        )
+        .with_file("tools/dashboard.yaml", "<!>:\n  entrypoint: true\ntitle: ops");
+    let dag = Dag::new(provider, create_multiloader())
+        .await
+        .expect("Failed to create DAG");
+
+    let report = dag.find_orphans(&["services/".to_string()]);
+
+    assert_eq!(report.orphans, vec!["common/legacy"]);
+    assert_eq!(report.entrypoints, vec!["services/api/config", "tools/dashboard"]);
+}
+
+#[tokio::test]
+async fn test_conditional_import_active_only_under_profile() {
+    use konf_provider::imports::ImportContext;
+
+    let provider = || {
+        konf_provider::fs::memory::MemoryFileProvider::new()
+            .with_file("common/prod-db.yaml", "host: prod.db.internal")
+            .with_file("common/dev-db.yaml", "host: localhost")
+            .with_file(
+                "services/api.yaml",
+                "<!>:\n  import:\n    common/prod-db: { alias: db, when: \"profile == prod\" }\n    common/dev-db: { alias: db, when: \"profile != prod\" }\nhost: ${db.host}",
+            )
+    };
+
+    let prod = Dag::with_context(provider(), create_multiloader(), ImportContext::new(Some("prod".to_string())))
+        .await
+        .expect("Failed to create DAG");
+    let rendered = prod.get_rendered("services/api").await.expect("Render failed");
+    assert_eq!(rendered.get("host"), Some(&Value::String("prod.db.internal".to_string())));
+    assert!(prod.check_graph(true).is_ok());
+
+    let default = Dag::new(provider(), create_multiloader())
+        .await
+        .expect("Failed to create DAG");
+    let rendered = default.get_rendered("services/api").await.expect("Render failed");
+    assert_eq!(rendered.get("host"), Some(&Value::String("localhost".to_string())));
+}
+
+#[tokio::test]
+async fn test_schema_validation_requires_integer_port() {
+    let schema = r#"{
+        "type": "object",
+        "properties": { "port": { "type": "integer" } },
+        "required": ["port"]
+    }"#;
+    let provider = konf_provider::fs::memory::MemoryFileProvider::new()
+        .with_file("good.yaml", "host: localhost\nport: 5432")
+        .with_file("good.schema.json", schema)
+        .with_file("bad.yaml", "host: localhost\nport: \"5432\"")
+        .with_file("bad.schema.json", schema);
+    let dag = Dag::new(provider, create_multiloader())
+        .await
+        .expect("Failed to create DAG");
+
+    // Schema files validate their config instead of being loaded as configs
+    assert_eq!(dag.keys(), vec!["bad", "good"]);
+    assert!(dag.has_schema("good"));
+
+    let good = dag.get_rendered("good").await.expect("Render failed");
+    assert!(dag.schema_violations("good", &good).is_empty());
+
+    let bad = dag.get_rendered("bad").await.expect("Render failed");
+    let errors = dag.schema_violations("bad", &bad);
+    assert_eq!(errors.len(), 1, "{errors:?}");
+    assert!(errors[0].starts_with("/port:"), "{errors:?}");
+}
+
+#[tokio::test]
+async fn test_pipe_functions_render_end_to_end() {
+    let provider = konf_provider::fs::memory::MemoryFileProvider::new()
+        .with_file("common/database.yaml", "password: hunter2\nname: \"  app  \"")
+        .with_file(
+            "app.yaml",
+            "<!>:\n  import:\n    common/database: db\nsecret: ${db.password | base64}\nlabel: \"db-${db.name | trim | upper}\"\n",
+        )
+        .with_file(
+            "broken.yaml",
+            "<!>:\n  import:\n    common/database: db\nsecret: ${db.password | rot13}\n",
+        );
+    let dag = Dag::new(provider, create_multiloader())
+        .await
+        .expect("Failed to create DAG");
+
+    let rendered = dag.get_rendered("app").await.unwrap();
+    assert_eq!(rendered.get("secret"), Some(&Value::String("aHVudGVyMg==".to_string())));
+    assert_eq!(rendered.get("label"), Some(&Value::String("db-APP".to_string())));
+
+    // An unknown function fails the render instead of leaving the placeholder
+    let error = dag.get_rendered("broken").await.unwrap_err().to_string();
+    assert!(error.contains("unknown function: rot13"), "{error}");
+}
+
+#[tokio::test]
+async fn test_render_from_tar_archive() {
+    use konf_provider::fs::archive::ArchiveFileProvider;
+
+    let mut builder = tar::Builder::new(Vec::new());
+    for (path, content) in [
+        ("common/database.yaml", "host: db.internal\nport: 5432"),
+        ("services/api.yaml", "<!>:\n  import:\n    ../common/database: db\nurl: \"postgres://${db.host}:${db.port}\"\n"),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, path, content.as_bytes()).unwrap();
+    }
+    let bytes = builder.into_inner().unwrap();
+
+    let provider = ArchiveFileProvider::from_bytes(&bytes).expect("Failed to read archive");
+    let dag = Dag::new(provider, create_multiloader())
+        .await
+        .expect("Failed to create DAG");
+
+    assert_eq!(dag.keys(), vec!["common/database", "services/api"]);
+    let rendered = dag.get_rendered("services/api").await.unwrap();
+    assert_eq!(
+        rendered.get("url"),
+        Some(&Value::String("postgres://db.internal:5432".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn test_render_mixed_yaml_and_json() {
+    use konf_provider::{fs::memory::MemoryFileProvider, loaders::json::JsonLoader};
+
+    let fs = MemoryFileProvider::new()
+        .with_file("common/database.json", r#"{"host": "db.internal", "port": 5432}"#)
+        .with_file("services/api.yaml", "<!>:\n  import:\n    ../common/database: db\nurl: \"postgres://${db.host}:${db.port}\"\n")
+        .with_file(
+            "services/worker.json",
+            r#"{"<!>": {"import": {"./api": "api"}}, "upstream": "${api.url}"}"#,
+        );
+    let multiloader = Arc::new(MultiLoader::new(vec![Box::new(YamlLoader {}), Box::new(JsonLoader {})]));
+    let dag = Dag::new(fs, multiloader).await.expect("Failed to create DAG");
+
+    assert_eq!(dag.keys(), vec!["common/database", "services/api", "services/worker"]);
+    let api = dag.get_rendered("services/api").await.unwrap();
+    assert_eq!(api.get("url"), Some(&Value::String("postgres://db.internal:5432".to_string())));
+    let worker = dag.get_rendered("services/worker").await.unwrap();
+    assert_eq!(worker.get("upstream"), api.get("url"));
+}
+
+#[tokio::test]
+async fn test_circular_imports_fail_instead_of_hanging() {
+    let provider = konf_provider::fs::memory::MemoryFileProvider::new()
+        .with_file("a.yaml", "<!>:\n  import:\n    b: b\nname: a\nother: ${b.name}\n")
+        .with_file("b.yaml", "<!>:\n  import:\n    a: a\nname: b\nother: ${a.name}\n")
+        .with_file("c.yaml", "<!>:\n  import:\n    a: a\nvalue: ${a.name}\n");
+    let dag = Dag::new(provider, create_multiloader())
+        .await
+        .expect("Failed to create DAG");
+
+    let render = |key: &'static str| {
+        let dag = dag.clone();
+        async move {
+            tokio::time::timeout(std::time::Duration::from_secs(5), dag.get_rendered(key))
+                .await
+                .expect("Rendering a cycle must not hang")
+                .unwrap_err()
+                .to_string()
+        }
+    };
+
+    assert_eq!(render("a").await, "circular import: a -> b -> a");
+    assert_eq!(render("b").await, "circular import: b -> a -> b");
+    // A file importing into a cycle names the cycle, not its own path
+    assert_eq!(render("c").await, "circular import: a -> b -> a");
+}
+
+#[tokio::test]
+async fn test_strict_mode_fails_on_typo_reference() {
+    use konf_provider::render_helper::UnresolvedPolicy;
+
+    let folder = std::env::temp_dir().join(format!("konf-strict-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&folder);
+    std::fs::create_dir_all(&folder).unwrap();
+    std::fs::write(folder.join("db.yaml"), "host: localhost\n").unwrap();
+    std::fs::write(
+        folder.join("app.yaml"),
+        "<!>:\n  import:\n    db:\nhost: ${db.host}\nurl: http://${db.hots}\n",
+    )
+    .unwrap();
+
+    let lenient = Dag::new(BasicFsFileProvider::new(folder.clone()), create_multiloader())
+        .await
+        .expect("Failed to create DAG");
+    let strict = lenient.clone().with_unresolved_policy(UnresolvedPolicy::Error);
+    let kept = lenient.get_rendered("app").await;
+    let failed = strict.get_rendered("app").await;
+    let _ = std::fs::remove_dir_all(&folder);
+
+    let kept = kept.expect("Lenient mode should keep the placeholder");
+    assert_eq!(kept.get("url"), Some(&Value::String("http://${db.hots}".to_string())));
+    let error = failed.expect_err("Strict mode should fail").to_string();
+    assert!(error.contains("${db.hots}"), "{error}");
+    assert!(!error.contains("${db.host}"), "{error}");
+}
+
+#[tokio::test]
+async fn test_env_references_need_an_allowed_prefix() {
+    use konf_provider::render_helper::EnvAccess;
+
+    let folder = std::env::temp_dir().join(format!("konf-env-access-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&folder);
+    std::fs::create_dir_all(&folder).unwrap();
+    std::fs::write(folder.join("app.yaml"), "level: ${env.APP_DAG_LEVEL}\n").unwrap();
+    std::fs::write(folder.join("leak.yaml"), "secret: ${env.KONF_DAG_SECRET}\n").unwrap();
+    std::fs::write(folder.join("db.yaml"), "host: localhost\n").unwrap();
+    std::fs::write(folder.join("shadow.yaml"), "<!>:\n  import:\n    db: env\nhost: ${env.host}\n").unwrap();
+    // SAFETY: the variable names are unique to this test
+    unsafe {
+        std::env::set_var("APP_DAG_LEVEL", "debug");
+        std::env::set_var("KONF_DAG_SECRET", "s3cret");
+    }
+
+    let provider = || BasicFsFileProvider::new(folder.clone());
+    let closed = Dag::new(provider(), create_multiloader()).await.expect("Failed to create DAG");
+    let open = Dag::new(provider(), create_multiloader())
+        .await
+        .expect("Failed to create DAG")
+        .with_env_access(EnvAccess::with_prefixes([String::new()]));
+    let denied = closed.get_rendered("app").await;
+    let allowed = open.get_rendered("app").await;
+    let leak = open.get_rendered("leak").await;
+    let shadow = open.get_rendered("shadow").await;
+    let _ = std::fs::remove_dir_all(&folder);
+    unsafe {
+        std::env::remove_var("APP_DAG_LEVEL");
+        std::env::remove_var("KONF_DAG_SECRET");
+    }
+
+    assert!(denied.is_err(), "no variable is readable without --env-prefix");
+    assert_eq!(allowed.unwrap().get("level"), Some(&Value::String("debug".to_string())));
+    let error = leak.expect_err("KONF_* variables are never readable").to_string();
+    assert!(!error.contains("s3cret"), "{error}");
+    let error = shadow.expect_err("an import aliased env is rejected").to_string();
+    assert!(error.contains("reserved for environment variables"), "{error}");
+}
+
+#[tokio::test]
+async fn test_escaped_placeholder_survives_imports() {
+    use konf_provider::render_helper::UnresolvedPolicy;
+
+    let folder = std::env::temp_dir().join(format!("konf-escape-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&folder);
+    std::fs::create_dir_all(&folder).unwrap();
+    std::fs::write(folder.join("base.yaml"), "template: $${HOME}/data\n").unwrap();
+    std::fs::write(
+        folder.join("app.yaml"),
+        "<!>:\n  import:\n    base:\ntemplate: ${base.template}\nshell: echo $${USER}\n",
+    )
+    .unwrap();
+
+    let dag = Dag::new(BasicFsFileProvider::new(folder.clone()), create_multiloader())
+        .await
+        .expect("Failed to create DAG")
+        .with_unresolved_policy(UnresolvedPolicy::Error);
+    let rendered = dag.get_rendered("app").await;
+    let (isolated, failures) = dag.get_rendered_isolated("app").await.expect("Isolated render should succeed");
+    let _ = std::fs::remove_dir_all(&folder);
+
+    // Unescaped once, in the final output only, and never taken for a reference
+    let rendered = rendered.expect("Escaped placeholders are not unresolved references");
+    assert_eq!(rendered.get("template"), Some(&Value::String("${HOME}/data".to_string())));
+    assert_eq!(rendered.get("shell"), Some(&Value::String("echo ${USER}".to_string())));
+    assert!(failures.is_empty(), "{failures:?}");
+    assert_eq!(isolated, rendered);
+}
+
+#[tokio::test]
+async fn test_watch_reloads_dag_when_a_file_changes() {
+    let folder = std::env::temp_dir().join(format!("konf-watch-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&folder);
+    std::fs::create_dir_all(&folder).unwrap();
+    std::fs::write(folder.join("app.yaml"), "port: 8080\n").unwrap();
+
+    let dag = Dag::new(BasicFsFileProvider::new(folder.clone()), create_multiloader())
+        .await
+        .expect("Failed to create DAG");
+    let watched = dag.clone();
+    let _watcher = konf_provider::watch::watch_folder(&folder, std::time::Duration::from_millis(50), move |_| {
+        let dag = watched.clone();
+        async move { dag.reload().await.expect("Reload should succeed") }
+    })
+    .expect("Failed to watch folder");
+    assert_eq!(dag.get_rendered("app").await.unwrap().get("port"), Some(&Value::Int(8080)));
+
+    std::fs::write(folder.join("app.yaml"), "port: 9090\n").unwrap();
+    let mut port = None;
+    for _ in 0..50 {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        port = dag.get_rendered("app").await.unwrap().get("port").cloned();
+        if port == Some(Value::Int(9090)) {
+            break;
+        }
+    }
+    let _ = std::fs::remove_dir_all(&folder);
+
+    assert_eq!(port, Some(Value::Int(9090)), "The DAG should see the new content");
+}
+
+#[tokio::test]
+async fn test_extends_deep_merges_imported_configs() {
+    let provider = konf_provider::fs::memory::MemoryFileProvider::new()
+        .with_file(
+            "base.yaml",
+            "server:\n  host: 0.0.0.0\n  port: 80\n  tls:\n    enabled: false\n    cert: /etc/cert.pem\nlogging: verbose\nhosts: [a, b]\n",
+        )
+        .with_file("prod.yaml", "server:\n  tls:\n    enabled: true\nregion: eu\n")
+        .with_file(
+            "app.yaml",
+            "<!>:\n  import:\n    base: base\n    prod: prod\n  extends:\n    - base\n    - prod\nserver:\n  port: 443\nlogging:\n  level: warn\nhosts: [c]\nname: ${prod.region}-app\n",
+        )
+        .with_file("orphan.yaml", "<!>:\n  extends:\n    - base\nport: 1\n");
+    let dag = Dag::new(provider, create_multiloader())
+        .await
+        .expect("Failed to create DAG");
+
+    let rendered = dag.get_rendered("app").await.expect("app should render");
+    let server = rendered.get("server").unwrap();
+
+    // Nested override: keys merge recursively, later sources winning
+    assert_eq!(server.get("host"), Some(&Value::String("0.0.0.0".to_string())));
+    assert_eq!(server.get("port"), Some(&Value::Int(443)));
+    let tls = server.get("tls").unwrap();
+    assert_eq!(tls.get("enabled"), Some(&Value::Boolean(true)));
+    assert_eq!(tls.get("cert"), Some(&Value::String("/etc/cert.pem".to_string())));
+    // A mapping replaces an inherited scalar
+    assert_eq!(
+        rendered.get("logging").and_then(|l| l.get("level")),
+        Some(&Value::String("warn".to_string()))
+    );
+    // Sequences are replaced, not concatenated
+    assert_eq!(rendered.get("hosts"), Some(&Value::Sequence(vec![Value::String("c".to_string())])));
+    assert_eq!(rendered.get("region"), Some(&Value::String("eu".to_string())));
+    assert_eq!(rendered.get("name"), Some(&Value::String("eu-app".to_string())));
+    assert!(rendered.get("<!>").is_none());
+
+    let (isolated, failures) = dag.get_rendered_isolated("app").await.unwrap();
+    assert!(failures.is_empty(), "{failures:?}");
+    assert_eq!(isolated, rendered);
+
+    let err = dag.get_rendered("orphan").await.unwrap_err();
+    assert!(err.to_string().contains("not an import alias"), "{err}");
+}
+
+#[tokio::test]
+async fn test_extends_scalar_overrides_inherited_mapping() {
+    let provider = konf_provider::fs::memory::MemoryFileProvider::new()
+        .with_file("base.yaml", "database:\n  host: localhost\n  port: 5432\n")
+        .with_file("app.yaml", "<!>:\n  import:\n    base:\n  extends:\n    - base\ndatabase: sqlite://memory\n");
+    let dag = Dag::new(provider, create_multiloader())
+        .await
+        .expect("Failed to create DAG");
+
+    let rendered = dag.get_rendered("app").await.unwrap();
+
+    assert_eq!(rendered.get("database"), Some(&Value::String("sqlite://memory".to_string())));
+}
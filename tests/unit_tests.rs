@@ -3,10 +3,18 @@
 //! Run with: `cargo test --test unit_tests`
 
 use std::collections::HashMap;
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
 
 use konf_provider::{
+    diff::diff_values,
+    in_flight::InFlight,
     loader::{Loader, MultiLoader},
-    loaders::yaml::YamlLoader,
+    loaders::{json::JsonLoader, yaml::YamlLoader},
+    schema::infer_schema,
+    utils::{with_render_meta, RENDER_META_KEY},
     writer::{
         json::JsonWriter,
         yaml::YamlWriter,
@@ -14,6 +22,14 @@ use konf_provider::{
         env::EnvVarWriter,
         properties::PropertiesWriter,
         docker_env::DockerEnvVarWriter,
+        helm::HelmValuesWriter,
+        ini::IniWriter,
+        xml::XmlWriter,
+        shell::ShellWriter,
+        k8s::{resource_name, ConfigMapWriter, SecretWriter},
+        bundle::{env_bundle, parse_bundle_spec, BundleError, BundlePart},
+        MultiWriter,
+        SequenceEncoding,
         ValueWriter,
     },
     Value,
@@ -59,6 +75,148 @@ fn test_value_as_str() {
     assert_eq!(value.as_str(), None);
 }
 
+#[test]
+fn test_value_get_path_nested_mapping() {
+    let loader = YamlLoader {};
+    let value = loader
+        .load("database:\n  connection:\n    host: localhost\n    port: 5432")
+        .unwrap();
+
+    assert_eq!(
+        value.get_path("database.connection.host"),
+        Some(&Value::String("localhost".to_string()))
+    );
+    assert_eq!(value.get_path("database.connection.port"), Some(&Value::Int(5432)));
+    assert!(value.get_path("database").unwrap().as_mapping().is_some());
+}
+
+#[test]
+fn test_value_get_path_indexes_sequences() {
+    let loader = YamlLoader {};
+    let value = loader
+        .load("servers:\n  - host: a.internal\n  - host: b.internal")
+        .unwrap();
+
+    assert_eq!(
+        value.get_path("servers.1.host"),
+        Some(&Value::String("b.internal".to_string()))
+    );
+    assert!(value.get_path("servers.2.host").is_none());
+    assert!(value.get_path("servers.first").is_none());
+}
+
+#[test]
+fn test_value_get_path_missing_key() {
+    let loader = YamlLoader {};
+    let value = loader.load("database:\n  host: localhost").unwrap();
+
+    assert!(value.get_path("database.port").is_none());
+    assert!(value.get_path("cache.host").is_none());
+    // Scalars have no children
+    assert!(value.get_path("database.host.name").is_none());
+}
+
+#[test]
+fn test_value_merge_overrides_nested_key() {
+    let loader = YamlLoader {};
+    let mut base = loader
+        .load("service:\n  name: api\n  port: 8080\nlevel: info")
+        .unwrap();
+    let overlay = loader.load("service:\n  port: 9090").unwrap();
+
+    base.merge(&overlay);
+
+    let service = base.get("service").unwrap();
+    assert_eq!(service.get("port"), Some(&Value::Int(9090)));
+    assert_eq!(service.get("name"), Some(&Value::String("api".to_string())));
+    assert_eq!(base.get("level"), Some(&Value::String("info".to_string())));
+}
+
+#[test]
+fn test_value_merge_replaces_non_mappings() {
+    let loader = YamlLoader {};
+    let mut base = loader.load("items: [a, b, c]\nnested:\n  key: value").unwrap();
+    let overlay = loader.load("items: [d]\nnested: scalar").unwrap();
+
+    base.merge(&overlay);
+
+    assert_eq!(
+        base.get("items"),
+        Some(&Value::Sequence(vec![Value::String("d".to_string())]))
+    );
+    assert_eq!(base.get("nested"), Some(&Value::String("scalar".to_string())));
+}
+
+#[test]
+fn test_diff_values_reports_leaves_by_path() {
+    let loader = YamlLoader {};
+    let from = loader
+        .load("db:\n  host: a\n  port: 5432\nhosts: [x, y]\nlegacy:\n  flag: true")
+        .unwrap();
+    let to = loader
+        .load("db:\n  host: b\n  port: 5432\n  pool: 10\nhosts: [x, y]\ncache: {}")
+        .unwrap();
+
+    let diff = diff_values(&from, &to);
+
+    assert_eq!(
+        diff.changed.get("db.host"),
+        Some(&(Value::String("a".to_string()), Value::String("b".to_string())))
+    );
+    assert_eq!(diff.changed.len(), 1, "{diff:?}");
+    assert_eq!(diff.added.get("db.pool"), Some(&Value::Int(10)));
+    assert_eq!(diff.added.get("cache"), Some(&Value::Mapping(HashMap::new())));
+    assert_eq!(diff.removed.get("legacy.flag"), Some(&Value::Boolean(true)));
+    assert!(diff_values(&from, &from).is_empty());
+}
+
+/// The value of `{"name": "app", "port": 8080, "ratio": 0.5, "debug": false, "tags": ["a", null], "db": {"host": "x"}}`
+fn deserialized_value() -> Value {
+    let mut db = HashMap::new();
+    db.insert("host".to_string(), Value::String("x".to_string()));
+
+    let mut map = HashMap::new();
+    map.insert("name".to_string(), Value::String("app".to_string()));
+    map.insert("port".to_string(), Value::Int(8080));
+    map.insert("ratio".to_string(), Value::Float(0.5));
+    map.insert("debug".to_string(), Value::Boolean(false));
+    map.insert("tags".to_string(), Value::Sequence(vec![Value::String("a".to_string()), Value::Null]));
+    map.insert("db".to_string(), Value::Mapping(db));
+    Value::Mapping(map)
+}
+
+#[test]
+fn test_value_deserializes_from_json() {
+    let json = r#"{"name": "app", "port": 8080, "ratio": 0.5, "debug": false, "tags": ["a", null], "db": {"host": "x"}}"#;
+
+    let value: Value = serde_json::from_str(json).unwrap();
+
+    assert_eq!(value, deserialized_value());
+}
+
+#[test]
+fn test_value_deserializes_from_yaml() {
+    let yaml = "name: app\nport: 8080\nratio: 0.5\ndebug: false\ntags: [a, ~]\ndb:\n  host: !secret x\n";
+
+    let value: Value = serde_yaml::from_str(yaml).unwrap();
+
+    assert_eq!(value, deserialized_value());
+    assert_eq!(value, YamlLoader {}.load(yaml).unwrap(), "Should match the YAML loader");
+}
+
+#[test]
+fn test_value_deserializes_yaml_keys_like_the_loader() {
+    let yaml = "1: one\ntrue: yes\n[a]: skipped\n";
+
+    let value: Value = serde_yaml::from_str(yaml).unwrap();
+
+    let mut map = HashMap::new();
+    map.insert("1".to_string(), Value::String("one".to_string()));
+    map.insert("true".to_string(), Value::String("yes".to_string()));
+    assert_eq!(value, Value::Mapping(map));
+    assert_eq!(value, YamlLoader {}.load(yaml).unwrap());
+}
+
 // ============================================================================
 // Loader tests
 // ============================================================================
@@ -82,6 +240,34 @@ boolean: true
     assert_eq!(value.get("key").unwrap().as_str(), Some(&"value".to_string()));
 }
 
+#[test]
+fn test_json_loader_nested() {
+    let loader = JsonLoader {};
+    assert_eq!(loader.ext(), "json");
+
+    let value = loader
+        .load(r#"{"database": {"host": "localhost", "port": 5432, "ratio": 0.5, "tls": true, "replicas": ["a", null]}}"#)
+        .unwrap();
+    let database = value.get("database").unwrap();
+    assert_eq!(database.get("host").unwrap().as_str(), Some(&"localhost".to_string()));
+    assert_eq!(database.get("port"), Some(&Value::Int(5432)));
+    assert_eq!(database.get("ratio"), Some(&Value::Float(0.5)));
+    assert_eq!(database.get("tls"), Some(&Value::Boolean(true)));
+    assert_eq!(
+        database.get("replicas"),
+        Some(&Value::Sequence(vec![Value::String("a".to_string()), Value::Null]))
+    );
+
+    assert!(loader.load("key: value").is_err());
+}
+
+#[test]
+fn test_yaml_loader_booleans() {
+    let value = YamlLoader {}.load("flag: true\noff: false\n").unwrap();
+    assert_eq!(value.get("flag"), Some(&Value::Boolean(true)));
+    assert_eq!(value.get("off"), Some(&Value::Boolean(false)));
+}
+
 #[test]
 fn test_yaml_loader_nested() {
     let loader = YamlLoader {};
@@ -166,7 +352,7 @@ fn sample_value() -> Value {
 
 #[test]
 fn test_json_writer() {
-    let writer = JsonWriter {};
+    let writer = JsonWriter::default();
     assert_eq!(writer.ext(), "json");
 
     let value = sample_value();
@@ -177,9 +363,30 @@ fn test_json_writer() {
     assert!(json_str.contains("\"string\":\"hello\"") || json_str.contains("\"string\": \"hello\""));
 }
 
+#[test]
+fn test_json_writer_booleans_unquoted() {
+    let value = YamlLoader {}.load("flag: true\n").unwrap();
+    let json_str = JsonWriter::default().to_str(&value).unwrap();
+    assert!(json_str.contains("true"), "{}", json_str);
+    assert!(!json_str.contains("\"true\""), "Boolean written as a string: {}", json_str);
+}
+
+#[test]
+fn test_json_writer_pretty() {
+    let value = sample_value();
+    let compact = JsonWriter::default().to_str(&value).unwrap();
+    let pretty = JsonWriter::pretty().to_str(&value).unwrap();
+
+    assert!(!compact.contains('\n'), "{}", compact);
+    assert!(pretty.contains("\n  \"string\": \"hello\""), "{}", pretty);
+    assert!(pretty.contains("\n    \"nested\": \"value\""), "{}", pretty);
+    let parse = |s: &str| serde_json::from_str::<serde_json::Value>(s).unwrap();
+    assert_eq!(parse(&compact), parse(&pretty));
+}
+
 #[test]
 fn test_yaml_writer() {
-    let writer = YamlWriter {};
+    let writer = YamlWriter::default();
     assert_eq!(writer.ext(), "yaml");
 
     let value = sample_value();
@@ -190,6 +397,27 @@ fn test_yaml_writer() {
     assert!(yaml_str.contains("string: hello") || yaml_str.contains("string: 'hello'"));
 }
 
+#[test]
+fn test_yaml_and_json_output_is_deterministic() {
+    // Each `HashMap` gets its own random iteration order
+    let values: Vec<Value> = (0..10).map(|_| sample_value()).collect();
+
+    for writer in [&YamlWriter::default() as &dyn ValueWriter, &JsonWriter::default()] {
+        let first = writer.to_str(&values[0]).unwrap();
+        for value in &values[1..] {
+            assert_eq!(writer.to_str(value).unwrap(), first, "{} output changed", writer.ext());
+        }
+    }
+
+    let yaml = YamlWriter::default().to_str(&values[0]).unwrap();
+    let keys: Vec<&str> = yaml
+        .lines()
+        .filter(|line| !line.starts_with(' ') && !line.starts_with('-'))
+        .filter_map(|line| line.split(':').next())
+        .collect();
+    assert_eq!(keys, vec!["array", "boolean", "'null'", "number", "object", "string"]);
+}
+
 #[test]
 fn test_toml_writer() {
     let writer = TomlWriter {};
@@ -202,7 +430,7 @@ fn test_toml_writer() {
 
 #[test]
 fn test_env_writer() {
-    let writer = EnvVarWriter {};
+    let writer = EnvVarWriter::default();
     assert_eq!(writer.ext(), "env");
 
     let mut map = HashMap::new();
@@ -235,9 +463,24 @@ fn test_docker_env_writer() {
     assert!(env_str.contains("KEY=value"));
 }
 
+#[test]
+fn test_docker_env_writer_rejects_invalid_names() {
+    let writer = DockerEnvVarWriter {};
+
+    let mut map = HashMap::new();
+    map.insert("log-level".to_string(), Value::String("debug".to_string()));
+    let err = writer.to_str(&Value::Mapping(map)).unwrap_err();
+    assert_eq!(err.format, "docker-env");
+    assert!(err.message.contains("'LOG-LEVEL'"), "{err}");
+
+    let mut map = HashMap::new();
+    map.insert("1st".to_string(), Value::Int(1));
+    assert!(writer.to_str(&Value::Mapping(map)).is_err());
+}
+
 #[test]
 fn test_properties_writer() {
-    let writer = PropertiesWriter {};
+    let writer = PropertiesWriter::default();
     assert_eq!(writer.ext(), "properties");
 
     let mut map = HashMap::new();
@@ -249,6 +492,465 @@ fn test_properties_writer() {
     assert!(result.is_ok());
 }
 
+#[test]
+fn test_ini_writer_sections() {
+    let writer = IniWriter {};
+    assert_eq!(writer.ext(), "ini");
+
+    let mut pool = HashMap::new();
+    pool.insert("max".to_string(), Value::Int(10));
+    let mut database = HashMap::new();
+    database.insert("host".to_string(), Value::String("localhost".to_string()));
+    database.insert("port".to_string(), Value::Int(5432));
+    database.insert("pool".to_string(), Value::Mapping(pool));
+    let mut cache = HashMap::new();
+    cache.insert("enabled".to_string(), Value::Boolean(true));
+    let mut map = HashMap::new();
+    map.insert("name".to_string(), Value::String("my app".to_string()));
+    map.insert("database".to_string(), Value::Mapping(database));
+    map.insert("cache".to_string(), Value::Mapping(cache));
+
+    let ini = writer.to_str(&Value::Mapping(map)).unwrap();
+    assert_eq!(
+        ini,
+        "name=my app\n\n[cache]\nenabled=true\n\n[database]\nhost=localhost\npool.max=10\nport=5432\n"
+    );
+    assert!(writer.to_str(&Value::Int(1)).is_err());
+}
+
+#[test]
+fn test_xml_writer_nested_mappings() {
+    let writer = XmlWriter::default();
+    assert_eq!(writer.ext(), "xml");
+
+    let mut database = HashMap::new();
+    database.insert("host".to_string(), Value::String("localhost".to_string()));
+    database.insert("port".to_string(), Value::Int(5432));
+    let mut map = HashMap::new();
+    map.insert("database".to_string(), Value::Mapping(database));
+    map.insert("debug".to_string(), Value::Boolean(false));
+
+    let xml = writer.to_str(&Value::Mapping(map)).unwrap();
+    assert_eq!(
+        xml,
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <root>\n  <database>\n    <host>localhost</host>\n    <port>5432</port>\n  </database>\n  \
+         <debug>false</debug>\n</root>\n"
+    );
+}
+
+#[test]
+fn test_xml_writer_sequences_repeat_elements() {
+    let writer = XmlWriter::with_root("config");
+
+    let mut map = HashMap::new();
+    map.insert(
+        "host".to_string(),
+        Value::Sequence(vec![Value::String("a".to_string()), Value::String("b".to_string())]),
+    );
+    let xml = writer.to_str(&Value::Mapping(map)).unwrap();
+    assert!(xml.contains("<config>\n  <host>a</host>\n  <host>b</host>\n</config>"), "{xml}");
+
+    let xml = writer.to_str(&Value::Sequence(vec![Value::Int(1), Value::Null])).unwrap();
+    assert!(xml.contains("<config>\n  <item>1</item>\n  <item/>\n</config>"), "{xml}");
+}
+
+#[test]
+fn test_xml_writer_escapes_text() {
+    let writer = XmlWriter::default();
+
+    let mut map = HashMap::new();
+    map.insert("query".to_string(), Value::String("a < b && c > \"d\" 'e'".to_string()));
+    let xml = writer.to_str(&Value::Mapping(map)).unwrap();
+    assert!(
+        xml.contains("<query>a &lt; b &amp;&amp; c &gt; &quot;d&quot; &apos;e&apos;</query>"),
+        "{xml}"
+    );
+}
+
+fn k8s_sample() -> Value {
+    let mut database = HashMap::new();
+    database.insert("host".to_string(), Value::String("localhost".to_string()));
+    database.insert("port".to_string(), Value::Int(5432));
+    let mut map = HashMap::new();
+    map.insert("database".to_string(), Value::Mapping(database));
+    map.insert("debug".to_string(), Value::Boolean(true));
+    Value::Mapping(map)
+}
+
+#[test]
+fn test_configmap_writer_parses_back() {
+    let writer = ConfigMapWriter {};
+    assert_eq!(writer.ext(), "configmap");
+
+    let manifest = writer.to_str_named(&k8s_sample(), "api").unwrap();
+    let parsed: serde_yaml::Value = serde_yaml::from_str(&manifest).unwrap();
+
+    assert_eq!(parsed["apiVersion"], "v1");
+    assert_eq!(parsed["kind"], "ConfigMap");
+    assert_eq!(parsed["metadata"]["name"], "api");
+    assert_eq!(parsed["data"]["DATABASE_HOST"], "localhost");
+    // ConfigMap data only holds strings
+    assert_eq!(parsed["data"]["DATABASE_PORT"], "5432");
+    assert_eq!(parsed["data"]["DEBUG"], "true");
+}
+
+#[test]
+fn test_secret_writer_base64_encodes_values() {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    let writer = SecretWriter {};
+    assert_eq!(writer.ext(), "secret");
+
+    let manifest = writer.to_str(&k8s_sample()).unwrap();
+    let parsed: serde_yaml::Value = serde_yaml::from_str(&manifest).unwrap();
+
+    assert_eq!(parsed["kind"], "Secret");
+    assert_eq!(parsed["type"], "Opaque");
+    assert_eq!(parsed["metadata"]["name"], "config");
+    let host = parsed["data"]["DATABASE_HOST"].as_str().unwrap();
+    assert_eq!(host, STANDARD.encode("localhost"));
+    assert_eq!(STANDARD.decode(host).unwrap(), b"localhost");
+    assert!(writer.to_str(&Value::Int(1)).is_err());
+}
+
+#[test]
+fn test_k8s_resource_name_from_path() {
+    assert_eq!(resource_name("services/api/config"), "config");
+    assert_eq!(resource_name("/common/Redis_Cache/"), "redis-cache");
+    assert_eq!(resource_name(""), "config");
+}
+
+#[test]
+fn test_shell_writer_quotes_values() {
+    let writer = ShellWriter {};
+    assert_eq!(writer.ext(), "sh");
+
+    let mut map = HashMap::new();
+    map.insert("greeting".to_string(), Value::String("hello world".to_string()));
+    map.insert("cmd".to_string(), Value::String("$(rm -rf /) `id` $HOME".to_string()));
+    map.insert("quote".to_string(), Value::String("it's".to_string()));
+    map.insert("empty".to_string(), Value::Null);
+    map.insert("my-key".to_string(), Value::Int(1));
+    let sh = writer.to_str(&Value::Mapping(map)).unwrap();
+    let lines: Vec<&str> = sh.lines().collect();
+
+    assert!(lines.contains(&"export GREETING='hello world'"), "{sh}");
+    assert!(lines.contains(&"export CMD='$(rm -rf /) `id` $HOME'"), "{sh}");
+    assert!(lines.contains(&r"export QUOTE='it'\''s'"), "{sh}");
+    assert!(lines.contains(&"export EMPTY=''"), "{sh}");
+    assert!(lines.contains(&"export MY_KEY='1'"), "{sh}");
+}
+
+#[test]
+fn test_helm_values_writer() {
+    let writer = HelmValuesWriter {};
+    assert_eq!(writer.ext(), "helm-values");
+
+    let mut map = match sample_value() {
+        Value::Mapping(map) => map,
+        _ => unreachable!(),
+    };
+    map.insert("empty".to_string(), Value::Mapping(HashMap::new()));
+    map.insert("list".to_string(), Value::Sequence(vec![Value::Int(1), Value::Null]));
+    let helm_str = writer.to_str(&Value::Mapping(map)).unwrap();
+
+    assert!(helm_str.contains("empty: {}"), "{helm_str}");
+    assert!(helm_str.contains("number: 42"));
+    assert!(helm_str.contains("boolean: true"));
+    assert!(!helm_str.contains("null:"), "Null keys should be omitted: {helm_str}");
+    assert!(helm_str.contains("- null"), "Nulls in sequences are kept: {helm_str}");
+    // Keys are sorted
+    assert!(helm_str.find("array:") < helm_str.find("string:"));
+
+    assert_eq!(writer.to_str(&Value::Null).unwrap().trim(), "{}");
+    assert!(writer.to_str(&Value::Int(1)).is_err());
+}
+
+#[test]
+fn test_multi_writer_default_format() {
+    let writer = MultiWriter::new(vec![YamlWriter::new_boxed(), JsonWriter::new_boxed()]);
+    assert_eq!(writer.default_format, "yaml");
+
+    let writer = writer.with_default_format("json").unwrap();
+    assert_eq!(writer.default_format, "json");
+
+    // Unknown formats are rejected
+    assert!(writer.with_default_format("xml").is_none());
+}
+
+#[test]
+fn test_multi_writer_format_for_content_type() {
+    let writer = MultiWriter::new(vec![
+        YamlWriter::new_boxed(),
+        JsonWriter::new_boxed(),
+        EnvVarWriter::new_boxed(),
+        PropertiesWriter::new_boxed(),
+        TomlWriter::new_boxed(),
+    ]);
+
+    assert_eq!(writer.format_for_content_type("application/json"), Some("json"));
+    assert_eq!(writer.format_for_content_type("application/x-yaml"), Some("yaml"));
+    assert_eq!(writer.format_for_content_type("application/toml"), Some("toml"));
+    // Writers sharing a type resolve to the first registered one
+    assert_eq!(writer.format_for_content_type("text/plain"), Some("env"));
+    assert_eq!(writer.format_for_content_type("*/*"), Some("yaml"));
+    // Ranges are tried by weight, unknown ones skipped
+    assert_eq!(
+        writer.format_for_content_type("text/html, application/yaml;q=0.5, application/json;q=0.9"),
+        Some("json")
+    );
+    assert_eq!(writer.format_for_content_type("application/json;q=0, */*;q=0.1"), Some("yaml"));
+    assert_eq!(writer.format_for_content_type("image/png"), None);
+
+    let json = writer
+        .write_for_content_type("application/json", &Value::Int(1))
+        .unwrap()
+        .unwrap();
+    assert_eq!(json, "1");
+}
+
+fn list_value() -> Value {
+    let mut map = HashMap::new();
+    map.insert("hosts".to_string(), Value::Sequence(vec![
+        Value::String("a.example.com".to_string()),
+        Value::String("b.example.com".to_string()),
+    ]));
+    Value::Mapping(map)
+}
+
+#[test]
+fn test_env_writer_indexed_sequences() {
+    let writer = EnvVarWriter::default();
+    let env_str = writer.to_str(&list_value()).unwrap();
+
+    assert!(env_str.contains("HOSTS_0=\"a.example.com\""));
+    assert!(env_str.contains("HOSTS_1=\"b.example.com\""));
+}
+
+#[test]
+fn test_env_writer_joined_sequences() {
+    let writer = EnvVarWriter::with_sequence_encoding(SequenceEncoding::Joined(",".to_string()));
+    let env_str = writer.to_str(&list_value()).unwrap();
+
+    assert_eq!(env_str, "HOSTS=\"a.example.com,b.example.com\"");
+}
+
+#[test]
+fn test_env_writer_joined_keeps_complex_sequences_indexed() {
+    let mut item = HashMap::new();
+    item.insert("name".to_string(), Value::String("x".to_string()));
+    let mut map = HashMap::new();
+    map.insert("items".to_string(), Value::Sequence(vec![Value::Mapping(item)]));
+
+    let writer = EnvVarWriter::with_sequence_encoding(SequenceEncoding::Joined(",".to_string()));
+    let env_str = writer.to_str(&Value::Mapping(map)).unwrap();
+
+    assert_eq!(env_str, "ITEMS_0_NAME=\"x\"");
+}
+
+#[test]
+fn test_properties_writer_indexed_sequences() {
+    let writer = PropertiesWriter::default();
+    let props = writer.to_str(&list_value()).unwrap();
+
+    assert!(props.contains("hosts[0]=a.example.com\n"));
+    assert!(props.contains("hosts[1]=b.example.com\n"));
+}
+
+#[test]
+fn test_properties_writer_joined_sequences() {
+    let writer = PropertiesWriter::with_sequence_encoding(SequenceEncoding::Joined(";".to_string()));
+    let props = writer.to_str(&list_value()).unwrap();
+
+    assert_eq!(props, "hosts=a.example.com;b.example.com\n");
+}
+
+#[test]
+fn test_joined_sequences_escape_the_separator() {
+    let mut map = HashMap::new();
+    map.insert("tags".to_string(), Value::Sequence(vec![
+        Value::String("a,b".to_string()),
+        Value::String("c\\d".to_string()),
+        Value::Float(1.5),
+    ]));
+    let value = Value::Mapping(map);
+
+    let sequences = SequenceEncoding::from_separator(Some(",".to_string()));
+    let env_str = EnvVarWriter::with_sequence_encoding(sequences).to_str(&value).unwrap();
+    assert_eq!(env_str, r#"TAGS="a\,b,c\\d,1.5""#);
+
+    let sequences = SequenceEncoding::from_separator(Some(".".to_string()));
+    let env_str = EnvVarWriter::with_sequence_encoding(sequences).to_str(&value).unwrap();
+    assert_eq!(env_str, r#"TAGS="a,b.c\\d.1\.5""#);
+
+    assert_eq!(SequenceEncoding::from_separator(Some(String::new())), SequenceEncoding::Indexed);
+    assert_eq!(SequenceEncoding::from_separator(None), SequenceEncoding::Indexed);
+}
+
+#[test]
+fn test_properties_writer_escapes_special_characters() {
+    let writer = PropertiesWriter::default();
+
+    let mut map = HashMap::new();
+    map.insert("url".to_string(), Value::String("jdbc:db?a=b".to_string()));
+    map.insert("motd".to_string(), Value::String("line 1\nline 2".to_string()));
+    map.insert("city".to_string(), Value::String("Zürich €".to_string()));
+    map.insert("padded".to_string(), Value::String("  x\\y #1!".to_string()));
+    map.insert("my key".to_string(), Value::Int(1));
+    let props = writer.to_str(&Value::Mapping(map)).unwrap();
+    let lines: Vec<&str> = props.lines().collect();
+
+    assert!(lines.contains(&r"url=jdbc\:db?a\=b"), "{props}");
+    assert!(lines.contains(&r"motd=line 1\nline 2"), "{props}");
+    assert!(lines.contains(&r"city=Z\u00FCrich \u20AC"), "{props}");
+    assert!(lines.contains(&r"padded=\  x\\y \#1\!"), "{props}");
+    assert!(lines.contains(&r"my\ key=1"), "{props}");
+}
+
+fn bundle_part(prefix: Option<&str>, path: &str, yaml: &str) -> (BundlePart, Value) {
+    let part = BundlePart {
+        prefix: prefix.map(String::from),
+        path: path.to_string(),
+    };
+    (part, YamlLoader {}.load(yaml).unwrap())
+}
+
+#[test]
+fn test_parse_bundle_spec() {
+    let parts = parse_bundle_spec("db:common/database, a,");
+
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0].prefix.as_deref(), Some("db"));
+    assert_eq!(parts[0].path, "common/database");
+    assert_eq!(parts[1].prefix, None);
+    assert_eq!(parts[1].path, "a");
+}
+
+#[test]
+fn test_env_bundle_two_configs() {
+    let parts = [
+        bundle_part(Some("db"), "common/database", "host: db.local\nport: 5432"),
+        bundle_part(None, "app", "name: api\nhost: db.local"),
+    ];
+
+    let body = env_bundle("env", &parts).unwrap();
+
+    assert_eq!(body, "DB_HOST=\"db.local\"\nDB_PORT=5432\nHOST=\"db.local\"\nNAME=\"api\"");
+}
+
+#[test]
+fn test_env_bundle_deduplicates_identical_values() {
+    let parts = [
+        bundle_part(None, "a", "region: eu"),
+        bundle_part(None, "b", "region: eu\nzone: 1"),
+    ];
+
+    let body = env_bundle("docker-env", &parts).unwrap();
+
+    assert_eq!(body, "REGION=eu\nZONE=1");
+}
+
+#[test]
+fn test_env_bundle_key_collision() {
+    let parts = [
+        bundle_part(None, "common/database", "port: 5432"),
+        bundle_part(None, "common/redis", "port: 6379"),
+    ];
+
+    let err = env_bundle("env", &parts).unwrap_err();
+
+    assert!(matches!(
+        err,
+        BundleError::Collision { ref key, ref first, ref second }
+            if key == "PORT" && first == "common/database" && second == "common/redis"
+    ));
+}
+
+// ============================================================================
+// Render metadata tests
+// ============================================================================
+
+#[test]
+fn test_render_meta_records_source_and_commit() {
+    let value = with_render_meta(sample_value(), "services/api/config", Some("abc123"));
+
+    let meta = value.get(RENDER_META_KEY).expect("Should have _meta");
+    assert_eq!(meta.get("source"), Some(&Value::String("services/api/config".into())));
+    assert_eq!(meta.get("commit"), Some(&Value::String("abc123".into())));
+    assert!(matches!(meta.get("rendered_at"), Some(Value::Int(t)) if *t > 0));
+    // The original keys are kept
+    assert!(value.get("string").is_some());
+}
+
+#[test]
+fn test_render_meta_without_commit() {
+    let value = with_render_meta(sample_value(), "common/database", None);
+
+    let meta = value.get(RENDER_META_KEY).expect("Should have _meta");
+    assert_eq!(meta.get("commit"), None);
+}
+
+#[test]
+fn test_render_meta_leaves_non_mappings_unchanged() {
+    let value = with_render_meta(Value::Int(1), "a", None);
+    assert_eq!(value, Value::Int(1));
+}
+
+// ============================================================================
+// Schema inference tests
+// ============================================================================
+
+#[test]
+fn test_infer_schema_types_and_required_keys() {
+    let loader = YamlLoader {};
+    let value = loader
+        .load(
+            r#"
+service:
+  name: api
+  port: 8080
+  ratio: 0.5
+  debug: false
+hosts:
+  - a.example.com
+  - b.example.com
+"#,
+        )
+        .unwrap();
+
+    let schema = infer_schema(&value);
+
+    assert_eq!(schema["type"], "object");
+    assert_eq!(schema["required"], serde_json::json!(["hosts", "service"]));
+
+    let service = &schema["properties"]["service"];
+    assert_eq!(service["properties"]["name"]["type"], "string");
+    assert_eq!(service["properties"]["port"]["type"], "integer");
+    assert_eq!(service["properties"]["ratio"]["type"], "number");
+    assert_eq!(service["properties"]["debug"]["type"], "boolean");
+
+    let hosts = &schema["properties"]["hosts"];
+    assert_eq!(hosts["type"], "array");
+    assert_eq!(hosts["items"]["type"], "string");
+}
+
+#[test]
+fn test_infer_schema_merges_sequence_items() {
+    let loader = YamlLoader {};
+    let value = loader
+        .load("items:\n  - {name: a, port: 1}\n  - {name: b}\nmixed: [1, 2.5]")
+        .unwrap();
+
+    let schema = infer_schema(&value);
+
+    let items = &schema["properties"]["items"]["items"];
+    assert_eq!(items["type"], "object");
+    assert_eq!(items["required"], serde_json::json!(["name"]));
+    assert_eq!(items["properties"]["port"]["type"], "integer");
+    assert_eq!(schema["properties"]["mixed"]["items"]["type"], "number");
+}
+
 // ============================================================================
 // Round-trip tests (load -> write -> load)
 // ============================================================================
@@ -256,7 +958,7 @@ fn test_properties_writer() {
 #[test]
 fn test_yaml_roundtrip() {
     let loader = YamlLoader {};
-    let writer = YamlWriter {};
+    let writer = YamlWriter::default();
 
     let original = r#"
 key: value
@@ -279,3 +981,131 @@ nested:
         reloaded.get("key").unwrap().as_str()
     );
 }
+
+#[test]
+fn test_integers_roundtrip_without_fraction() {
+    let loader = YamlLoader {};
+    let value = loader.load("port: 5432\nratio: 0.5\n").unwrap();
+    assert_eq!(value.get("port"), Some(&Value::Int(5432)));
+    assert_eq!(value.get("ratio"), Some(&Value::Float(0.5)));
+
+    let writers: Vec<Box<dyn ValueWriter>> = vec![
+        Box::new(JsonWriter::default()),
+        Box::new(YamlWriter::default()),
+        Box::new(TomlWriter {}),
+        Box::new(EnvVarWriter::default()),
+        Box::new(PropertiesWriter::default()),
+        Box::new(DockerEnvVarWriter {}),
+    ];
+    for writer in writers {
+        let written = writer.to_str(&value).unwrap();
+        assert!(written.contains("5432"), "{}: {}", writer.ext(), written);
+        assert!(!written.contains("5432.0"), "{} wrote a float: {}", writer.ext(), written);
+        assert!(written.contains("0.5"), "{}: {}", writer.ext(), written);
+    }
+
+    let reloaded = loader.load(&YamlWriter::default().to_str(&value).unwrap()).unwrap();
+    assert_eq!(reloaded.get("port"), Some(&Value::Int(5432)));
+}
+
+// ============================================================================
+// In-flight deduplication tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_in_flight_runs_concurrent_identical_work_once() {
+    let in_flight: InFlight<usize> = InFlight::new();
+    let runs = Arc::new(AtomicUsize::new(0));
+
+    let calls = (0..8).map(|_| {
+        let runs = runs.clone();
+        in_flight.run("commit/path", move || async move {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            runs.fetch_add(1, Ordering::SeqCst) + 1
+        })
+    });
+    let outputs = futures::future::join_all(calls).await;
+
+    assert_eq!(runs.load(Ordering::SeqCst), 1, "The work should run once");
+    assert!(outputs.iter().all(|&output| output == 1), "{outputs:?}");
+    assert!(in_flight.is_empty(), "Completed work leaves the map");
+
+    // Later calls start the work again
+    let runs_again = runs.clone();
+    let output = in_flight
+        .run("commit/path", move || async move { runs_again.fetch_add(1, Ordering::SeqCst) + 1 })
+        .await;
+    assert_eq!(output, 2);
+}
+
+#[test]
+fn test_webhook_verify_accepts_github_signature_and_gitlab_token() {
+    use konf_provider::webhook::{sign, verify, GITLAB_TOKEN_HEADER, SIGNATURE_HEADER};
+    use xitca_web::http::{HeaderMap, HeaderValue};
+
+    let headers = |name, value: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    };
+    let body = b"{}";
+
+    assert!(verify("secret", &headers(SIGNATURE_HEADER, &sign("secret", body)), body).is_ok());
+    assert!(verify("secret", &headers(SIGNATURE_HEADER, &sign("secret", b"[]")), body).is_err());
+    assert!(verify("secret", &headers(SIGNATURE_HEADER, "sha256=zz"), body).is_err());
+    assert!(verify("secret", &headers(GITLAB_TOKEN_HEADER, "secret"), body).is_ok());
+    assert!(verify("secret", &headers(GITLAB_TOKEN_HEADER, "secre"), body).is_err());
+    assert!(verify("secret", &HeaderMap::new(), body).is_err());
+}
+
+#[test]
+fn test_get_error_json_body_has_stable_code_and_fields() {
+    use konf_provider::utils::GetError;
+
+    let forbidden = GetError::Forbidden { path: "secrets/db".to_string() }.to_json();
+    assert_eq!(forbidden["code"], "forbidden");
+    assert_eq!(forbidden["path"], "secrets/db");
+    assert_eq!(forbidden["error"], "forbidden: not authorized to access 'secrets/db'");
+
+    let not_found = GetError::ConfigNotFound {
+        path: "secrets/bd".to_string(),
+        suggestions: vec!["secrets/db".to_string()],
+    }
+    .to_json();
+    assert_eq!(not_found["code"], "config_not_found");
+    assert_eq!(not_found["suggestions"], serde_json::json!(["secrets/db"]));
+
+    let commit = GetError::CommitNotFound { commit: "main".to_string() }.to_json();
+    assert_eq!(commit["code"], "commit_not_found");
+    assert_eq!(commit["commit"], "main");
+
+    let internal = GetError::InternalError { reason: "boom".to_string() }.to_json();
+    assert_eq!(internal, serde_json::json!({"error": "internal error: boom", "code": "internal_error"}));
+}
+
+#[test]
+fn test_server_config_parses_toml_and_yaml() {
+    use konf_provider::config::ServerConfig;
+
+    let toml = ServerConfig::parse(
+        "repo_url = \"https://example.com/configs.git\"\nport = 8080\nrender_meta = true\npeers = [\"http://a:4000\", \"http://b:4000\"]\n",
+        false,
+    )
+    .unwrap();
+    let yaml = ServerConfig::parse(
+        "repo_url: https://example.com/configs.git\nport: 8080\nrender_meta: true\npeers: [http://a:4000, http://b:4000]\n",
+        true,
+    )
+    .unwrap();
+    assert_eq!(toml, yaml);
+    assert_eq!(toml.port, Some(8080));
+    assert_eq!(toml.branch, None);
+
+    assert_eq!(toml.render_meta, Some(true));
+    assert_eq!(
+        toml.peers,
+        Some(vec!["http://a:4000".to_string(), "http://b:4000".to_string()])
+    );
+
+    assert!(ServerConfig::parse("prot = 8080\n", false).is_err(), "unknown keys are rejected");
+}